@@ -7,10 +7,55 @@ use rustfft::{num_complex::Complex, FftPlanner};
 use std::f32::consts::PI;
 use std::sync::Arc;
 
+/// Number of log-spaced bands in the per-frame energies used for bars and phrases
+const ENERGY_BANDS: usize = 8;
+
+/// A stretch of a beat grid at constant tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoSegment {
+    /// Beat number where the segment starts
+    pub start_beat: f64,
+    /// Sample position of the segment's first beat (stereo interleaved)
+    pub start_position: f64,
+    /// BPM within the segment
+    pub bpm: f32,
+    /// Number of samples per beat (cached for performance)
+    samples_per_beat: f64,
+}
+
+impl TempoSegment {
+    fn new(start_beat: f64, start_position: f64, bpm: f32, sample_rate: u32) -> Self {
+        Self {
+            start_beat,
+            start_position,
+            bpm,
+            samples_per_beat: (60.0 / bpm as f64) * sample_rate as f64 * 2.0,
+        }
+    }
+
+    /// Get samples per beat within the segment
+    pub fn samples_per_beat(&self) -> f64 {
+        self.samples_per_beat
+    }
+
+    fn beat_at_position(&self, position: f64) -> f64 {
+        self.start_beat + (position - self.start_position) / self.samples_per_beat
+    }
+
+    fn position_for_beat(&self, beat: f64) -> f64 {
+        self.start_position + (beat - self.start_beat) * self.samples_per_beat
+    }
+}
+
 /// Represents a beat grid for a track
+///
+/// The grid is a tempo map: one or more tempo segments, each running until
+/// the next one starts. Constant-tempo tracks have a single segment. Beats
+/// group into bars of [`BeatGrid::BEATS_PER_BAR`] from the downbeat, and
+/// bars into phrases.
 #[derive(Debug, Clone)]
 pub struct BeatGrid {
-    /// BPM of the track
+    /// BPM of the track (the tempo covering the most beats for tempo maps)
     pub bpm: f32,
     /// Offset of the first beat in samples (stereo interleaved)
     pub first_beat_offset: u64,
@@ -20,11 +65,60 @@ pub struct BeatGrid {
     samples_per_beat: f64,
     /// Confidence score (0.0 - 1.0) indicating detection reliability
     pub confidence: f32,
+    /// Tempo segments in track order, the first starting at beat 0
+    segments: Vec<TempoSegment>,
+    /// Beat number of the first downbeat (0 - 3); bar 0 starts there
+    pub downbeat: u32,
+    /// Bars where phrases start, in order
+    phrases: Vec<u32>,
 }
 
 impl BeatGrid {
     /// Create a new beat grid
     pub fn new(bpm: f32, first_beat_offset: u64, sample_rate: u32, confidence: f32) -> Self {
+        Self::with_tempo_map(
+            bpm,
+            first_beat_offset,
+            sample_rate,
+            confidence,
+            &[(0.0, bpm)],
+        )
+    }
+
+    /// Create a beat grid that changes tempo
+    ///
+    /// `tempo_map` lists `(start_beat, bpm)` pairs in beat order. The first
+    /// pair always starts at beat 0, the first beat of the track.
+    pub fn with_tempo_map(
+        bpm: f32,
+        first_beat_offset: u64,
+        sample_rate: u32,
+        confidence: f32,
+        tempo_map: &[(f64, f32)],
+    ) -> Self {
+        let mut segments: Vec<TempoSegment> = Vec::with_capacity(tempo_map.len().max(1));
+        for &(start_beat, segment_bpm) in tempo_map {
+            let segment = match segments.last() {
+                Some(prev) if start_beat > prev.start_beat => TempoSegment::new(
+                    start_beat,
+                    prev.position_for_beat(start_beat),
+                    segment_bpm,
+                    sample_rate,
+                ),
+                Some(_) => continue,
+                None => TempoSegment::new(0.0, first_beat_offset as f64, segment_bpm, sample_rate),
+            };
+            segments.push(segment);
+        }
+        if segments.is_empty() {
+            segments.push(TempoSegment::new(
+                0.0,
+                first_beat_offset as f64,
+                bpm,
+                sample_rate,
+            ));
+        }
+
         // Samples per beat for stereo interleaved audio
         let samples_per_beat = (60.0 / bpm as f64) * sample_rate as f64 * 2.0;
         Self {
@@ -33,12 +127,185 @@ impl BeatGrid {
             sample_rate,
             samples_per_beat,
             confidence,
+            segments,
+            downbeat: 0,
+            phrases: Vec::new(),
         }
     }
 
+    /// Beats in a bar
+    pub const BEATS_PER_BAR: u32 = 4;
+
+    /// Set the downbeat and the bars where phrases start
+    pub fn set_bars(&mut self, downbeat: u32, mut phrases: Vec<u32>) {
+        phrases.sort_unstable();
+        phrases.dedup();
+        self.downbeat = downbeat % Self::BEATS_PER_BAR;
+        self.phrases = phrases;
+    }
+
+    /// Get the bars where phrases start
+    pub fn phrases(&self) -> &[u32] {
+        &self.phrases
+    }
+
+    /// Get the bar number (can be fractional) at a given sample position
+    pub fn bar_at_position(&self, position: f64) -> f64 {
+        (self.beat_at_position(position) - self.downbeat as f64) / Self::BEATS_PER_BAR as f64
+    }
+
+    /// Get the phase (0.0 - 1.0) within the current bar at a given position
+    pub fn bar_phase_at_position(&self, position: f64) -> f32 {
+        self.bar_at_position(position).rem_euclid(1.0) as f32
+    }
+
+    /// Get the beat within the bar (0 = downbeat) at a given position
+    pub fn beat_in_bar(&self, position: f64) -> u32 {
+        let beat = self.beat_at_position(position).floor() - self.downbeat as f64;
+        beat.rem_euclid(Self::BEATS_PER_BAR as f64) as u32
+    }
+
+    /// Get sample position for a specific bar number
+    pub fn position_for_bar(&self, bar: f64) -> f64 {
+        self.position_for_beat(self.downbeat as f64 + bar * Self::BEATS_PER_BAR as f64)
+    }
+
+    /// Get the tempo segments
+    pub fn segments(&self) -> &[TempoSegment] {
+        &self.segments
+    }
+
+    /// Get the tempo map as `(start_beat, bpm)` pairs (see [`BeatGrid::with_tempo_map`])
+    pub fn tempo_map(&self) -> Vec<(f64, f32)> {
+        self.segments
+            .iter()
+            .map(|s| (s.start_beat, s.bpm))
+            .collect()
+    }
+
+    /// Move the whole grid by a number of samples (positive = later)
+    ///
+    /// If the first beat would fall before the start of the track, beats
+    /// are renumbered from a later bar so bar numbers keep their meaning.
+    pub fn shift(&mut self, samples: f64) {
+        let mut bars = 0u32;
+        while self.position_for_beat((bars * Self::BEATS_PER_BAR) as f64) + samples < 0.0 {
+            bars += 1;
+        }
+        let from_beat = (bars * Self::BEATS_PER_BAR) as f64;
+        let first = self.segment_for_beat(from_beat);
+        let first_beat = first.position_for_beat(from_beat) + samples;
+        let tempo_map: Vec<(f64, f32)> = self
+            .segments
+            .iter()
+            .filter(|s| s.start_beat > from_beat)
+            .map(|s| (s.start_beat - from_beat, s.bpm))
+            .collect();
+        let phrases = self
+            .phrases
+            .iter()
+            .filter_map(|&p| p.checked_sub(bars))
+            .collect();
+        self.rebuild(
+            self.bpm,
+            first_beat,
+            [(0.0, first.bpm)].into_iter().chain(tempo_map).collect(),
+            self.downbeat,
+            phrases,
+        );
+    }
+
+    /// Move the grid so a beat lands on `position` and make it a downbeat
+    pub fn set_downbeat_at(&mut self, position: f64) {
+        let beat = self.beat_at_position(position).round();
+        self.shift(position - self.position_for_beat(beat));
+        // Shifting renumbers by whole bars at most, so the beat's place in
+        // the bar is unchanged
+        let downbeat = (beat as i64).rem_euclid(Self::BEATS_PER_BAR as i64) as u32;
+        self.downbeat = downbeat;
+    }
+
+    /// Multiply every tempo by `factor` (2.0 doubles, 0.5 halves)
+    ///
+    /// The first beat stays put and the rest of the grid stretches from it.
+    pub fn scale_tempo(&mut self, factor: f64) {
+        if !(factor > 0.0 && factor.is_finite()) {
+            return;
+        }
+        let beats_per_bar = Self::BEATS_PER_BAR as f64;
+        let tempo_map = self
+            .segments
+            .iter()
+            .map(|s| (s.start_beat * factor, (s.bpm as f64 * factor) as f32))
+            .collect();
+        let old_downbeat = self.downbeat as f64;
+        let downbeat = (old_downbeat * factor).round().rem_euclid(beats_per_bar);
+        let phrases = self
+            .phrases
+            .iter()
+            .map(|&bar| {
+                let beat = (old_downbeat + bar as f64 * beats_per_bar) * factor;
+                ((beat - downbeat) / beats_per_bar).round().max(0.0) as u32
+            })
+            .collect();
+        self.rebuild(
+            (self.bpm as f64 * factor) as f32,
+            self.segments[0].start_position,
+            tempo_map,
+            downbeat as u32,
+            phrases,
+        );
+    }
+
+    /// Set the main tempo, scaling the rest of the tempo map with it
+    pub fn set_bpm(&mut self, bpm: f32) {
+        if self.bpm > 0.0 {
+            self.scale_tempo(bpm as f64 / self.bpm as f64);
+        }
+    }
+
+    /// Replace the grid with one built from an edited tempo map
+    fn rebuild(
+        &mut self,
+        bpm: f32,
+        first_beat: f64,
+        tempo_map: Vec<(f64, f32)>,
+        downbeat: u32,
+        phrases: Vec<u32>,
+    ) {
+        let mut grid = Self::with_tempo_map(
+            bpm,
+            first_beat.round().max(0.0) as u64,
+            self.sample_rate,
+            self.confidence,
+            &tempo_map,
+        );
+        grid.set_bars(downbeat, phrases);
+        *self = grid;
+    }
+
+    /// Tempo segment playing at a sample position
+    fn segment_at_position(&self, position: f64) -> &TempoSegment {
+        let index = self
+            .segments
+            .partition_point(|s| s.start_position <= position)
+            .saturating_sub(1);
+        &self.segments[index]
+    }
+
+    /// Tempo segment containing a beat
+    fn segment_for_beat(&self, beat: f64) -> &TempoSegment {
+        let index = self
+            .segments
+            .partition_point(|s| s.start_beat <= beat)
+            .saturating_sub(1);
+        &self.segments[index]
+    }
+
     /// Get the beat number (can be fractional) at a given sample position
     pub fn beat_at_position(&self, position: f64) -> f64 {
-        (position - self.first_beat_offset as f64) / self.samples_per_beat
+        self.segment_at_position(position)
+            .beat_at_position(position)
     }
 
     /// Get the phase (0.0 - 1.0) within the current beat at a given position
@@ -49,7 +316,17 @@ impl BeatGrid {
 
     /// Get sample position for a specific beat number
     pub fn position_for_beat(&self, beat: f64) -> f64 {
-        self.first_beat_offset as f64 + (beat * self.samples_per_beat)
+        self.segment_for_beat(beat).position_for_beat(beat)
+    }
+
+    /// Get the BPM at a given sample position
+    pub fn bpm_at_position(&self, position: f64) -> f32 {
+        self.segment_at_position(position).bpm
+    }
+
+    /// Get samples per beat at a given sample position (at tempo 1.0)
+    pub fn samples_per_beat_at(&self, position: f64) -> f64 {
+        self.segment_at_position(position).samples_per_beat
     }
 
     /// Get samples per beat adjusted for a tempo multiplier
@@ -108,7 +385,8 @@ impl BeatGridAnalyzer {
 
     /// Analyze audio samples and build a beat grid
     ///
-    /// This should be called on track load with the first 30 seconds or so of audio.
+    /// This should be called on track load with the whole track, so tempo
+    /// changes anywhere in it end up in the tempo map.
     /// Returns None if beat detection fails.
     pub fn analyze(&self, samples: &[f32]) -> Option<BeatGrid> {
         if samples.len() < self.sample_rate as usize * 4 {
@@ -117,7 +395,7 @@ impl BeatGridAnalyzer {
         }
 
         // 1. Compute spectral flux onset detection function
-        let onset_function = self.compute_onset_function(samples);
+        let (onset_function, bands) = self.compute_onset_function(samples);
 
         if onset_function.len() < 100 {
             return None;
@@ -129,17 +407,40 @@ impl BeatGridAnalyzer {
         // 3. Estimate BPM using autocorrelation
         let (bpm, confidence) = self.estimate_bpm_autocorrelation(&onset_function)?;
 
-        // 4. Find first downbeat
-        let first_beat = self.find_first_downbeat(&onsets, bpm);
+        // 4. Find the first beat
+        let first_beat_frame = self.find_first_downbeat(&onsets, bpm);
+        let first_beat = self.frame_position(first_beat_frame as f64).round() as u64;
 
-        Some(BeatGrid::new(bpm, first_beat, self.sample_rate, confidence))
+        // 5. Follow the beat through the track and fit a tempo map to it,
+        // numbering beats from the earliest one found
+        let before = self.track_beats(&onset_function, first_beat_frame, bpm, true);
+        let after = self.track_beats(&onset_function, first_beat_frame, bpm, false);
+        let lead_in = before.last().map_or(0, |&(n, _)| n);
+        let beats: Vec<(usize, f64)> = before
+            .iter()
+            .rev()
+            .map(|&(n, frame)| (lead_in - n, frame))
+            .chain(after.iter().skip(1).map(|&(n, frame)| (lead_in + n, frame)))
+            .collect();
+        let mut grid = self
+            .fit_tempo_map(&beats, confidence)
+            .unwrap_or_else(|| BeatGrid::new(bpm, first_beat, self.sample_rate, confidence));
+
+        // 6. Find the downbeat and phrase starts from how the sound changes
+        let beat_bands = self.beat_bands(&grid, &bands);
+        let downbeat = Self::find_downbeat(&beat_bands);
+        let phrases = Self::find_phrases(&beat_bands, downbeat);
+        grid.set_bars(downbeat, phrases);
+
+        Some(grid)
     }
 
     /// Compute spectral flux onset detection function
     ///
     /// Spectral flux measures the change in magnitude spectrum between consecutive frames.
     /// Transients (kicks, snares) cause large positive flux values.
-    fn compute_onset_function(&self, samples: &[f32]) -> Vec<f32> {
+    /// Also returns each frame's log energy in [`ENERGY_BANDS`] bands.
+    fn compute_onset_function(&self, samples: &[f32]) -> (Vec<f32>, Vec<[f32; ENERGY_BANDS]>) {
         // Convert stereo to mono
         let mono: Vec<f32> = samples
             .chunks(2)
@@ -147,8 +448,18 @@ impl BeatGridAnalyzer {
             .collect();
 
         let mut onset_fn = Vec::new();
+        let mut bands = Vec::new();
         let mut prev_spectrum: Option<Vec<f32>> = None;
 
+        // Log-spaced band edges (in FFT bins) from 40Hz to 16kHz
+        let bin_hz = self.sample_rate as f32 / self.fft_size as f32;
+        let band_edges: Vec<usize> = (0..=ENERGY_BANDS)
+            .map(|b| {
+                let hz = 40.0 * (16000.0f32 / 40.0).powf(b as f32 / ENERGY_BANDS as f32);
+                ((hz / bin_hz) as usize).clamp(1, self.fft_size / 2)
+            })
+            .collect();
+
         let mut frame_start = 0;
         while frame_start + self.fft_size <= mono.len() {
             let frame = &mono[frame_start..frame_start + self.fft_size];
@@ -177,6 +488,13 @@ impl BeatGridAnalyzer {
                     .map(|(curr, prev)| (curr - prev).max(0.0))
                     .sum();
                 onset_fn.push(flux);
+
+                let mut energies = [0.0f32; ENERGY_BANDS];
+                for (energy, edges) in energies.iter_mut().zip(band_edges.windows(2)) {
+                    let power: f32 = spectrum[edges[0]..edges[1]].iter().map(|m| m * m).sum();
+                    *energy = (power + 1e-6).ln();
+                }
+                bands.push(energies);
             }
 
             prev_spectrum = Some(spectrum);
@@ -191,7 +509,7 @@ impl BeatGridAnalyzer {
             }
         }
 
-        onset_fn
+        (onset_fn, bands)
     }
 
     /// Find peaks in the onset detection function
@@ -437,8 +755,339 @@ impl BeatGridAnalyzer {
         raw_bpm
     }
 
-    /// Find the first downbeat by aligning a beat grid to the strongest onsets
-    fn find_first_downbeat(&self, onsets: &[usize], bpm: f32) -> u64 {
+    /// Follow the beat from a beat to the end (or, backwards, the start) of the track
+    ///
+    /// Each beat is expected one beat period after the previous one and
+    /// snapped to the strongest onset near there; the period slowly follows
+    /// the snapped beats, so the tracker stays on the beat through tempo
+    /// changes. Returns `(beats from the start beat, onset frame)` for every
+    /// beat that was found on an onset. Beats in quiet passages are skipped.
+    fn track_beats(
+        &self,
+        onset_fn: &[f32],
+        first_beat: usize,
+        bpm: f32,
+        backwards: bool,
+    ) -> Vec<(usize, f64)> {
+        let frames_per_second = self.sample_rate as f64 / self.hop_size as f64;
+        let base_period = frames_per_second * 60.0 / bpm as f64;
+        if base_period < 2.0 || first_beat >= onset_fn.len() {
+            return Vec::new();
+        }
+
+        // Same adaptive threshold as onset peak picking
+        let mean: f32 = onset_fn.iter().sum::<f32>() / onset_fn.len() as f32;
+        let variance: f32 =
+            onset_fn.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / onset_fn.len() as f32;
+        let threshold = (mean + 0.5 * variance.sqrt()).max(0.1);
+
+        let direction = if backwards { -1.0 } else { 1.0 };
+        let mut period = base_period;
+        let mut beat_frame = first_beat as f64;
+        let mut beats = vec![(0, beat_frame)];
+        let mut beat_num = 0;
+
+        loop {
+            beat_num += 1;
+            let expected = beat_frame + direction * period;
+            let radius = period * Self::TRACK_RADIUS;
+            if expected - radius < 1.0 || expected + radius + 1.0 >= onset_fn.len() as f64 {
+                break;
+            }
+            let lo = (expected - radius).ceil() as usize;
+            let hi = (expected + radius).floor() as usize;
+
+            // Strongest onset peak in the window, favoring ones close to the expected beat
+            let best = (lo..=hi)
+                .filter(|&i| {
+                    onset_fn[i] > threshold
+                        && onset_fn[i] > onset_fn[i - 1]
+                        && onset_fn[i] >= onset_fn[i + 1]
+                })
+                .map(|i| {
+                    let distance = (i as f64 - expected) / radius;
+                    (i, onset_fn[i] as f64 * (1.0 - 0.5 * distance * distance))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            match best {
+                Some((i, _)) => {
+                    // Parabolic interpolation for sub-frame accuracy
+                    let (y0, y1, y2) = (onset_fn[i - 1], onset_fn[i], onset_fn[i + 1]);
+                    let curvature = y0 - 2.0 * y1 + y2;
+                    let offset = if curvature < 0.0 {
+                        (0.5 * (y0 - y2) / curvature).clamp(-0.5, 0.5) as f64
+                    } else {
+                        0.0
+                    };
+                    let frame = i as f64 + offset;
+                    let interval = (frame - beat_frame) * direction;
+                    period += Self::TRACK_ADAPT * (interval - period);
+                    period = period.clamp(
+                        base_period * (1.0 - Self::TRACK_MAX_DRIFT),
+                        base_period * (1.0 + Self::TRACK_MAX_DRIFT),
+                    );
+                    beat_frame = frame;
+                    beats.push((beat_num, frame));
+                }
+                None => beat_frame = expected,
+            }
+        }
+
+        beats
+    }
+
+    /// Search window around each expected beat (fraction of a beat)
+    const TRACK_RADIUS: f64 = 0.1;
+    /// How quickly the tracked beat period follows the music
+    const TRACK_ADAPT: f64 = 0.1;
+    /// How far the tempo may wander from the detected BPM
+    const TRACK_MAX_DRIFT: f64 = 0.15;
+    /// Shortest tempo segment in beats
+    const MIN_SEGMENT_BEATS: usize = 16;
+    /// Consecutive beats off the grid that start a new tempo segment
+    const SEGMENT_SPLIT_RUN: usize = 4;
+    /// How far (in onset frames, ~12ms each) a beat may be off the grid
+    const SEGMENT_TOLERANCE: f64 = 2.0;
+
+    /// Fit a piecewise constant tempo map to tracked beats
+    ///
+    /// A segment grows while its beats stay on a straight line; once several
+    /// beats in a row fall off it the tempo changed and a new segment starts.
+    /// Neighbouring segments meet where their lines cross, so the grid stays
+    /// continuous.
+    fn fit_tempo_map(&self, beats: &[(usize, f64)], confidence: f32) -> Option<BeatGrid> {
+        let min_beats = Self::MIN_SEGMENT_BEATS + Self::SEGMENT_SPLIT_RUN;
+        if beats.len() < min_beats {
+            return None;
+        }
+
+        // Split the beats into runs at a steady tempo
+        let mut runs: Vec<&[(usize, f64)]> = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+        while end < beats.len() {
+            end += 1;
+            if end - start < min_beats {
+                continue;
+            }
+            let split = end - Self::SEGMENT_SPLIT_RUN;
+            let line = BeatLine::fit(&beats[start..split], None);
+            let errors: Vec<f64> = beats[split..end]
+                .iter()
+                .map(|&(b, f)| f - line.frame_at(b as f64))
+                .collect();
+            let late = errors.iter().all(|&e| e > Self::SEGMENT_TOLERANCE);
+            let early = errors.iter().all(|&e| e < -Self::SEGMENT_TOLERANCE);
+            if late || early {
+                runs.push(&beats[start..split]);
+                start = split;
+                end = split;
+            }
+        }
+        runs.push(&beats[start..]);
+
+        // Fit each run, starting where it meets the previous one
+        let mut lines = vec![BeatLine::fit(runs[0], None)];
+        for pair in runs.windows(2) {
+            let prev = lines[lines.len() - 1];
+            let last = pair[0][pair[0].len() - 1].0 as f64;
+            let first = pair[1][0].0 as f64;
+            let line = BeatLine::fit(pair[1], None);
+            let crossing = (line.frame_at(0.0) - prev.frame_at(0.0)) / (prev.period - line.period);
+            // The last few beats of a run may already be at the new tempo
+            let line = if crossing > prev.start_beat && crossing <= first {
+                line.starting_at(crossing)
+            } else {
+                // Lines too close to parallel to cross near the runs
+                let boundary = (last + first) / 2.0;
+                BeatLine::fit(pair[1], Some((boundary, prev.frame_at(boundary))))
+            };
+            lines.push(line);
+        }
+
+        if lines
+            .iter()
+            .any(|l| !(l.period > 0.0 && l.period.is_finite()))
+        {
+            return None;
+        }
+
+        // Main tempo: the segment covering the most beats
+        let frames_per_second = self.sample_rate as f64 / self.hop_size as f64;
+        let to_bpm = |line: &BeatLine| (frames_per_second * 60.0 / line.period) as f32;
+        let (_, main) = runs
+            .iter()
+            .zip(&lines)
+            .max_by_key(|(run, _)| run[run.len() - 1].0 - run[0].0)?;
+
+        let first_beat = self.frame_position(lines[0].frame_at(0.0)).max(0.0).round() as u64;
+        let tempo_map: Vec<(f64, f32)> = lines.iter().map(|l| (l.start_beat, to_bpm(l))).collect();
+        Some(BeatGrid::with_tempo_map(
+            to_bpm(main),
+            first_beat,
+            self.sample_rate,
+            confidence,
+            &tempo_map,
+        ))
+    }
+
+    /// Average band energies of every beat of the grid, from beat 0 on
+    fn beat_bands(
+        &self,
+        grid: &BeatGrid,
+        bands: &[[f32; ENERGY_BANDS]],
+    ) -> Vec<[f32; ENERGY_BANDS]> {
+        let frame_at = |beat: f64| self.position_frame(grid.position_for_beat(beat)).max(0.0);
+        let mut beat_bands = Vec::new();
+        let mut beat = 0.0;
+        loop {
+            let start = frame_at(beat).round() as usize;
+            let end = (frame_at(beat + 1.0).round() as usize).min(bands.len());
+            if end <= start {
+                break;
+            }
+            let mut mean = [0.0f32; ENERGY_BANDS];
+            for frame in &bands[start..end] {
+                for (m, e) in mean.iter_mut().zip(frame) {
+                    *m += e / (end - start) as f32;
+                }
+            }
+            beat_bands.push(mean);
+            beat += 1.0;
+        }
+        beat_bands
+    }
+
+    /// Find which beat of the bar is the downbeat (0 - 3)
+    ///
+    /// Bass notes, chords and new sounds tend to come in on the "1", so the
+    /// downbeat is the beat of the bar where the band energies rise most.
+    fn find_downbeat(beat_bands: &[[f32; ENERGY_BANDS]]) -> u32 {
+        let beats_per_bar = BeatGrid::BEATS_PER_BAR as usize;
+        let mut scores = vec![0.0f32; beats_per_bar];
+        for (beat, pair) in beat_bands.windows(2).enumerate() {
+            let rise: f32 = pair[1]
+                .iter()
+                .zip(&pair[0])
+                .enumerate()
+                .map(|(band, (curr, prev))| {
+                    // Bass changes mark bars more than hi-hats do
+                    let weight = if band < 2 { 2.0 } else { 1.0 };
+                    (curr - prev).max(0.0) * weight
+                })
+                .sum();
+            scores[(beat + 1) % beats_per_bar] += rise;
+        }
+        scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(beat, _)| beat as u32)
+    }
+
+    /// Bars either side of a bar compared for phrase boundaries
+    const PHRASE_CONTEXT_BARS: usize = 4;
+    /// Shortest phrase in bars
+    const MIN_PHRASE_BARS: usize = 8;
+
+    /// Find the bars where phrases start
+    ///
+    /// A phrase starts where the sound of the next few bars differs most
+    /// from the previous few (a breakdown, a drop, a new part coming in).
+    /// Starts land on the 4-bar grid when they are near it, since phrases
+    /// almost always come in multiples of four bars.
+    fn find_phrases(beat_bands: &[[f32; ENERGY_BANDS]], downbeat: u32) -> Vec<u32> {
+        let beats_per_bar = BeatGrid::BEATS_PER_BAR as usize;
+        let bars: Vec<[f32; ENERGY_BANDS]> = beat_bands
+            .get(downbeat as usize..)
+            .unwrap_or_default()
+            .chunks_exact(beats_per_bar)
+            .map(|beats| {
+                let mut mean = [0.0f32; ENERGY_BANDS];
+                for beat in beats {
+                    for (m, e) in mean.iter_mut().zip(beat) {
+                        *m += e / beats_per_bar as f32;
+                    }
+                }
+                mean
+            })
+            .collect();
+
+        let context = Self::PHRASE_CONTEXT_BARS;
+        if bars.len() < context * 2 {
+            return vec![0];
+        }
+        let mean_of = |bars: &[[f32; ENERGY_BANDS]]| {
+            let mut mean = [0.0f32; ENERGY_BANDS];
+            for bar in bars {
+                for (m, e) in mean.iter_mut().zip(bar) {
+                    *m += e / bars.len() as f32;
+                }
+            }
+            mean
+        };
+        let novelty: Vec<f32> = (0..bars.len())
+            .map(|bar| {
+                if bar < context / 2 || bar + context / 2 > bars.len() {
+                    return 0.0;
+                }
+                let before = mean_of(&bars[bar.saturating_sub(context)..bar]);
+                let after = mean_of(&bars[bar..(bar + context).min(bars.len())]);
+                before.iter().zip(&after).map(|(a, b)| (a - b).abs()).sum()
+            })
+            .collect();
+
+        let mean = novelty.iter().sum::<f32>() / novelty.len() as f32;
+        let variance =
+            novelty.iter().map(|n| (n - mean).powi(2)).sum::<f32>() / novelty.len() as f32;
+        let threshold = mean + 0.5 * variance.sqrt();
+
+        // Strongest boundaries first, at least a phrase apart
+        let mut candidates: Vec<usize> = (1..bars.len())
+            .filter(|&bar| {
+                let lo = bar.saturating_sub(context);
+                let hi = (bar + context + 1).min(bars.len());
+                novelty[bar] > threshold && novelty[lo..hi].iter().all(|&n| n <= novelty[bar])
+            })
+            .collect();
+        candidates.sort_by(|a, b| novelty[*b].total_cmp(&novelty[*a]));
+
+        let mut phrases = vec![0];
+        for bar in candidates {
+            let on_grid = (bar + 2) / 4 * 4;
+            let bar = if bar.abs_diff(on_grid) <= 1 {
+                on_grid
+            } else {
+                bar
+            };
+            if phrases
+                .iter()
+                .all(|&p| bar.abs_diff(p as usize) >= Self::MIN_PHRASE_BARS)
+            {
+                phrases.push(bar as u32);
+            }
+        }
+        phrases.sort_unstable();
+        phrases
+    }
+
+    /// Onset function frame at a sample position (inverse of `frame_position`)
+    fn position_frame(&self, position: f64) -> f64 {
+        (position / 2.0 - (self.hop_size + self.fft_size / 2) as f64) / self.hop_size as f64
+    }
+
+    /// Sample position (stereo interleaved) of an onset function frame
+    ///
+    /// Frame `i` compares the FFT windows starting `i` and `i + 1` hops in,
+    /// so an onset there sits around the middle of the later window.
+    fn frame_position(&self, frame: f64) -> f64 {
+        (frame * self.hop_size as f64 + (self.hop_size + self.fft_size / 2) as f64) * 2.0
+    }
+
+    /// Find the first downbeat (as an onset function frame) by aligning a beat
+    /// grid to the strongest onsets
+    fn find_first_downbeat(&self, onsets: &[usize], bpm: f32) -> usize {
         if onsets.is_empty() {
             return 0;
         }
@@ -481,8 +1130,60 @@ impl BeatGridAnalyzer {
             }
         }
 
-        // Convert from onset function frame index to sample position (stereo)
-        best_onset as u64 * self.hop_size as u64 * 2
+        best_onset
+    }
+}
+
+/// Straight line through tracked beats: onset frame as a function of beat number
+#[derive(Debug, Clone, Copy)]
+struct BeatLine {
+    /// Beat number the line starts at
+    start_beat: f64,
+    /// Onset frame of that beat
+    start_frame: f64,
+    /// Onset frames per beat
+    period: f64,
+}
+
+impl BeatLine {
+    /// Least-squares fit through `beats`, through `anchor` `(beat, frame)` if given
+    fn fit(beats: &[(usize, f64)], anchor: Option<(f64, f64)>) -> Self {
+        let n = beats.len().max(1) as f64;
+        let (start_beat, start_frame) = anchor.unwrap_or_else(|| {
+            // Free fit through the mean
+            (
+                beats.iter().map(|&(b, _)| b as f64).sum::<f64>() / n,
+                beats.iter().map(|&(_, f)| f).sum::<f64>() / n,
+            )
+        });
+        let (mut sxy, mut sxx) = (0.0, 0.0);
+        for &(b, f) in beats {
+            let dx = b as f64 - start_beat;
+            sxy += dx * (f - start_frame);
+            sxx += dx * dx;
+        }
+        let line = Self {
+            start_beat,
+            start_frame,
+            period: if sxx > 0.0 { sxy / sxx } else { 0.0 },
+        };
+        match anchor {
+            Some(_) => line,
+            None => line.starting_at(beats.first().map_or(0.0, |&(b, _)| b as f64)),
+        }
+    }
+
+    fn frame_at(&self, beat: f64) -> f64 {
+        self.start_frame + (beat - self.start_beat) * self.period
+    }
+
+    /// Same line, starting at another beat
+    fn starting_at(self, beat: f64) -> Self {
+        Self {
+            start_beat: beat,
+            start_frame: self.frame_at(beat),
+            ..self
+        }
     }
 }
 
@@ -514,6 +1215,181 @@ mod tests {
         assert!((grid.beat_at_position(offset as f64) - 0.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_tempo_map_positions() {
+        // 120 BPM for 8 beats, then 128 BPM
+        let grid = BeatGrid::with_tempo_map(120.0, 1000, 44100, 1.0, &[(0.0, 120.0), (8.0, 128.0)]);
+        let beat_120 = 44100.0;
+        let beat_128 = 60.0 / 128.0 * 44100.0 * 2.0;
+
+        let change = 1000.0 + 8.0 * beat_120;
+        assert!((grid.position_for_beat(8.0) - change).abs() < 1e-6);
+        assert!((grid.position_for_beat(10.0) - (change + 2.0 * beat_128)).abs() < 1e-6);
+        assert!((grid.beat_at_position(change + 0.5 * beat_128) - 8.5).abs() < 1e-9);
+        assert!((grid.phase_at_position(change + 3.25 * beat_128) - 0.25).abs() < 1e-4);
+        assert_eq!(grid.bpm_at_position(change - 1.0), 120.0);
+        assert_eq!(grid.bpm_at_position(change), 128.0);
+
+        for beat in [-2.0, 0.0, 3.7, 8.0, 21.3] {
+            let position = grid.position_for_beat(beat);
+            assert!((grid.beat_at_position(position) - beat).abs() < 1e-9);
+        }
+    }
+
+    /// Interleaved stereo clicks at the given times (seconds)
+    fn click_track(clicks: &[f64], secs: f64, sample_rate: u32) -> Vec<f32> {
+        let mut samples = vec![0.0f32; (secs * sample_rate as f64) as usize * 2];
+        for &t in clicks {
+            let start = (t * sample_rate as f64) as usize;
+            for i in 0..(sample_rate as usize / 100) {
+                let Some(frame) = samples.get_mut((start + i) * 2..(start + i) * 2 + 2) else {
+                    break;
+                };
+                let v = (i as f32 * 0.9).sin() * (-(i as f32) / 60.0).exp();
+                frame.fill(v);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_analyzer_follows_tempo_change() {
+        // 40 beats at 120 BPM, then 128 BPM to the end
+        let sample_rate = 22050;
+        let mut clicks = Vec::new();
+        let mut t = 0.5;
+        while t < 60.0 {
+            clicks.push(t);
+            t += if clicks.len() <= 40 {
+                0.5
+            } else {
+                60.0 / 128.0
+            };
+        }
+        let samples = click_track(&clicks, 60.0, sample_rate);
+
+        let grid = BeatGridAnalyzer::new(sample_rate)
+            .analyze(&samples)
+            .expect("click track has a grid");
+        let at = |secs: f64| secs * sample_rate as f64 * 2.0;
+        assert!(grid.segments().len() >= 2);
+        assert!((grid.bpm_at_position(at(10.0)) - 120.0).abs() < 0.5);
+        assert!((grid.bpm_at_position(at(50.0)) - 128.0).abs() < 0.5);
+
+        // Late clicks still land on the grid
+        for &click in clicks.iter().rev().take(8) {
+            let phase = grid.phase_at_position(at(click));
+            assert!(
+                phase.min(1.0 - phase) < 0.05,
+                "click at {click}s off by {phase}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bars_from_downbeat() {
+        let mut grid = BeatGrid::new(120.0, 0, 44100, 1.0);
+        grid.set_bars(2, vec![8, 0]);
+        let beat = grid.samples_per_beat();
+
+        assert_eq!(grid.phrases(), &[0, 8]);
+        assert_eq!(grid.beat_in_bar(2.0 * beat), 0);
+        assert_eq!(grid.beat_in_bar(5.5 * beat), 3);
+        assert_eq!(grid.beat_in_bar(1.0 * beat), 3);
+        assert!((grid.bar_at_position(10.0 * beat) - 2.0).abs() < 1e-9);
+        assert!((grid.bar_phase_at_position(3.0 * beat) - 0.25).abs() < 1e-6);
+        assert!((grid.bar_phase_at_position(0.0) - 0.5).abs() < 1e-6);
+        assert!((grid.position_for_bar(1.0) - 6.0 * beat).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_grid_edits() {
+        let mut grid = BeatGrid::new(87.0, 44100, 44100, 1.0);
+        grid.set_bars(1, vec![0, 16]);
+
+        // Half-time detection doubled, the first beat stays put
+        grid.scale_tempo(2.0);
+        assert_eq!(grid.bpm, 174.0);
+        assert_eq!(grid.first_beat_offset, 44100);
+        assert_eq!(grid.downbeat, 2);
+        assert_eq!(grid.phrases(), &[0, 32]);
+
+        // Shifting earlier than the track start renumbers from a later bar
+        let beat = grid.samples_per_beat();
+        grid.shift(-44100.0);
+        assert_eq!(grid.first_beat_offset, 0);
+        grid.shift(-0.5 * beat);
+        assert!((grid.first_beat_offset as f64 - 3.5 * beat).abs() < 1.0);
+        assert_eq!(grid.downbeat, 2);
+        assert_eq!(grid.phrases(), &[31]);
+
+        // The playhead becomes a downbeat on the nearest beat
+        grid.set_downbeat_at(5.2 * beat);
+        assert!((grid.beat_at_position(5.2 * beat) - 2.0).abs() < 1e-3);
+        assert_eq!(grid.beat_in_bar(5.2 * beat), 0);
+
+        grid.set_bpm(170.0);
+        assert!((grid.bpm - 170.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_analyzer_finds_downbeat_and_phrases() {
+        // 120 BPM clicks, bass on every 4th beat from beat 1 and hats in
+        // bars 8-15 and 24-31
+        let sample_rate = 22050;
+        let beat_secs = 0.5;
+        let first = 0.5;
+        let bars = 36;
+        let clicks: Vec<f64> = (0..bars * 4)
+            .map(|b| first + b as f64 * beat_secs)
+            .collect();
+        let mut samples = click_track(&clicks, first + bars as f64 * 2.0 + 1.0, sample_rate);
+        let frame_at = |t: f64| (t * sample_rate as f64) as usize;
+        for (b, &t) in clicks.iter().enumerate() {
+            let start = frame_at(t);
+            if b % 4 == 1 {
+                for i in 0..frame_at(beat_secs) {
+                    let v = (2.0 * PI * 55.0 * i as f32 / sample_rate as f32).sin() * 0.5;
+                    samples[(start + i) * 2] += v;
+                    samples[(start + i) * 2 + 1] += v;
+                }
+            }
+            let bar = b.saturating_sub(1) / 4;
+            if (8..16).contains(&bar) || (24..32).contains(&bar) {
+                let hat = start + frame_at(beat_secs / 2.0);
+                for i in 0..400 {
+                    let v = ((i * 7919 + b * 31) % 97) as f32 / 97.0 - 0.5;
+                    samples[(hat + i) * 2] += v * 0.4;
+                    samples[(hat + i) * 2 + 1] += v * 0.4;
+                }
+            }
+        }
+
+        let grid = BeatGridAnalyzer::new(sample_rate)
+            .analyze(&samples)
+            .expect("click track has a grid");
+        let downbeat = grid.position_for_bar(0.0) / 2.0 / sample_rate as f64;
+        assert!(
+            (downbeat - clicks[1]).abs() < 0.05,
+            "downbeat at {downbeat}s"
+        );
+        for phrase in [8.0, 16.0, 24.0] {
+            let secs = clicks[1] + phrase * 4.0 * beat_secs;
+            assert!(
+                grid.phrases()
+                    .iter()
+                    .any(
+                        |&bar| (grid.position_for_bar(bar as f64) / 2.0 / sample_rate as f64
+                            - secs)
+                            .abs()
+                            < 0.1
+                    ),
+                "no phrase at {secs}s: {:?}",
+                grid.phrases()
+            );
+        }
+    }
+
     #[test]
     fn test_tempo_adjustment() {
         let grid = BeatGrid::new(120.0, 0, 44100, 1.0);
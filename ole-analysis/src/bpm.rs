@@ -6,6 +6,7 @@ use std::collections::VecDeque;
 ///
 /// Note: This is a legacy fallback detector. For accurate beat detection,
 /// prefer using `BeatGridAnalyzer` which uses spectral flux analysis.
+#[derive(Debug)]
 pub struct BpmDetector {
     sample_rate: u32,
     energy_history: VecDeque<f32>,
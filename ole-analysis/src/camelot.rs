@@ -113,6 +113,16 @@ impl MusicalKey {
             _ => unreachable!(),
         }
     }
+
+    /// Transpose by a number of semitones (negative = down), keeping the mode
+    pub fn transpose(&self, semitones: i32) -> Self {
+        let pc = (self.root_pitch_class() as i32 + semitones).rem_euclid(12) as u8;
+        if self.is_major() {
+            Self::major_from_pitch_class(pc)
+        } else {
+            Self::minor_from_pitch_class(pc)
+        }
+    }
 }
 
 impl fmt::Display for MusicalKey {
@@ -321,9 +331,16 @@ impl CamelotKey {
         }
     }
 
+    /// Transpose by a number of semitones (negative = down)
+    ///
+    /// One semitone up moves 7 steps around the wheel (e.g., 8A +1 = 3A).
+    pub fn transpose(&self, semitones: i32) -> Self {
+        Self::from_musical_key(self.to_musical_key().transpose(semitones))
+    }
+
     /// Get display string (e.g., "8A", "12B")
     pub fn display(&self) -> String {
-        format!("{}{}", self.number, if self.is_major { 'B' } else { 'A' })
+        self.to_string()
     }
 
     /// Parse from string (e.g., "8A", "12B")
@@ -450,7 +467,12 @@ impl CamelotKey {
 
 impl fmt::Display for CamelotKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.display())
+        write!(
+            f,
+            "{}{}",
+            self.number,
+            if self.is_major { 'B' } else { 'A' }
+        )
     }
 }
 
@@ -682,4 +704,20 @@ mod tests {
             assert_eq!(key, back);
         }
     }
+
+    #[test]
+    fn test_transpose() {
+        assert_eq!(MusicalKey::CMajor.transpose(7), MusicalKey::GMajor);
+        assert_eq!(MusicalKey::AMinor.transpose(-12), MusicalKey::AMinor);
+        assert_eq!(MusicalKey::CMinor.transpose(-1), MusicalKey::BMinor);
+
+        let am = CamelotKey::parse("8A").unwrap();
+        assert_eq!(am.transpose(7).display(), "9A"); // Am -> Em
+        assert_eq!(am.transpose(1).display(), "3A"); // Am -> Bbm
+        assert_eq!(am.transpose(-7).display(), "7A"); // Am -> Dm
+        assert_eq!(am.transpose(0), am);
+
+        let c = CamelotKey::parse("8B").unwrap();
+        assert_eq!(c.transpose(7).display(), "9B"); // C -> G
+    }
 }
@@ -403,7 +403,7 @@ mod tests {
 
     #[test]
     fn test_analyze_insufficient_audio() {
-        let analyzer = KeyAnalyzer::new(44100);
+        let mut analyzer = KeyAnalyzer::new(44100);
 
         // Too short
         let samples = vec![0.0f32; 1000];
@@ -412,7 +412,7 @@ mod tests {
 
     #[test]
     fn test_analyze_silence() {
-        let analyzer = KeyAnalyzer::new(44100);
+        let mut analyzer = KeyAnalyzer::new(44100);
 
         // Silence should return None or low confidence
         let samples = vec![0.0f32; 44100 * 4]; // 2 seconds stereo
@@ -441,7 +441,7 @@ mod tests {
     #[test]
     fn test_detect_pure_c() {
         let sample_rate = 44100;
-        let analyzer = KeyAnalyzer::new(sample_rate);
+        let mut analyzer = KeyAnalyzer::new(sample_rate);
 
         // Generate C major chord (C4 + E4 + G4) for 2 seconds
         let duration_samples = sample_rate as usize * 2 * 2; // 2 sec stereo
@@ -10,7 +10,7 @@ mod key;
 mod spectrum;
 mod waveform;
 
-pub use beatgrid::{BeatGrid, BeatGridAnalyzer};
+pub use beatgrid::{BeatGrid, BeatGridAnalyzer, TempoSegment};
 pub use bpm::BpmDetector;
 pub use camelot::{CamelotKey, MusicalKey};
 pub use key::{DetectedKey, KeyAnalyzer};
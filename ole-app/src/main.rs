@@ -8,10 +8,16 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb, Obs};
 
-use ole_audio::{AudioCommand, AudioEngine, AudioEvent, EngineState};
+use ole_audio::{
+    output, triple_buffer, AudioCommand, AudioEngine, AudioEvent, DeckGrids, EngineState,
+    OutputInfo, OutputSettings, Retired,
+};
 use ole_gui::OleApp;
+use ole_library::Config;
 
 fn main() -> anyhow::Result<()> {
     // Initialize logging
@@ -60,129 +66,532 @@ fn main() -> anyhow::Result<()> {
     result.map_err(|e| anyhow::anyhow!("eframe error: {}", e))
 }
 
+/// Time between attempts to reopen a lost output (and checks whether
+/// a missing configured device is back)
+const OUTPUT_RETRY: Duration = Duration::from_secs(2);
+
 fn run_audio_thread(
     cmd_rx: Receiver<AudioCommand>,
-    evt_tx: crossbeam_channel::Sender<AudioEvent>,
+    evt_tx: Sender<AudioEvent>,
     shutdown: Arc<AtomicBool>,
 ) {
-    // Get audio host and device
-    let host = cpal::default_host();
-    let device = match host.default_output_device() {
-        Some(d) => d,
-        None => {
-            let _ = evt_tx.send(AudioEvent::Error("No audio output device found".into()));
-            return;
-        }
+    let config = Config::load();
+    let cue_device = config.cue_device;
+    let mut settings = OutputSettings {
+        host: config.audio.host,
+        device: config.audio.device,
+        sample_rate: config.audio.sample_rate,
+        buffer_size: config.audio.buffer_size,
     };
 
-    let config = match device.default_output_config() {
-        Ok(c) => c,
-        Err(e) => {
-            let _ = evt_tx.send(AudioEvent::Error(format!(
-                "Failed to get audio config: {}",
-                e
-            )));
-            return;
+    // The engine lives in the audio callback: commands reach it through a
+    // lock-free queue drained at the start of each block, and its state
+    // comes back through a triple buffer, so the audio path never locks.
+    // While no stream runs (the device is gone or being switched) the
+    // callback state is parked here instead.
+    let mut sample_rate = output::find_output(&settings)
+        .and_then(|(_, device)| output::stream_config(&device, &settings, None))
+        .map_or(48000, |config| config.sample_rate.0);
+    let mut engine = EngineState::new(sample_rate);
+    // Replaced tracks (and the like) come back here to be freed
+    let (retired_tx, mut retired_rx) = HeapRb::<Retired>::new(64).split();
+    engine.set_retired(retired_tx);
+    let (state_tx, state_rx) = triple_buffer(engine.get_state());
+    // Beat grids as last sent, for working out grid corrections here
+    let mut grids = DeckGrids::default();
+    let (mut queue_tx, queue_rx) = HeapRb::<AudioCommand>::new(1024).split();
+    let mut parked = Some(CallbackState::new(engine, queue_rx, state_tx));
+    let (tx, rx) = unbounded();
+    let home = Home { tx, rx };
+    let (error_tx, error_rx) = unbounded();
+
+    let mut output: Option<Output> = None;
+    let mut generation = 0;
+    let mut reopen_at = Instant::now();
+    let mut recheck_at = Instant::now();
+    let mut last_failure: Option<String> = None;
+
+    // Command processing loop: slow work happens here, off the audio thread
+    while !shutdown.load(Ordering::Acquire) {
+        // (Re)build the stream when there is none
+        if output.is_none() && Instant::now() >= reopen_at {
+            generation += 1;
+            match open_output(
+                &settings,
+                cue_device.as_deref(),
+                &mut parked,
+                &home,
+                generation,
+                &error_tx,
+                &evt_tx,
+            ) {
+                Ok((opened, info)) => {
+                    last_failure = None;
+                    sample_rate = info.sample_rate;
+                    output = Some(opened);
+                    let _ = evt_tx.try_send(AudioEvent::OutputOpened(info));
+                }
+                Err(e) => {
+                    // Report each failure once, not on every retry
+                    if last_failure.as_ref() != Some(&e) {
+                        let _ = evt_tx.try_send(AudioEvent::Error(e.clone()));
+                        last_failure = Some(e);
+                    }
+                    reopen_at = Instant::now() + OUTPUT_RETRY;
+                }
+            }
+            if output.is_none() && parked.is_none() {
+                let _ = evt_tx.send(AudioEvent::Error("Audio engine lost".into()));
+                return;
+            }
+        }
+
+        let mut close = false;
+
+        // A stream error usually means the device went away: rebuild, or
+        // for the cue device stop feeding it until outputs are listed or set
+        while let Ok((stream, role, e)) = error_rx.try_recv() {
+            let Some(current) = output.as_mut().filter(|_| stream == generation && !close) else {
+                continue;
+            };
+            match role {
+                StreamRole::Main => {
+                    let _ = evt_tx.try_send(AudioEvent::OutputLost(e));
+                    close = true;
+                }
+                StreamRole::Cue => {
+                    if current.cue_stream.take().is_some() {
+                        let _ = evt_tx.try_send(AudioEvent::Error(format!(
+                            "Cue device lost ({}), list or set outputs to reopen it",
+                            e
+                        )));
+                    }
+                }
+            }
+        }
+
+        // Playing on the default device: switch back once the configured one returns
+        if output.as_ref().is_some_and(|o| o.fallback) && Instant::now() >= recheck_at {
+            recheck_at = Instant::now() + OUTPUT_RETRY;
+            close |= output::find_output(&settings).is_ok();
+        }
+
+        match cmd_rx.recv_timeout(Duration::from_millis(10)) {
+            Ok(AudioCommand::Shutdown) => break,
+            Ok(AudioCommand::ListOutputs) => {
+                let _ = evt_tx.try_send(AudioEvent::Outputs(output::list_outputs()));
+                close |= output.as_ref().is_some_and(|o| o.cue_lost());
+            }
+            Ok(AudioCommand::SetOutput(new_settings)) => {
+                settings = new_settings;
+                last_failure = None;
+                close = true;
+            }
+            Ok(cmd) => match cmd.prepare(sample_rate, &mut grids) {
+                Ok(cmd) => match parked.as_mut() {
+                    Some(state) => state.engine.handle_command(cmd),
+                    None => push_command(&mut queue_tx, cmd),
+                },
+                Err(e) => {
+                    let _ = evt_tx.try_send(AudioEvent::Error(e));
+                }
+            },
+            Err(_) => {}
         }
-    };
 
-    let sample_rate = config.sample_rate().0;
-    let channels = config.channels() as usize;
+        if close {
+            if let Some(closed) = output.take() {
+                // The stream drops its callback as it closes, sending the state home
+                drop(closed);
+                parked = home.take_back();
+                if parked.is_none() {
+                    let _ = evt_tx.send(AudioEvent::Error("Audio engine lost".into()));
+                    return;
+                }
+            }
+            reopen_at = Instant::now();
+        }
+
+        // Parked: keep the UI fed from here
+        if let Some(state) = parked.as_mut() {
+            state.drain_commands();
+            state.publish_state();
+        }
+
+        // Free what the engine let go of
+        while retired_rx.try_pop().is_some() {}
 
-    // Create engine state
-    let engine_state = Arc::new(std::sync::Mutex::new(EngineState::new(sample_rate)));
-    let engine_for_callback = engine_state.clone();
+        // Forward state updates as they are published
+        if state_rx.updated() {
+            let state = state_rx.read();
+            grids.follow(&state);
+            let _ = evt_tx.try_send(state);
+        }
+    }
 
-    // Pre-allocate mono conversion buffer (avoid allocation in audio callback)
+    // Close the stream, then finish a recording still running before the app exits
+    if let Some(closed) = output.take() {
+        drop(closed);
+        parked = home.take_back();
+    }
+    if let Some(mut state) = parked {
+        state.engine.finish_recording();
+    }
+}
+/// Everything the audio callback owns
+///
+/// Moves into each new stream's callback and comes back when that stream
+/// closes (see `Homing`), so the engine outlives device changes.
+struct CallbackState {
+    engine: EngineState,
+    commands: HeapCons<AudioCommand>,
+    state_tx: triple_buffer::Input<AudioEvent>,
+    /// Feed for a second output device playing the headphone cue
+    cue_tx: Option<HeapProd<f32>>,
+    // Pre-allocated mono conversion and cue buffers (avoid allocation in audio callback)
     // Size for up to 32768 mono samples -> 65536 stereo samples
-    let mut mono_conversion_buffer = vec![0.0f32; 65536];
+    mono_conversion_buffer: Vec<f32>,
+    cue_buffer: Vec<f32>,
+    frames_since_state: usize,
+}
 
-    // State update interval
-    let mut last_state_update = Instant::now();
-    let state_update_interval = Duration::from_millis(33); // ~30fps
+impl CallbackState {
+    fn new(
+        engine: EngineState,
+        commands: HeapCons<AudioCommand>,
+        state_tx: triple_buffer::Input<AudioEvent>,
+    ) -> Self {
+        Self {
+            engine,
+            commands,
+            state_tx,
+            cue_tx: None,
+            mono_conversion_buffer: vec![0.0f32; 65536],
+            cue_buffer: vec![0.0f32; 65536],
+            frames_since_state: 0,
+        }
+    }
 
-    // Build audio stream
-    let stream = device.build_output_stream(
-        &config.into(),
-        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-            if let Ok(mut state) = engine_for_callback.try_lock() {
-                if channels == 2 {
-                    state.process(data);
-                } else if channels == 1 {
-                    let stereo_len = data.len() * 2;
-                    if stereo_len <= mono_conversion_buffer.len() {
-                        let stereo = &mut mono_conversion_buffer[..stereo_len];
-                        stereo.fill(0.0);
-                        state.process(stereo);
-                        for (i, sample) in data.iter_mut().enumerate() {
-                            *sample = (stereo[i * 2] + stereo[i * 2 + 1]) * 0.5;
-                        }
-                    } else {
-                        data.fill(0.0);
-                    }
-                } else {
-                    // Multi-channel (>2): process stereo, copy to first 2 channels, silence rest
-                    let frames = data.len() / channels;
-                    let stereo_len = frames * 2;
-                    if stereo_len <= mono_conversion_buffer.len() {
-                        let stereo = &mut mono_conversion_buffer[..stereo_len];
-                        stereo.fill(0.0);
-                        state.process(stereo);
-                        for f in 0..frames {
-                            data[f * channels] = stereo[f * 2];
-                            data[f * channels + 1] = stereo[f * 2 + 1];
-                            for ch in 2..channels {
-                                data[f * channels + ch] = 0.0;
-                            }
-                        }
-                    } else {
-                        data.fill(0.0);
+    /// Apply the commands queued for the engine
+    fn drain_commands(&mut self) {
+        while let Some(cmd) = self.commands.try_pop() {
+            self.engine.handle_command(cmd);
+        }
+    }
+
+    /// Publish the engine state for the UI (updated in place, so the
+    /// callback neither allocates nor frees)
+    fn publish_state(&mut self) {
+        self.frames_since_state = 0;
+        let engine = &mut self.engine;
+        self.state_tx.write_with(|event| engine.write_state(event));
+    }
+
+    /// Fill one device buffer of `channels` interleaved channels
+    fn render(&mut self, data: &mut [f32], channels: usize, cue_on_channels: bool) {
+        self.drain_commands();
+        // State update interval (~30fps)
+        self.frames_since_state += data.len() / channels;
+        if self.frames_since_state >= self.engine.sample_rate() as usize / 30 {
+            self.publish_state();
+        }
+
+        let stereo_len = data.len() / channels * 2;
+        if stereo_len > self.cue_buffer.len() {
+            data.fill(0.0);
+            return;
+        }
+        let engine = &mut self.engine;
+        let cue = &mut self.cue_buffer[..stereo_len];
+        let wants_cue = cue_on_channels || self.cue_tx.is_some();
+        if channels == 2 {
+            engine.process(data, wants_cue.then_some(cue));
+        } else if channels == 1 {
+            if stereo_len <= self.mono_conversion_buffer.len() {
+                let stereo = &mut self.mono_conversion_buffer[..stereo_len];
+                stereo.fill(0.0);
+                engine.process(stereo, wants_cue.then_some(&mut *cue));
+                for (i, sample) in data.iter_mut().enumerate() {
+                    *sample = (stereo[i * 2] + stereo[i * 2 + 1]) * 0.5;
+                }
+            } else {
+                data.fill(0.0);
+            }
+        } else {
+            // Multi-channel (>2): process stereo, copy to first 2 channels,
+            // the headphone cue to channels 3/4 and silence the rest
+            let frames = data.len() / channels;
+            if stereo_len <= self.mono_conversion_buffer.len() {
+                let stereo = &mut self.mono_conversion_buffer[..stereo_len];
+                stereo.fill(0.0);
+                engine.process(stereo, wants_cue.then_some(&mut *cue));
+                for f in 0..frames {
+                    data[f * channels] = stereo[f * 2];
+                    data[f * channels + 1] = stereo[f * 2 + 1];
+                    for ch in 2..channels {
+                        data[f * channels + ch] = match ch {
+                            2 | 3 if cue_on_channels => cue[f * 2 + ch - 2],
+                            _ => 0.0,
+                        };
                     }
                 }
             } else {
                 data.fill(0.0);
             }
-        },
-        |err| {
-            eprintln!("Audio stream error: {}", err);
-        },
-        None,
-    );
+        }
+        // Second device: hand the cue over (dropped if that device falls
+        // behind), until its stream is gone
+        match self.cue_tx {
+            Some(ref mut tx) if tx.read_is_held() => {
+                tx.push_slice(&self.cue_buffer[..stereo_len]);
+            }
+            Some(_) => self.cue_tx = None,
+            None => {}
+        }
+    }
+}
 
-    let stream = match stream {
-        Ok(s) => s,
-        Err(e) => {
-            let _ = evt_tx.send(AudioEvent::Error(format!(
-                "Failed to create audio stream: {}",
-                e
-            )));
-            return;
+/// Callback state on loan to a stream
+///
+/// Sent back home when the stream drops its callback, whether the stream
+/// was closed or failed to build.
+struct Homing {
+    state: Option<CallbackState>,
+    home: Sender<CallbackState>,
+}
+
+impl Drop for Homing {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            let _ = self.home.send(state);
         }
-    };
+    }
+}
+
+/// Where callback state goes while no stream holds it
+struct Home {
+    tx: Sender<CallbackState>,
+    rx: Receiver<CallbackState>,
+}
 
-    if let Err(e) = stream.play() {
-        let _ = evt_tx.send(AudioEvent::Error(format!("Failed to start audio: {}", e)));
-        return;
+impl Home {
+    /// Wait for the state of a dropped callback
+    fn take_back(&self) -> Option<CallbackState> {
+        self.rx.recv_timeout(Duration::from_secs(2)).ok()
     }
+}
 
-    // Command processing loop
-    while !shutdown.load(Ordering::Acquire) {
-        match cmd_rx.recv_timeout(Duration::from_millis(10)) {
-            Ok(AudioCommand::Shutdown) => break,
-            Ok(cmd) => {
-                if let Ok(mut state) = engine_state.lock() {
-                    state.handle_command(cmd);
+/// Which of an output's streams reported an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamRole {
+    Main,
+    Cue,
+}
+
+/// A running output stream
+struct Output {
+    _stream: cpal::Stream,
+    /// Second device playing the headphone cue (dropped when it fails)
+    cue_stream: Option<cpal::Stream>,
+    /// Keeps the cue ring alive until this output closes, so the callback
+    /// letting go of its feed never frees it on the audio thread
+    cue_ring: Option<Obs<Arc<HeapRb<f32>>>>,
+    /// Playing on the default device because the configured one is missing
+    fallback: bool,
+}
+
+impl Output {
+    /// Whether the cue device was opened and has failed since
+    fn cue_lost(&self) -> bool {
+        self.cue_ring.is_some() && self.cue_stream.is_none()
+    }
+}
+
+/// Open the output `settings` name and move the callback state into it
+///
+/// Falls back to the default device when the configured one is missing.
+/// Stream errors (the cue device's too) are sent to `errors` tagged with
+/// `generation` and the stream's role. On failure
+/// the callback state is left in (or comes back to) `parked`.
+fn open_output(
+    settings: &OutputSettings,
+    cue_device: Option<&str>,
+    parked: &mut Option<CallbackState>,
+    home: &Home,
+    generation: u64,
+    errors: &Sender<(u64, StreamRole, String)>,
+    events: &Sender<AudioEvent>,
+) -> Result<(Output, OutputInfo), String> {
+    let (host, device, fallback) = match output::find_output(settings) {
+        Ok((host, device)) => (host, device, false),
+        Err(e) if settings.host.is_some() || settings.device.is_some() => {
+            match output::find_output(&settings.default_device()) {
+                Ok((host, device)) => (host, device, true),
+                Err(_) => return Err(e),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    let Some(state) = parked.as_mut() else {
+        return Err("Audio engine lost".into());
+    };
+    let config = output::stream_config(&device, settings, Some(state.engine.sample_rate()))?;
+    let sample_rate = config.sample_rate.0;
+    let channels = config.channels as usize;
+    let engine_restarted = sample_rate != state.engine.sample_rate();
+    if engine_restarted {
+        state.engine.reset_sample_rate(sample_rate);
+    }
+
+    // Headphone cue: channels 3/4 of a multichannel device, or a second device
+    let cue_on_channels = channels >= 4;
+    let (cue_stream, cue_tx) = match cue_device {
+        Some(name) if !cue_on_channels => {
+            let cue_errors = errors.clone();
+            let on_error = move |err: String| {
+                let _ = cue_errors.send((generation, StreamRole::Cue, err));
+            };
+            match open_cue_device(&host, name, sample_rate, on_error) {
+                Ok((stream, tx)) => (Some(stream), Some(tx)),
+                Err(e) => {
+                    let _ = events.try_send(AudioEvent::Error(format!("Cue device: {}", e)));
+                    (None, None)
                 }
             }
-            Err(_) => {}
         }
+        _ => (None, None),
+    };
+    let cue_ring = cue_tx.as_ref().map(|tx| tx.observe());
+    state.cue_tx = cue_tx;
+
+    // Build audio stream
+    let mut homing = Homing {
+        state: parked.take(),
+        home: home.tx.clone(),
+    };
+    let errors = errors.clone();
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| match homing.state {
+                Some(ref mut state) => state.render(data, channels, cue_on_channels),
+                None => data.fill(0.0),
+            },
+            move |err| {
+                let _ = errors.send((generation, StreamRole::Main, err.to_string()));
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to create audio stream: {}", e))
+        .and_then(|stream| match stream.play() {
+            Ok(()) => Ok(stream),
+            Err(e) => Err(format!("Failed to start audio: {}", e)),
+        });
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            // The callback went down with the stream
+            *parked = home.take_back();
+            return Err(e);
+        }
+    };
 
-        // Send state updates periodically
-        if last_state_update.elapsed() >= state_update_interval {
-            if let Ok(state) = engine_state.lock() {
-                let _ = evt_tx.try_send(state.get_state());
+    let info = OutputInfo {
+        host: host.id().name().to_string(),
+        device: device.name().unwrap_or_default(),
+        sample_rate,
+        channels: config.channels,
+        buffer_size: match config.buffer_size {
+            cpal::BufferSize::Fixed(frames) => Some(frames),
+            cpal::BufferSize::Default => None,
+        },
+        fallback,
+        engine_restarted,
+    };
+    let output = Output {
+        _stream: stream,
+        cue_stream,
+        cue_ring,
+        fallback,
+    };
+    Ok((output, info))
+}
+
+/// Queue a command for the audio callback
+///
+/// The callback drains the queue every block, so a full queue frees up
+/// quickly; the command is dropped if it doesn't within a second.
+fn push_command(queue: &mut HeapProd<AudioCommand>, mut cmd: AudioCommand) {
+    for _ in 0..1000 {
+        match queue.try_push(cmd) {
+            Ok(()) => return,
+            Err(rejected) => {
+                cmd = rejected;
+                thread::sleep(Duration::from_millis(1));
             }
-            last_state_update = Instant::now();
         }
     }
 }
+
+/// Open a second output device for the headphone cue
+///
+/// Runs at the master's sample rate (a device that doesn't support it is
+/// an error) and plays the stereo cue feed from the returned producer,
+/// with silence whenever it runs dry. Stream errors go to `on_error`.
+///
+/// The ring buffer starts half full of silence so each clock has room to
+/// run ahead of the other. Nothing corrects drift between the two device
+/// clocks, so over a long session the cue can still slip into under- or
+/// overruns (heard as a click while the ring refills or drops a block).
+fn open_cue_device(
+    host: &cpal::Host,
+    name: &str,
+    sample_rate: u32,
+    mut on_error: impl FnMut(String) + Send + 'static,
+) -> anyhow::Result<(cpal::Stream, HeapProd<f32>)> {
+    let device = host
+        .output_devices()?
+        .find(|d| d.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| anyhow::anyhow!("no output device named '{}'", name))?;
+    let settings = OutputSettings {
+        sample_rate: Some(sample_rate),
+        ..OutputSettings::default()
+    };
+    let config = output::stream_config(&device, &settings, None).map_err(anyhow::Error::msg)?;
+    let channels = config.channels as usize;
+
+    // ~250ms of stereo audio between the two device clocks, half of it
+    // primed with silence
+    let capacity = sample_rate as usize / 2;
+    let (mut tx, mut rx) = HeapRb::<f32>::new(capacity).split();
+    tx.push_iter(std::iter::repeat_n(0.0, capacity / 4 * 2));
+    let mut stereo_buffer = vec![0.0f32; 65536];
+
+    let stream = device.build_output_stream(
+        &config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            let frames = data.len() / channels;
+            let stereo_len = (frames * 2).min(stereo_buffer.len());
+            let stereo = &mut stereo_buffer[..stereo_len];
+            let read = rx.pop_slice(stereo);
+            stereo[read..].fill(0.0);
+            for (f, frame) in data.chunks_mut(channels).enumerate() {
+                let (l, r) = stereo
+                    .get(f * 2..f * 2 + 2)
+                    .map_or((0.0, 0.0), |s| (s[0], s[1]));
+                for (ch, sample) in frame.iter_mut().enumerate() {
+                    *sample = match (channels, ch) {
+                        (1, _) => (l + r) * 0.5,
+                        (_, 0) => l,
+                        (_, 1) => r,
+                        _ => 0.0,
+                    };
+                }
+            }
+        },
+        move |err| on_error(err.to_string()),
+        None,
+    )?;
+    stream.play()?;
+    Ok((stream, tx))
+}
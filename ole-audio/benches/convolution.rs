@@ -0,0 +1,41 @@
+//! Convolution reverb cost with the longest impulse response
+//!
+//! Run with `cargo bench -p ole-audio`. Throughput is in output frames per
+//! second: one deck needs 48k/s to keep up at 48 kHz. The work is spread
+//! evenly over blocks, so small callbacks should cost the same per frame as
+//! large ones.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ole_audio::{ConvolutionReverb, Effect, ImpulseResponse, MAX_IMPULSE_SECS};
+
+const SAMPLE_RATE: u32 = 48000;
+
+/// A stereo hall: decaying noise over the full length
+fn hall() -> ImpulseResponse {
+    let frames = (MAX_IMPULSE_SECS * SAMPLE_RATE as f32) as usize;
+    let samples: Vec<f32> = (0..frames * 2)
+        .map(|i| {
+            let noise = ((i * 7919) % 1013) as f32 / 506.5 - 1.0;
+            noise * (-(i as f32) / (SAMPLE_RATE as f32 * 2.0)).exp()
+        })
+        .collect();
+    ImpulseResponse::new("hall", &samples, 2, SAMPLE_RATE, SAMPLE_RATE).unwrap()
+}
+
+fn bench_convolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convolution_longest_impulse");
+    for callback in [64, 512] {
+        let mut reverb = ConvolutionReverb::new();
+        reverb.set_impulse(Box::new(hall()));
+        reverb.set_enabled(true);
+        let mut buffer: Vec<f32> = (0..callback * 2).map(|i| (i as f32 * 0.1).sin()).collect();
+        group.throughput(Throughput::Elements(callback as u64));
+        group.bench_function(BenchmarkId::from_parameter(callback), |b| {
+            b.iter(|| reverb.process(black_box(&mut buffer)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_convolution);
+criterion_main!(benches);
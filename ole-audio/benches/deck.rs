@@ -0,0 +1,99 @@
+//! Deck playback cost per interpolation mode
+//!
+//! Run with `cargo bench -p ole-audio`. Throughput is in output frames per
+//! second: one deck needs 48k/s to keep up at 48 kHz, and the engine bench
+//! plays all four decks at once, so leave plenty of headroom above that.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ole_analysis::EnhancedWaveform;
+use ole_audio::{AudioCommand, Deck, EngineState, Interpolation};
+use std::sync::Arc;
+
+const SAMPLE_RATE: u32 = 48000;
+/// Frames per audio callback
+const BLOCK_FRAMES: usize = 512;
+
+/// Ten seconds of a stereo chord at 44.1 kHz, so every read is fractional
+fn track() -> Arc<Vec<f32>> {
+    let rate = 44100.0;
+    Arc::new(
+        (0..441_000)
+            .flat_map(|i| {
+                let t = i as f32 / rate;
+                let s = [220.0, 277.2, 329.6, 3520.0]
+                    .iter()
+                    .map(|f| (std::f32::consts::TAU * f * t).sin() * 0.2)
+                    .sum::<f32>();
+                [s, -s]
+            })
+            .collect(),
+    )
+}
+
+fn playing_deck(samples: &Arc<Vec<f32>>, interpolation: Interpolation) -> Deck {
+    let mut deck = Deck::new(SAMPLE_RATE);
+    deck.load(
+        samples.clone(),
+        44100,
+        None,
+        Arc::new(Vec::new()),
+        Arc::new(EnhancedWaveform::default()),
+        None,
+    );
+    deck.set_interpolation(interpolation);
+    deck.set_tempo(1.03);
+    deck.play();
+    deck
+}
+
+/// Start over when the track runs out
+fn keep_playing(deck: &mut Deck) {
+    if !deck.is_playing() {
+        deck.seek(0.0);
+        deck.play();
+    }
+}
+
+fn bench_deck(c: &mut Criterion) {
+    let samples = track();
+    let mut group = c.benchmark_group("deck_process");
+    group.throughput(Throughput::Elements(BLOCK_FRAMES as u64));
+    for mode in Interpolation::ALL {
+        let mut deck = playing_deck(&samples, mode);
+        let mut buffer = vec![0.0f32; BLOCK_FRAMES * 2];
+        group.bench_function(BenchmarkId::from_parameter(mode.name()), |b| {
+            b.iter(|| {
+                deck.process(black_box(&mut buffer));
+                keep_playing(&mut deck);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_engine(c: &mut Criterion) {
+    let samples = track();
+    let mut group = c.benchmark_group("engine_four_decks");
+    group.throughput(Throughput::Elements(BLOCK_FRAMES as u64));
+    for mode in Interpolation::ALL {
+        let mut engine = EngineState::new(SAMPLE_RATE);
+        for deck in &mut engine.decks {
+            *deck = playing_deck(&samples, mode);
+        }
+        engine.handle_command(AudioCommand::SetCrossfader(0.0));
+        let mut buffer = vec![0.0f32; BLOCK_FRAMES * 2];
+        group.bench_function(BenchmarkId::from_parameter(mode.name()), |b| {
+            b.iter(|| {
+                buffer.fill(0.0);
+                engine.process(black_box(&mut buffer), None);
+                for deck in &mut engine.decks {
+                    keep_playing(deck);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_deck, bench_engine);
+criterion_main!(benches);
@@ -1,10 +1,66 @@
 //! Deck implementation - track playback with pitch/tempo control
 
+use crate::fixed::{FixedVec, Label};
+use crate::interpolation::Interpolation;
+use crate::sync::{self, SyncRole};
+use crate::timestretcher::PitchShifter;
 use ole_analysis::{
-    BeatGrid, BeatGridAnalyzer, BpmDetector, EnhancedWaveform, SpectrumAnalyzer, SpectrumData,
+    BeatGrid, BeatGridAnalyzer, BpmDetector, CamelotKey, EnhancedWaveform, SpectrumAnalyzer,
+    SpectrumData,
 };
+use std::fmt::Write;
 use std::sync::Arc;
 
+/// Number of decks the engine runs
+pub const DECK_COUNT: usize = 4;
+
+/// Deck identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DeckId {
+    #[default]
+    A,
+    B,
+    C,
+    D,
+}
+
+impl DeckId {
+    /// All decks, in order
+    pub const ALL: [DeckId; DECK_COUNT] = [DeckId::A, DeckId::B, DeckId::C, DeckId::D];
+
+    /// Position in per-deck collections
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Deck at a collection position
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+
+    /// Deck letter ("A" - "D")
+    pub fn name(self) -> &'static str {
+        match self {
+            DeckId::A => "A",
+            DeckId::B => "B",
+            DeckId::C => "C",
+            DeckId::D => "D",
+        }
+    }
+
+    /// Parse a deck letter ("a" - "d", any case)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "a" => Some(DeckId::A),
+            "b" => Some(DeckId::B),
+            "c" => Some(DeckId::C),
+            "d" => Some(DeckId::D),
+            _ => None,
+        }
+    }
+}
+
 /// Playback state for a deck
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PlaybackState {
@@ -35,14 +91,65 @@ pub struct SyncTransition {
     pub active: bool,
 }
 
+/// Most tempo segments and phrases a `BeatGridInfo` carries
+pub const MAX_GRID_MARKERS: usize = 512;
+
 /// Beat grid info for UI display
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct BeatGridInfo {
     pub bpm: f32,
     pub confidence: f32,
     pub has_grid: bool,
     /// First beat offset in seconds (for rendering beat markers on waveform)
     pub first_beat_offset_secs: f64,
+    /// Tempo segments as (start in seconds, start beat, BPM), the first at the first beat
+    pub tempo_map: FixedVec<(f64, f64, f32), MAX_GRID_MARKERS>,
+    /// Beat number of the first downbeat (0 - 3)
+    pub downbeat: u32,
+    /// Bars where phrases start
+    pub phrases: FixedVec<u32, MAX_GRID_MARKERS>,
+    /// Phrase starts in seconds (for rendering phrase markers on waveform)
+    pub phrases_secs: FixedVec<f64, MAX_GRID_MARKERS>,
+    /// Corrections made since the track was loaded (the grid needs saving when this changes)
+    pub edits: u32,
+}
+
+/// Loop info for UI display
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LoopInfo {
+    /// Loop start in seconds
+    pub start: f64,
+    /// Loop end in seconds
+    pub end: f64,
+    /// Length in beats for beat-based loops (None for manual loops)
+    pub beats: Option<f32>,
+    /// Whether playback is currently looping
+    pub active: bool,
+    /// Whether the loop is a roll (track keeps running underneath)
+    pub rolling: bool,
+}
+
+/// Loop region in samples (stereo interleaved)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LoopRegion {
+    start: f64,
+    end: f64,
+    /// Length in beats for beat-based loops
+    beats: Option<f32>,
+}
+
+impl LoopRegion {
+    fn len(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// A loop roll in progress (its timeline is the slip position)
+#[derive(Debug, Clone, Copy)]
+struct LoopRoll {
+    /// Loop to restore when the roll is released
+    previous: Option<LoopRegion>,
+    previous_active: bool,
 }
 
 /// Size of scope buffer for oscilloscope display
@@ -52,16 +159,21 @@ pub const SCOPE_SAMPLES_SIZE: usize = 512;
 #[derive(Debug, Clone)]
 pub struct DeckState {
     pub playback: PlaybackState,
-    pub position: f64,       // seconds
-    pub duration: f64,       // seconds
-    pub tempo: f32,          // 1.0 = original speed
-    pub pitch: f32,          // semitones shift
-    pub gain: f32,           // 0.0 - 2.0
-    pub bpm: Option<f32>,    // detected BPM (adjusted for tempo)
-    pub key: Option<String>, // Camelot notation: "8A", "12B"
-    pub track_name: Option<String>,
+    pub position: f64,      // seconds
+    pub duration: f64,      // seconds
+    pub tempo: f32,         // 1.0 = original speed
+    pub key_lock: bool,     // tempo changes keep the original pitch
+    pub slip: bool,         // slip mode: actions return to the running timeline
+    pub reverse: bool,      // playing backwards
+    pub pitch: f32,         // semitones shift
+    pub gain: f32,          // 0.0 - 2.0
+    pub bpm: Option<f32>,   // detected BPM (adjusted for tempo)
+    pub key: Option<Label>, // Camelot notation: "8A", "12B" (as heard, after pitch/tempo)
+    pub track_name: Option<Label>,
     pub spectrum: SpectrumData,
-    pub beat_phase: f32, // current phase within beat (0.0 - 1.0)
+    pub beat_phase: f32,          // current phase within beat (0.0 - 1.0)
+    pub bar: Option<i32>,         // current bar (from the first downbeat, negative before it)
+    pub beat_in_bar: Option<u32>, // current beat within the bar (0 = downbeat)
     pub beat_grid_info: Option<BeatGridInfo>,
     pub waveform_overview: Arc<Vec<f32>>, // pre-computed peaks for waveform display
     pub enhanced_waveform: Arc<EnhancedWaveform>, // enhanced waveform with frequency bands
@@ -69,6 +181,11 @@ pub struct DeckState {
     pub peak_hold: f32,                   // peak hold level (decays slowly after hold time)
     pub is_clipping: bool,                // true if clipping detected
     pub cue_points: [Option<f64>; 8],     // cue point positions in seconds (1-8)
+    pub loop_in: Option<f64>,             // pending loop in marker in seconds
+    pub loop_info: Option<LoopInfo>,      // current loop (active or ready to re-enter)
+    pub slip_position: Option<f64>, // where the track would be without the slip action (seconds)
+    pub sync_role: SyncRole,        // part in sync lock
+    pub phase_error: Option<f32>,   // beats off the sync leader (followers, + = ahead)
     /// Recent audio samples for oscilloscope display (stereo interleaved: [L, R, L, R, ...])
     pub scope_samples: Box<[f32; SCOPE_SAMPLES_SIZE * 2]>,
 }
@@ -80,6 +197,9 @@ impl Default for DeckState {
             position: 0.0,
             duration: 0.0,
             tempo: 1.0,
+            key_lock: false,
+            slip: false,
+            reverse: false,
             pitch: 0.0,
             gain: 1.0,
             bpm: None,
@@ -87,6 +207,8 @@ impl Default for DeckState {
             track_name: None,
             spectrum: SpectrumData::default(),
             beat_phase: 0.0,
+            bar: None,
+            beat_in_bar: None,
             beat_grid_info: None,
             waveform_overview: Arc::new(Vec::new()),
             enhanced_waveform: Arc::new(EnhancedWaveform::default()),
@@ -94,25 +216,123 @@ impl Default for DeckState {
             peak_hold: 0.0,
             is_clipping: false,
             cue_points: [None; 8],
+            loop_in: None,
+            loop_info: None,
+            slip_position: None,
+            sync_role: SyncRole::Off,
+            phase_error: None,
             scope_samples: Box::new([0.0; SCOPE_SAMPLES_SIZE * 2]),
         }
     }
 }
 
+/// A decoded track with its beat grid analyzed, ready for a deck
+///
+/// The analysis takes a while, so hosts running the engine on the audio
+/// thread do it beforehand (see `AudioCommand::prepare`).
+#[derive(Debug)]
+pub struct AnalyzedTrack {
+    pub samples: Arc<Vec<f32>>,
+    pub sample_rate: u32,
+    pub name: Option<String>,
+    pub waveform: Arc<Vec<f32>>,
+    pub enhanced_waveform: Arc<EnhancedWaveform>,
+    pub key: Option<String>,
+    pub bpm: Option<f32>,
+    pub beat_grid: Option<Box<BeatGrid>>,
+    /// Fresh fallback BPM detector for the deck (built here, it allocates)
+    pub bpm_detector: BpmDetector,
+}
+
+impl AnalyzedTrack {
+    /// Analyze the beat grid (a tempo map over the whole track)
+    pub fn analyze(
+        samples: Arc<Vec<f32>>,
+        sample_rate: u32,
+        name: Option<String>,
+        waveform: Arc<Vec<f32>>,
+        enhanced_waveform: Arc<EnhancedWaveform>,
+        key: Option<String>,
+    ) -> Self {
+        let mut bpm = None;
+        let mut beat_grid = None;
+        if !samples.is_empty() {
+            let analyzer = BeatGridAnalyzer::new(sample_rate);
+
+            if let Some(grid) = analyzer.analyze(&samples) {
+                bpm = Some(grid.bpm);
+                beat_grid = Some(Box::new(grid));
+            } else {
+                // Fallback to legacy BPM detector
+                let mut detector = BpmDetector::new(sample_rate);
+                let analysis_samples = samples.len().min(sample_rate as usize * 10);
+                for chunk in samples[..analysis_samples].chunks(1024) {
+                    let mono: Vec<f32> = chunk
+                        .chunks(2)
+                        .map(|s| {
+                            if s.len() == 2 {
+                                (s[0] + s[1]) * 0.5
+                            } else {
+                                s[0]
+                            }
+                        })
+                        .collect();
+                    detector.process(&mono);
+                }
+                bpm = detector.bpm();
+            }
+        }
+        Self {
+            samples,
+            sample_rate,
+            name,
+            waveform,
+            enhanced_waveform,
+            key,
+            bpm,
+            beat_grid,
+            bpm_detector: BpmDetector::new(sample_rate),
+        }
+    }
+}
+
 /// A single DJ deck with audio playback capabilities
 pub struct Deck {
     /// Audio samples (interleaved stereo) - Arc to avoid copying through channels
     samples: Arc<Vec<f32>>,
     /// Sample rate of loaded audio
     sample_rate: u32,
+    /// Sample rate the deck renders at (the engine's output rate)
+    ///
+    /// Tracks at another rate are resampled on the fly, so positions,
+    /// cues and beat grids stay in the track's own samples.
+    output_rate: u32,
+    /// How samples are read between frames
+    interpolation: Interpolation,
     /// Current playback position in samples
     position: f64,
     /// Playback state
     state: PlaybackState,
     /// Playback speed (1.0 = normal)
     tempo: f32,
-    /// Pitch shift in semitones
+    /// Key lock (master tempo): tempo changes are time-stretched instead of resampled
+    key_lock: bool,
+    /// Phase vocoder based shifter used for key lock and pitch shifting
+    shifter: PitchShifter,
+    /// Pitch shift in semitones (cent resolution)
     pitch: f32,
+    /// Play backwards
+    reverse: bool,
+    /// Slip mode: loops, cue jumps, reverse, beatjumps and tape stops run
+    /// over a shadow position that keeps advancing
+    slip: bool,
+    /// Where the track would be without the current slip action, in samples
+    slip_position: Option<f64>,
+    /// Quantize grid step in beats (None = quantize off)
+    quantize: Option<f64>,
+    /// Sync lock status (set by the engine)
+    sync_role: SyncRole,
+    phase_error: Option<f32>,
     /// Volume gain
     gain: f32,
     /// Track name
@@ -122,7 +342,9 @@ pub struct Deck {
     /// Detected BPM (from beat grid or legacy detector)
     bpm: Option<f32>,
     /// Beat grid for phase-aligned sync
-    beat_grid: Option<BeatGrid>,
+    beat_grid: Option<Box<BeatGrid>>,
+    /// Beat grid corrections since the track was loaded
+    grid_edits: u32,
     /// Sync transition state for smooth syncing
     sync_transition: SyncTransition,
     /// Spectrum analyzer
@@ -137,6 +359,23 @@ pub struct Deck {
     enhanced_waveform: Arc<EnhancedWaveform>,
     /// Cue points (up to 8), stored as sample positions
     cue_points: [Option<f64>; 8],
+    /// Loop in marker waiting for a loop out, in samples
+    loop_in: Option<f64>,
+    /// Current loop (kept after exiting so it can be re-entered)
+    loop_region: Option<LoopRegion>,
+    /// Whether playback wraps at the loop end
+    ///
+    /// While looping the read position keeps counting up past the loop end
+    /// and is folded back into the loop when reading (see `fold_position`).
+    loop_active: bool,
+    /// Loop roll in progress
+    loop_roll: Option<LoopRoll>,
+    /// Loop crossfade samples remaining
+    loop_fade_samples: u32,
+    /// Length of the current loop crossfade
+    loop_fade_len: u32,
+    /// Read position of the audio being faded out by the loop crossfade
+    loop_tail_position: f64,
     /// Current peak level for metering
     peak_level: f32,
     /// Peak hold level (max peak that decays slowly)
@@ -173,6 +412,10 @@ impl Deck {
     /// Fade-out duration in samples (~20ms at 48kHz) to prevent clicks on pause/stop
     const FADE_OUT_SAMPLES: u32 = 960;
 
+    /// Shortest and longest beat-based loops
+    const MIN_LOOP_BEATS: f32 = 1.0 / 32.0;
+    const MAX_LOOP_BEATS: f32 = 32.0;
+
     /// Gain smoothing coefficient (higher = slower smoothing, ~0.999 = 20ms time constant)
     const GAIN_SMOOTH_COEFF: f32 = 0.995;
 
@@ -187,18 +430,30 @@ impl Deck {
 
     /// Create a new empty deck
     pub fn new(target_sample_rate: u32) -> Self {
+        Interpolation::prepare_tables();
         Self {
             samples: Arc::new(Vec::new()),
             sample_rate: target_sample_rate,
+            output_rate: target_sample_rate,
+            interpolation: Interpolation::default(),
             position: 0.0,
             state: PlaybackState::Stopped,
             tempo: 1.0,
+            key_lock: false,
+            shifter: PitchShifter::new(),
             pitch: 0.0,
+            reverse: false,
+            slip: false,
+            slip_position: None,
+            quantize: None,
+            sync_role: SyncRole::Off,
+            phase_error: None,
             gain: 1.0,
             track_name: None,
             key: None,
             bpm: None,
             beat_grid: None,
+            grid_edits: 0,
             sync_transition: SyncTransition::default(),
             spectrum_analyzer: SpectrumAnalyzer::new(target_sample_rate),
             bpm_detector: BpmDetector::new(target_sample_rate),
@@ -206,6 +461,13 @@ impl Deck {
             waveform_overview: Arc::new(Vec::new()),
             enhanced_waveform: Arc::new(EnhancedWaveform::default()),
             cue_points: [None; 8],
+            loop_in: None,
+            loop_region: None,
+            loop_active: false,
+            loop_roll: None,
+            loop_fade_samples: 0,
+            loop_fade_len: 0,
+            loop_tail_position: 0.0,
             peak_level: 0.0,
             peak_hold: 0.0,
             peak_hold_samples: 0,
@@ -236,47 +498,43 @@ impl Deck {
         enhanced_waveform: Arc<EnhancedWaveform>,
         key: Option<String>,
     ) {
-        self.samples = samples;
-        self.sample_rate = sample_rate;
-        self.position = 0.0;
+        self.load_analyzed(Box::new(AnalyzedTrack::analyze(
+            samples,
+            sample_rate,
+            name,
+            waveform,
+            enhanced_waveform,
+            key,
+        )));
+    }
+
+    /// Load a track whose beat grid was already analyzed (quick enough
+    /// for the audio thread)
+    ///
+    /// Returns the track it replaces (samples, name, grid, ...) in the same
+    /// box, so the caller can free it off the audio thread.
+    pub fn load_analyzed(&mut self, mut track: Box<AnalyzedTrack>) -> Box<AnalyzedTrack> {
+        std::mem::swap(&mut self.samples, &mut track.samples);
+        std::mem::swap(&mut self.track_name, &mut track.name);
+        std::mem::swap(&mut self.key, &mut track.key);
+        std::mem::swap(&mut self.beat_grid, &mut track.beat_grid);
+        std::mem::swap(&mut self.bpm_detector, &mut track.bpm_detector);
+        std::mem::swap(&mut self.waveform_overview, &mut track.waveform);
+        std::mem::swap(&mut self.enhanced_waveform, &mut track.enhanced_waveform);
+        std::mem::swap(&mut self.sample_rate, &mut track.sample_rate);
+        std::mem::swap(&mut self.bpm, &mut track.bpm);
+        self.loop_in = None;
+        self.loop_region = None;
+        self.loop_active = false;
+        self.loop_roll = None;
+        self.loop_fade_samples = 0;
+        self.slip_position = None;
+        self.set_playback_position(0.0);
         self.state = PlaybackState::Stopped;
-        self.track_name = name;
-        self.key = key;
-        self.bpm = None;
-        self.beat_grid = None;
+        self.grid_edits = 0;
         self.sync_transition = SyncTransition::default();
-        self.bpm_detector = BpmDetector::new(sample_rate);
-        self.waveform_overview = waveform;
-        self.enhanced_waveform = enhanced_waveform;
-
-        // Analyze beat grid from first 30 seconds of audio
-        if !self.samples.is_empty() {
-            let analyzer = BeatGridAnalyzer::new(sample_rate);
-            // Analyze first 30 seconds (or full track if shorter)
-            let analysis_samples = self.samples.len().min(sample_rate as usize * 60); // 30 seconds stereo
-
-            if let Some(grid) = analyzer.analyze(&self.samples[..analysis_samples]) {
-                self.bpm = Some(grid.bpm);
-                self.beat_grid = Some(grid);
-            } else {
-                // Fallback to legacy BPM detector
-                let analysis_samples = self.samples.len().min(sample_rate as usize * 10);
-                for chunk in self.samples[..analysis_samples].chunks(1024) {
-                    let mono: Vec<f32> = chunk
-                        .chunks(2)
-                        .map(|s| {
-                            if s.len() == 2 {
-                                (s[0] + s[1]) * 0.5
-                            } else {
-                                s[0]
-                            }
-                        })
-                        .collect();
-                    self.bpm_detector.process(&mono);
-                }
-                self.bpm = self.bpm_detector.bpm();
-            }
-        }
+        self.prime_vocoder();
+        track
     }
 
     /// Check if deck has a track loaded
@@ -317,7 +575,8 @@ impl Deck {
             self.pending_state = Some(PlaybackState::Stopped);
         } else {
             self.state = PlaybackState::Stopped;
-            self.position = 0.0;
+            self.set_playback_position(0.0);
+            self.prime_vocoder();
         }
     }
 
@@ -329,18 +588,25 @@ impl Deck {
         }
     }
 
+    /// Check if the deck is playing
+    pub fn is_playing(&self) -> bool {
+        self.state == PlaybackState::Playing
+    }
+
     /// Set playback position in seconds
     pub fn seek(&mut self, position_secs: f64) {
         let max_pos = self.duration();
-        self.position = (position_secs * self.sample_rate as f64 * 2.0)
-            .clamp(0.0, max_pos * self.sample_rate as f64 * 2.0);
+        self.set_playback_position(
+            (position_secs * self.sample_rate as f64 * 2.0)
+                .clamp(0.0, max_pos * self.sample_rate as f64 * 2.0),
+        );
         // Trigger fade-in to prevent click at new position
-        self.fade_in_samples = Self::FADE_IN_SAMPLES;
+        self.start_jump_fade_in();
     }
 
     /// Nudge position forward/backward by given seconds
     pub fn nudge(&mut self, delta_secs: f64) {
-        let current_secs = self.position / (self.sample_rate as f64 * 2.0);
+        let current_secs = self.position_secs();
         self.seek(current_secs + delta_secs);
     }
 
@@ -348,59 +614,70 @@ impl Deck {
     /// More musical than time-based nudge for beat alignment
     pub fn beat_nudge(&mut self, beat_fraction: f32) {
         if let Some(grid) = &self.beat_grid {
-            let samples_per_beat = grid.samples_per_beat_at_tempo(self.tempo);
-            let nudge_samples = beat_fraction as f64 * samples_per_beat;
-            let new_pos = (self.position + nudge_samples).clamp(0.0, self.samples.len() as f64);
-            self.position = new_pos;
+            let beat = grid.beat_at_position(self.playback_position()) + beat_fraction as f64;
+            let new_pos = grid
+                .position_for_beat(beat)
+                .clamp(0.0, self.samples.len() as f64);
+            self.set_playback_position(new_pos);
             // Trigger fade-in to prevent click
-            self.fade_in_samples = Self::FADE_IN_SAMPLES;
+            self.start_jump_fade_in();
         } else if let Some(bpm) = self.bpm {
             // Fallback: calculate from BPM
             let beats_per_sec = bpm as f64 / 60.0;
             let samples_per_beat = (self.sample_rate as f64 * 2.0) / beats_per_sec;
             let nudge_samples = beat_fraction as f64 * samples_per_beat;
-            let new_pos = (self.position + nudge_samples).clamp(0.0, self.samples.len() as f64);
-            self.position = new_pos;
+            let new_pos =
+                (self.playback_position() + nudge_samples).clamp(0.0, self.samples.len() as f64);
+            self.set_playback_position(new_pos);
             // Trigger fade-in to prevent click
-            self.fade_in_samples = Self::FADE_IN_SAMPLES;
+            self.start_jump_fade_in();
         }
     }
 
     /// Jump by N beats (positive = forward, negative = backward)
+    /// In slip mode the jump lasts until `slip_release`
     pub fn beatjump(&mut self, beats: i32) {
+        if self.beat_grid.is_some() || self.bpm.is_some() {
+            self.start_slip(false);
+        }
         if let Some(grid) = &self.beat_grid {
-            let samples_per_beat = grid.samples_per_beat_at_tempo(self.tempo);
-            let jump_samples = beats as f64 * samples_per_beat;
-            let new_pos = (self.position + jump_samples).clamp(0.0, self.samples.len() as f64);
-            self.position = new_pos;
+            let beat = grid.beat_at_position(self.playback_position()) + beats as f64;
+            let new_pos = grid
+                .position_for_beat(beat)
+                .clamp(0.0, self.samples.len() as f64);
+            self.set_playback_position(new_pos);
             // Trigger fade-in to prevent click
-            self.fade_in_samples = Self::FADE_IN_SAMPLES;
+            self.start_jump_fade_in();
         } else if let Some(bpm) = self.bpm {
             // Fallback: calculate from BPM
             let beats_per_sec = bpm as f64 / 60.0;
             let samples_per_beat = (self.sample_rate as f64 * 2.0) / beats_per_sec;
             let jump_samples = beats as f64 * samples_per_beat;
-            let new_pos = (self.position + jump_samples).clamp(0.0, self.samples.len() as f64);
-            self.position = new_pos;
+            let new_pos =
+                (self.playback_position() + jump_samples).clamp(0.0, self.samples.len() as f64);
+            self.set_playback_position(new_pos);
             // Trigger fade-in to prevent click
-            self.fade_in_samples = Self::FADE_IN_SAMPLES;
+            self.start_jump_fade_in();
         }
     }
 
     /// Set cue point at current position (1-4)
     pub fn set_cue(&mut self, cue_num: u8) {
         if (1..=4).contains(&cue_num) {
-            self.cue_points[(cue_num - 1) as usize] = Some(self.position);
+            self.cue_points[(cue_num - 1) as usize] =
+                Some(self.snap_to_grid(self.playback_position()));
         }
     }
 
     /// Jump to cue point (1-4)
+    /// In slip mode the jump lasts until `slip_release`
     pub fn jump_cue(&mut self, cue_num: u8) {
         if (1..=4).contains(&cue_num) {
             if let Some(pos) = self.cue_points[(cue_num - 1) as usize] {
-                self.position = pos;
+                self.start_slip(false);
+                self.set_playback_position(pos);
                 // Trigger fade-in to prevent click
-                self.fade_in_samples = Self::FADE_IN_SAMPLES;
+                self.start_jump_fade_in();
             }
         }
     }
@@ -414,9 +691,283 @@ impl Deck {
         }
     }
 
+    /// Set loop in marker at current position
+    pub fn loop_in(&mut self) {
+        if self.is_loaded() {
+            self.loop_in = Some(self.snap_to_grid(self.playback_position()));
+        }
+    }
+
+    /// Set loop out at current position and start looping from loop in
+    pub fn loop_out(&mut self) {
+        let Some(start) = self.loop_in else {
+            return;
+        };
+        let end = self.snap_to_grid(self.playback_position());
+        if end > start {
+            self.loop_in = None;
+            self.enter_loop(LoopRegion {
+                start,
+                end,
+                beats: None,
+            });
+        }
+    }
+
+    /// Loop N beats (1/32 - 32) starting on the current beat
+    /// Same length again exits the loop
+    pub fn auto_loop(&mut self, beats: f32) {
+        let beats = beats.clamp(Self::MIN_LOOP_BEATS, Self::MAX_LOOP_BEATS);
+        let same_loop = self.active_loop().and_then(|r| r.beats) == Some(beats);
+        if same_loop && self.loop_roll.is_none() {
+            self.exit_loop();
+        } else if let Some(region) = self.beat_loop_at(self.playback_position(), beats) {
+            self.enter_loop(region);
+        }
+    }
+
+    /// Halve loop length (keeps the loop start)
+    pub fn loop_halve(&mut self) {
+        self.resize_loop(0.5);
+    }
+
+    /// Double loop length (keeps the loop start)
+    pub fn loop_double(&mut self) {
+        self.resize_loop(2.0);
+    }
+
+    /// Move the loop by N beats (negative = backward)
+    /// While looping, playback moves with it
+    pub fn loop_move(&mut self, beats: f32) {
+        let Some(region) = self.loop_region else {
+            return;
+        };
+        let Some(samples_per_beat) = self.source_samples_per_beat(region.start) else {
+            return;
+        };
+        let max_end = self.samples.len() as f64;
+        let delta = (beats as f64 * samples_per_beat)
+            .max(-region.start)
+            .min(max_end - region.end);
+        self.change_loop(|deck| {
+            deck.loop_region = Some(LoopRegion {
+                start: region.start + delta,
+                end: region.end + delta,
+                beats: region.beats,
+            });
+            if deck.loop_active {
+                deck.position += delta;
+            }
+        });
+    }
+
+    /// Exit the loop, or re-enter the last one
+    pub fn reloop(&mut self) {
+        if self.loop_active {
+            self.exit_loop();
+        } else if self.loop_region.is_some() {
+            self.start_slip(false);
+            self.change_loop(|deck| deck.loop_active = true);
+        }
+    }
+
+    /// Exit the loop and play on (the loop stays set for `reloop`)
+    /// In slip mode playback returns to where the track would have been
+    pub fn exit_loop(&mut self) {
+        if self.slip_position.is_some() {
+            self.slip_release();
+            return;
+        }
+        self.change_loop(|deck| {
+            deck.position = deck.fold_position(deck.position);
+            deck.loop_active = false;
+        });
+    }
+
+    /// Start a loop roll of N beats (1/32 - 32)
+    ///
+    /// The track keeps running underneath; `loop_roll_release` resumes
+    /// where it would have been without the roll.
+    pub fn loop_roll(&mut self, beats: f32) {
+        let beats = beats.clamp(Self::MIN_LOOP_BEATS, Self::MAX_LOOP_BEATS);
+        let Some(region) = self.beat_loop_at(self.playback_position(), beats) else {
+            return;
+        };
+        if self.loop_roll.is_none() {
+            self.start_slip(true);
+            self.loop_roll = Some(LoopRoll {
+                previous: self.loop_region,
+                previous_active: self.loop_active,
+            });
+        }
+        self.enter_loop(region);
+    }
+
+    /// Release the loop roll and jump to where the track would have been
+    pub fn loop_roll_release(&mut self) {
+        if self.loop_roll.is_some() {
+            self.slip_release();
+        }
+    }
+
+    /// Set the quantize grid step in beats (None = off)
+    ///
+    /// Cue points and manual loop points snap to the nearest grid line.
+    pub fn set_quantize(&mut self, beats: Option<f64>) {
+        self.quantize = beats.filter(|b| *b > 0.0);
+    }
+
+    /// Output frames until playback reaches the next quantize grid line
+    ///
+    /// 0 when right on one. None when quantize is off, the deck is not
+    /// playing forwards or it has no beat grid.
+    pub fn frames_to_grid_line(&self) -> Option<usize> {
+        if self.state != PlaybackState::Playing || self.reverse {
+            return None;
+        }
+        let (Some(step), Some(grid)) = (self.quantize, &self.beat_grid) else {
+            return None;
+        };
+        let beat = grid.beat_at_position(self.playback_position());
+        let next = grid.position_for_beat((beat / step - 1e-6).ceil() * step);
+        let samples = (next - self.playback_position()).max(0.0);
+        Some((samples / (2.0 * self.tempo as f64 * self.rate_ratio())).round() as usize)
+    }
+
+    /// Snap a position to the nearest quantize grid line (when quantize is on)
+    fn snap_to_grid(&self, position: f64) -> f64 {
+        let (Some(step), Some(grid)) = (self.quantize, &self.beat_grid) else {
+            return position;
+        };
+        let beat = (grid.beat_at_position(position) / step).round() * step;
+        let snapped = grid.position_for_beat(beat);
+        if (0.0..self.samples.len() as f64).contains(&snapped) {
+            snapped
+        } else {
+            position
+        }
+    }
+
+    /// Enable/disable slip mode
+    ///
+    /// Turning it off during an action keeps playing from where it is.
+    pub fn set_slip(&mut self, enabled: bool) {
+        self.slip = enabled;
+        if !enabled && self.loop_roll.is_none() {
+            self.slip_position = None;
+        }
+    }
+
+    /// Toggle slip mode
+    pub fn toggle_slip(&mut self) {
+        self.set_slip(!self.slip);
+    }
+
+    /// Check if slip mode is on
+    pub fn slip(&self) -> bool {
+        self.slip
+    }
+
+    /// Check if a slip action is in progress
+    pub fn is_slipping(&self) -> bool {
+        self.slip_position.is_some()
+    }
+
+    /// Start a slip action for an effect outside the deck (e.g. tape stop)
+    pub fn slip_begin(&mut self) {
+        self.start_slip(false);
+    }
+
+    /// End the slip action and jump to where the track would have been
+    ///
+    /// Exits the loop, restoring the one a loop roll replaced.
+    pub fn slip_release(&mut self) {
+        let Some(shadow) = self.slip_position.take() else {
+            return;
+        };
+        let roll = self.loop_roll.take();
+        let max_pos = self.samples.len() as f64;
+        self.change_loop(|deck| {
+            match roll {
+                Some(roll) => {
+                    deck.loop_region = roll.previous;
+                    deck.loop_active = roll.previous_active;
+                }
+                None => deck.loop_active = false,
+            }
+            let position = deck.fold_position(shadow).min(max_pos);
+            deck.position = position + deck.read_ahead();
+        });
+    }
+
+    /// Remember where the track is, to keep advancing it during an action
+    /// Only in slip mode unless `force` (loop roll)
+    fn start_slip(&mut self, force: bool) {
+        if (self.slip || force) && self.slip_position.is_none() && self.is_loaded() {
+            self.slip_position = Some(self.playback_position());
+        }
+    }
+
+    /// Play backwards (in slip mode, until reverse is turned off)
+    pub fn set_reverse(&mut self, enabled: bool) {
+        if enabled == self.reverse {
+            return;
+        }
+        if enabled {
+            self.start_slip(false);
+        }
+        // The pitch shifter's read position flips to the other side of the
+        // audible one, so refill it
+        let position = self.playback_position();
+        self.reverse = enabled;
+        if self.is_stretching() {
+            self.set_playback_position(position);
+            self.start_jump_fade_in();
+        }
+        if !enabled {
+            self.slip_release();
+        }
+    }
+
+    /// Toggle reverse playback
+    pub fn toggle_reverse(&mut self) {
+        self.set_reverse(!self.reverse);
+    }
+
+    /// Check if playing backwards
+    pub fn reverse(&self) -> bool {
+        self.reverse
+    }
+
     /// Set tempo (playback speed)
     pub fn set_tempo(&mut self, tempo: f32) {
         self.tempo = tempo.clamp(0.5, 2.0);
+        self.shifter.set_stretch_ratio(1.0 / self.tempo);
+    }
+
+    /// Enable/disable key lock (tempo changes keep the original pitch)
+    pub fn set_key_lock(&mut self, enabled: bool) {
+        self.change_playback_path(|deck| deck.key_lock = enabled);
+    }
+
+    /// Toggle key lock
+    pub fn toggle_key_lock(&mut self) {
+        self.set_key_lock(!self.key_lock);
+    }
+
+    /// Check if key lock is active
+    pub fn key_lock(&self) -> bool {
+        self.key_lock
+    }
+
+    /// Set how samples are read between frames
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Current interpolation mode
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
     }
 
     /// Adjust tempo by delta
@@ -424,6 +975,70 @@ impl Deck {
         self.set_tempo(self.tempo + delta);
     }
 
+    /// Current tempo multiplier (1.0 = original speed)
+    pub fn tempo(&self) -> f32 {
+        self.tempo
+    }
+
+    /// Set pitch shift in semitones (-12 to +12, rounded to cents)
+    /// Transposes without changing tempo
+    pub fn set_pitch(&mut self, semitones: f32) {
+        let pitch = (semitones.clamp(-12.0, 12.0) * 100.0).round() / 100.0;
+        self.change_playback_path(|deck| {
+            deck.pitch = pitch;
+            deck.shifter.set_semitones(pitch);
+        });
+    }
+
+    /// Adjust pitch shift by delta semitones (0.01 = one cent)
+    pub fn adjust_pitch(&mut self, delta: f32) {
+        self.set_pitch(self.pitch + delta);
+    }
+
+    /// Get pitch shift in semitones
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    /// Total pitch change of the output in semitones
+    ///
+    /// The pitch shift while stretching, otherwise the pitch change caused
+    /// by playing faster or slower.
+    fn heard_semitones(&self) -> f32 {
+        if self.is_stretching() {
+            self.pitch
+        } else {
+            12.0 * self.tempo.log2()
+        }
+    }
+
+    /// Key as heard, in Camelot notation
+    ///
+    /// The detected key moved around the wheel by the nearest whole number
+    /// of semitones the output is shifted by.
+    pub fn heard_key(&self) -> Option<Label> {
+        let key = self.key.as_deref()?;
+        let semitones = self.heard_semitones().round() as i32;
+        match CamelotKey::parse(key) {
+            Some(camelot) if semitones != 0 => {
+                let mut heard = Label::new();
+                let _ = write!(heard, "{}", camelot.transpose(semitones));
+                Some(heard)
+            }
+            _ => Some(Label::from(key)),
+        }
+    }
+
+    /// Get gain
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Get the loaded track's name
+    pub fn track_name(&self) -> Option<&str> {
+        self.track_name.as_deref()
+    }
+
     /// Set gain
     pub fn set_gain(&mut self, gain: f32) {
         self.gain = gain.clamp(0.0, 2.0);
@@ -444,83 +1059,388 @@ impl Deck {
 
     /// Get current position in seconds
     pub fn position_secs(&self) -> f64 {
-        self.position / (self.sample_rate as f64 * 2.0)
+        self.playback_position() / (self.sample_rate as f64 * 2.0)
+    }
+
+    /// Whether playback runs through the pitch shifter
+    ///
+    /// Pitch shifting uses the same path as key lock, so a transposed deck
+    /// also keeps its (shifted) key when the tempo changes.
+    fn is_stretching(&self) -> bool {
+        self.key_lock || self.pitch != 0.0
+    }
+
+    /// Track frames per output frame at normal speed
+    fn rate_ratio(&self) -> f64 {
+        if self.output_rate == 0 {
+            return 1.0;
+        }
+        self.sample_rate as f64 / self.output_rate as f64
+    }
+
+    /// Change the rate the deck renders at (the engine's output rate)
+    ///
+    /// The audible position stays where it is.
+    pub fn set_output_rate(&mut self, sample_rate: u32) {
+        let position = self.playback_position();
+        self.output_rate = sample_rate;
+        self.set_playback_position(position);
+        self.prime_vocoder();
+    }
+
+    /// Playback direction (1.0 forward, -1.0 reverse)
+    fn direction(&self) -> f64 {
+        if self.reverse {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// How far the read position runs ahead of the audible one, in samples
+    ///
+    /// The phase vocoder holds about one FFT frame of audio, so while
+    /// stretching the read position leads the output by its latency
+    /// (negative when playing backwards). Otherwise they coincide.
+    fn read_ahead(&self) -> f64 {
+        if self.is_stretching() {
+            self.shifter.latency() as f64 * 2.0 * self.rate_ratio() * self.direction()
+        } else {
+            0.0
+        }
+    }
+
+    /// Audible position in samples (what display, cues and beat phase use)
+    fn playback_position(&self) -> f64 {
+        self.fold_position((self.position - self.read_ahead()).max(0.0))
+    }
+
+    /// Move the audible position, keeping the read position ahead of it
+    /// Jumping out of an active loop exits it
+    fn set_playback_position(&mut self, position: f64) {
+        if let Some(region) = self.active_loop() {
+            if position < region.start || position >= region.end {
+                self.loop_active = false;
+            }
+        }
+        self.position = position + self.read_ahead();
+    }
+
+    /// Trigger fade-in after a position jump
+    fn start_jump_fade_in(&mut self) {
+        self.fade_in_samples = Self::FADE_IN_SAMPLES;
+        self.prime_vocoder();
+    }
+
+    /// Refill the pitch shifter with the audio leading up to the read position
+    ///
+    /// Without this the vocoder would keep playing audio from before a jump
+    /// and then output a frame of silence while it refills. Twice the
+    /// latency is fed so every frame overlapping the first heard sample is
+    /// complete.
+    fn prime_vocoder(&mut self) {
+        if !self.is_stretching() {
+            return;
+        }
+        self.shifter.reset();
+        self.shifter.set_stretch_ratio(1.0 / self.tempo);
+
+        let step = 2.0 * self.rate_ratio() * self.direction();
+        let frames = (2.0 * self.read_ahead() / step) as usize;
+        let mut position = self.position - frames as f64 * step;
+        for _ in 0..frames {
+            let (left, right) = self.read_frame(self.fold_position(position), step);
+            self.shifter.push(left, right);
+            position += step;
+        }
+        while self.shifter.pop().is_some() {}
+    }
+
+    /// The loop playback wraps at, if looping
+    fn active_loop(&self) -> Option<LoopRegion> {
+        self.loop_region.filter(|_| self.loop_active)
+    }
+
+    /// Fold a read position past the end of the active loop back into it
+    fn fold_position(&self, position: f64) -> f64 {
+        match self.active_loop() {
+            Some(region) if position >= region.end => {
+                region.start + (position - region.start) % region.len()
+            }
+            _ => position,
+        }
+    }
+
+    /// Samples per beat of the source audio at a position (not adjusted for tempo)
+    fn source_samples_per_beat(&self, position: f64) -> Option<f64> {
+        match (&self.beat_grid, self.bpm) {
+            (Some(grid), _) => Some(grid.samples_per_beat_at(position)),
+            (None, Some(bpm)) if bpm > 0.0 => {
+                Some(self.sample_rate as f64 * 2.0 * 60.0 / bpm as f64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Beat loop around a position, starting on the beat grid
+    ///
+    /// Loops of a beat or longer start on the current beat, shorter ones on
+    /// the current fraction of a beat. Without a grid the loop starts at the
+    /// position itself.
+    fn beat_loop_at(&self, position: f64, beats: f32) -> Option<LoopRegion> {
+        let (start, end) = match &self.beat_grid {
+            Some(grid) => {
+                let step = beats.min(1.0) as f64;
+                // Small bias so a position right on a beat counts as that beat
+                let beat = (grid.beat_at_position(position) / step + 1e-6).floor() * step;
+                let start = grid.position_for_beat(beat);
+                let end = grid.position_for_beat(beat + beats as f64);
+                (start.max(0.0), end)
+            }
+            None => {
+                let samples_per_beat = self.source_samples_per_beat(position)?;
+                (position, position + beats as f64 * samples_per_beat)
+            }
+        };
+        Some(LoopRegion {
+            start,
+            end,
+            beats: Some(beats),
+        })
+    }
+
+    /// Start looping a region
+    fn enter_loop(&mut self, region: LoopRegion) {
+        self.start_slip(false);
+        self.change_loop(|deck| {
+            deck.loop_region = Some(region);
+            deck.loop_active = true;
+        });
+    }
+
+    /// Change the loop length by a factor, keeping its start
+    fn resize_loop(&mut self, factor: f32) {
+        let Some(region) = self.loop_region else {
+            return;
+        };
+        let beats = region.beats.map(|b| b * factor);
+        if beats.is_some_and(|b| !(Self::MIN_LOOP_BEATS..=Self::MAX_LOOP_BEATS).contains(&b)) {
+            return;
+        }
+        let end = region.start + region.len() * factor as f64;
+        if end - region.start < 2.0 || end > self.samples.len() as f64 {
+            return;
+        }
+        self.change_loop(|deck| {
+            deck.loop_region = Some(LoopRegion {
+                start: region.start,
+                end,
+                beats,
+            })
+        });
+    }
+
+    /// Apply a change to the loop, crossfading if the audio jumps
+    ///
+    /// Whole laps the read position has counted past the loop are dropped
+    /// first, so the new loop is measured from the lap being heard.
+    fn change_loop(&mut self, change: impl FnOnce(&mut Self)) {
+        if let Some(region) = self.active_loop() {
+            let laps = ((self.position - self.read_ahead() - region.start) / region.len()).floor();
+            if laps > 0.0 {
+                self.position -= laps * region.len();
+            }
+        }
+        let before = self.fold_position(self.position);
+        change(self);
+        if self.fold_position(self.position) != before {
+            self.start_loop_crossfade(before);
+        }
+    }
+
+    /// Crossfade from the audio at `tail_position` into the read position
+    ///
+    /// Uses the same S-curve and length as the jump fade-in, shortened to
+    /// half the loop so very short loops still get a full fade.
+    fn start_loop_crossfade(&mut self, tail_position: f64) {
+        let max_len = self
+            .active_loop()
+            .map(|r| (r.len() / 4.0) as u32)
+            .unwrap_or(u32::MAX);
+        self.loop_fade_len = Self::FADE_IN_SAMPLES.min(max_len).max(1);
+        self.loop_fade_samples = self.loop_fade_len;
+        self.loop_tail_position = tail_position;
+    }
+
+    /// Read the next source frame and advance the read position by `step`
+    ///
+    /// Handles looping: crossing the loop end (or the start, backwards)
+    /// starts a crossfade from the audio past it into the other end.
+    fn next_source_frame(&mut self, step: f64) -> (f32, f32) {
+        let source = self.fold_position(self.position);
+        let (mut left, mut right) = self.read_frame(source, step);
+
+        if self.loop_fade_samples > 0 {
+            let fade =
+                Self::s_curve(1.0 - self.loop_fade_samples as f32 / self.loop_fade_len as f32);
+            let (tail_left, tail_right) = self.read_frame(self.loop_tail_position, step);
+            left = tail_left + fade * (left - tail_left);
+            right = tail_right + fade * (right - tail_right);
+            self.loop_tail_position += step;
+            self.loop_fade_samples -= 1;
+        }
+
+        self.position += step;
+        if step < 0.0 {
+            // Going backwards out of the first lap: continue from the end
+            if let Some(region) = self.active_loop() {
+                if source >= region.start && self.position < region.start {
+                    self.position += region.len();
+                }
+            }
+        }
+        let next = self.fold_position(self.position);
+        if (step > 0.0 && next < source) || (step < 0.0 && next > source) {
+            self.start_loop_crossfade(source + step);
+        }
+        (left, right)
+    }
+
+    /// Apply a change that may switch between direct and stretched playback
+    ///
+    /// The audible position stays where it is; only the read-ahead changes.
+    fn change_playback_path(&mut self, change: impl FnOnce(&mut Self)) {
+        let was_stretching = self.is_stretching();
+        let position = self.playback_position();
+        change(self);
+        if self.is_stretching() != was_stretching {
+            self.set_playback_position(position);
+            self.start_jump_fade_in();
+        }
     }
 
     /// Get current BPM (adjusted for tempo)
     pub fn current_bpm(&self) -> Option<f32> {
-        self.bpm.map(|b| b * self.tempo)
+        self.base_bpm().map(|b| b * self.tempo)
     }
 
     /// Get beat grid reference
     pub fn beat_grid(&self) -> Option<&BeatGrid> {
-        self.beat_grid.as_ref()
+        self.beat_grid.as_deref()
+    }
+
+    /// Replace the beat grid (e.g. from a cached analysis)
+    ///
+    /// Returns the grid it replaces, for the caller to free off the audio
+    /// thread.
+    pub fn set_beat_grid(&mut self, grid: Box<BeatGrid>) -> Option<Box<BeatGrid>> {
+        self.bpm = Some(grid.bpm);
+        self.beat_grid.replace(grid)
     }
 
-    /// Calculate current beat phase (0.0 - 1.0), accounting for tempo
+    /// Swap in a corrected beat grid (see `AudioCommand::prepare`)
+    ///
+    /// Returns the grid it replaces, or the correction itself when the deck
+    /// has no grid to correct, for the caller to free off the audio thread.
+    pub fn apply_grid_edit(&mut self, mut grid: Box<BeatGrid>) -> Box<BeatGrid> {
+        if let Some(current) = &mut self.beat_grid {
+            std::mem::swap(current, &mut grid);
+            self.bpm = Some(current.bpm);
+            self.grid_edits += 1;
+        }
+        grid
+    }
+
+    /// Calculate current beat phase (0.0 - 1.0)
     pub fn beat_phase(&self) -> Option<f32> {
         let grid = self.beat_grid.as_ref()?;
 
-        // Get samples per beat adjusted for current tempo
-        let samples_per_beat = grid.samples_per_beat_at_tempo(self.tempo);
-
-        // Calculate phase
-        let position_from_first_beat = self.position - grid.first_beat_offset as f64;
-        let beat_position = position_from_first_beat / samples_per_beat;
+        // Positions are in source samples, so the grid's own beat length
+        // applies whatever the tempo
+        Some(grid.phase_at_position(self.playback_position()))
+    }
 
-        Some(beat_position.fract().abs() as f32)
+    /// Position on the beat grid in beats (fractional, from the first beat)
+    pub fn beat_position(&self) -> Option<f64> {
+        let grid = self.beat_grid.as_ref()?;
+        Some(grid.beat_at_position(self.playback_position()))
     }
 
     /// Get current beat number (which beat we're on in the track)
     pub fn current_beat_number(&self) -> Option<u32> {
         let grid = self.beat_grid.as_ref()?;
-        let samples_per_beat = grid.samples_per_beat_at_tempo(self.tempo);
-        let position_from_first_beat = self.position - grid.first_beat_offset as f64;
+        let beat = grid.beat_at_position(self.playback_position());
 
-        if position_from_first_beat < 0.0 {
+        if beat < 0.0 {
             return Some(0);
         }
 
-        Some((position_from_first_beat / samples_per_beat).floor() as u32)
+        Some(beat.floor() as u32)
     }
 
-    /// Calculate position offset needed to align phase with target
-    /// Returns the number of samples to nudge (positive = forward, negative = backward)
-    pub fn phase_offset_to_align(&self, target_phase: f32) -> Option<f64> {
+    /// Calculate bar phase (0.0 - 1.0 through the bar from the downbeat)
+    pub fn bar_phase(&self) -> Option<f32> {
         let grid = self.beat_grid.as_ref()?;
-        let current_phase = self.beat_phase()?;
+        Some(grid.bar_phase_at_position(self.playback_position()))
+    }
 
-        // Calculate shortest path to align (can go forward or backward)
-        let mut phase_diff = target_phase - current_phase;
+    /// Get current bar and beat within the bar (0 = downbeat)
+    pub fn current_bar(&self) -> Option<(i32, u32)> {
+        let grid = self.beat_grid.as_ref()?;
+        let position = self.playback_position();
+        Some((
+            grid.bar_at_position(position).floor() as i32,
+            grid.beat_in_bar(position),
+        ))
+    }
 
-        // Normalize to -0.5 to 0.5 (shortest path to alignment)
-        if phase_diff > 0.5 {
-            phase_diff -= 1.0;
-        } else if phase_diff < -0.5 {
-            phase_diff += 1.0;
-        }
+    /// Calculate position offset needed to align bar phase with target
+    /// Returns the number of samples to nudge (positive = forward, negative = backward)
+    pub fn phase_offset_to_align(&self, target_bar_phase: f32) -> Option<f64> {
+        let grid = self.beat_grid.as_ref()?;
+        let current_phase = self.bar_phase()?;
+
+        // Shortest path to alignment: at most half a bar either way
+        let phase_diff = sync::phase_error(target_bar_phase, current_phase);
 
-        // Convert phase difference to samples
-        let samples_per_beat = grid.samples_per_beat_at_tempo(self.tempo);
-        Some(phase_diff as f64 * samples_per_beat)
+        // Convert bar phase difference to samples
+        let beats = phase_diff as f64 * BeatGrid::BEATS_PER_BAR as f64;
+        let samples_per_beat = grid.samples_per_beat_at(self.playback_position());
+        Some(beats * samples_per_beat)
     }
 
     /// Nudge position by a given number of samples
     pub fn nudge_samples(&mut self, samples: f64) {
-        let new_pos = self.position + samples;
+        let new_pos = self.playback_position() + samples;
         let max_pos = self.samples.len() as f64;
-        self.position = new_pos.clamp(0.0, max_pos);
+        self.set_playback_position(new_pos.clamp(0.0, max_pos));
         // Trigger fade-in to prevent click
-        self.fade_in_samples = Self::FADE_IN_SAMPLES;
+        self.start_jump_fade_in();
     }
 
     /// Start a smooth sync transition
+    ///
+    /// Whole beats of the phase offset are jumped straight away, like a
+    /// beatjump (dropped while looping); only the rest is glided.
     pub fn start_sync_transition(
         &mut self,
         target_tempo: f32,
         phase_offset: f64,
         duration_samples: u64,
     ) {
+        let mut phase_offset = phase_offset;
+        if let Some(samples_per_beat) = self.source_samples_per_beat(self.playback_position()) {
+            let jump = (phase_offset / samples_per_beat).round() * samples_per_beat;
+            phase_offset -= jump;
+            if jump != 0.0 && !self.loop_active {
+                let position = self.playback_position() + jump;
+                self.set_playback_position(position.clamp(0.0, self.samples.len() as f64));
+                if let Some(shadow) = &mut self.slip_position {
+                    *shadow += jump;
+                }
+                self.start_jump_fade_in();
+            }
+        }
         self.sync_transition = SyncTransition {
             target_tempo,
             start_tempo: self.tempo,
@@ -538,55 +1458,117 @@ impl Deck {
         self.sync_transition.active
     }
 
-    /// Get deck state for UI
+    /// Original BPM of the track at the playback position (at tempo 1.0)
+    pub fn base_bpm(&self) -> Option<f32> {
+        self.beat_grid
+            .as_ref()
+            .map(|g| g.bpm_at_position(self.playback_position()))
+            .or(self.bpm)
+    }
+
+    /// Record the deck's sync lock role and phase error (for the UI)
+    pub fn set_sync_status(&mut self, role: SyncRole, phase_error: Option<f32>) {
+        self.sync_role = role;
+        self.phase_error = phase_error;
+    }
+
+    /// Current state for the UI
     pub fn state(&self) -> DeckState {
-        let beat_grid_info = self.beat_grid.as_ref().map(|g| {
-            // Convert first beat offset from samples to seconds
-            let sample_rate_stereo = self.sample_rate as f64 * 2.0;
-            let first_beat_offset_secs = g.first_beat_offset as f64 / sample_rate_stereo;
-            BeatGridInfo {
-                bpm: g.bpm,
-                confidence: g.confidence,
-                has_grid: true,
-                first_beat_offset_secs,
+        let mut state = DeckState::default();
+        self.write_state(&mut state);
+        state
+    }
+
+    /// Update `state` in place for the UI (no allocation, so it can run in
+    /// the audio callback)
+    ///
+    /// Returns the waveforms it replaced when the track changed, for the
+    /// caller to free off the audio thread.
+    pub fn write_state(
+        &self,
+        state: &mut DeckState,
+    ) -> Option<(Arc<Vec<f32>>, Arc<EnhancedWaveform>)> {
+        let sample_rate_stereo = self.sample_rate as f64 * 2.0;
+
+        match &self.beat_grid {
+            Some(g) => {
+                let info = state
+                    .beat_grid_info
+                    .get_or_insert_with(BeatGridInfo::default);
+                info.bpm = g.bpm;
+                info.confidence = g.confidence;
+                info.has_grid = true;
+                // Convert first beat offset from samples to seconds
+                info.first_beat_offset_secs = g.first_beat_offset as f64 / sample_rate_stereo;
+                info.tempo_map.assign(
+                    g.segments()
+                        .iter()
+                        .map(|s| (s.start_position / sample_rate_stereo, s.start_beat, s.bpm)),
+                );
+                info.downbeat = g.downbeat;
+                info.phrases.assign(g.phrases().iter().copied());
+                info.phrases_secs.assign(
+                    g.phrases()
+                        .iter()
+                        .map(|&bar| g.position_for_bar(bar as f64) / sample_rate_stereo),
+                );
+                info.edits = self.grid_edits;
             }
-        });
+            None => state.beat_grid_info = None,
+        }
 
         // Convert cue points from sample positions to seconds
-        let sample_rate_stereo = self.sample_rate as f64 * 2.0;
-        let cue_points = self
+        state.cue_points = self
             .cue_points
             .map(|opt| opt.map(|pos| pos / sample_rate_stereo));
 
+        state.loop_info = self.loop_region.map(|r| LoopInfo {
+            start: r.start / sample_rate_stereo,
+            end: r.end / sample_rate_stereo,
+            beats: r.beats,
+            active: self.loop_active,
+            rolling: self.loop_roll.is_some(),
+        });
+        state.slip_position = self.slip_position.map(|pos| pos / sample_rate_stereo);
+
         // Copy scope buffer for oscilloscope display
         // We read from the ring buffer in order, starting from write position
-        let mut scope_samples = Box::new([0.0f32; SCOPE_SAMPLES_SIZE * 2]);
         for i in 0..Self::SCOPE_BUFFER_SIZE {
             let src_idx = (self.scope_write_pos + i) % Self::SCOPE_BUFFER_SIZE;
-            scope_samples[i] = self.scope_buffer[src_idx];
-        }
-
-        DeckState {
-            playback: self.state,
-            position: self.position_secs(),
-            duration: self.duration(),
-            tempo: self.tempo,
-            pitch: self.pitch,
-            gain: self.gain,
-            bpm: self.current_bpm(),
-            key: self.key.clone(),
-            track_name: self.track_name.clone(),
-            spectrum: self.current_spectrum,
-            beat_phase: self.beat_phase().unwrap_or(0.0),
-            beat_grid_info,
-            waveform_overview: self.waveform_overview.clone(),
-            enhanced_waveform: self.enhanced_waveform.clone(),
-            peak_level: self.peak_level,
-            peak_hold: self.peak_hold,
-            is_clipping: self.is_clipping,
-            cue_points,
-            scope_samples,
+            state.scope_samples[i] = self.scope_buffer[src_idx];
         }
+
+        state.playback = self.state;
+        state.position = self.position_secs();
+        state.duration = self.duration();
+        state.tempo = self.tempo;
+        state.key_lock = self.key_lock;
+        state.slip = self.slip;
+        state.reverse = self.reverse;
+        state.pitch = self.pitch;
+        state.gain = self.gain;
+        state.bpm = self.current_bpm();
+        state.key = self.heard_key();
+        state.track_name = self.track_name.as_deref().map(Label::from);
+        state.spectrum = self.current_spectrum;
+        state.beat_phase = self.beat_phase().unwrap_or(0.0);
+        state.bar = self.current_bar().map(|(bar, _)| bar);
+        state.beat_in_bar = self.current_bar().map(|(_, beat)| beat);
+        state.peak_level = self.peak_level;
+        state.peak_hold = self.peak_hold;
+        state.is_clipping = self.is_clipping;
+        state.loop_in = self.loop_in.map(|pos| pos / sample_rate_stereo);
+        state.sync_role = self.sync_role;
+        state.phase_error = self.phase_error;
+
+        let same_track = Arc::ptr_eq(&state.waveform_overview, &self.waveform_overview)
+            && Arc::ptr_eq(&state.enhanced_waveform, &self.enhanced_waveform);
+        (!same_track).then(|| {
+            (
+                std::mem::replace(&mut state.waveform_overview, self.waveform_overview.clone()),
+                std::mem::replace(&mut state.enhanced_waveform, self.enhanced_waveform.clone()),
+            )
+        })
     }
 
     /// Process and return audio samples for output buffer
@@ -607,7 +1589,13 @@ impl Deck {
         // Update sync transition if active
         self.update_sync_transition(output.len() as u64);
 
+        // Stretch time by the inverse of tempo so pitch stays put
+        if self.is_stretching() {
+            self.shifter.set_stretch_ratio(1.0 / self.tempo);
+        }
+
         let sample_count = self.samples.len();
+        let ratio = self.rate_ratio();
 
         // Reuse pre-allocated buffer for spectrum analysis
         self.spectrum_buffer.clear();
@@ -616,7 +1604,7 @@ impl Deck {
         let mut current_peak = 0.0f32;
 
         for frame in output.chunks_mut(2) {
-            let pos = self.position as usize;
+            let pos = self.playback_position() as usize;
 
             // Smooth gain to prevent clicks during volume changes
             self.smoothed_gain = Self::GAIN_SMOOTH_COEFF * self.smoothed_gain
@@ -634,7 +1622,8 @@ impl Deck {
                     if let Some(pending) = self.pending_state.take() {
                         self.state = pending;
                         if pending == PlaybackState::Stopped {
-                            self.position = 0.0;
+                            self.set_playback_position(0.0);
+                            self.prime_vocoder();
                         }
                     }
                 }
@@ -651,8 +1640,13 @@ impl Deck {
             // Combined gain: smoothed gain * fade envelope
             let effective_gain = self.smoothed_gain * fade_envelope;
 
-            if pos + 1 >= sample_count {
-                // End of track - trigger fade-out if not already fading
+            let at_end = if self.reverse {
+                self.position - self.read_ahead() <= 0.0
+            } else {
+                pos + 1 >= sample_count
+            };
+            if at_end {
+                // End of track (or its start, backwards) - trigger fade-out if not already fading
                 if self.fade_out_samples == 0 && self.pending_state.is_none() {
                     self.fade_out_samples = Self::FADE_OUT_SAMPLES;
                     self.pending_state = Some(PlaybackState::Stopped);
@@ -663,31 +1657,25 @@ impl Deck {
                 continue;
             }
 
-            // Linear interpolation for smoother playback at non-integer positions
-            let frac = self.position.fract() as f32;
-            let pos_even = pos & !1; // Ensure we start at left channel
-
-            if pos_even + 3 < sample_count {
-                let l0 = self.samples[pos_even];
-                let r0 = self.samples[pos_even + 1];
-                let l1 = self.samples[pos_even + 2];
-                let r1 = self.samples[pos_even + 3];
-
-                frame[0] = (l0 + frac * (l1 - l0)) * effective_gain;
-                frame[1] = (r0 + frac * (r1 - r0)) * effective_gain;
+            let (left, right) = if self.is_stretching() {
+                self.next_stretched_frame()
             } else {
-                frame[0] = self.samples[pos_even] * effective_gain;
-                frame[1] = self.samples[pos_even + 1] * effective_gain;
+                // Advance position based on tempo (and the track's sample rate)
+                self.next_source_frame(2.0 * self.tempo as f64 * ratio * self.direction())
+            };
+
+            // The track keeps running underneath a slip action
+            if let Some(shadow) = &mut self.slip_position {
+                *shadow += 2.0 * self.tempo as f64 * ratio;
             }
+            frame[0] = left * effective_gain;
+            frame[1] = right * effective_gain;
 
             // Track peak level inline (avoid separate iteration)
             current_peak = current_peak.max(frame[0].abs()).max(frame[1].abs());
 
             // Collect mono samples for spectrum analysis
             self.spectrum_buffer.push((frame[0] + frame[1]) * 0.5);
-
-            // Advance position based on tempo
-            self.position += 2.0 * self.tempo as f64;
         }
 
         // Update spectrum
@@ -722,6 +1710,37 @@ impl Deck {
         }
     }
 
+    /// Read one stereo frame at a (fractional) sample position, moving by
+    /// `step` samples per frame (for the sinc kernel's anti-aliasing)
+    /// Silence outside the track
+    #[inline]
+    fn read_frame(&self, position: f64, step: f64) -> (f32, f32) {
+        if position < 0.0 {
+            return (0.0, 0.0);
+        }
+        // Positions count interleaved samples: two per frame
+        let frame = position * 0.5;
+        let index = frame as usize;
+        let frac = (frame - index as f64) as f32;
+        self.interpolation
+            .read(&self.samples, index, frac, (step.abs() * 0.5) as f32)
+    }
+
+    /// Pull the next output frame from the pitch shifter
+    ///
+    /// Source frames are fed at the original speed (resampled to the output
+    /// rate); the shifter's stretch ratio decides how many output frames
+    /// each hop of input produces.
+    fn next_stretched_frame(&mut self) -> (f32, f32) {
+        loop {
+            if let Some(out) = self.shifter.pop() {
+                return out;
+            }
+            let (left, right) = self.next_source_frame(2.0 * self.rate_ratio() * self.direction());
+            self.shifter.push(left, right);
+        }
+    }
+
     /// Update sync transition state (called from process())
     fn update_sync_transition(&mut self, samples_in_buffer: u64) {
         if !self.sync_transition.active {
@@ -751,10 +1770,15 @@ impl Deck {
             target_offset * eased as f64 - self.sync_transition.applied_phase_offset;
         self.position += offset_to_apply;
         self.sync_transition.applied_phase_offset += offset_to_apply;
+        if let Some(shadow) = &mut self.slip_position {
+            *shadow += offset_to_apply;
+        }
 
-        // Clamp position to valid range
-        let max_pos = self.samples.len() as f64;
-        self.position = self.position.clamp(0.0, max_pos);
+        // Clamp position to valid range (while looping it counts past the loop end)
+        if !self.loop_active {
+            let max_pos = self.samples.len() as f64 + self.read_ahead();
+            self.position = self.position.clamp(0.0, max_pos);
+        }
 
         // Complete transition
         if self.sync_transition.progress >= 1.0 {
@@ -769,3 +1793,428 @@ impl Default for Deck {
         Self::new(44100)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    /// Deck loaded with a stereo sine
+    fn sine_deck(freq: f32, seconds: f32) -> Deck {
+        let frames = (SAMPLE_RATE as f32 * seconds) as usize;
+        let mut samples = Vec::with_capacity(frames * 2);
+        for i in 0..frames {
+            let s = (2.0 * PI * freq * i as f32 / SAMPLE_RATE as f32).sin() * 0.5;
+            samples.push(s);
+            samples.push(s);
+        }
+        let mut deck = Deck::new(SAMPLE_RATE);
+        deck.load(
+            Arc::new(samples),
+            SAMPLE_RATE,
+            None,
+            Arc::new(Vec::new()),
+            Arc::new(EnhancedWaveform::default()),
+            None,
+        );
+        deck
+    }
+
+    /// Render `seconds` of output and return the measured frequency of the
+    /// last half (left channel zero crossings)
+    fn rendered_frequency(deck: &mut Deck, seconds: f32) -> f32 {
+        let mut left = Vec::new();
+        let mut buffer = [0.0f32; 1024];
+        while left.len() < (SAMPLE_RATE as f32 * seconds) as usize {
+            deck.process(&mut buffer);
+            left.extend(buffer.chunks(2).map(|f| f[0]));
+        }
+        let tail = &left[left.len() / 2..];
+        let crossings = tail
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        crossings as f32 / 2.0 / (tail.len() as f32 / SAMPLE_RATE as f32)
+    }
+
+    /// Render `seconds` of output, returning the left channel
+    fn render(deck: &mut Deck, seconds: f32) -> Vec<f32> {
+        let mut left = Vec::new();
+        let mut buffer = [0.0f32; 512];
+        while left.len() < (SAMPLE_RATE as f32 * seconds) as usize {
+            deck.process(&mut buffer);
+            left.extend(buffer.chunks(2).map(|f| f[0]));
+        }
+        left
+    }
+
+    /// Sine deck with a 120 BPM grid starting at 0 (one beat = 0.5s)
+    fn gridded_deck(freq: f32, seconds: f32) -> Deck {
+        let mut deck = sine_deck(freq, seconds);
+        deck.beat_grid = Some(Box::new(BeatGrid::new(120.0, 0, SAMPLE_RATE, 1.0)));
+        deck.bpm = Some(120.0);
+        deck
+    }
+
+    #[test]
+    fn test_reads_between_frames() {
+        // A ramp: left = frame index, right = minus that
+        let samples: Vec<f32> = (0..200).flat_map(|i| [i as f32, -(i as f32)]).collect();
+        let mut deck = Deck::new(SAMPLE_RATE);
+        deck.load(
+            Arc::new(samples),
+            SAMPLE_RATE,
+            None,
+            Arc::new(Vec::new()),
+            Arc::new(EnhancedWaveform::default()),
+            None,
+        );
+        for mode in Interpolation::ALL {
+            deck.set_interpolation(mode);
+            // Positions count interleaved samples: 101.0 is halfway through frame 50
+            for (position, expected) in [(100.0, 50.0), (101.0, 50.5), (102.5, 51.25)] {
+                let (left, right) = deck.read_frame(position, 2.0);
+                assert!((left - expected).abs() < 1e-3, "{:?}: {}", mode, left);
+                assert!((right + expected).abs() < 1e-3, "{:?}: {}", mode, right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_track_at_another_rate_keeps_pitch_and_speed() {
+        // A 44.1 kHz track on a 48 kHz deck
+        let track_rate = 44100;
+        let samples: Vec<f32> = (0..track_rate * 4)
+            .flat_map(|i| {
+                let s = (2.0 * PI * 440.0 * i as f32 / track_rate as f32).sin() * 0.5;
+                [s, s]
+            })
+            .collect();
+        for key_lock in [false, true] {
+            let mut deck = Deck::new(SAMPLE_RATE);
+            deck.load(
+                Arc::new(samples.clone()),
+                track_rate,
+                None,
+                Arc::new(Vec::new()),
+                Arc::new(EnhancedWaveform::default()),
+                None,
+            );
+            deck.set_key_lock(key_lock);
+            deck.play();
+            let freq = rendered_frequency(&mut deck, 1.0);
+            assert!(
+                (freq - 440.0).abs() < 5.0,
+                "key lock {}: {} Hz",
+                key_lock,
+                freq
+            );
+            let secs = deck.position_secs();
+            assert!(
+                (secs - 1.0).abs() < 0.03,
+                "key lock {}: at {}s",
+                key_lock,
+                secs
+            );
+            assert!((deck.duration() - 4.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_auto_loop_snaps_to_grid_and_repeats() {
+        for key_lock in [false, true] {
+            let mut deck = gridded_deck(440.0, 10.0);
+            deck.set_key_lock(key_lock);
+            deck.seek(1.2);
+            deck.auto_loop(1.0);
+
+            let info = deck.state().loop_info.unwrap();
+            assert!((info.start - 1.0).abs() < 1e-9 && (info.end - 1.5).abs() < 1e-9);
+            assert!(info.active);
+
+            deck.play();
+            for _ in 0..20 {
+                render(&mut deck, 0.1);
+                let pos = deck.position_secs();
+                assert!((1.0..1.5).contains(&pos), "position {}", pos);
+            }
+
+            // Same length again exits and plays on
+            deck.auto_loop(1.0);
+            assert!(!deck.state().loop_info.unwrap().active);
+            render(&mut deck, 0.6);
+            assert!(deck.position_secs() > 1.5);
+        }
+    }
+
+    #[test]
+    fn test_beatjump_and_loops_follow_tempo_map() {
+        let mut deck = sine_deck(440.0, 10.0);
+        // 120 BPM for 8 beats, then 150 BPM
+        let grid =
+            BeatGrid::with_tempo_map(120.0, 0, SAMPLE_RATE, 1.0, &[(0.0, 120.0), (8.0, 150.0)]);
+        deck.set_beat_grid(Box::new(grid.clone()));
+
+        deck.set_playback_position(grid.position_for_beat(6.0));
+        deck.beatjump(4);
+        let position = deck.playback_position();
+        assert!((position - grid.position_for_beat(10.0)).abs() < 1.0);
+        assert!(deck.beat_phase().unwrap() < 1e-3);
+        assert_eq!(deck.current_beat_number(), Some(10));
+        assert_eq!(deck.base_bpm(), Some(150.0));
+
+        // A loop across the tempo change still spans whole beats
+        let region = deck.beat_loop_at(grid.position_for_beat(7.0), 2.0).unwrap();
+        assert!((region.end - grid.position_for_beat(9.0)).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_short_loop_with_key_lock_stays_in_loop() {
+        let mut deck = gridded_deck(440.0, 10.0);
+        deck.set_key_lock(true);
+        deck.seek(2.0);
+        deck.auto_loop(1.0 / 32.0);
+        deck.play();
+        for _ in 0..10 {
+            render(&mut deck, 0.05);
+            let pos = deck.position_secs();
+            assert!((2.0..2.016).contains(&pos), "position {}", pos);
+        }
+    }
+
+    #[test]
+    fn test_loop_boundary_does_not_click() {
+        // 1/4 beat at 123 BPM is not a whole number of sine cycles
+        let mut deck = sine_deck(440.0, 10.0);
+        deck.beat_grid = Some(Box::new(BeatGrid::new(123.0, 0, SAMPLE_RATE, 1.0)));
+        deck.seek(1.0);
+        deck.auto_loop(0.25);
+        deck.play();
+        let out = render(&mut deck, 1.0);
+
+        // Skip the initial fade-in; a sine step is ~0.03 at this level
+        let max_step = out[2000..]
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_step < 0.1, "max step {}", max_step);
+    }
+
+    #[test]
+    fn test_manual_loop_halve_double_and_reloop() {
+        let mut deck = gridded_deck(440.0, 10.0);
+        deck.seek(1.0);
+        deck.loop_in();
+        deck.play();
+        render(&mut deck, 0.5);
+        deck.loop_out();
+
+        let info = deck.state().loop_info.unwrap();
+        assert!(info.active && info.beats.is_none());
+        let len = info.end - info.start;
+        assert!((info.start - 1.0).abs() < 1e-9);
+        assert!(deck.position_secs() < info.start + 0.05);
+
+        deck.loop_halve();
+        let info = deck.state().loop_info.unwrap();
+        assert!(((info.end - info.start) - len / 2.0).abs() < 1e-6);
+        deck.loop_double();
+        deck.loop_double();
+        let info = deck.state().loop_info.unwrap();
+        assert!(((info.end - info.start) - len * 2.0).abs() < 1e-6);
+
+        deck.loop_move(1.0);
+        let info = deck.state().loop_info.unwrap();
+        assert!((info.start - 1.5).abs() < 1e-6);
+        assert!((info.start..info.end).contains(&deck.position_secs()));
+
+        deck.reloop();
+        assert!(!deck.state().loop_info.unwrap().active);
+        deck.reloop();
+        assert!(deck.state().loop_info.unwrap().active);
+    }
+
+    #[test]
+    fn test_loop_roll_resumes_on_timeline() {
+        for key_lock in [false, true] {
+            let mut deck = gridded_deck(440.0, 10.0);
+            deck.set_key_lock(key_lock);
+            deck.seek(1.0);
+            deck.play();
+            deck.loop_roll(0.25);
+            assert!(deck.state().loop_info.unwrap().rolling);
+            render(&mut deck, 0.5);
+            assert!(deck.position_secs() < 1.125);
+
+            deck.loop_roll_release();
+            assert!(deck.state().loop_info.is_none());
+            let pos = deck.position_secs();
+            assert!((pos - 1.5).abs() < 0.02, "position {}", pos);
+        }
+    }
+
+    #[test]
+    fn test_slip_loop_returns_to_timeline() {
+        for key_lock in [false, true] {
+            let mut deck = gridded_deck(440.0, 10.0);
+            deck.set_key_lock(key_lock);
+            deck.set_slip(true);
+            deck.seek(1.0);
+            deck.play();
+            deck.auto_loop(0.5);
+            render(&mut deck, 1.0);
+            assert!(deck.position_secs() < 1.25);
+
+            let ghost = deck.state().slip_position.unwrap();
+            assert!((ghost - 2.0).abs() < 0.02, "ghost {}", ghost);
+
+            deck.exit_loop();
+            assert!(deck.state().slip_position.is_none());
+            assert!(!deck.state().loop_info.unwrap().active);
+            let pos = deck.position_secs();
+            assert!((pos - 2.0).abs() < 0.02, "position {}", pos);
+        }
+    }
+
+    #[test]
+    fn test_slip_cue_jump_returns_on_release() {
+        let mut deck = gridded_deck(440.0, 10.0);
+        deck.seek(1.0);
+        deck.set_cue(1);
+        deck.seek(3.0);
+        deck.play();
+
+        // Without slip mode the jump sticks
+        deck.jump_cue(1);
+        deck.slip_release();
+        assert!((deck.position_secs() - 1.0).abs() < 0.01);
+
+        deck.set_slip(true);
+        deck.seek(3.0);
+        deck.jump_cue(1);
+        render(&mut deck, 0.5);
+        assert!((deck.position_secs() - 1.5).abs() < 0.02);
+        deck.slip_release();
+        let pos = deck.position_secs();
+        assert!((pos - 3.5).abs() < 0.02, "position {}", pos);
+    }
+
+    #[test]
+    fn test_reverse_plays_backwards() {
+        for key_lock in [false, true] {
+            let mut deck = gridded_deck(440.0, 10.0);
+            deck.set_key_lock(key_lock);
+            deck.seek(3.0);
+            deck.play();
+            deck.set_reverse(true);
+            assert!((deck.position_secs() - 3.0).abs() < 0.01);
+            render(&mut deck, 1.0);
+            let pos = deck.position_secs();
+            assert!((pos - 2.0).abs() < 0.02, "position {}", pos);
+
+            // Slip reverse: playback resumes where the track would be
+            deck.set_reverse(false);
+            deck.set_slip(true);
+            deck.set_reverse(true);
+            render(&mut deck, 0.5);
+            deck.set_reverse(false);
+            let pos = deck.position_secs();
+            assert!((pos - 2.5).abs() < 0.02, "position {}", pos);
+        }
+    }
+
+    #[test]
+    fn test_reverse_stops_at_track_start() {
+        let mut deck = sine_deck(440.0, 3.0);
+        deck.seek(0.2);
+        deck.play();
+        deck.set_reverse(true);
+        render(&mut deck, 0.5);
+        assert_eq!(deck.state().playback, PlaybackState::Stopped);
+        assert_eq!(deck.position_secs(), 0.0);
+    }
+
+    #[test]
+    fn test_tempo_shifts_pitch_without_key_lock() {
+        let mut deck = sine_deck(440.0, 3.0);
+        deck.set_tempo(1.25);
+        deck.play();
+        let freq = rendered_frequency(&mut deck, 1.0);
+        assert!((freq / 550.0 - 1.0).abs() < 0.02, "frequency {}", freq);
+    }
+
+    #[test]
+    fn test_key_lock_keeps_pitch() {
+        let mut deck = sine_deck(440.0, 3.0);
+        deck.set_key_lock(true);
+        deck.set_tempo(1.25);
+        deck.play();
+        let start = deck.position_secs();
+        let freq = rendered_frequency(&mut deck, 1.0);
+        assert!((freq / 440.0 - 1.0).abs() < 0.02, "frequency {}", freq);
+
+        // Still moves through the track at the faster tempo
+        let elapsed = deck.position_secs() - start;
+        assert!((elapsed - 1.25).abs() < 0.05, "elapsed {}", elapsed);
+        assert!(deck.state().key_lock);
+    }
+
+    #[test]
+    fn test_key_lock_toggle_keeps_position() {
+        let mut deck = sine_deck(440.0, 3.0);
+        deck.seek(1.0);
+        deck.toggle_key_lock();
+        assert!((deck.position_secs() - 1.0).abs() < 1e-9);
+        deck.toggle_key_lock();
+        assert!((deck.position_secs() - 1.0).abs() < 1e-9);
+        assert!(!deck.key_lock());
+    }
+
+    #[test]
+    fn test_pitch_shift_keeps_tempo() {
+        let mut deck = sine_deck(440.0, 3.0);
+        deck.set_pitch(12.0);
+        deck.play();
+        let start = deck.position_secs();
+        let freq = rendered_frequency(&mut deck, 1.0);
+        assert!((freq / 880.0 - 1.0).abs() < 0.02, "frequency {}", freq);
+
+        let elapsed = deck.position_secs() - start;
+        assert!((elapsed - 1.0).abs() < 0.05, "elapsed {}", elapsed);
+    }
+
+    #[test]
+    fn test_pitch_rounds_to_cents_and_clamps() {
+        let mut deck = Deck::new(SAMPLE_RATE);
+        deck.set_pitch(1.234);
+        assert!((deck.pitch() - 1.23).abs() < 1e-6);
+        deck.adjust_pitch(20.0);
+        assert_eq!(deck.pitch(), 12.0);
+        deck.set_pitch(0.0);
+        assert_eq!(deck.state().pitch, 0.0);
+    }
+
+    #[test]
+    fn test_shown_key_follows_pitch_and_tempo() {
+        let mut deck = sine_deck(440.0, 1.0);
+        deck.key = Some("8A".to_string());
+
+        deck.set_pitch(7.0);
+        assert_eq!(deck.state().key.as_deref(), Some("9A"));
+
+        // Key lock keeps the shifted key whatever the tempo
+        deck.set_key_lock(true);
+        deck.set_tempo(1.06);
+        assert_eq!(deck.state().key.as_deref(), Some("9A"));
+
+        // Without key lock or pitch, tempo moves the key
+        deck.set_key_lock(false);
+        deck.set_pitch(0.0);
+        deck.set_tempo(1.06); // ~ +1 semitone
+        assert_eq!(deck.state().key.as_deref(), Some("3A"));
+        deck.set_tempo(1.0);
+        assert_eq!(deck.state().key.as_deref(), Some("8A"));
+    }
+}
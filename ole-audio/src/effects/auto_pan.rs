@@ -0,0 +1,307 @@
+//! Auto-pan effect - LFO-driven stereo panning
+//!
+//! Pans the stereo image back and forth with a constant-power law, so the
+//! level stays even as the sound moves. Hard LFO shapes are smoothed a
+//! little to avoid clicks at the edges.
+
+use super::beat_clock::LfoSync;
+use super::{Effect, EffectParam};
+use std::f32::consts::{FRAC_PI_4, PI, SQRT_2};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("rate", 0.05, 10.0),
+    EffectParam::new("depth", 0.0, 1.0),
+    EffectParam::new("shape", 0.0, 3.0),
+    EffectParam::new("beats", 0.0, AutoPan::MAX_SYNC_BEATS),
+];
+
+/// LFO waveform for the auto-pan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum LfoShape {
+    /// Smooth sweep
+    #[default]
+    Sine,
+    /// Linear sweep
+    Triangle,
+    /// Hard left/right switching
+    Square,
+    /// Ramp across, jump back
+    Saw,
+}
+
+impl LfoShape {
+    /// Shapes in parameter order
+    const ALL: [LfoShape; 4] = [
+        LfoShape::Sine,
+        LfoShape::Triangle,
+        LfoShape::Square,
+        LfoShape::Saw,
+    ];
+
+    /// Shape for a parameter value (0 = sine ... 3 = saw)
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    /// Parameter value of the shape
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// LFO value (-1.0 to 1.0) at a phase (0.0 - 1.0)
+    #[inline]
+    fn value(self, phase: f32) -> f32 {
+        match self {
+            LfoShape::Sine => (phase * 2.0 * PI).sin(),
+            LfoShape::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::Saw => 2.0 * phase - 1.0,
+        }
+    }
+}
+
+/// Auto-pan with selectable LFO shape
+pub struct AutoPan {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// LFO rate in Hz (0.05 - 10.0)
+    rate: f32,
+
+    /// Pan depth (0.0 = centred, 1.0 = hard left to hard right)
+    depth: f32,
+
+    /// LFO waveform
+    shape: LfoShape,
+
+    /// LFO phase (0.0 - 1.0)
+    lfo_phase: f32,
+
+    /// Smoothed pan position (-1.0 to 1.0)
+    pan: f32,
+
+    /// Wet envelope for click-free enable/disable
+    wet_target: f32,
+    wet_current: f32,
+
+    /// LFO locked to the deck's tempo
+    sync: LfoSync,
+}
+
+impl AutoPan {
+    /// Wet envelope smoothing coefficient
+    const WET_SMOOTH_COEFF: f32 = 0.9995;
+
+    /// Pan smoothing coefficient (takes the edge off square and saw steps)
+    const PAN_SMOOTH_COEFF: f32 = 0.995;
+
+    /// Shortest and longest beat-synced cycle (1/8 to 16 beats)
+    const MIN_SYNC_BEATS: f32 = 0.125;
+    const MAX_SYNC_BEATS: f32 = 16.0;
+
+    /// Create a new auto-pan effect
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            enabled: false,
+            sample_rate,
+            rate: 1.0,
+            depth: 0.8,
+            shape: LfoShape::default(),
+            lfo_phase: 0.0,
+            pan: 0.0,
+            wet_target: 0.0,
+            wet_current: 0.0,
+            sync: LfoSync::new(Self::MIN_SYNC_BEATS, Self::MAX_SYNC_BEATS),
+        }
+    }
+
+    /// Set LFO rate in Hz (0.05 - 10.0), stops following the beat
+    pub fn set_rate(&mut self, rate: f32) {
+        self.sync.set_beats(None);
+        self.rate = rate.clamp(0.05, 10.0);
+    }
+
+    /// Get LFO rate
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Set pan depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Get depth
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Set LFO shape
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// Get LFO shape
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    /// Lock one LFO cycle to the deck's beats (1/8 - 16 beats), or free
+    /// it with None. Without a tempo the current rate is kept.
+    pub fn set_sync_beats(&mut self, beats: Option<f32>) {
+        self.sync.set_beats(beats);
+        self.update_sync();
+    }
+
+    /// LFO cycle length in beats, if synced
+    pub fn sync_beats(&self) -> Option<f32> {
+        self.sync.beats()
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(rate) = self.sync.rate(10.0) {
+            self.rate = rate;
+        }
+    }
+}
+
+impl Effect for AutoPan {
+    fn process(&mut self, samples: &mut [f32]) {
+        // Skip if fully disabled and envelope settled
+        if !self.enabled && self.wet_current < 0.0001 {
+            return;
+        }
+
+        let lfo_inc = self.rate / self.sample_rate;
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            // Smooth wet envelope
+            self.wet_current = Self::WET_SMOOTH_COEFF * self.wet_current
+                + (1.0 - Self::WET_SMOOTH_COEFF) * self.wet_target;
+
+            let target = self.shape.value(self.lfo_phase) * self.depth * self.wet_current;
+            self.pan = Self::PAN_SMOOTH_COEFF * self.pan + (1.0 - Self::PAN_SMOOTH_COEFF) * target;
+
+            self.lfo_phase += lfo_inc;
+            if self.lfo_phase >= 1.0 {
+                self.lfo_phase -= 1.0;
+            }
+
+            // Constant-power pan, scaled so the centre is unity gain
+            let angle = (self.pan + 1.0) * FRAC_PI_4;
+            frame[0] *= angle.cos() * SQRT_2;
+            frame[1] *= angle.sin() * SQRT_2;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo_phase = 0.0;
+        self.pan = 0.0;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.wet_target = if enabled { 1.0 } else { 0.0 };
+    }
+
+    fn name(&self) -> &'static str {
+        "Auto Pan"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.rate()),
+            1 => Some(self.depth()),
+            2 => Some(self.shape().index() as f32),
+            3 => Some(self.sync.beats().unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_shape(LfoShape::from_index(value.round().max(0.0) as usize)),
+            // 0 = free-running
+            3 => self.set_sync_beats((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if self.sync.set_tempo(bpm) {
+            self.update_sync();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_pan_creation() {
+        let pan = AutoPan::new(48000.0);
+        assert!(!pan.is_enabled());
+        assert_eq!(pan.shape(), LfoShape::Sine);
+    }
+
+    #[test]
+    fn test_lfo_shapes() {
+        for shape in LfoShape::ALL {
+            assert_eq!(LfoShape::from_index(shape.index()), shape);
+            for i in 0..100 {
+                let v = shape.value(i as f32 / 100.0);
+                assert!((-1.0..=1.0).contains(&v), "{:?} {}", shape, v);
+            }
+        }
+        assert!((LfoShape::Triangle.value(0.25) - 1.0).abs() < 1e-6);
+        assert!((LfoShape::Triangle.value(0.75) + 1.0).abs() < 1e-6);
+        assert_eq!(LfoShape::Square.value(0.75), -1.0);
+    }
+
+    #[test]
+    fn test_auto_pan_moves_the_image() {
+        let mut pan = AutoPan::new(48000.0);
+        pan.set_enabled(true);
+        pan.wet_current = 1.0; // Force wet for test
+        pan.set_shape(LfoShape::Square);
+        pan.set_depth(1.0);
+        pan.set_rate(1.0);
+
+        // First half of the cycle pans hard to one side
+        let mut samples = vec![1.0f32; 48000];
+        pan.process(&mut samples);
+        let (l, r) = (samples[40000], samples[40001]);
+        assert!(l < 0.05, "left {}", l);
+        assert!((r - SQRT_2).abs() < 0.05, "right {}", r);
+    }
+
+    #[test]
+    fn test_rate_locks_to_beats() {
+        let mut pan = AutoPan::new(48000.0);
+        pan.set_sync_beats(Some(0.5));
+        pan.set_tempo(120.0);
+        // Half a beat at 120 BPM = 4 Hz
+        assert!((pan.rate() - 4.0).abs() < 1e-6);
+    }
+}
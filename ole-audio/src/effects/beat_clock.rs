@@ -0,0 +1,193 @@
+//! Beat clock - a deck's beat grid position, sample by sample
+//!
+//! The engine hands effects the grid position once per buffer; the clock
+//! advances it at the deck's tempo for every frame in between. Without a
+//! grid (or while the deck is stopped) it keeps counting on its own.
+//!
+//! `LfoSync` is the tempo side only: modulation effects use it to lock
+//! their LFO cycle to a number of beats or bars.
+
+/// Tempo used when the deck has none
+pub(crate) const FALLBACK_BPM: f32 = 120.0;
+
+/// Slowest rate of a tempo-locked LFO in Hz (long cycles at slow tempos go
+/// below the free-running minimum)
+const MIN_SYNC_RATE: f32 = 0.005;
+
+/// Position in beats for grid-locked effects
+pub(crate) struct BeatClock {
+    sample_rate: f32,
+    bpm: f32,
+    beat: f64,
+    locked: bool,
+}
+
+impl BeatClock {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            bpm: 0.0,
+            beat: 0.0,
+            locked: false,
+        }
+    }
+
+    /// Deck tempo in BPM (0.0 = unknown, counts at 120)
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.bpm = bpm;
+    }
+
+    /// Deck tempo, or the fallback without one
+    pub fn bpm(&self) -> f32 {
+        if self.bpm > 0.0 {
+            self.bpm
+        } else {
+            FALLBACK_BPM
+        }
+    }
+
+    /// Jump to the grid position at the start of a buffer, or free-run
+    pub fn set_beat(&mut self, beat: Option<f64>) {
+        self.locked = beat.is_some();
+        if let Some(beat) = beat {
+            self.beat = beat;
+        }
+    }
+
+    /// Whether the position comes from the deck's grid
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Frames (stereo pairs) per beat at the current tempo
+    pub fn frames_per_beat(&self) -> f32 {
+        self.sample_rate * 60.0 / self.bpm()
+    }
+
+    /// The current position, then advance one frame
+    #[inline]
+    pub fn tick(&mut self) -> f64 {
+        let beat = self.beat;
+        self.beat += self.bpm() as f64 / 60.0 / self.sample_rate as f64;
+        beat
+    }
+
+    pub fn reset(&mut self) {
+        self.beat = 0.0;
+    }
+}
+
+/// An LFO cycle locked to the deck's tempo, in beats
+///
+/// Without a tempo the effect keeps its current rate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LfoSync {
+    /// One LFO cycle in beats (None = free-running)
+    beats: Option<f32>,
+    /// Deck tempo in BPM (0.0 = unknown)
+    bpm: f32,
+    min_beats: f32,
+    max_beats: f32,
+}
+
+impl LfoSync {
+    /// Shortest and longest bar-locked cycle (1/4 to 16 bars of 4 beats)
+    pub const MIN_BARS: f32 = 0.25;
+    pub const MAX_BARS: f32 = 16.0;
+
+    /// Cycles of `min_beats` to `max_beats`, free-running to start with
+    pub fn new(min_beats: f32, max_beats: f32) -> Self {
+        Self {
+            beats: None,
+            bpm: 0.0,
+            min_beats,
+            max_beats,
+        }
+    }
+
+    /// Cycles of `MIN_BARS` to `MAX_BARS`
+    pub fn for_bars() -> Self {
+        Self::new(Self::MIN_BARS * 4.0, Self::MAX_BARS * 4.0)
+    }
+
+    /// Lock one cycle to `beats` (clamped to the range), or free it with None
+    pub fn set_beats(&mut self, beats: Option<f32>) {
+        self.beats = beats.map(|b| b.clamp(self.min_beats, self.max_beats));
+    }
+
+    /// Cycle length in beats, if locked
+    pub fn beats(&self) -> Option<f32> {
+        self.beats
+    }
+
+    /// Lock one cycle to `bars` of 4 beats, or free it with None
+    pub fn set_bars(&mut self, bars: Option<f32>) {
+        self.set_beats(bars.map(|b| b * 4.0));
+    }
+
+    /// Cycle length in bars, if locked
+    pub fn bars(&self) -> Option<f32> {
+        self.beats.map(|b| b / 4.0)
+    }
+
+    /// Follow the deck's tempo (0.0 = unknown); true if it changed
+    pub fn set_tempo(&mut self, bpm: f32) -> bool {
+        let changed = bpm != self.bpm;
+        self.bpm = bpm;
+        changed
+    }
+
+    /// LFO rate in Hz (at most `max_rate`), when locked and the deck has
+    /// a tempo
+    pub fn rate(&self, max_rate: f32) -> Option<f32> {
+        let beats = self.beats.filter(|_| self.bpm > 0.0)?;
+        Some((self.bpm / 60.0 / beats).clamp(MIN_SYNC_RATE, max_rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfo_sync_follows_the_tempo() {
+        let mut sync = LfoSync::for_bars();
+        sync.set_bars(Some(2.0));
+        assert_eq!(sync.rate(5.0), None);
+
+        // Two bars at 120 BPM = 4 seconds
+        assert!(sync.set_tempo(120.0));
+        assert!(!sync.set_tempo(120.0));
+        assert_eq!(sync.rate(5.0), Some(0.25));
+        assert_eq!(sync.bars(), Some(2.0));
+
+        // Clamped to the range, and to the slowest rate
+        sync.set_bars(Some(64.0));
+        assert_eq!(sync.bars(), Some(LfoSync::MAX_BARS));
+        sync.set_tempo(10.0);
+        assert_eq!(sync.rate(5.0), Some(MIN_SYNC_RATE));
+
+        sync.set_beats(None);
+        assert_eq!(sync.rate(5.0), None);
+    }
+
+    #[test]
+    fn test_clock_counts_beats() {
+        let mut clock = BeatClock::new(48000.0);
+        clock.set_tempo(120.0);
+        clock.set_beat(Some(8.0));
+        assert!(clock.is_locked());
+
+        // Half a second at 120 BPM is one beat
+        for _ in 0..24000 {
+            clock.tick();
+        }
+        assert!((clock.tick() - 9.0).abs() < 1e-6);
+
+        // Free-running at the fallback tempo without a grid
+        clock.set_beat(None);
+        clock.set_tempo(0.0);
+        assert!(!clock.is_locked());
+        assert_eq!(clock.frames_per_beat(), 24000.0);
+    }
+}
@@ -3,7 +3,14 @@
 //! Reduces bit depth and sample rate for that crunchy retro sound.
 //! Perfect for adding grit and character to digital tracks.
 
-use super::Effect;
+use super::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("bits", 1.0, 16.0),
+    EffectParam::new("downsample", 1.0, 50.0),
+    EffectParam::new("mix", 0.0, 1.0),
+    EffectParam::new("jitter", 0.0, 1.0),
+];
 
 /// Bitcrusher effect with bit depth and sample rate reduction
 pub struct Bitcrusher {
@@ -186,6 +193,30 @@ impl Effect for Bitcrusher {
     fn name(&self) -> &'static str {
         "Bitcrusher"
     }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.bits() as f32),
+            1 => Some(self.downsample() as f32),
+            2 => Some(self.mix()),
+            3 => Some(self.jitter()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_bits(value.round().clamp(1.0, 16.0) as u8),
+            1 => self.set_downsample(value.round().clamp(1.0, 50.0) as u8),
+            2 => self.set_mix(value),
+            3 => self.set_jitter(value),
+            _ => {}
+        }
+    }
 }
 
 #[cfg(test)]
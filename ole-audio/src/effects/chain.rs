@@ -0,0 +1,562 @@
+//! Effect chain - a deck's effects as an ordered list of slots
+//!
+//! Each slot holds a boxed `Effect` and a wet/dry mix. Slots can be added,
+//! removed and reordered at runtime; parameters are addressed by slot and
+//! parameter index, so the chain doesn't need to know what it holds.
+
+use super::{
+    AutoPan, Bitcrusher, Chorus, ConvolutionReverb, DeckFilter, Delay, EchoOut, Effect,
+    EffectParam, Flanger, Gate, NoiseSweep, Phaser, Reverb, Stutter, TapeStop,
+};
+use crate::fixed::FixedVec;
+use crate::vinyl::VinylEmulator;
+use std::any::Any;
+
+/// Most slots a chain holds (reserved up front, so inserting never allocates)
+pub const MAX_EFFECT_SLOTS: usize = 16;
+
+/// Most parameters an effect exposes (so slot state fits inline)
+pub const MAX_EFFECT_PARAMS: usize = 8;
+
+/// Frames blended per pass when a slot's mix is below 1.0
+const DRY_BUFFER_SIZE: usize = 4096;
+
+/// Effects that can be added to a chain by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectKind {
+    TapeStop,
+    Vinyl,
+    Stutter,
+    Bitcrusher,
+    Filter,
+    Gate,
+    Phaser,
+    Flanger,
+    Chorus,
+    AutoPan,
+    NoiseSweep,
+    EchoOut,
+    Delay,
+    Convolution,
+    Reverb,
+}
+
+impl EffectKind {
+    /// Every effect, in the default chain order
+    pub const ALL: [EffectKind; 15] = [
+        EffectKind::TapeStop,
+        EffectKind::Vinyl,
+        EffectKind::Stutter,
+        EffectKind::Bitcrusher,
+        EffectKind::Filter,
+        EffectKind::Gate,
+        EffectKind::Phaser,
+        EffectKind::Flanger,
+        EffectKind::Chorus,
+        EffectKind::AutoPan,
+        EffectKind::NoiseSweep,
+        EffectKind::EchoOut,
+        EffectKind::Delay,
+        EffectKind::Convolution,
+        EffectKind::Reverb,
+    ];
+
+    /// Short lowercase name (for commands and config)
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectKind::TapeStop => "tapestop",
+            EffectKind::Vinyl => "vinyl",
+            EffectKind::Stutter => "stutter",
+            EffectKind::Bitcrusher => "bitcrusher",
+            EffectKind::Filter => "filter",
+            EffectKind::Gate => "gate",
+            EffectKind::Phaser => "phaser",
+            EffectKind::Flanger => "flanger",
+            EffectKind::Chorus => "chorus",
+            EffectKind::AutoPan => "autopan",
+            EffectKind::NoiseSweep => "noisesweep",
+            EffectKind::EchoOut => "echoout",
+            EffectKind::Delay => "delay",
+            EffectKind::Convolution => "convolution",
+            EffectKind::Reverb => "reverb",
+        }
+    }
+
+    /// Parse a name ("delay", "crush", ...)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "tapestop" | "tape" => Some(EffectKind::TapeStop),
+            "vinyl" => Some(EffectKind::Vinyl),
+            "stutter" | "repeat" => Some(EffectKind::Stutter),
+            "bitcrusher" | "crush" => Some(EffectKind::Bitcrusher),
+            "filter" | "filt" => Some(EffectKind::Filter),
+            "gate" | "trans" => Some(EffectKind::Gate),
+            "phaser" | "phase" => Some(EffectKind::Phaser),
+            "flanger" | "flange" => Some(EffectKind::Flanger),
+            "chorus" => Some(EffectKind::Chorus),
+            "autopan" | "pan" => Some(EffectKind::AutoPan),
+            "noisesweep" | "noise" | "riser" => Some(EffectKind::NoiseSweep),
+            "echoout" | "echo" => Some(EffectKind::EchoOut),
+            "delay" | "dly" => Some(EffectKind::Delay),
+            "convolution" | "conv" | "ir" => Some(EffectKind::Convolution),
+            "reverb" | "verb" => Some(EffectKind::Reverb),
+            _ => None,
+        }
+    }
+
+    /// Create the effect (allocates its buffers - keep off the audio thread)
+    pub fn build(self, sample_rate: u32) -> Box<dyn Effect> {
+        let sr = sample_rate as f32;
+        match self {
+            EffectKind::TapeStop => Box::new(TapeStop::new(sr)),
+            EffectKind::Vinyl => Box::new(VinylEmulator::new(sr)),
+            EffectKind::Stutter => Box::new(Stutter::new(sr)),
+            EffectKind::Bitcrusher => Box::new(Bitcrusher::new(sr)),
+            EffectKind::Filter => Box::new(DeckFilter::new(sr)),
+            EffectKind::Gate => Box::new(Gate::new(sr)),
+            EffectKind::Phaser => Box::new(Phaser::new(sr)),
+            EffectKind::Flanger => Box::new(Flanger::new(sr)),
+            EffectKind::Chorus => Box::new(Chorus::new(sr)),
+            EffectKind::AutoPan => Box::new(AutoPan::new(sr)),
+            EffectKind::NoiseSweep => Box::new(NoiseSweep::new(sr)),
+            EffectKind::EchoOut => Box::new(EchoOut::new(sr)),
+            EffectKind::Delay => Box::new(Delay::new(sample_rate)),
+            EffectKind::Convolution => Box::new(ConvolutionReverb::new()),
+            EffectKind::Reverb => Box::new(Reverb::new(sample_rate)),
+        }
+    }
+
+    /// Whether `effect` is one of this kind
+    pub fn is(self, effect: &dyn Effect) -> bool {
+        let effect = effect as &dyn Any;
+        match self {
+            EffectKind::TapeStop => effect.is::<TapeStop>(),
+            EffectKind::Vinyl => effect.is::<VinylEmulator>(),
+            EffectKind::Stutter => effect.is::<Stutter>(),
+            EffectKind::Bitcrusher => effect.is::<Bitcrusher>(),
+            EffectKind::Filter => effect.is::<DeckFilter>(),
+            EffectKind::Gate => effect.is::<Gate>(),
+            EffectKind::Phaser => effect.is::<Phaser>(),
+            EffectKind::Flanger => effect.is::<Flanger>(),
+            EffectKind::Chorus => effect.is::<Chorus>(),
+            EffectKind::AutoPan => effect.is::<AutoPan>(),
+            EffectKind::NoiseSweep => effect.is::<NoiseSweep>(),
+            EffectKind::EchoOut => effect.is::<EchoOut>(),
+            EffectKind::Delay => effect.is::<Delay>(),
+            EffectKind::Convolution => effect.is::<ConvolutionReverb>(),
+            EffectKind::Reverb => effect.is::<Reverb>(),
+        }
+    }
+}
+
+/// One effect in a chain
+pub struct EffectSlot {
+    effect: Box<dyn Effect>,
+    /// Wet/dry mix (0.0 = bypassed, 1.0 = effect only)
+    mix: f32,
+}
+
+impl EffectSlot {
+    pub fn new(effect: Box<dyn Effect>) -> Self {
+        Self { effect, mix: 1.0 }
+    }
+
+    pub fn effect(&self) -> &dyn Effect {
+        self.effect.as_ref()
+    }
+
+    pub fn effect_mut(&mut self) -> &mut dyn Effect {
+        self.effect.as_mut()
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Snapshot for the UI
+    pub fn state(&self) -> EffectSlotState {
+        let params = self.effect.params();
+        EffectSlotState {
+            name: self.effect.name(),
+            enabled: self.effect.is_enabled(),
+            mix: self.mix,
+            params,
+            values: (0..params.len())
+                .map(|i| self.effect.param(i).unwrap_or_default())
+                .collect(),
+        }
+    }
+}
+
+/// A slot as the UI sees it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EffectSlotState {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub mix: f32,
+    pub params: &'static [EffectParam],
+    /// Current value of each of `params`
+    pub values: FixedVec<f32, MAX_EFFECT_PARAMS>,
+}
+
+impl EffectSlotState {
+    /// Index of a parameter by name
+    pub fn param_index(&self, name: &str) -> Option<usize> {
+        self.params
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// An ordered list of effect slots, processed first to last
+pub struct EffectChain {
+    slots: Vec<EffectSlot>,
+    // Dry copy for slots mixed below 1.0 (pre-allocated)
+    dry: Vec<f32>,
+}
+
+impl Default for EffectChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EffectChain {
+    /// An empty chain
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::with_capacity(MAX_EFFECT_SLOTS),
+            dry: vec![0.0; DRY_BUFFER_SIZE],
+        }
+    }
+
+    /// A chain of `kinds`, in order
+    pub fn with_effects(kinds: &[EffectKind], sample_rate: u32) -> Self {
+        let mut chain = Self::new();
+        for kind in kinds {
+            chain.push(kind.build(sample_rate));
+        }
+        chain
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn slots(&self) -> &[EffectSlot] {
+        &self.slots
+    }
+
+    pub fn slot_mut(&mut self, index: usize) -> Option<&mut EffectSlot> {
+        self.slots.get_mut(index)
+    }
+
+    /// Add an effect at the end; false if the chain is full
+    pub fn push(&mut self, effect: Box<dyn Effect>) -> bool {
+        self.insert(self.slots.len(), effect)
+    }
+
+    /// Insert an effect before slot `index` (past the end appends);
+    /// false if the chain is full
+    pub fn insert(&mut self, index: usize, effect: Box<dyn Effect>) -> bool {
+        if self.slots.len() >= MAX_EFFECT_SLOTS {
+            return false;
+        }
+        let index = index.min(self.slots.len());
+        self.slots.insert(index, EffectSlot::new(effect));
+        true
+    }
+
+    /// Take an effect out of the chain
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn Effect>> {
+        (index < self.slots.len()).then(|| self.slots.remove(index).effect)
+    }
+
+    /// Move slot `from` to position `to` (past the end moves it last)
+    pub fn move_slot(&mut self, from: usize, to: usize) {
+        if from < self.slots.len() {
+            let slot = self.slots.remove(from);
+            let to = to.min(self.slots.len());
+            self.slots.insert(to, slot);
+        }
+    }
+
+    /// The first effect of type `T`
+    pub fn find<T: Effect>(&self) -> Option<&T> {
+        self.slots
+            .iter()
+            .find_map(|slot| (slot.effect.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    /// Slot of the first effect of `kind`
+    pub fn position(&self, kind: EffectKind) -> Option<usize> {
+        self.slots.iter().position(|slot| kind.is(slot.effect()))
+    }
+
+    /// Rebuild every effect for a new sample rate (allocates - keep off
+    /// the audio thread)
+    ///
+    /// Slot order, mix, parameters and enabled state carry over; `carry`
+    /// copies anything else an effect keeps from the old one to the new
+    /// one. Effects that aren't an `EffectKind` stay as they are.
+    pub fn rebuild(
+        &mut self,
+        sample_rate: u32,
+        mut carry: impl FnMut(&dyn Effect, &mut dyn Effect),
+    ) {
+        for slot in &mut self.slots {
+            let Some(kind) = EffectKind::ALL
+                .into_iter()
+                .find(|kind| kind.is(slot.effect()))
+            else {
+                continue;
+            };
+            let old = slot.effect.as_ref();
+            let mut effect = kind.build(sample_rate);
+            carry(old, effect.as_mut());
+            for index in 0..old.params().len() {
+                if let Some(value) = old.param(index) {
+                    effect.set_param(index, value);
+                }
+            }
+            effect.set_enabled(old.is_enabled());
+            slot.effect = effect;
+        }
+    }
+
+    /// The first effect of type `T`, for changes
+    pub fn find_mut<T: Effect>(&mut self) -> Option<&mut T> {
+        self.slots
+            .iter_mut()
+            .find_map(|slot| (slot.effect.as_mut() as &mut dyn Any).downcast_mut::<T>())
+    }
+
+    /// Snapshot of every slot for the UI
+    pub fn state(&self) -> FixedVec<EffectSlotState, MAX_EFFECT_SLOTS> {
+        self.slots.iter().map(EffectSlot::state).collect()
+    }
+
+    /// Run audio through every slot in order
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        let Self { slots, dry } = self;
+        for slot in slots.iter_mut() {
+            if slot.mix >= 1.0 {
+                slot.effect.process(buffer);
+                continue;
+            }
+            // Blend with the dry signal, a dry buffer at a time
+            for chunk in buffer.chunks_mut(dry.len()) {
+                let dry = &mut dry[..chunk.len()];
+                dry.copy_from_slice(chunk);
+                slot.effect.process(chunk);
+                for (out, dry) in chunk.iter_mut().zip(dry.iter()) {
+                    *out = dry + (*out - dry) * slot.mix;
+                }
+            }
+        }
+    }
+
+    /// Pass the deck's tempo (BPM, 0.0 if unknown) to every effect
+    pub fn set_tempo(&mut self, bpm: f32) {
+        for slot in &mut self.slots {
+            slot.effect.set_tempo(bpm);
+        }
+    }
+
+    /// Pass the deck's beat grid position (None if unknown) to every effect
+    pub fn set_beat(&mut self, beat: Option<f64>) {
+        for slot in &mut self.slots {
+            slot.effect.set_beat(beat);
+        }
+    }
+
+    /// Clear every effect's state (delay lines, reverb tails, ...)
+    pub fn reset(&mut self) {
+        for slot in &mut self.slots {
+            slot.effect.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Multiplies by a factor (for checking order and mix)
+    struct Gain(f32);
+
+    impl Effect for Gain {
+        fn process(&mut self, samples: &mut [f32]) {
+            for s in samples {
+                *s *= self.0;
+            }
+        }
+        fn reset(&mut self) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn name(&self) -> &'static str {
+            "Gain"
+        }
+    }
+
+    /// Adds an offset
+    struct Offset(f32);
+
+    impl Effect for Offset {
+        fn process(&mut self, samples: &mut [f32]) {
+            for s in samples {
+                *s += self.0;
+            }
+        }
+        fn reset(&mut self) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn name(&self) -> &'static str {
+            "Offset"
+        }
+    }
+
+    #[test]
+    fn test_order_and_reorder() {
+        let mut chain = EffectChain::new();
+        chain.push(Box::new(Gain(2.0)));
+        chain.push(Box::new(Offset(1.0)));
+
+        let mut buffer = [1.0f32; 4];
+        chain.process(&mut buffer);
+        assert_eq!(buffer[0], 3.0); // 1 * 2 + 1
+
+        chain.move_slot(1, 0);
+        assert_eq!(chain.slots()[0].effect().name(), "Offset");
+        let mut buffer = [1.0f32; 4];
+        chain.process(&mut buffer);
+        assert_eq!(buffer[0], 4.0); // (1 + 1) * 2
+    }
+
+    #[test]
+    fn test_mix_blends_with_dry() {
+        let mut chain = EffectChain::new();
+        chain.push(Box::new(Gain(3.0)));
+        chain.slot_mut(0).unwrap().set_mix(0.5);
+
+        // Longer than the dry buffer, so it blends in passes
+        let mut buffer = vec![1.0f32; DRY_BUFFER_SIZE * 2 + 6];
+        chain.process(&mut buffer);
+        assert!(buffer.iter().all(|&s| (s - 2.0).abs() < 1e-6));
+
+        chain.slot_mut(0).unwrap().set_mix(0.0);
+        let mut buffer = [1.0f32; 4];
+        chain.process(&mut buffer);
+        assert_eq!(buffer[0], 1.0);
+    }
+
+    #[test]
+    fn test_insert_remove_and_find() {
+        let mut chain = EffectChain::with_effects(&EffectKind::ALL, 44100);
+        assert_eq!(chain.len(), EffectKind::ALL.len());
+        assert!(chain.find::<Delay>().is_some());
+
+        let index = chain
+            .slots()
+            .iter()
+            .position(|s| s.effect().name() == "Delay")
+            .unwrap();
+        assert!(chain.remove(index).is_some());
+        assert!(chain.find::<Delay>().is_none());
+        assert!(chain.remove(99).is_none());
+
+        // Past the end appends; a full chain refuses more
+        assert!(chain.insert(99, EffectKind::Delay.build(44100)));
+        assert_eq!(chain.slots().last().unwrap().effect().name(), "Delay");
+        while chain.len() < MAX_EFFECT_SLOTS {
+            chain.push(Box::new(Gain(1.0)));
+        }
+        assert!(!chain.push(Box::new(Gain(1.0))));
+    }
+
+    #[test]
+    fn test_params_by_index() {
+        let mut chain = EffectChain::with_effects(&[EffectKind::Delay], 44100);
+        let state = chain.state();
+        let feedback = state[0].param_index("feedback").unwrap();
+
+        chain
+            .slot_mut(0)
+            .unwrap()
+            .effect_mut()
+            .set_param(feedback, 0.7);
+        assert_eq!(chain.find::<Delay>().unwrap().feedback(), 0.7);
+        assert_eq!(chain.state()[0].values[feedback], 0.7);
+
+        // Clamped by the effect
+        chain
+            .slot_mut(0)
+            .unwrap()
+            .effect_mut()
+            .set_param(feedback, 5.0);
+        assert_eq!(chain.find::<Delay>().unwrap().feedback(), 0.98);
+    }
+
+    #[test]
+    fn test_rebuild_keeps_the_settings() {
+        let mut chain = EffectChain::with_effects(&[EffectKind::Reverb, EffectKind::Delay], 44100);
+        chain.push(Box::new(Gain(0.5)));
+        chain.move_slot(1, 0);
+        let delay = chain.slot_mut(0).unwrap();
+        delay.set_mix(0.4);
+        delay.effect_mut().set_param(1, 0.7);
+        delay.effect_mut().set_enabled(true);
+        let before = chain.state();
+
+        let mut carried = 0;
+        chain.rebuild(96000, |old, new| {
+            assert_eq!(old.name(), new.name());
+            carried += 1;
+        });
+        assert_eq!(carried, 2);
+        assert_eq!(chain.state(), before);
+        assert_eq!(chain.find::<Delay>().unwrap().feedback(), 0.7);
+    }
+
+    #[test]
+    fn test_params_fit_slot_state() {
+        for kind in EffectKind::ALL {
+            let effect = kind.build(44100);
+            assert!(
+                effect.params().len() <= MAX_EFFECT_PARAMS,
+                "{}",
+                kind.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in EffectKind::ALL {
+            assert_eq!(EffectKind::parse(kind.name()), Some(kind));
+        }
+        assert_eq!(EffectKind::parse("crush"), Some(EffectKind::Bitcrusher));
+        assert_eq!(EffectKind::parse("wah"), None);
+    }
+
+    #[test]
+    fn test_position_by_kind() {
+        let chain = EffectChain::with_effects(&EffectKind::ALL, 48000);
+        for (i, kind) in EffectKind::ALL.into_iter().enumerate() {
+            assert_eq!(chain.position(kind), Some(i));
+        }
+        assert_eq!(EffectChain::new().position(EffectKind::Phaser), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! Chorus effect - detuned voices from modulated delays
+//!
+//! Each voice reads a short delay line at a time swept by its own LFO; the
+//! voices' LFOs are spread evenly across the cycle and panned alternately
+//! left and right so the ensemble sounds wide.
+
+use super::beat_clock::LfoSync;
+use super::{Effect, EffectParam};
+use std::f32::consts::PI;
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("rate", 0.05, 5.0),
+    EffectParam::new("depth", 0.0, 1.0),
+    EffectParam::new("voices", 1.0, Chorus::MAX_VOICES as f32),
+    EffectParam::new("mix", 0.0, 1.0),
+    EffectParam::new("bars", 0.0, LfoSync::MAX_BARS),
+];
+
+/// Multi-voice stereo chorus
+pub struct Chorus {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// LFO rate in Hz (0.05 - 5.0)
+    rate: f32,
+
+    /// Modulation depth (0.0 - 1.0)
+    depth: f32,
+
+    /// Voices in use (1 - 4)
+    voices: usize,
+
+    /// Wet/dry mix (0.0 - 1.0)
+    mix: f32,
+
+    /// LFO phase (0.0 - 1.0), voices are offset from it
+    lfo_phase: f32,
+
+    /// Delay buffers (stereo)
+    buffer_l: Vec<f32>,
+    buffer_r: Vec<f32>,
+    write_pos: usize,
+
+    /// Wet envelope for click-free enable/disable
+    wet_target: f32,
+    wet_current: f32,
+
+    /// LFO locked to the deck's tempo
+    sync: LfoSync,
+}
+
+impl Chorus {
+    /// Wet envelope smoothing coefficient
+    const WET_SMOOTH_COEFF: f32 = 0.9995;
+
+    /// Most voices
+    const MAX_VOICES: usize = 4;
+
+    /// Centre delay and sweep at full depth in milliseconds
+    const BASE_DELAY_MS: f32 = 15.0;
+    const SWEEP_MS: f32 = 5.0;
+
+    /// Delay buffer length in milliseconds (covers the full sweep)
+    const BUFFER_MS: f32 = 50.0;
+
+    /// Create a new chorus effect
+    pub fn new(sample_rate: f32) -> Self {
+        let buffer_size = (sample_rate * Self::BUFFER_MS / 1000.0) as usize + 2;
+
+        Self {
+            enabled: false,
+            sample_rate,
+            rate: 0.8,
+            depth: 0.5,
+            voices: 3,
+            mix: 0.5,
+            lfo_phase: 0.0,
+            buffer_l: vec![0.0; buffer_size],
+            buffer_r: vec![0.0; buffer_size],
+            write_pos: 0,
+            wet_target: 0.0,
+            wet_current: 0.0,
+            sync: LfoSync::for_bars(),
+        }
+    }
+
+    /// Set LFO rate in Hz (0.05 - 5.0), stops following the beat
+    pub fn set_rate(&mut self, rate: f32) {
+        self.sync.set_beats(None);
+        self.rate = rate.clamp(0.05, 5.0);
+    }
+
+    /// Get LFO rate
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Set modulation depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Get depth
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Set number of voices (1 - 4)
+    pub fn set_voices(&mut self, voices: usize) {
+        self.voices = voices.clamp(1, Self::MAX_VOICES);
+    }
+
+    /// Get number of voices
+    pub fn voices(&self) -> usize {
+        self.voices
+    }
+
+    /// Set wet/dry mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Get mix
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    /// Lock one LFO cycle to the deck's bars (1/4 - 16 bars of 4 beats),
+    /// or free it with None. Without a tempo the current rate is kept.
+    pub fn set_sync_bars(&mut self, bars: Option<f32>) {
+        self.sync.set_bars(bars);
+        self.update_sync();
+    }
+
+    /// LFO cycle length in bars, if synced
+    pub fn sync_bars(&self) -> Option<f32> {
+        self.sync.bars()
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(rate) = self.sync.rate(5.0) {
+            self.rate = rate;
+        }
+    }
+
+    /// Read from a delay buffer with linear interpolation
+    #[inline]
+    fn read_delayed(buffer: &[f32], write_pos: usize, delay_samples: f32) -> f32 {
+        let len = buffer.len();
+        let read_pos = (write_pos as f32 - delay_samples + len as f32) % len as f32;
+        let idx0 = read_pos as usize % len;
+        let idx1 = (idx0 + 1) % len;
+        let frac = read_pos - read_pos.floor();
+        buffer[idx0] * (1.0 - frac) + buffer[idx1] * frac
+    }
+}
+
+impl Effect for Chorus {
+    fn process(&mut self, samples: &mut [f32]) {
+        // Skip if fully disabled and envelope settled
+        if !self.enabled && self.wet_current < 0.0001 {
+            return;
+        }
+
+        let lfo_inc = self.rate / self.sample_rate;
+        let ms_to_samples = self.sample_rate / 1000.0;
+        let voice_offset = 1.0 / self.voices as f32;
+        // Keep the ensemble level with a single voice
+        let voice_gain = 1.0 / (self.voices as f32).sqrt();
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            // Smooth wet envelope
+            self.wet_current = Self::WET_SMOOTH_COEFF * self.wet_current
+                + (1.0 - Self::WET_SMOOTH_COEFF) * self.wet_target;
+
+            self.buffer_l[self.write_pos] = frame[0];
+            self.buffer_r[self.write_pos] = frame[1];
+
+            let mut wet_l = 0.0;
+            let mut wet_r = 0.0;
+            for voice in 0..self.voices {
+                let phase = self.lfo_phase + voice as f32 * voice_offset;
+                let lfo = (phase * 2.0 * PI).sin();
+                let delay_ms = Self::BASE_DELAY_MS + lfo * self.depth * Self::SWEEP_MS;
+                let delay_samples = delay_ms * ms_to_samples;
+
+                let l = Self::read_delayed(&self.buffer_l, self.write_pos, delay_samples);
+                let r = Self::read_delayed(&self.buffer_r, self.write_pos, delay_samples);
+
+                // Alternate voices lean left and right; a lone voice stays centred
+                let (gain_l, gain_r) = match (self.voices, voice % 2) {
+                    (1, _) => (1.0, 1.0),
+                    (_, 0) => (1.2, 0.8),
+                    _ => (0.8, 1.2),
+                };
+                wet_l += l * gain_l;
+                wet_r += r * gain_r;
+            }
+            wet_l *= voice_gain;
+            wet_r *= voice_gain;
+
+            self.write_pos = (self.write_pos + 1) % self.buffer_l.len();
+            self.lfo_phase += lfo_inc;
+            if self.lfo_phase >= 1.0 {
+                self.lfo_phase -= 1.0;
+            }
+
+            // Mix dry and wet with envelope
+            let effective_mix = self.mix * self.wet_current;
+            frame[0] = frame[0] * (1.0 - effective_mix) + wet_l * effective_mix;
+            frame[1] = frame[1] * (1.0 - effective_mix) + wet_r * effective_mix;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer_l.fill(0.0);
+        self.buffer_r.fill(0.0);
+        self.write_pos = 0;
+        self.lfo_phase = 0.0;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.wet_target = if enabled { 1.0 } else { 0.0 };
+    }
+
+    fn name(&self) -> &'static str {
+        "Chorus"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.rate()),
+            1 => Some(self.depth()),
+            2 => Some(self.voices() as f32),
+            3 => Some(self.mix()),
+            4 => Some(self.sync.bars().unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_voices(value.round().max(0.0) as usize),
+            3 => self.set_mix(value),
+            // 0 = free-running
+            4 => self.set_sync_bars((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if self.sync.set_tempo(bpm) {
+            self.update_sync();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chorus_creation() {
+        let chorus = Chorus::new(48000.0);
+        assert!(!chorus.is_enabled());
+        assert_eq!(chorus.voices(), 3);
+    }
+
+    #[test]
+    fn test_chorus_parameter_clamping() {
+        let mut chorus = Chorus::new(48000.0);
+
+        chorus.set_voices(0);
+        assert_eq!(chorus.voices(), 1);
+        chorus.set_voices(8);
+        assert_eq!(chorus.voices(), 4);
+
+        chorus.set_depth(1.5);
+        assert_eq!(chorus.depth(), 1.0);
+    }
+
+    #[test]
+    fn test_chorus_delays_the_wet_signal() {
+        let mut chorus = Chorus::new(48000.0);
+        chorus.set_enabled(true);
+        chorus.wet_current = 1.0; // Force wet for test
+        chorus.set_mix(1.0);
+        chorus.set_voices(1);
+        chorus.set_depth(0.0);
+
+        // An impulse comes out at the base delay
+        let mut samples = vec![0.0f32; 4800];
+        samples[0] = 1.0;
+        samples[1] = 1.0;
+        chorus.process(&mut samples);
+
+        let peak_frame = samples
+            .chunks(2)
+            .enumerate()
+            .max_by(|a, b| a.1[0].abs().total_cmp(&b.1[0].abs()))
+            .map(|(i, _)| i)
+            .unwrap();
+        assert_eq!(peak_frame, 720); // 15 ms at 48 kHz
+    }
+
+    #[test]
+    fn test_rate_locks_to_bars() {
+        let mut chorus = Chorus::new(48000.0);
+        chorus.set_sync_bars(Some(1.0));
+        chorus.set_tempo(120.0);
+        // One bar at 120 BPM = 2 seconds
+        assert!((chorus.rate() - 0.5).abs() < 1e-6);
+
+        chorus.set_rate(2.0);
+        assert_eq!(chorus.sync_bars(), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Deck filter - one filter slot with a selectable implementation
+//!
+//! The biquad is the source of truth for type, cutoff and resonance; the
+//! ladder and SVF follow it when the mode switches to them.
+
+use super::{
+    Effect, EffectParam, Filter, FilterMode, FilterType, LadderFilter, StateVariableFilter,
+    SvfOutputType,
+};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("cutoff", 20.0, 20000.0),
+    EffectParam::new("resonance", 0.0, 1.0),
+    EffectParam::new("drive", 0.0, 1.0),
+];
+
+/// Biquad, ladder or state variable filter, selected by `FilterMode`
+pub struct DeckFilter {
+    // Original biquad filter
+    pub filter: Filter,
+    // Ladder filter (Moog-style)
+    pub ladder: LadderFilter,
+    // SVF filter
+    pub svf: StateVariableFilter,
+    mode: FilterMode,
+    // Current preset level (0 = off, 1-10)
+    level: u8,
+}
+
+impl DeckFilter {
+    /// Lookup table for filter level (1-10) to cutoff frequency in Hz
+    /// Index 0 is default, indices 1-10 map to levels 1-10
+    const LEVEL_CUTOFF: [f32; 11] = [
+        1000.0, // default (index 0)
+        200.0, 400.0, 600.0, 1000.0, 2000.0, // levels 1-5
+        4000.0, 6000.0, 10000.0, 15000.0, 20000.0, // levels 6-10
+    ];
+
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            filter: Filter::new(sample_rate),
+            ladder: LadderFilter::new(sample_rate),
+            svf: StateVariableFilter::new(sample_rate),
+            mode: FilterMode::default(),
+            level: 0,
+        }
+    }
+
+    /// Map filter level (1-10) to cutoff frequency in Hz
+    #[inline]
+    fn level_to_cutoff(level: u8) -> f32 {
+        Self::LEVEL_CUTOFF
+            .get(level as usize)
+            .copied()
+            .unwrap_or(1000.0)
+    }
+
+    /// Selected filter implementation
+    pub fn mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    /// Current preset level (0 = off)
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.filter.cutoff()
+    }
+
+    pub fn filter_type(&self) -> FilterType {
+        self.filter.filter_type()
+    }
+
+    /// Resonance of the current mode (0.0 - 1.0)
+    pub fn resonance(&self) -> f32 {
+        match self.mode {
+            FilterMode::Biquad => (self.filter.resonance() - 0.5) / 19.5,
+            FilterMode::Ladder => self.ladder.resonance(),
+            FilterMode::SVF => self.svf.resonance(),
+        }
+    }
+
+    pub fn set_type(&mut self, ft: FilterType) {
+        self.filter.set_type(ft);
+        // Also update SVF output type if in SVF mode
+        if self.mode == FilterMode::SVF {
+            self.svf.set_output_type(match ft {
+                FilterType::LowPass => SvfOutputType::LowPass,
+                FilterType::HighPass => SvfOutputType::HighPass,
+                FilterType::BandPass => SvfOutputType::BandPass,
+            });
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        // Update biquad (source of truth) and active filter only
+        self.filter.set_cutoff(cutoff);
+        match self.mode {
+            FilterMode::Ladder => self.ladder.set_cutoff(cutoff),
+            FilterMode::SVF => self.svf.set_cutoff(cutoff),
+            FilterMode::Biquad => {} // Already updated above
+        }
+    }
+
+    pub fn adjust_cutoff(&mut self, delta: f32) {
+        let current = self.filter.cutoff();
+        // Exponential adjustment for more natural feel
+        let factor: f32 = if delta > 0.0 { 1.1 } else { 0.9 };
+        self.set_cutoff(current * factor.powf(delta.abs()));
+    }
+
+    pub fn set_preset(&mut self, filter_type: FilterType, level: u8) {
+        if level == 0 {
+            self.filter.set_enabled(false);
+            self.level = 0;
+        } else {
+            self.filter.set_type(filter_type);
+            self.filter.set_cutoff(Self::level_to_cutoff(level));
+            self.filter.set_enabled(true);
+            self.level = level;
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: FilterMode) {
+        self.mode = mode;
+        // Sync cutoff and resonance to the new filter
+        let cutoff = self.filter.cutoff();
+        let resonance = self.filter.resonance();
+        match mode {
+            FilterMode::Ladder => {
+                self.ladder.set_cutoff(cutoff);
+                self.ladder.set_resonance(resonance / 20.0); // Scale Q to 0-1
+            }
+            FilterMode::SVF => {
+                self.svf.set_cutoff(cutoff);
+                self.svf.set_resonance(resonance / 20.0);
+            }
+            FilterMode::Biquad => {}
+        }
+    }
+
+    pub fn set_resonance(&mut self, res: f32) {
+        let res_clamped = res.clamp(0.0, 1.0);
+        match self.mode {
+            FilterMode::Biquad => self.filter.set_resonance(0.5 + res_clamped * 19.5),
+            FilterMode::Ladder => self.ladder.set_resonance(res_clamped),
+            FilterMode::SVF => self.svf.set_resonance(res_clamped),
+        }
+    }
+
+    /// Ladder filter only
+    pub fn set_drive(&mut self, drive: f32) {
+        self.ladder.set_drive(drive);
+    }
+
+    fn active(&mut self) -> &mut dyn Effect {
+        match self.mode {
+            FilterMode::Biquad => &mut self.filter,
+            FilterMode::Ladder => &mut self.ladder,
+            FilterMode::SVF => &mut self.svf,
+        }
+    }
+}
+
+impl Effect for DeckFilter {
+    fn process(&mut self, samples: &mut [f32]) {
+        self.active().process(samples);
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+        self.ladder.reset();
+        self.svf.reset();
+    }
+
+    /// Whether the filter of the current mode is on
+    fn is_enabled(&self) -> bool {
+        match self.mode {
+            FilterMode::Biquad => self.filter.is_enabled(),
+            FilterMode::Ladder => self.ladder.is_enabled(),
+            FilterMode::SVF => self.svf.is_enabled(),
+        }
+    }
+
+    /// Switch the filter of the current mode
+    fn set_enabled(&mut self, enabled: bool) {
+        self.active().set_enabled(enabled);
+    }
+
+    fn name(&self) -> &'static str {
+        "Filter"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.cutoff()),
+            1 => Some(self.resonance()),
+            2 => Some(self.ladder.drive()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_cutoff(value),
+            1 => self.set_resonance(value),
+            2 => self.set_drive(value),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_switch_carries_cutoff() {
+        let mut filter = DeckFilter::new(44100.0);
+        filter.set_cutoff(2500.0);
+        filter.set_mode(FilterMode::Ladder);
+        assert_eq!(filter.ladder.cutoff(), 2500.0);
+
+        // Enable follows the mode
+        filter.set_enabled(true);
+        assert!(filter.ladder.is_enabled());
+        assert!(!filter.filter.is_enabled());
+        filter.set_mode(FilterMode::Biquad);
+        assert!(!filter.is_enabled());
+    }
+}
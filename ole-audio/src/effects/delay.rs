@@ -7,12 +7,19 @@
 //! - Soft-knee saturation on feedback path
 //! - BPM-synced delay times
 
-use super::Effect;
+use super::{Effect, EffectParam};
 use std::f32::consts::PI;
 
 /// Maximum delay time in seconds
 const MAX_DELAY_SECS: f32 = 2.0;
 
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("time", 1.0, MAX_DELAY_SECS * 1000.0),
+    EffectParam::new("feedback", 0.0, 0.98),
+    EffectParam::new("mix", 0.0, 1.0),
+    EffectParam::new("beats", 0.0, Delay::MAX_SYNC_BEATS),
+];
+
 /// Delay interpolation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
 pub enum DelayInterpolation {
@@ -98,9 +105,17 @@ pub struct Delay {
     /// Wet envelope for click-free enable/disable
     wet_target: f32,
     wet_current: f32,
+    /// Delay time in beats when synced to the deck (None = free-running)
+    sync_beats: Option<f32>,
+    /// Deck tempo in BPM (0.0 = unknown)
+    bpm: f32,
 }
 
 impl Delay {
+    /// Shortest and longest beat-synced delay (1/16 to 4 beats)
+    const MIN_SYNC_BEATS: f32 = 1.0 / 16.0;
+    const MAX_SYNC_BEATS: f32 = 4.0;
+
     /// Create a new delay effect
     pub fn new(sample_rate: u32) -> Self {
         let sr = sample_rate as f32;
@@ -130,19 +145,31 @@ impl Delay {
             enabled: false,
             wet_target: 0.0,
             wet_current: 0.0,
+            sync_beats: None,
+            bpm: 0.0,
         }
     }
 
     /// Wet envelope smoothing coefficient (~10ms at 48kHz)
     const WET_SMOOTH_COEFF: f32 = 0.9995;
 
-    /// Set delay time in milliseconds
+    /// Set delay time in milliseconds (stops following the beat)
     pub fn set_delay_ms(&mut self, ms: f32) {
+        self.sync_beats = None;
+        self.set_target_ms(ms);
+    }
+
+    fn set_target_ms(&mut self, ms: f32) {
         let max_ms = MAX_DELAY_SECS * 1000.0;
         let clamped_ms = ms.clamp(1.0, max_ms);
         self.target_delay = (clamped_ms / 1000.0) * self.sample_rate;
     }
 
+    /// Delay time the delay is heading for, in milliseconds
+    fn target_ms(&self) -> f32 {
+        self.target_delay / self.sample_rate * 1000.0
+    }
+
     /// Get delay time in milliseconds
     pub fn delay_ms(&self) -> f32 {
         (self.delay_samples / self.sample_rate) * 1000.0
@@ -151,7 +178,25 @@ impl Delay {
     /// Set delay time synced to BPM
     pub fn set_delay_bpm_sync(&mut self, bpm: f32, beats: f32) {
         let beat_ms = 60000.0 / bpm.max(20.0);
-        self.set_delay_ms(beat_ms * beats);
+        self.set_target_ms(beat_ms * beats);
+    }
+
+    /// Lock the delay time to the deck's beat (1/16 - 4 beats), or free
+    /// it with None. Without a tempo the current time is kept.
+    pub fn set_sync_beats(&mut self, beats: Option<f32>) {
+        self.sync_beats = beats.map(|b| b.clamp(Self::MIN_SYNC_BEATS, Self::MAX_SYNC_BEATS));
+        self.update_sync();
+    }
+
+    /// Delay time in beats, if synced
+    pub fn sync_beats(&self) -> Option<f32> {
+        self.sync_beats
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(beats) = self.sync_beats.filter(|_| self.bpm > 0.0) {
+            self.set_delay_bpm_sync(self.bpm, beats);
+        }
     }
 
     /// Set feedback amount (0.0 - 0.98)
@@ -403,6 +448,39 @@ impl Effect for Delay {
     fn name(&self) -> &'static str {
         "Delay"
     }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            // The time it's heading for, not the smoothed one
+            0 => Some(self.target_ms()),
+            1 => Some(self.feedback()),
+            2 => Some(self.mix()),
+            3 => Some(self.sync_beats.unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_delay_ms(value),
+            1 => self.set_feedback(value),
+            2 => self.set_mix(value),
+            // 0 = free-running
+            3 => self.set_sync_beats((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if bpm != self.bpm {
+            self.bpm = bpm;
+            self.update_sync();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -446,6 +524,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sync_beats_follow_tempo() {
+        let mut delay = Delay::new(48000);
+        delay.set_delay_ms(300.0);
+        delay.set_sync_beats(Some(0.5));
+        assert_eq!(delay.target_ms(), 300.0); // No tempo yet: unchanged
+
+        delay.set_tempo(120.0);
+        assert!((delay.target_ms() - 250.0).abs() < 0.01);
+        delay.set_tempo(125.0); // e.g. a sync transition
+        assert!((delay.target_ms() - 240.0).abs() < 0.01);
+
+        // Clamped to 1/16 - 4 beats; a fixed time frees it
+        delay.set_sync_beats(Some(16.0));
+        assert_eq!(delay.sync_beats(), Some(4.0));
+        delay.set_delay_ms(100.0);
+        assert_eq!(delay.sync_beats(), None);
+        delay.set_tempo(90.0);
+        assert_eq!(delay.target_ms(), 100.0);
+    }
+
     #[test]
     fn test_modulation_modes() {
         for mode in [
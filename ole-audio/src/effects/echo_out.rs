@@ -0,0 +1,365 @@
+//! Echo-out effect - cut the input, keep the echoes
+//!
+//! While idle the delay line keeps filling with the deck's audio. Triggering
+//! cuts the input and lets the last beat ring out as a high-passed, decaying
+//! tail; the delay time is frozen at the tempo it was triggered at, so the
+//! tail keeps its timing after the deck stops. It switches itself off once
+//! the tail has died away.
+
+use super::beat_clock::FALLBACK_BPM;
+use super::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("beats", EchoOut::MIN_BEATS, EchoOut::MAX_BEATS),
+    EffectParam::new("feedback", 0.3, 0.95),
+    EffectParam::new("highpass", 20.0, 2000.0),
+];
+
+/// One-button echo-out transition
+pub struct EchoOut {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// Echo spacing in beats (1/4 - 2)
+    beats: f32,
+
+    /// Feedback amount (0.3 - 0.95)
+    feedback: f32,
+
+    /// High-pass cutoff on each repeat in Hz (20 - 2000)
+    highpass: f32,
+    hp_coeff: f32,
+    hp_state_l: f32,
+    hp_state_r: f32,
+    hp_prev_l: f32,
+    hp_prev_r: f32,
+
+    /// Delay line (stereo interleaved)
+    buffer: Vec<f32>,
+    write_frame: usize,
+
+    /// Echo spacing in frames, frozen while the tail plays
+    delay_frames: usize,
+
+    /// Smoothed input level (cut to 0.0 while echoing out)
+    input_gain: f32,
+
+    /// Wet envelope for click-free trigger/release
+    wet_target: f32,
+    wet_current: f32,
+
+    /// Loudest tail sample since the last check, and frames until the check
+    tail_peak: f32,
+    tail_countdown: usize,
+
+    /// Deck tempo in BPM (0.0 = unknown)
+    bpm: f32,
+}
+
+impl EchoOut {
+    /// Wet envelope and input cut smoothing coefficient
+    const SMOOTH_COEFF: f32 = 0.999;
+
+    /// Shortest and longest echo spacing (1/4 to 2 beats)
+    const MIN_BEATS: f32 = 0.25;
+    const MAX_BEATS: f32 = 2.0;
+
+    /// Slowest tempo the delay line covers at the longest spacing
+    const MIN_BPM: f32 = 40.0;
+
+    /// Tail level below which the echo-out ends (-80 dB)
+    const SILENCE: f32 = 0.0001;
+
+    /// Create a new echo-out effect
+    pub fn new(sample_rate: f32) -> Self {
+        let max_frames = (sample_rate * 60.0 / Self::MIN_BPM * Self::MAX_BEATS) as usize + 1;
+
+        let mut echo = Self {
+            enabled: false,
+            sample_rate,
+            beats: 0.75,
+            feedback: 0.6,
+            highpass: 0.0,
+            hp_coeff: 0.0,
+            hp_state_l: 0.0,
+            hp_state_r: 0.0,
+            hp_prev_l: 0.0,
+            hp_prev_r: 0.0,
+            buffer: vec![0.0; max_frames * 2],
+            write_frame: 0,
+            delay_frames: 1,
+            input_gain: 1.0,
+            wet_target: 0.0,
+            wet_current: 0.0,
+            tail_peak: 0.0,
+            tail_countdown: 0,
+            bpm: 0.0,
+        };
+        echo.set_highpass(400.0);
+        echo.update_delay();
+        echo
+    }
+
+    /// Set echo spacing in beats (1/4 - 2), used from the next trigger
+    pub fn set_beats(&mut self, beats: f32) {
+        self.beats = beats.clamp(Self::MIN_BEATS, Self::MAX_BEATS);
+        self.update_delay();
+    }
+
+    /// Get echo spacing in beats
+    pub fn beats(&self) -> f32 {
+        self.beats
+    }
+
+    /// Set feedback amount (0.3 - 0.95)
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.3, 0.95);
+    }
+
+    /// Get feedback
+    pub fn feedback(&self) -> f32 {
+        self.feedback
+    }
+
+    /// Set high-pass cutoff in Hz (20 - 2000)
+    pub fn set_highpass(&mut self, hz: f32) {
+        self.highpass = hz.clamp(20.0, 2000.0);
+        self.hp_coeff = (-2.0 * std::f32::consts::PI * self.highpass / self.sample_rate).exp();
+    }
+
+    /// Get high-pass cutoff
+    pub fn highpass(&self) -> f32 {
+        self.highpass
+    }
+
+    /// Whether the input is cut and the tail is playing
+    pub fn is_echoing(&self) -> bool {
+        self.enabled
+    }
+
+    /// Follow the tempo while idle; the spacing is frozen once triggered
+    fn update_delay(&mut self) {
+        // A stopping deck mustn't bend the tail
+        if self.enabled {
+            return;
+        }
+        let bpm = if self.bpm > 0.0 {
+            self.bpm.max(Self::MIN_BPM)
+        } else {
+            FALLBACK_BPM
+        };
+        let max_frames = self.buffer.len() / 2 - 1;
+        self.delay_frames =
+            ((self.sample_rate * 60.0 / bpm * self.beats) as usize).clamp(1, max_frames);
+    }
+
+    /// Cut the input and let the echoes ring out
+    pub fn trigger(&mut self) {
+        self.tail_peak = 0.0;
+        self.tail_countdown = self.delay_frames * 2;
+        self.enabled = true;
+        self.wet_target = 1.0;
+    }
+
+    /// Bring the input back and fade the tail out
+    pub fn release(&mut self) {
+        self.enabled = false;
+        self.wet_target = 0.0;
+    }
+
+    /// One-pole high-pass
+    #[inline]
+    fn highpass_sample(coeff: f32, state: &mut f32, prev: &mut f32, input: f32) -> f32 {
+        *state = coeff * (*state + input - *prev);
+        *prev = input;
+        *state
+    }
+}
+
+impl Effect for EchoOut {
+    fn process(&mut self, samples: &mut [f32]) {
+        let len = self.buffer.len() / 2;
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            let input_target = if self.enabled { 0.0 } else { 1.0 };
+            self.input_gain =
+                Self::SMOOTH_COEFF * self.input_gain + (1.0 - Self::SMOOTH_COEFF) * input_target;
+            self.wet_current = Self::SMOOTH_COEFF * self.wet_current
+                + (1.0 - Self::SMOOTH_COEFF) * self.wet_target;
+
+            let read = (self.write_frame + len - self.delay_frames) % len * 2;
+            let write = self.write_frame * 2;
+            self.write_frame = (self.write_frame + 1) % len;
+
+            // The high-pass runs while idle too, so triggering doesn't click
+            let echo_l = Self::highpass_sample(
+                self.hp_coeff,
+                &mut self.hp_state_l,
+                &mut self.hp_prev_l,
+                self.buffer[read],
+            );
+            let echo_r = Self::highpass_sample(
+                self.hp_coeff,
+                &mut self.hp_state_r,
+                &mut self.hp_prev_r,
+                self.buffer[read + 1],
+            );
+
+            if self.wet_current < 0.0001 && self.input_gain > 0.9999 {
+                // Idle: keep the last beats of the deck for the next trigger
+                self.buffer[write] = frame[0];
+                self.buffer[write + 1] = frame[1];
+                continue;
+            }
+
+            let in_l = frame[0] * self.input_gain;
+            let in_r = frame[1] * self.input_gain;
+            self.buffer[write] = in_l + echo_l * self.feedback;
+            self.buffer[write + 1] = in_r + echo_r * self.feedback;
+
+            frame[0] = in_l + echo_l * self.wet_current;
+            frame[1] = in_r + echo_r * self.wet_current;
+
+            // End once a full round of repeats has gone quiet
+            if self.enabled {
+                self.tail_peak = self.tail_peak.max(echo_l.abs()).max(echo_r.abs());
+                self.tail_countdown = self.tail_countdown.saturating_sub(1);
+                if self.tail_countdown == 0 {
+                    if self.tail_peak < Self::SILENCE {
+                        self.release();
+                    }
+                    self.tail_peak = 0.0;
+                    self.tail_countdown = self.delay_frames;
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_frame = 0;
+        self.hp_state_l = 0.0;
+        self.hp_state_r = 0.0;
+        self.hp_prev_l = 0.0;
+        self.hp_prev_r = 0.0;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.trigger();
+        } else {
+            self.release();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Echo Out"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.beats()),
+            1 => Some(self.feedback()),
+            2 => Some(self.highpass()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_beats(value),
+            1 => self.set_feedback(value),
+            2 => self.set_highpass(value),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if bpm != self.bpm {
+            self.bpm = bpm;
+            self.update_delay();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0f32, |m, s| m.max(s.abs()))
+    }
+
+    /// A 1 kHz tone (above the high-pass)
+    fn tone(frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let s = (i as f32 * 2.0 * std::f32::consts::PI * 1000.0 / 48000.0).sin() * 0.5;
+                [s, s]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_idle_echo_out_passes_through() {
+        let mut echo = EchoOut::new(48000.0);
+        let mut samples = tone(4800);
+        echo.process(&mut samples);
+        assert_eq!(samples, tone(4800));
+    }
+
+    #[test]
+    fn test_echo_out_cuts_input_and_keeps_the_tail() {
+        let mut echo = EchoOut::new(48000.0);
+        echo.set_tempo(120.0);
+        echo.set_beats(0.5);
+
+        let mut samples = tone(48000);
+        echo.process(&mut samples);
+        echo.trigger();
+        assert!(echo.is_echoing());
+
+        // Input carries on but is cut; the echoes of the tone remain
+        let mut samples = tone(48000);
+        echo.process(&mut samples);
+        assert!(peak(&samples[2 * 24000..]) > 0.05);
+
+        // Silence in (deck stopped): the tail decays and the effect ends
+        for _ in 0..30 {
+            let mut silence = vec![0.0f32; 48000 * 2];
+            echo.process(&mut silence);
+        }
+        assert!(!echo.is_echoing());
+    }
+
+    #[test]
+    fn test_tail_is_high_passed() {
+        let mut echo = EchoOut::new(48000.0);
+        echo.set_highpass(2000.0);
+
+        // A low rumble barely comes back
+        let mut samples: Vec<f32> = (0..48000)
+            .flat_map(|i| {
+                let s = (i as f32 * 2.0 * std::f32::consts::PI * 50.0 / 48000.0).sin() * 0.5;
+                [s, s]
+            })
+            .collect();
+        echo.process(&mut samples);
+        echo.trigger();
+        let mut silence = vec![0.0f32; 48000 * 2];
+        echo.process(&mut silence);
+        assert!(peak(&silence[2 * 4800..]) < 0.05);
+    }
+}
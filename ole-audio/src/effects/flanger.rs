@@ -3,9 +3,18 @@
 //! Classic DJ effect that creates a sweeping metallic sound by mixing
 //! the signal with a slightly delayed copy that varies over time.
 
-use super::Effect;
+use super::beat_clock::LfoSync;
+use super::{Effect, EffectParam};
 use std::f32::consts::PI;
 
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("rate", 0.05, 5.0),
+    EffectParam::new("depth", 0.0, 1.0),
+    EffectParam::new("feedback", -0.95, 0.95),
+    EffectParam::new("mix", 0.0, 1.0),
+    EffectParam::new("bars", 0.0, LfoSync::MAX_BARS),
+];
+
 /// Flanger effect with LFO modulation
 pub struct Flanger {
     enabled: bool,
@@ -45,6 +54,9 @@ pub struct Flanger {
     /// Wet envelope for click-free enable/disable
     wet_target: f32,
     wet_current: f32,
+
+    /// LFO locked to the deck's tempo
+    sync: LfoSync,
 }
 
 impl Flanger {
@@ -75,15 +87,36 @@ impl Flanger {
             feedback_r: 0.0,
             wet_target: 0.0,
             wet_current: 0.0,
+            sync: LfoSync::for_bars(),
         }
     }
 
-    /// Set LFO rate in Hz (0.05 - 5.0)
+    /// Set LFO rate in Hz (0.05 - 5.0), stops following the beat
     pub fn set_rate(&mut self, rate: f32) {
+        self.sync.set_beats(None);
         self.rate = rate.clamp(0.05, 5.0);
         self.lfo_inc = self.rate / self.sample_rate;
     }
 
+    /// Lock one LFO sweep to the deck's bars (1/4 - 16 bars of 4 beats),
+    /// or free it with None. Without a tempo the current rate is kept.
+    pub fn set_sync_bars(&mut self, bars: Option<f32>) {
+        self.sync.set_bars(bars);
+        self.update_sync();
+    }
+
+    /// LFO sweep length in bars, if synced
+    pub fn sync_bars(&self) -> Option<f32> {
+        self.sync.bars()
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(rate) = self.sync.rate(5.0) {
+            self.rate = rate;
+            self.lfo_inc = self.rate / self.sample_rate;
+        }
+    }
+
     /// Get LFO rate
     pub fn rate(&self) -> f32 {
         self.rate
@@ -243,6 +276,39 @@ impl Effect for Flanger {
     fn name(&self) -> &'static str {
         "Flanger"
     }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.rate()),
+            1 => Some(self.depth()),
+            2 => Some(self.feedback()),
+            3 => Some(self.mix()),
+            4 => Some(self.sync.bars().unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value),
+            3 => self.set_mix(value),
+            // 0 = free-running
+            4 => self.set_sync_bars((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if self.sync.set_tempo(bpm) {
+            self.update_sync();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -270,6 +336,22 @@ mod tests {
         assert_eq!(flanger.feedback(), -0.95);
     }
 
+    #[test]
+    fn test_rate_locks_to_bars() {
+        let mut flanger = Flanger::new(48000.0);
+        flanger.set_sync_bars(Some(1.0));
+        flanger.set_tempo(120.0);
+        // One bar at 120 BPM = 2 seconds
+        assert!((flanger.rate() - 0.5).abs() < 1e-6);
+        flanger.set_tempo(60.0);
+        assert!((flanger.rate() - 0.25).abs() < 1e-6);
+
+        flanger.set_rate(1.0);
+        assert_eq!(flanger.sync_bars(), None);
+        flanger.set_tempo(120.0);
+        assert_eq!(flanger.rate(), 1.0);
+    }
+
     #[test]
     fn test_flanger_processes_audio() {
         let mut flanger = Flanger::new(48000.0);
@@ -0,0 +1,242 @@
+//! Gate effect - beat-synced trans
+//!
+//! Chops the signal on the deck's beat grid: each step opens for the duty
+//! cycle and closes for the rest. Edges are smoothed so the chops don't
+//! click, and a depth below 1.0 ducks instead of muting.
+
+use super::beat_clock::BeatClock;
+use super::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("beats", Gate::MIN_STEP_BEATS, Gate::MAX_STEP_BEATS),
+    EffectParam::new("duty", 0.1, 0.9),
+    EffectParam::new("depth", 0.0, 1.0),
+    EffectParam::new("smooth", 0.5, 20.0),
+];
+
+/// Trans gate locked to the beat grid
+pub struct Gate {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// Step length in beats (1/32 - 1/4)
+    step_beats: f32,
+
+    /// Open part of each step (0.1 - 0.9)
+    duty: f32,
+
+    /// How far the gate closes (0.0 = not at all, 1.0 = silence)
+    depth: f32,
+
+    /// Edge smoothing time in milliseconds (0.5 - 20)
+    smooth_ms: f32,
+    smooth_coeff: f32,
+
+    /// Smoothed gain (1.0 = open)
+    gain: f32,
+
+    clock: BeatClock,
+}
+
+impl Gate {
+    /// Shortest and longest step (1/32 to 1/4 beat)
+    const MIN_STEP_BEATS: f32 = 0.03125;
+    const MAX_STEP_BEATS: f32 = 0.25;
+
+    /// Create a new gate effect
+    pub fn new(sample_rate: f32) -> Self {
+        let mut gate = Self {
+            enabled: false,
+            sample_rate,
+            step_beats: 0.125,
+            duty: 0.5,
+            depth: 1.0,
+            smooth_ms: 0.0,
+            smooth_coeff: 0.0,
+            gain: 1.0,
+            clock: BeatClock::new(sample_rate),
+        };
+        gate.set_smooth_ms(2.0);
+        gate
+    }
+
+    /// Set step length in beats (1/32 - 1/4)
+    pub fn set_step_beats(&mut self, beats: f32) {
+        self.step_beats = beats.clamp(Self::MIN_STEP_BEATS, Self::MAX_STEP_BEATS);
+    }
+
+    /// Get step length in beats
+    pub fn step_beats(&self) -> f32 {
+        self.step_beats
+    }
+
+    /// Set duty cycle (0.1 - 0.9)
+    pub fn set_duty(&mut self, duty: f32) {
+        self.duty = duty.clamp(0.1, 0.9);
+    }
+
+    /// Get duty cycle
+    pub fn duty(&self) -> f32 {
+        self.duty
+    }
+
+    /// Set depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Get depth
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Set edge smoothing in milliseconds (0.5 - 20)
+    pub fn set_smooth_ms(&mut self, ms: f32) {
+        self.smooth_ms = ms.clamp(0.5, 20.0);
+        self.smooth_coeff = (-1000.0 / (self.smooth_ms * self.sample_rate)).exp();
+    }
+
+    /// Get edge smoothing
+    pub fn smooth_ms(&self) -> f32 {
+        self.smooth_ms
+    }
+
+    /// Whether the gate is open at a grid position
+    #[inline]
+    fn is_open(&self, beat: f64) -> bool {
+        (beat / self.step_beats as f64).rem_euclid(1.0) < self.duty as f64
+    }
+}
+
+impl Effect for Gate {
+    fn process(&mut self, samples: &mut [f32]) {
+        // Skip if disabled and fully open again
+        if !self.enabled && self.gain > 0.9999 {
+            return;
+        }
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            let beat = self.clock.tick();
+            let target = if self.enabled && !self.is_open(beat) {
+                1.0 - self.depth
+            } else {
+                1.0
+            };
+            self.gain = self.smooth_coeff * self.gain + (1.0 - self.smooth_coeff) * target;
+
+            frame[0] *= self.gain;
+            frame[1] *= self.gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+        self.clock.reset();
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn name(&self) -> &'static str {
+        "Gate"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.step_beats()),
+            1 => Some(self.duty()),
+            2 => Some(self.depth()),
+            3 => Some(self.smooth_ms()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_step_beats(value),
+            1 => self.set_duty(value),
+            2 => self.set_depth(value),
+            3 => self.set_smooth_ms(value),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        self.clock.set_tempo(bpm);
+    }
+
+    fn set_beat(&mut self, beat: Option<f64>) {
+        self.clock.set_beat(beat);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_parameter_clamping() {
+        let mut gate = Gate::new(48000.0);
+        assert!(!gate.is_enabled());
+
+        gate.set_step_beats(1.0);
+        assert_eq!(gate.step_beats(), 0.25);
+        gate.set_step_beats(0.0);
+        assert_eq!(gate.step_beats(), 0.03125);
+        gate.set_duty(1.0);
+        assert_eq!(gate.duty(), 0.9);
+    }
+
+    #[test]
+    fn test_gate_follows_the_grid() {
+        let mut gate = Gate::new(48000.0);
+        gate.set_enabled(true);
+        gate.set_step_beats(0.25);
+        gate.set_tempo(120.0);
+        // Start just past a beat: open for the first half of each 1/4 beat
+        gate.set_beat(Some(16.0));
+
+        // 1/4 beat at 120 BPM = 6000 frames
+        let mut samples = vec![1.0f32; 12000 * 2];
+        gate.process(&mut samples);
+        assert!(samples[2 * 2500] > 0.99, "open {}", samples[2 * 2500]);
+        assert!(samples[2 * 5500] < 0.01, "closed {}", samples[2 * 5500]);
+        assert!(samples[2 * 8500] > 0.99, "open again {}", samples[2 * 8500]);
+
+        // Re-aligned when the grid moves: now half way through the step
+        gate.set_beat(Some(16.125));
+        let mut samples = vec![1.0f32; 2000 * 2];
+        gate.process(&mut samples);
+        assert!(samples[2 * 1500] < 0.01);
+    }
+
+    #[test]
+    fn test_gate_edges_are_smoothed() {
+        let mut gate = Gate::new(48000.0);
+        gate.set_enabled(true);
+        gate.set_tempo(120.0);
+        gate.set_beat(Some(0.0));
+
+        let mut samples = vec![1.0f32; 24000 * 2];
+        gate.process(&mut samples);
+        let max_step = samples
+            .windows(3)
+            .step_by(2)
+            .map(|w| (w[2] - w[0]).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_step < 0.05, "step {}", max_step);
+    }
+}
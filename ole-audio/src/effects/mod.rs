@@ -1,22 +1,45 @@
 //! Audio effects for OLE
 
+mod auto_pan;
+mod beat_clock;
 mod bitcrusher;
+mod chain;
+mod chorus;
+mod convolution;
+mod deck_filter;
 mod delay;
+mod echo_out;
 mod filter;
 mod flanger;
+mod gate;
 mod ladder_filter;
 mod limiter;
+mod noise_sweep;
+mod phaser;
 mod reverb;
+mod stutter;
 mod svf;
 mod tape_stop;
 
+pub use auto_pan::{AutoPan, LfoShape};
 pub use bitcrusher::Bitcrusher;
+pub use chain::{
+    EffectChain, EffectKind, EffectSlot, EffectSlotState, MAX_EFFECT_PARAMS, MAX_EFFECT_SLOTS,
+};
+pub use chorus::Chorus;
+pub use convolution::{ConvolutionReverb, ImpulseResponse, MAX_IMPULSE_SECS};
+pub use deck_filter::DeckFilter;
 pub use delay::{Delay, DelayInterpolation, DelayModulation};
+pub use echo_out::EchoOut;
 pub use filter::{Filter, FilterType};
 pub use flanger::Flanger;
+pub use gate::Gate;
 pub use ladder_filter::LadderFilter;
 pub use limiter::Limiter;
+pub use noise_sweep::NoiseSweep;
+pub use phaser::Phaser;
 pub use reverb::Reverb;
+pub use stutter::Stutter;
 pub use svf::{StateVariableFilter, SvfOutputType};
 pub use tape_stop::TapeStop;
 
@@ -32,8 +55,30 @@ pub enum FilterMode {
     SVF,
 }
 
+use std::any::Any;
+use std::fmt;
+
+/// A parameter an effect exposes to the chain, addressed by its index in
+/// `Effect::params`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectParam {
+    /// Short lowercase id (e.g. "feedback")
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl EffectParam {
+    pub const fn new(name: &'static str, min: f32, max: f32) -> Self {
+        Self { name, min, max }
+    }
+}
+
 /// Trait for audio effects
-pub trait Effect: Send {
+///
+/// Effects live in a deck's `EffectChain` as boxed slots. Parameters are
+/// optional: an effect lists them in `params` and reads/writes them by index.
+pub trait Effect: Any + Send {
     /// Process audio samples in place (stereo interleaved)
     fn process(&mut self, samples: &mut [f32]);
 
@@ -48,4 +93,35 @@ pub trait Effect: Send {
 
     /// Get effect name
     fn name(&self) -> &'static str;
+
+    /// Parameters this effect exposes
+    fn params(&self) -> &'static [EffectParam] {
+        &[]
+    }
+
+    /// Current value of parameter `index`
+    fn param(&self, _index: usize) -> Option<f32> {
+        None
+    }
+
+    /// Set parameter `index` (out of range values are clamped by the effect)
+    fn set_param(&mut self, _index: usize, _value: f32) {}
+
+    /// Follow the deck's tempo in BPM (0.0 when it has none), for effects
+    /// timed in beats or bars. Called once per buffer.
+    fn set_tempo(&mut self, _bpm: f32) {}
+
+    /// Follow the deck's beat grid: the position in beats where the next
+    /// buffer starts, or None when the deck has no grid or isn't playing.
+    /// Called once per buffer, after `set_tempo`.
+    fn set_beat(&mut self, _beat: Option<f64>) {}
+}
+
+impl fmt::Debug for dyn Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Effect")
+            .field("name", &self.name())
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
 }
@@ -0,0 +1,277 @@
+//! Noise sweep effect - white-noise riser for build-ups
+//!
+//! Triggering starts a sweep over a number of bars at the deck's tempo:
+//! white noise runs through a band-pass SVF whose cutoff rises from the low
+//! end to the top, and fades in on top of the deck. At the end of the sweep
+//! the noise drops out and the effect switches itself off.
+
+use super::beat_clock::FALLBACK_BPM;
+use super::{Effect, EffectParam, StateVariableFilter, SvfOutputType};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("bars", NoiseSweep::MIN_BARS, NoiseSweep::MAX_BARS),
+    EffectParam::new("level", 0.0, 1.0),
+    EffectParam::new("resonance", 0.0, 1.0),
+];
+
+/// One-button noise riser
+pub struct NoiseSweep {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// Sweep length in bars (1 - 32)
+    bars: f32,
+
+    /// Noise level at the top of the sweep (0.0 - 1.0)
+    level: f32,
+
+    /// Sweep position (0.0 = start, 1.0 = done)
+    progress: f32,
+
+    /// Noise filter
+    filter: StateVariableFilter,
+
+    /// Noise for one block (pre-allocated, stereo interleaved)
+    noise: Vec<f32>,
+
+    /// Noise generator state
+    rng: u32,
+
+    /// Smoothed noise gain, so the end of the sweep doesn't click
+    gain: f32,
+
+    /// Deck tempo in BPM (0.0 = unknown)
+    bpm: f32,
+}
+
+impl NoiseSweep {
+    /// Shortest and longest sweep (1 to 32 bars)
+    const MIN_BARS: f32 = 1.0;
+    const MAX_BARS: f32 = 32.0;
+
+    /// Cutoff at the start and end of the sweep in Hz
+    const START_HZ: f32 = 150.0;
+    const END_HZ: f32 = 12000.0;
+
+    /// Frames per filter update
+    const BLOCK_FRAMES: usize = 64;
+
+    /// Gain smoothing coefficient
+    const GAIN_SMOOTH_COEFF: f32 = 0.999;
+
+    /// Create a new noise sweep effect
+    pub fn new(sample_rate: f32) -> Self {
+        let mut filter = StateVariableFilter::new(sample_rate);
+        filter.set_output_type(SvfOutputType::BandPass);
+        filter.set_resonance(0.3);
+        filter.set_enabled(true);
+
+        Self {
+            enabled: false,
+            sample_rate,
+            bars: 8.0,
+            level: 0.5,
+            progress: 0.0,
+            filter,
+            noise: vec![0.0; Self::BLOCK_FRAMES * 2],
+            rng: 0x1234_5678,
+            gain: 0.0,
+            bpm: 0.0,
+        }
+    }
+
+    /// Set sweep length in bars (1 - 32)
+    pub fn set_bars(&mut self, bars: f32) {
+        self.bars = bars.clamp(Self::MIN_BARS, Self::MAX_BARS);
+    }
+
+    /// Get sweep length in bars
+    pub fn bars(&self) -> f32 {
+        self.bars
+    }
+
+    /// Set noise level at the top (0.0 - 1.0)
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level.clamp(0.0, 1.0);
+    }
+
+    /// Get noise level
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Set filter resonance (0.0 - 1.0)
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.filter.set_resonance(resonance);
+    }
+
+    /// Get filter resonance
+    pub fn resonance(&self) -> f32 {
+        self.filter.resonance()
+    }
+
+    /// Sweep position (0.0 - 1.0)
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Filter cutoff for the current position
+    pub fn cutoff(&self) -> f32 {
+        Self::START_HZ * (Self::END_HZ / Self::START_HZ).powf(self.progress)
+    }
+
+    /// Start the sweep from the bottom
+    pub fn trigger(&mut self) {
+        self.progress = 0.0;
+        self.enabled = true;
+        self.filter.set_cutoff(self.cutoff());
+        self.filter.reset();
+    }
+
+    /// White noise (-1.0 to 1.0), xorshift
+    #[inline]
+    fn next_noise(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Effect for NoiseSweep {
+    fn process(&mut self, samples: &mut [f32]) {
+        // Skip if idle and the noise has faded
+        if !self.enabled && self.gain < 0.0001 {
+            return;
+        }
+
+        let bpm = if self.bpm > 0.0 {
+            self.bpm
+        } else {
+            FALLBACK_BPM
+        };
+        let sweep_frames = self.bars * 4.0 * 60.0 / bpm * self.sample_rate;
+
+        for block in samples.chunks_mut(Self::BLOCK_FRAMES * 2) {
+            let frames = block.len() / 2;
+            let noise_len = frames * 2;
+
+            // Band-passed noise at the cutoff for this block
+            for i in 0..noise_len {
+                self.noise[i] = self.next_noise();
+            }
+            self.filter.set_cutoff(self.cutoff());
+            self.filter.process(&mut self.noise[..noise_len]);
+
+            // Level rises with the sweep
+            let target = if self.enabled {
+                self.level * self.progress.sqrt()
+            } else {
+                0.0
+            };
+            for (frame, noise) in block.chunks_mut(2).zip(self.noise.chunks(2)) {
+                if frame.len() < 2 {
+                    continue;
+                }
+                self.gain =
+                    Self::GAIN_SMOOTH_COEFF * self.gain + (1.0 - Self::GAIN_SMOOTH_COEFF) * target;
+                frame[0] += noise[0] * self.gain;
+                frame[1] += noise[1] * self.gain;
+            }
+
+            if self.enabled {
+                self.progress = (self.progress + frames as f32 / sweep_frames).min(1.0);
+                if self.progress >= 1.0 {
+                    self.enabled = false;
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+        self.progress = 0.0;
+        self.gain = 0.0;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.trigger();
+        } else {
+            self.enabled = false;
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Noise Sweep"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.bars()),
+            1 => Some(self.level()),
+            2 => Some(self.resonance()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_bars(value),
+            1 => self.set_level(value),
+            2 => self.set_resonance(value),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        self.bpm = bpm;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_idle_sweep_is_silent() {
+        let mut sweep = NoiseSweep::new(48000.0);
+        let mut samples = vec![0.0f32; 4800];
+        sweep.process(&mut samples);
+        assert!(samples.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_sweep_rises_over_bars() {
+        let mut sweep = NoiseSweep::new(48000.0);
+        sweep.set_tempo(120.0);
+        sweep.set_bars(1.0);
+        sweep.trigger();
+        assert_eq!(sweep.cutoff(), 150.0);
+
+        // One bar at 120 BPM = 2 seconds: half way after one
+        let mut first = vec![0.0f32; 48000 * 2];
+        sweep.process(&mut first);
+        assert!((sweep.progress() - 0.5).abs() < 0.01);
+        assert!(sweep.cutoff() > 1000.0);
+
+        // Louder towards the top, then done
+        let mut second = vec![0.0f32; 48000 * 2];
+        sweep.process(&mut second);
+        assert!(rms(&second) > rms(&first));
+        assert!(!sweep.is_enabled());
+        assert_eq!(sweep.progress(), 1.0);
+    }
+}
@@ -0,0 +1,378 @@
+//! Phaser effect - swept all-pass notches
+//!
+//! A chain of first-order all-pass filters shifts the phase of the signal;
+//! mixed back with the dry signal this cuts moving notches into the
+//! spectrum. The two channels sweep a quarter cycle apart for width.
+
+use super::beat_clock::LfoSync;
+use super::{Effect, EffectParam};
+use std::f32::consts::PI;
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("rate", 0.05, 5.0),
+    EffectParam::new("depth", 0.0, 1.0),
+    EffectParam::new(
+        "stages",
+        Phaser::MIN_STAGES as f32,
+        Phaser::MAX_STAGES as f32,
+    ),
+    EffectParam::new("feedback", -0.95, 0.95),
+    EffectParam::new("mix", 0.0, 1.0),
+    EffectParam::new("bars", 0.0, LfoSync::MAX_BARS),
+];
+
+/// Stereo all-pass phaser with LFO sweep
+pub struct Phaser {
+    enabled: bool,
+    sample_rate: f32,
+
+    /// LFO rate in Hz (0.05 - 5.0)
+    rate: f32,
+
+    /// Sweep depth (0.0 - 1.0)
+    depth: f32,
+
+    /// All-pass stages in use (4 - 12)
+    stages: usize,
+
+    /// Feedback amount (-0.95 to 0.95)
+    feedback: f32,
+
+    /// Wet/dry mix (0.0 - 1.0)
+    mix: f32,
+
+    /// LFO phase (0.0 - 1.0)
+    lfo_phase: f32,
+
+    /// All-pass states per channel
+    state_l: [f32; Self::MAX_STAGES],
+    state_r: [f32; Self::MAX_STAGES],
+
+    /// All-pass coefficients (updated every `COEFF_INTERVAL` frames)
+    coeff_l: f32,
+    coeff_r: f32,
+    coeff_countdown: usize,
+
+    /// Feedback state (stereo)
+    feedback_l: f32,
+    feedback_r: f32,
+
+    /// Wet envelope for click-free enable/disable
+    wet_target: f32,
+    wet_current: f32,
+
+    /// LFO locked to the deck's tempo
+    sync: LfoSync,
+}
+
+impl Phaser {
+    /// Wet envelope smoothing coefficient
+    const WET_SMOOTH_COEFF: f32 = 0.9995;
+
+    /// Fewest and most all-pass stages
+    const MIN_STAGES: usize = 4;
+    const MAX_STAGES: usize = 12;
+
+    /// Lowest notch frequency in Hz; depth 1.0 sweeps 5 octaves above it
+    const MIN_FREQ: f32 = 200.0;
+    const SWEEP_OCTAVES: f32 = 5.0;
+
+    /// Frames between coefficient updates
+    const COEFF_INTERVAL: usize = 16;
+
+    /// Create a new phaser effect
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            enabled: false,
+            sample_rate,
+            rate: 0.3,
+            depth: 0.8,
+            stages: 6,
+            feedback: 0.5,
+            mix: 0.5,
+            lfo_phase: 0.0,
+            state_l: [0.0; Self::MAX_STAGES],
+            state_r: [0.0; Self::MAX_STAGES],
+            coeff_l: 0.0,
+            coeff_r: 0.0,
+            coeff_countdown: 0,
+            feedback_l: 0.0,
+            feedback_r: 0.0,
+            wet_target: 0.0,
+            wet_current: 0.0,
+            sync: LfoSync::for_bars(),
+        }
+    }
+
+    /// Set LFO rate in Hz (0.05 - 5.0), stops following the beat
+    pub fn set_rate(&mut self, rate: f32) {
+        self.sync.set_beats(None);
+        self.rate = rate.clamp(0.05, 5.0);
+    }
+
+    /// Get LFO rate
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Set sweep depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Get depth
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Set number of all-pass stages (4 - 12)
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = stages.clamp(Self::MIN_STAGES, Self::MAX_STAGES);
+    }
+
+    /// Get number of stages
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+
+    /// Set feedback amount (-0.95 to 0.95)
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-0.95, 0.95);
+    }
+
+    /// Get feedback
+    pub fn feedback(&self) -> f32 {
+        self.feedback
+    }
+
+    /// Set wet/dry mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Get mix
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    /// Lock one LFO sweep to the deck's bars (1/4 - 16 bars of 4 beats),
+    /// or free it with None. Without a tempo the current rate is kept.
+    pub fn set_sync_bars(&mut self, bars: Option<f32>) {
+        self.sync.set_bars(bars);
+        self.update_sync();
+    }
+
+    /// LFO sweep length in bars, if synced
+    pub fn sync_bars(&self) -> Option<f32> {
+        self.sync.bars()
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(rate) = self.sync.rate(5.0) {
+            self.rate = rate;
+        }
+    }
+
+    /// All-pass coefficient for a notch frequency
+    #[inline]
+    fn allpass_coeff(&self, freq: f32) -> f32 {
+        let w = (PI * freq / self.sample_rate).min(1.5).tan();
+        (w - 1.0) / (w + 1.0)
+    }
+
+    /// Notch frequency for an LFO phase (0.0 - 1.0)
+    #[inline]
+    fn sweep_freq(&self, phase: f32) -> f32 {
+        let lfo = (phase * 2.0 * PI).sin() * 0.5 + 0.5;
+        Self::MIN_FREQ * (lfo * self.depth * Self::SWEEP_OCTAVES).exp2()
+    }
+
+    /// Run one sample through the all-pass chain
+    #[inline]
+    fn allpass_chain(states: &mut [f32], coeff: f32, input: f32) -> f32 {
+        let mut x = input;
+        for state in states {
+            let y = coeff * x + *state;
+            *state = x - coeff * y;
+            x = y;
+        }
+        x
+    }
+}
+
+impl Effect for Phaser {
+    fn process(&mut self, samples: &mut [f32]) {
+        // Skip if fully disabled and envelope settled
+        if !self.enabled && self.wet_current < 0.0001 {
+            return;
+        }
+
+        let lfo_inc = self.rate / self.sample_rate;
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            // Smooth wet envelope
+            self.wet_current = Self::WET_SMOOTH_COEFF * self.wet_current
+                + (1.0 - Self::WET_SMOOTH_COEFF) * self.wet_target;
+
+            // Sweep the notches (right channel a quarter cycle ahead)
+            if self.coeff_countdown == 0 {
+                self.coeff_l = self.allpass_coeff(self.sweep_freq(self.lfo_phase));
+                self.coeff_r = self.allpass_coeff(self.sweep_freq(self.lfo_phase + 0.25));
+                self.coeff_countdown = Self::COEFF_INTERVAL;
+            }
+            self.coeff_countdown -= 1;
+            self.lfo_phase += lfo_inc;
+            if self.lfo_phase >= 1.0 {
+                self.lfo_phase -= 1.0;
+            }
+
+            let input_l = frame[0] + self.feedback_l * self.feedback;
+            let input_r = frame[1] + self.feedback_r * self.feedback;
+            let wet_l =
+                Self::allpass_chain(&mut self.state_l[..self.stages], self.coeff_l, input_l);
+            let wet_r =
+                Self::allpass_chain(&mut self.state_r[..self.stages], self.coeff_r, input_r);
+            self.feedback_l = wet_l;
+            self.feedback_r = wet_r;
+
+            // Mix dry and wet with envelope
+            let effective_mix = self.mix * self.wet_current;
+            frame[0] = frame[0] * (1.0 - effective_mix) + wet_l * effective_mix;
+            frame[1] = frame[1] * (1.0 - effective_mix) + wet_r * effective_mix;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state_l = [0.0; Self::MAX_STAGES];
+        self.state_r = [0.0; Self::MAX_STAGES];
+        self.lfo_phase = 0.0;
+        self.coeff_countdown = 0;
+        self.feedback_l = 0.0;
+        self.feedback_r = 0.0;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.wet_target = if enabled { 1.0 } else { 0.0 };
+    }
+
+    fn name(&self) -> &'static str {
+        "Phaser"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.rate()),
+            1 => Some(self.depth()),
+            2 => Some(self.stages() as f32),
+            3 => Some(self.feedback()),
+            4 => Some(self.mix()),
+            5 => Some(self.sync.bars().unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_stages(value.round().max(0.0) as usize),
+            3 => self.set_feedback(value),
+            4 => self.set_mix(value),
+            // 0 = free-running
+            5 => self.set_sync_bars((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if self.sync.set_tempo(bpm) {
+            self.update_sync();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phaser_creation() {
+        let phaser = Phaser::new(48000.0);
+        assert!(!phaser.is_enabled());
+        assert_eq!(phaser.stages(), 6);
+    }
+
+    #[test]
+    fn test_phaser_parameter_clamping() {
+        let mut phaser = Phaser::new(48000.0);
+
+        phaser.set_stages(2);
+        assert_eq!(phaser.stages(), 4);
+        phaser.set_stages(20);
+        assert_eq!(phaser.stages(), 12);
+
+        phaser.set_feedback(2.0);
+        assert_eq!(phaser.feedback(), 0.95);
+    }
+
+    #[test]
+    fn test_allpass_keeps_level() {
+        let phaser = Phaser::new(48000.0);
+        let coeff = phaser.allpass_coeff(1000.0);
+        let mut states = [0.0f32; 12];
+
+        // A sine comes out of the all-pass chain at the same level
+        let mut peak = 0.0f32;
+        for i in 0..48000 {
+            let x = (i as f32 * 2.0 * PI * 440.0 / 48000.0).sin();
+            let y = Phaser::allpass_chain(&mut states, coeff, x);
+            if i > 4800 {
+                peak = peak.max(y.abs());
+            }
+        }
+        assert!((peak - 1.0).abs() < 0.01, "peak {}", peak);
+    }
+
+    #[test]
+    fn test_phaser_cuts_a_notch() {
+        let mut phaser = Phaser::new(48000.0);
+        phaser.set_enabled(true);
+        phaser.wet_current = 1.0; // Force wet for test
+        phaser.set_depth(0.0); // Notches parked at the bottom of the sweep
+        phaser.set_feedback(0.0);
+
+        // Full mix level of a tone at the first notch is lower than dry
+        let notch = Phaser::MIN_FREQ;
+        let mut samples: Vec<f32> = (0..48000)
+            .flat_map(|i| {
+                let s = (i as f32 * 2.0 * PI * notch / 48000.0).sin();
+                [s, s]
+            })
+            .collect();
+        phaser.process(&mut samples);
+        let peak = samples[48000..].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak < 0.9, "peak {}", peak);
+    }
+
+    #[test]
+    fn test_rate_locks_to_bars() {
+        let mut phaser = Phaser::new(48000.0);
+        phaser.set_sync_bars(Some(2.0));
+        phaser.set_tempo(120.0);
+        // Two bars at 120 BPM = 4 seconds
+        assert!((phaser.rate() - 0.25).abs() < 1e-6);
+    }
+}
@@ -3,7 +3,7 @@
 //! Uses parallel comb filters and series allpass filters for
 //! rich, natural-sounding reverberation.
 
-use super::Effect;
+use super::{Effect, EffectParam};
 
 /// Comb filter delay times in samples at 44.1kHz (from Freeverb)
 const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
@@ -14,6 +14,12 @@ const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
 /// Stereo spread in samples
 const STEREO_SPREAD: usize = 23;
 
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("room", 0.0, 1.0),
+    EffectParam::new("damping", 0.0, 1.0),
+    EffectParam::new("wet", 0.0, 1.0),
+];
+
 /// Lowpass-feedback comb filter
 struct CombFilter {
     buffer: Vec<f32>,
@@ -375,4 +381,26 @@ impl Effect for Reverb {
     fn name(&self) -> &'static str {
         "Reverb"
     }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.room_size()),
+            1 => Some(self.damping()),
+            2 => Some(self.wet()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_room_size(value),
+            1 => self.set_damping(value),
+            2 => self.set_wet(value),
+            _ => {}
+        }
+    }
 }
@@ -0,0 +1,318 @@
+//! Stutter effect - beat repeat
+//!
+//! Keeps the last few seconds of the deck in a history buffer. Switching
+//! it on captures the slice of beats that ends on the latest grid line and
+//! replays it, restarting on every grid step, so repeats land on the kick.
+
+use super::beat_clock::BeatClock;
+use super::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("beats", Stutter::MIN_BEATS, Stutter::MAX_BEATS),
+    EffectParam::new("rate", Stutter::MIN_BEATS, Stutter::MAX_BEATS),
+];
+
+/// Beat repeat locked to the beat grid
+pub struct Stutter {
+    enabled: bool,
+
+    /// Captured slice length in beats (1/32 - 4)
+    length_beats: f32,
+
+    /// Retrigger interval in beats (1/32 - 4)
+    rate_beats: f32,
+
+    /// Recent input (stereo interleaved ring buffer)
+    history: Vec<f32>,
+    /// Next frame written in `history`
+    write_frame: usize,
+
+    /// Captured slice: first frame in `history` and length in frames
+    slice_start: usize,
+    slice_frames: usize,
+
+    /// Grid position the repeats count from
+    trigger_beat: f64,
+
+    /// Capture the slice on the next frame
+    capture_pending: bool,
+
+    /// Wet envelope for click-free enable/disable
+    wet_target: f32,
+    wet_current: f32,
+
+    clock: BeatClock,
+}
+
+impl Stutter {
+    /// Wet envelope smoothing coefficient
+    const WET_SMOOTH_COEFF: f32 = 0.995;
+
+    /// Shortest and longest slice and retrigger interval (1/32 to 4 beats)
+    const MIN_BEATS: f32 = 0.03125;
+    const MAX_BEATS: f32 = 4.0;
+
+    /// Seconds of history (4 beats down to 60 BPM)
+    const HISTORY_SECS: f32 = 4.0;
+
+    /// Fade at the ends of each repeat in frames
+    const FADE_FRAMES: usize = 64;
+
+    /// Frames the wet envelope takes to die away after switching off,
+    /// while new input is already being recorded over the oldest history
+    const RELEASE_FRAMES: usize = 2048;
+
+    /// Create a new stutter effect
+    pub fn new(sample_rate: f32) -> Self {
+        let history_frames = (sample_rate * Self::HISTORY_SECS) as usize;
+
+        Self {
+            enabled: false,
+            length_beats: 0.25,
+            rate_beats: 0.25,
+            history: vec![0.0; history_frames * 2],
+            write_frame: 0,
+            slice_start: 0,
+            slice_frames: 0,
+            trigger_beat: 0.0,
+            capture_pending: false,
+            wet_target: 0.0,
+            wet_current: 0.0,
+            clock: BeatClock::new(sample_rate),
+        }
+    }
+
+    /// Set captured slice length in beats (1/32 - 4), takes effect on the
+    /// next capture
+    pub fn set_length_beats(&mut self, beats: f32) {
+        self.length_beats = beats.clamp(Self::MIN_BEATS, Self::MAX_BEATS);
+    }
+
+    /// Get slice length in beats
+    pub fn length_beats(&self) -> f32 {
+        self.length_beats
+    }
+
+    /// Set retrigger interval in beats (1/32 - 4)
+    pub fn set_rate_beats(&mut self, beats: f32) {
+        self.rate_beats = beats.clamp(Self::MIN_BEATS, Self::MAX_BEATS);
+    }
+
+    /// Get retrigger interval in beats
+    pub fn rate_beats(&self) -> f32 {
+        self.rate_beats
+    }
+
+    fn history_frames(&self) -> usize {
+        self.history.len() / 2
+    }
+
+    /// Take the slice that ends on the latest grid step (or right now
+    /// when free-running)
+    fn capture(&mut self, beat: f64) {
+        let rate = self.rate_beats as f64;
+        self.trigger_beat = if self.clock.is_locked() {
+            (beat / rate).floor() * rate
+        } else {
+            beat
+        };
+
+        let frames_per_beat = self.clock.frames_per_beat() as f64;
+        let len = self.history_frames();
+        // Leave the release's worth of history free in front of the slice
+        let room = len - Self::RELEASE_FRAMES;
+        let since_trigger = ((beat - self.trigger_beat) * frames_per_beat) as usize;
+        let slice = (self.length_beats as f64 * frames_per_beat) as usize;
+        self.slice_frames = slice.clamp(1, room - since_trigger.min(room - 1));
+        let back = (since_trigger + self.slice_frames).min(room);
+        self.slice_start = (self.write_frame + len - back) % len;
+    }
+
+    /// Slice frame to play at a grid position
+    #[inline]
+    fn slice_frame(&self, beat: f64) -> usize {
+        let offset = (beat - self.trigger_beat).rem_euclid(self.rate_beats as f64);
+        let frame = (offset * self.clock.frames_per_beat() as f64).round() as usize;
+        frame % self.slice_frames
+    }
+}
+
+impl Effect for Stutter {
+    fn process(&mut self, samples: &mut [f32]) {
+        let len = self.history_frames();
+
+        for frame in samples.chunks_mut(2) {
+            if frame.len() < 2 {
+                continue;
+            }
+
+            let beat = self.clock.tick();
+            if self.capture_pending {
+                self.capture(beat);
+                self.capture_pending = false;
+            }
+
+            // Smooth wet envelope
+            self.wet_current = Self::WET_SMOOTH_COEFF * self.wet_current
+                + (1.0 - Self::WET_SMOOTH_COEFF) * self.wet_target;
+
+            // History is frozen while repeating, so the slice stays intact;
+            // recording picks up again as soon as the release starts
+            if !self.enabled {
+                let i = self.write_frame * 2;
+                self.history[i] = frame[0];
+                self.history[i + 1] = frame[1];
+                self.write_frame = (self.write_frame + 1) % len;
+            }
+
+            if self.wet_current < 0.0001 {
+                continue;
+            }
+
+            let pos = self.slice_frame(beat);
+            let fade = if self.slice_frames > Self::FADE_FRAMES * 2 {
+                let edge = pos.min(self.slice_frames - pos).min(Self::FADE_FRAMES);
+                edge as f32 / Self::FADE_FRAMES as f32
+            } else {
+                1.0
+            };
+            let wet = self.wet_current * fade;
+            let i = (self.slice_start + pos) % len * 2;
+
+            frame[0] = frame[0] * (1.0 - self.wet_current) + self.history[i] * wet;
+            frame[1] = frame[1] * (1.0 - self.wet_current) + self.history[i + 1] * wet;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.write_frame = 0;
+        self.clock.reset();
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.capture_pending = true;
+        }
+        self.enabled = enabled;
+        self.wet_target = if enabled { 1.0 } else { 0.0 };
+    }
+
+    fn name(&self) -> &'static str {
+        "Stutter"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.length_beats()),
+            1 => Some(self.rate_beats()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_length_beats(value),
+            1 => self.set_rate_beats(value),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        self.clock.set_tempo(bpm);
+    }
+
+    fn set_beat(&mut self, beat: Option<f64>) {
+        self.clock.set_beat(beat);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ramp that counts frames, so output shows which frame it came from
+    fn ramp(start: usize, frames: usize) -> Vec<f32> {
+        (start..start + frames)
+            .flat_map(|i| [i as f32, i as f32])
+            .collect()
+    }
+
+    #[test]
+    fn test_stutter_parameter_clamping() {
+        let mut stutter = Stutter::new(48000.0);
+        assert!(!stutter.is_enabled());
+
+        stutter.set_length_beats(8.0);
+        assert_eq!(stutter.length_beats(), 4.0);
+        stutter.set_rate_beats(0.0);
+        assert_eq!(stutter.rate_beats(), 0.03125);
+    }
+
+    #[test]
+    fn test_disabled_stutter_passes_through() {
+        let mut stutter = Stutter::new(48000.0);
+        let mut samples = ramp(0, 1000);
+        stutter.process(&mut samples);
+        assert_eq!(samples, ramp(0, 1000));
+    }
+
+    #[test]
+    fn test_repeats_the_slice_before_the_grid_line() {
+        let mut stutter = Stutter::new(48000.0);
+        stutter.set_tempo(120.0);
+        stutter.set_length_beats(0.25);
+        stutter.set_rate_beats(0.5);
+
+        // One beat of audio from beat 0 (24000 frames at 120 BPM)
+        stutter.set_beat(Some(0.0));
+        let mut samples = ramp(0, 24000);
+        stutter.process(&mut samples);
+
+        // Switched on just after beat 1: the quarter beat before it repeats
+        stutter.set_enabled(true);
+        stutter.wet_target = 1.0;
+        stutter.wet_current = 1.0; // Force wet for test
+        stutter.set_beat(Some(1.0));
+        let mut samples = ramp(24000, 24000);
+        stutter.process(&mut samples);
+
+        // Frames 18000..24000 play from the grid line...
+        assert_eq!(samples[2 * 100], 18100.0);
+        assert_eq!(samples[2 * 5000], 23000.0);
+        // ...and restart every half beat
+        assert_eq!(samples[2 * 12100], 18100.0);
+        // Between repeats the slice loops (it's shorter than the rate)
+        assert_eq!(samples[2 * 6100], 18100.0);
+    }
+    #[test]
+    fn test_keeps_recording_while_the_repeat_fades_out() {
+        let mut stutter = Stutter::new(48000.0);
+        stutter.set_tempo(120.0);
+        let mut samples = ramp(0, 24000);
+        stutter.process(&mut samples);
+
+        stutter.set_enabled(true);
+        let mut samples = ramp(24000, 24000);
+        stutter.process(&mut samples);
+        assert_eq!(stutter.write_frame, 24000);
+
+        // Switched off: the release still plays the slice, but the deck's
+        // audio goes into the history from the first frame
+        stutter.set_enabled(false);
+        let mut samples = ramp(48000, 4800);
+        stutter.process(&mut samples);
+        assert!(samples[0] != 48000.0);
+        assert_eq!(stutter.write_frame, 28800);
+        assert_eq!(stutter.history[2 * 24000], 48000.0);
+        assert_eq!(stutter.history[2 * 28799], 52799.0);
+    }
+}
@@ -3,7 +3,12 @@
 //! Creates the dramatic slowdown effect used in DJ drops and transitions.
 //! The pitch drops exponentially while the audio slows to a stop.
 
-use super::Effect;
+use super::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("time", 0.1, 5.0),
+    EffectParam::new("beats", 0.0, TapeStop::MAX_SYNC_BEATS),
+];
 
 /// Tape stop effect with configurable stop time
 pub struct TapeStop {
@@ -43,12 +48,22 @@ pub struct TapeStop {
     /// Wet envelope for click-free enable/disable
     wet_target: f32,
     wet_current: f32,
+
+    /// Stop length in beats when synced to the deck (None = seconds)
+    sync_beats: Option<f32>,
+
+    /// Deck tempo in BPM (0.0 = unknown)
+    bpm: f32,
 }
 
 impl TapeStop {
     /// Wet envelope smoothing coefficient
     const WET_SMOOTH_COEFF: f32 = 0.9995;
 
+    /// Shortest and longest beat-synced stop (1/4 to 8 beats)
+    const MIN_SYNC_BEATS: f32 = 0.25;
+    const MAX_SYNC_BEATS: f32 = 8.0;
+
     /// Buffer size for resampling (must be power of 2)
     const BUFFER_SIZE: usize = 8192;
 
@@ -71,15 +86,41 @@ impl TapeStop {
             buffer_read_pos: 0.0,
             wet_target: 0.0,
             wet_current: 0.0,
+            sync_beats: None,
+            bpm: 0.0,
         }
     }
 
-    /// Set stop time in seconds (0.1 - 5.0)
+    /// Set stop time in seconds (0.1 - 5.0), stops following the beat
     pub fn set_stop_time(&mut self, seconds: f32) {
+        self.sync_beats = None;
+        self.apply_stop_time(seconds);
+    }
+
+    fn apply_stop_time(&mut self, seconds: f32) {
         self.stop_time = seconds.clamp(0.1, 5.0);
         self.update_coefficient();
     }
 
+    /// Set the stop length in the deck's beats (1/4 - 8 beats), or go back
+    /// to seconds with None. Without a tempo the current time is kept.
+    pub fn set_sync_beats(&mut self, beats: Option<f32>) {
+        self.sync_beats = beats.map(|b| b.clamp(Self::MIN_SYNC_BEATS, Self::MAX_SYNC_BEATS));
+        self.update_sync();
+    }
+
+    /// Stop length in beats, if synced
+    pub fn sync_beats(&self) -> Option<f32> {
+        self.sync_beats
+    }
+
+    fn update_sync(&mut self) {
+        if let Some(beats) = self.sync_beats.filter(|_| self.bpm > 0.0) {
+            // Follows tempo changes mid-stop too
+            self.apply_stop_time(beats * 60.0 / self.bpm);
+        }
+    }
+
     /// Get stop time
     pub fn stop_time(&self) -> f32 {
         self.stop_time
@@ -251,6 +292,34 @@ impl Effect for TapeStop {
     fn name(&self) -> &'static str {
         "Tape Stop"
     }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.stop_time()),
+            1 => Some(self.sync_beats.unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_stop_time(value),
+            // 0 = timed in seconds
+            1 => self.set_sync_beats((value > 0.0).then_some(value)),
+            _ => {}
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f32) {
+        if bpm != self.bpm {
+            self.bpm = bpm;
+            self.update_sync();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -264,6 +333,23 @@ mod tests {
         assert_eq!(ts.stop_time(), 1.0);
     }
 
+    #[test]
+    fn test_stop_time_follows_tempo_in_beats() {
+        let mut ts = TapeStop::new(48000.0);
+        ts.set_sync_beats(Some(2.0));
+        assert_eq!(ts.stop_time(), 1.0); // No tempo yet: unchanged
+        ts.set_tempo(120.0);
+        assert_eq!(ts.stop_time(), 1.0);
+        ts.set_tempo(60.0);
+        assert_eq!(ts.stop_time(), 2.0);
+
+        // Seconds again
+        ts.set_stop_time(0.5);
+        assert_eq!(ts.sync_beats(), None);
+        ts.set_tempo(120.0);
+        assert_eq!(ts.stop_time(), 0.5);
+    }
+
     #[test]
     fn test_tape_stop_trigger() {
         let mut ts = TapeStop::new(48000.0);
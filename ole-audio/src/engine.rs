@@ -1,25 +1,37 @@
 //! Audio engine - orchestrates decks, mixer, and effects
 
-use crate::deck::{Deck, DeckState};
+use crate::deck::{AnalyzedTrack, Deck, DeckId, DeckState, DECK_COUNT};
 use crate::effects::{
-    Bitcrusher, Delay, DelayModulation, Effect, Filter, FilterMode, FilterType, Flanger,
-    LadderFilter, Limiter, Reverb, StateVariableFilter, SvfOutputType, TapeStop,
+    Bitcrusher, ConvolutionReverb, DeckFilter, Delay, DelayModulation, EchoOut, Effect,
+    EffectChain, EffectKind, EffectSlotState, FilterMode, FilterType, Flanger, ImpulseResponse,
+    Limiter, NoiseSweep, Reverb, TapeStop, MAX_EFFECT_SLOTS,
 };
+use crate::eq::{EqBand, EqMode};
+use crate::fixed::{FixedVec, Label};
+use crate::interpolation::Interpolation;
 use crate::mastering::{LufsValues, MasteringChain, MasteringPreset};
-use crate::mixer::Mixer;
-use crate::timestretcher::{FftSize, PhaseVocoder};
+use crate::mixer::{CrossfaderAssign, CrossfaderCurve, Mixer, UpfaderCurve};
+use crate::output::{OutputDevice, OutputInfo, OutputSettings};
+use crate::quantize::QuantizeResolution;
+use crate::recorder::{Recorder, RecordingStatus};
+use crate::sync::{self, MasterClock, SyncLeader, SyncRole};
 use crate::vinyl::{VinylEmulator, VinylPreset};
 use crossbeam_channel::{bounded, Receiver, Sender};
-use ole_analysis::EnhancedWaveform;
+use ole_analysis::{BeatGrid, EnhancedWaveform};
+use ringbuf::traits::Producer;
+use ringbuf::HeapProd;
+use std::any::Any;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 /// Commands sent to the audio engine
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum AudioCommand {
-    // Deck commands (samples, sample_rate, name, waveform_overview, enhanced_waveform, key)
+    // Deck commands (deck, samples, sample_rate, name, waveform_overview, enhanced_waveform, key)
     // Using Arc to avoid copying large sample data through channels
-    LoadDeckA(
+    LoadDeck(
+        DeckId,
         Arc<Vec<f32>>,
         u32,
         Option<String>,
@@ -27,110 +39,127 @@ pub enum AudioCommand {
         Arc<EnhancedWaveform>,
         Option<String>,
     ),
-    LoadDeckB(
-        Arc<Vec<f32>>,
-        u32,
-        Option<String>,
-        Arc<Vec<f32>>,
-        Arc<EnhancedWaveform>,
-        Option<String>,
-    ),
-    PlayA,
-    PlayB,
-    PauseA,
-    PauseB,
-    StopA,
-    StopB,
-    ToggleA,
-    ToggleB,
-    SeekA(f64),
-    SeekB(f64),
-    NudgeA(f64),
-    NudgeB(f64),
-    BeatNudgeA(f32), // Nudge by fraction of beat (e.g., 0.0625 = 1/16)
-    BeatNudgeB(f32),
-    BeatjumpA(i32), // Jump by N beats
-    BeatjumpB(i32),
-    SetCueA(u8), // Set cue point 1-4
-    SetCueB(u8),
-    JumpCueA(u8), // Jump to cue point 1-4
-    JumpCueB(u8),
-    SetTempoA(f32),
-    SetTempoB(f32),
-    AdjustTempoA(f32),
-    AdjustTempoB(f32),
-    SetGainA(f32),
-    SetGainB(f32),
-    AdjustGainA(f32),
-    AdjustGainB(f32),
+    // Loads analyzed off the audio thread (see `AudioCommand::prepare`)
+    LoadAnalyzed(DeckId, Box<AnalyzedTrack>),
+    Play(DeckId),
+    Pause(DeckId),
+    Stop(DeckId),
+    Toggle(DeckId),
+    Seek(DeckId, f64),
+    Nudge(DeckId, f64),
+    BeatNudge(DeckId, f32), // Nudge by fraction of beat (e.g., 0.0625 = 1/16)
+    Beatjump(DeckId, i32),  // Jump by N beats
+    SetCue(DeckId, u8),     // Set cue point 1-4
+    JumpCue(DeckId, u8),    // Jump to cue point 1-4
+    LoopIn(DeckId),
+    LoopOut(DeckId),
+    AutoLoop(DeckId, f32), // Loop N beats (1/32 - 32), same length again exits
+    LoopHalve(DeckId),
+    LoopDouble(DeckId),
+    LoopMove(DeckId, f32), // Move loop by N beats
+    Reloop(DeckId),        // Exit loop or re-enter the last one
+    LoopRoll(DeckId, f32), // Loop N beats while the track runs underneath
+    LoopRollRelease(DeckId),
+    ToggleSlip(DeckId), // Slip mode: actions return to where the track would have been
+    SlipRelease(DeckId), // End a slip action (cue/beatjump key released)
+    SetReverse(DeckId, bool),
+    // Beat grid corrections (edits count in BeatGridInfo::edits)
+    SetBeatGrid(DeckId, Box<BeatGrid>), // Replace the grid (e.g. a saved correction)
+    ShiftGrid(DeckId, f64),             // Move the grid by N seconds
+    SetDownbeat(DeckId),                // Playback position becomes a downbeat
+    ScaleGridTempo(DeckId, f64),        // 2.0 = double BPM, 0.5 = halve
+    SetGridBpm(DeckId, f32),            // e.g. from tap tempo
+    // A grid correction worked out off the audio thread (see `AudioCommand::prepare`)
+    EditGrid(DeckId, Box<BeatGrid>),
+    // Quantize: cue/loop points snap to the grid, cue jumps, beatjumps and
+    // play wait for the next grid line
+    ToggleQuantize,
+    SetQuantizeResolution(QuantizeResolution),
+    CycleQuantizeResolution,
+    SetTempo(DeckId, f32),
+    AdjustTempo(DeckId, f32),
+    SetGain(DeckId, f32),
+    AdjustGain(DeckId, f32),
 
     // Sync commands
-    SyncBToA,
-    SyncAToB,
+    Sync(DeckId),           // Match tempo and bars to the leader (or loudest other deck)
+    ToggleSyncLock(DeckId), // Keep tempo and phase locked to the sync leader
+    SetMasterClock(Option<f32>), // Some(bpm) = master clock leads, None = automatic
+
+    // Channel EQ (gain in dB, -26 to +6)
+    SetEq(DeckId, EqBand, f32),
+    AdjustEq(DeckId, EqBand, f32),
+    ToggleEqKill(DeckId, EqBand),
+    SetEqMode(EqMode), // Classic or isolator, all channels
+
+    // Channel strips: upfader 0.0 - 1.0, trim in dB (-12 to +12)
+    SetUpfader(DeckId, f32),
+    AdjustUpfader(DeckId, f32),
+    SetTrim(DeckId, f32),
+    AdjustTrim(DeckId, f32),
+    SetUpfaderCurve(UpfaderCurve),
 
     // Mixer commands
     SetCrossfader(f32),
     MoveCrossfader(f32),
     CenterCrossfader,
+    SetCrossfaderCurve(CrossfaderCurve),
+    SetCrossfaderSharpness(f32), // 0.0 - 1.0
+    SetHamster(bool),            // Reverse the crossfader
+    SetCrossfaderAssign(DeckId, CrossfaderAssign),
+
+    // Headphone cue (PFL)
+    ToggleCue(DeckId),
+    SetCueMix(f32),          // 0.0 = cue only, 1.0 = master only
+    SetHeadphoneVolume(f32), // 0.0 - 2.0
     SetMasterVolume(f32),
 
-    // Effect commands for deck A
-    ToggleFilterA,
-    SetFilterTypeA(FilterType),
-    SetFilterCutoffA(f32),
-    AdjustFilterCutoffA(f32),
-    ToggleDelayA,
-    SetDelayTimeA(f32),
-    SetDelayFeedbackA(f32),
-    ToggleReverbA,
-
-    // Effect commands for deck B
-    ToggleFilterB,
-    SetFilterTypeB(FilterType),
-    SetFilterCutoffB(f32),
-    AdjustFilterCutoffB(f32),
-    ToggleDelayB,
-    SetDelayTimeB(f32),
-    SetDelayFeedbackB(f32),
-    ToggleReverbB,
+    // Recording (WAV or FLAC by extension)
+    StartRecording(PathBuf),
+    // A recording started off the audio thread (see `AudioCommand::prepare`)
+    AttachRecorder(Box<Recorder>),
+    StopRecording,
+
+    // Effect commands
+    ToggleFilter(DeckId),
+    SetFilterType(DeckId, FilterType),
+    SetFilterCutoff(DeckId, f32),
+    AdjustFilterCutoff(DeckId, f32),
+    ToggleDelay(DeckId),
+    SetDelayTime(DeckId, f32),
+    SetDelayFeedback(DeckId, f32),
+    ToggleReverb(DeckId),
 
     // Preset-based effect commands (level 1-5 for delay/reverb, 1-10 for filter)
-    SetDelayLevelA(u8),
-    SetDelayLevelB(u8),
-    SetFilterPresetA(FilterType, u8),
-    SetFilterPresetB(FilterType, u8),
-    SetReverbLevelA(u8),
-    SetReverbLevelB(u8),
+    SetDelayLevel(DeckId, u8),
+    SetFilterPreset(DeckId, FilterType, u8),
+    SetReverbLevel(DeckId, u8),
 
     // Filter mode selection (Biquad, Ladder, SVF)
-    SetFilterModeA(FilterMode),
-    SetFilterModeB(FilterMode),
-    SetFilterResonanceA(f32),
-    SetFilterResonanceB(f32),
-    SetFilterDriveA(f32), // Ladder filter only
-    SetFilterDriveB(f32),
+    SetFilterMode(DeckId, FilterMode),
+    SetFilterResonance(DeckId, f32),
+    SetFilterDrive(DeckId, f32), // Ladder filter only
 
     // Vinyl emulation
-    ToggleVinylA,
-    ToggleVinylB,
-    SetVinylPresetA(VinylPreset),
-    SetVinylPresetB(VinylPreset),
-    SetVinylWowA(f32), // 0.0-1.0
-    SetVinylWowB(f32),
-    SetVinylNoiseA(f32), // 0.0-1.0
-    SetVinylNoiseB(f32),
-    SetVinylWarmthA(f32), // 0.0-1.0
-    SetVinylWarmthB(f32),
-
-    // Time stretching (phase vocoder)
-    ToggleTimeStretchA,
-    ToggleTimeStretchB,
-    SetTimeStretchRatioA(f32), // 0.25-4.0
-    SetTimeStretchRatioB(f32),
+    ToggleVinyl(DeckId),
+    SetVinylPreset(DeckId, VinylPreset),
+    SetVinylWow(DeckId, f32),    // 0.0-1.0
+    SetVinylNoise(DeckId, f32),  // 0.0-1.0
+    SetVinylWarmth(DeckId, f32), // 0.0-1.0
+
+    // Key lock (tempo changes keep pitch, via phase vocoder)
+    ToggleKeyLock(DeckId),
+    SetKeyLock(DeckId, bool),
+
+    // Playback quality: how decks read between samples, all decks
+    SetInterpolation(Interpolation),
+
+    // Key shift in semitones (-12 to +12, 0.01 = one cent)
+    SetPitch(DeckId, f32),
+    AdjustPitch(DeckId, f32),
 
     // Delay modulation
-    SetDelayModulationA(DelayModulation),
-    SetDelayModulationB(DelayModulation),
+    SetDelayModulation(DeckId, DelayModulation),
 
     // Mastering chain
     ToggleMastering,
@@ -138,87 +167,269 @@ pub enum AudioCommand {
     CycleMasteringPreset,
 
     // Tape Stop effect
-    ToggleTapeStopA,
-    ToggleTapeStopB,
-    TriggerTapeStopA,
-    TriggerTapeStopB,
-    TriggerTapeStartA,
-    TriggerTapeStartB,
-    SetTapeStopTimeA(f32),
-    SetTapeStopTimeB(f32),
+    ToggleTapeStop(DeckId),
+    TriggerTapeStop(DeckId),
+    TriggerTapeStart(DeckId),
+    SetTapeStopTime(DeckId, f32),
+
+    // Transition effects
+    TriggerEchoOut(DeckId), // cut the input, stop the deck, let the echoes ring
+    TriggerNoiseSweep(DeckId),
+
+    // Convolution reverb: decoded impulse response (samples, sample rate, channels, name)
+    LoadImpulse(DeckId, Vec<f32>, u32, u16, String),
+    // An impulse response prepared off the audio thread (see `AudioCommand::prepare`)
+    SetImpulse(DeckId, Box<ImpulseResponse>),
+    SetConvolutionWet(DeckId, f32), // 0.0-1.0
 
     // Flanger effect
-    ToggleFlangerA,
-    ToggleFlangerB,
-    SetFlangerRateA(f32),
-    SetFlangerRateB(f32),
-    SetFlangerDepthA(f32),
-    SetFlangerDepthB(f32),
-    SetFlangerFeedbackA(f32),
-    SetFlangerFeedbackB(f32),
+    ToggleFlanger(DeckId),
+    SetFlangerRate(DeckId, f32),
+    SetFlangerDepth(DeckId, f32),
+    SetFlangerFeedback(DeckId, f32),
 
     // Bitcrusher effect
-    ToggleBitcrusherA,
-    ToggleBitcrusherB,
-    SetBitcrusherBitsA(u8),
-    SetBitcrusherBitsB(u8),
-    SetBitcrusherDownsampleA(u8),
-    SetBitcrusherDownsampleB(u8),
+    ToggleBitcrusher(DeckId),
+    SetBitcrusherBits(DeckId, u8),
+    SetBitcrusherDownsample(DeckId, u8),
+
+    // Effect chain: slots count from 0, past the end means last
+    AddEffect(DeckId, usize, EffectKind),
+    // An effect built off the audio thread (see `AudioCommand::prepare`)
+    InsertEffect(DeckId, usize, Box<dyn Effect>),
+    RemoveEffect(DeckId, usize),
+    MoveEffect(DeckId, usize, usize), // from, to
+    ToggleEffectSlot(DeckId, usize),
+    ToggleEffectKind(DeckId, EffectKind), // first slot of that kind
+    SetEffectMix(DeckId, usize, f32),     // 0.0 = dry, 1.0 = wet
+    SetEffectParam(DeckId, usize, usize, f32), // slot, parameter index, value
+
+    // Output device (handled by the host that owns the stream)
+    ListOutputs,
+    SetOutput(OutputSettings),
 
     // System
     Shutdown,
 }
 
+impl AudioCommand {
+    /// Do the slow part of a command before it reaches the audio thread
+    ///
+    /// Loads get their beat grid analyzed, grid corrections get applied to
+    /// the copy in `grids`, recordings get their file created and writer
+    /// thread started, added effects get built and impulse responses get
+    /// transformed, so the engine only swaps the result in. Other commands
+    /// pass through unchanged.
+    pub fn prepare(
+        self,
+        sample_rate: u32,
+        grids: &mut DeckGrids,
+    ) -> Result<AudioCommand, String> {
+        Ok(match self {
+            AudioCommand::LoadDeck(deck, samples, sr, name, waveform, enhanced, key) => {
+                let track = AnalyzedTrack::analyze(samples, sr, name, waveform, enhanced, key);
+                grids.grids[deck.index()] = track.beat_grid.clone();
+                AudioCommand::LoadAnalyzed(deck, Box::new(track))
+            }
+            AudioCommand::SetBeatGrid(deck, grid) => {
+                grids.grids[deck.index()] = Some(grid.clone());
+                AudioCommand::SetBeatGrid(deck, grid)
+            }
+            AudioCommand::ShiftGrid(deck, _)
+            | AudioCommand::SetDownbeat(deck)
+            | AudioCommand::ScaleGridTempo(deck, _)
+            | AudioCommand::SetGridBpm(deck, _) => {
+                let i = deck.index();
+                let position = grids.positions[i];
+                match grids.grids[i].as_deref().and_then(|g| self.edit_grid(g, position)) {
+                    Some(grid) => {
+                        let grid = Box::new(grid);
+                        grids.grids[i] = Some(grid.clone());
+                        AudioCommand::EditGrid(deck, grid)
+                    }
+                    None => self,
+                }
+            }
+            AudioCommand::StartRecording(path) => {
+                let recorder =
+                    Recorder::start(path, sample_rate).map_err(|e| format!("Recording: {}", e))?;
+                AudioCommand::AttachRecorder(Box::new(recorder))
+            }
+            AudioCommand::AddEffect(deck, slot, kind) => {
+                AudioCommand::InsertEffect(deck, slot, kind.build(sample_rate))
+            }
+            AudioCommand::LoadImpulse(deck, samples, sr, channels, name) => {
+                let impulse = ImpulseResponse::new(name, &samples, channels, sr, sample_rate)
+                    .map_err(|e| format!("Impulse response: {}", e))?;
+                AudioCommand::SetImpulse(deck, Box::new(impulse))
+            }
+            other => other,
+        })
+    }
+
+    /// The grid a beat grid correction turns `grid` into, None for other
+    /// commands (`position` is the deck's playback position in seconds,
+    /// where `SetDownbeat` puts the downbeat)
+    fn edit_grid(&self, grid: &BeatGrid, position: f64) -> Option<BeatGrid> {
+        let samples_per_sec = grid.sample_rate as f64 * 2.0;
+        let mut grid = grid.clone();
+        match *self {
+            AudioCommand::ShiftGrid(_, secs) => grid.shift(secs * samples_per_sec),
+            AudioCommand::SetDownbeat(_) => grid.set_downbeat_at(position * samples_per_sec),
+            AudioCommand::ScaleGridTempo(_, factor) => grid.scale_tempo(factor),
+            AudioCommand::SetGridBpm(_, bpm) if bpm > 0.0 => grid.set_bpm(bpm),
+            _ => return None,
+        }
+        Some(grid)
+    }
+}
+
+/// Each deck's beat grid as the command thread last sent it, and the
+/// playback positions it last saw published
+///
+/// Grid corrections are worked out on these copies (see
+/// `AudioCommand::prepare`), so the audio thread only swaps the result in.
+#[derive(Debug, Default)]
+pub struct DeckGrids {
+    grids: [Option<Box<BeatGrid>>; DECK_COUNT],
+    positions: [f64; DECK_COUNT],
+}
+
+impl DeckGrids {
+    /// Follow the deck positions in a published state update
+    pub fn follow(&mut self, event: &AudioEvent) {
+        if let AudioEvent::StateUpdate { channels, .. } = event {
+            for (position, channel) in self.positions.iter_mut().zip(channels.iter()) {
+                *position = channel.deck.position;
+            }
+        }
+    }
+}
+
+/// Something the engine let go of, freed by the host off the audio thread
+/// (see `EngineState::set_retired`)
+pub enum Retired {
+    /// A deck's previous track (see `Deck::load_analyzed`)
+    Track(Box<AnalyzedTrack>),
+    /// An effect taken out of a chain (or that didn't fit in a full one)
+    Effect(Box<dyn Effect>),
+    /// Waveforms of a track the UI state no longer shows
+    /// (see `EngineState::write_state`)
+    Waveforms(Arc<Vec<f32>>, Arc<EnhancedWaveform>),
+    /// An impulse response replaced by a new one (or with no convolution
+    /// slot to go to)
+    Impulse(Box<ImpulseResponse>),
+    /// A deck's beat grid before a correction (or a correction with no
+    /// grid left to apply it to)
+    Grid(Box<BeatGrid>),
+    /// A stopped recorder replaced by a new recording
+    Recorder(Box<Recorder>),
+}
+
+/// One deck's channel for UI rendering: the deck, its channel strip and its effects
+#[derive(Debug, Clone)]
+pub struct ChannelState {
+    pub deck: DeckState,
+    // Channel EQ state (low, mid, high)
+    pub eq_gains: [f32; 3],
+    pub eq_kills: [bool; 3],
+    // Channel strip
+    pub upfader: f32,
+    pub trim: f32,
+    pub cue: bool,
+    pub assign: CrossfaderAssign,
+    // Filter state
+    pub filter_enabled: bool,
+    pub filter_cutoff: f32,
+    pub filter_type: FilterType,
+    pub filter_level: u8,
+    pub filter_mode: FilterMode,
+    // Delay state
+    pub delay_enabled: bool,
+    pub delay_level: u8,
+    pub delay_modulation: DelayModulation,
+    // Reverb state
+    pub reverb_enabled: bool,
+    pub reverb_level: u8,
+    // Convolution reverb impulse response (name)
+    pub impulse: Option<Label>,
+    // Vinyl emulation state
+    pub vinyl_enabled: bool,
+    pub vinyl_preset: VinylPreset,
+    // Effect chain, in processing order
+    pub effects: FixedVec<EffectSlotState, MAX_EFFECT_SLOTS>,
+}
+
+impl Default for ChannelState {
+    fn default() -> Self {
+        Self {
+            deck: DeckState::default(),
+            eq_gains: [0.0; 3],
+            eq_kills: [false; 3],
+            upfader: 1.0,
+            trim: 0.0,
+            cue: false,
+            assign: CrossfaderAssign::default(),
+            filter_enabled: false,
+            filter_cutoff: 1000.0,
+            filter_type: FilterType::LowPass,
+            filter_level: 0,
+            filter_mode: FilterMode::default(),
+            delay_enabled: false,
+            delay_level: 0,
+            delay_modulation: DelayModulation::default(),
+            reverb_enabled: false,
+            reverb_level: 0,
+            impulse: None,
+            vinyl_enabled: false,
+            vinyl_preset: VinylPreset::default(),
+            effects: FixedVec::new(),
+        }
+    }
+}
+
 /// Events sent from the audio engine
 #[derive(Debug, Clone)]
 pub enum AudioEvent {
     /// State update for UI rendering
     StateUpdate {
-        deck_a: Box<DeckState>,
-        deck_b: Box<DeckState>,
+        /// Every deck's channel, indexed by `DeckId::index`
+        channels: Box<[ChannelState; DECK_COUNT]>,
         crossfader: f32,
         master_volume: f32,
-        // Filter state
-        filter_a_enabled: bool,
-        filter_a_cutoff: f32,
-        filter_a_type: FilterType,
-        filter_a_level: u8,
-        filter_a_mode: FilterMode,
-        filter_b_enabled: bool,
-        filter_b_cutoff: f32,
-        filter_b_type: FilterType,
-        filter_b_level: u8,
-        filter_b_mode: FilterMode,
-        // Delay state
-        delay_a_enabled: bool,
-        delay_a_level: u8,
-        delay_a_modulation: DelayModulation,
-        delay_b_enabled: bool,
-        delay_b_level: u8,
-        delay_b_modulation: DelayModulation,
-        // Reverb state
-        reverb_a_enabled: bool,
-        reverb_a_level: u8,
-        reverb_b_enabled: bool,
-        reverb_b_level: u8,
-        // Vinyl emulation state
-        vinyl_a_enabled: bool,
-        vinyl_a_preset: VinylPreset,
-        vinyl_b_enabled: bool,
-        vinyl_b_preset: VinylPreset,
-        // Time stretch state
-        time_stretch_a_enabled: bool,
-        time_stretch_a_ratio: f32,
-        time_stretch_b_enabled: bool,
-        time_stretch_b_ratio: f32,
+        eq_mode: EqMode,
+        // Channel strip and crossfader setup
+        upfader_curve: UpfaderCurve,
+        crossfader_curve: CrossfaderCurve,
+        crossfader_sharpness: f32,
+        hamster: bool,
+        // Headphone cue
+        cue_mix: f32,
+        headphone_volume: f32,
+        // Recording (boxed like `channels`, so the event stays small)
+        recording: Box<RecordingStatus>,
+        // Playback quality
+        interpolation: Interpolation,
         // Mastering state
         mastering_enabled: bool,
         mastering_preset: MasteringPreset,
         mastering_lufs: LufsValues,
         mastering_gain_reduction: f32,
+        // Quantize state
+        quantize: bool,
+        quantize_resolution: QuantizeResolution,
+        // Sync lock state
+        sync_leader: SyncLeader,
+        master_bpm: f32,
     },
     /// Track loaded successfully
-    TrackLoaded { deck: char },
+    TrackLoaded { deck: DeckId },
+    /// Output devices on every host (answers `ListOutputs`)
+    Outputs(Vec<OutputDevice>),
+    /// The output stream (re)started
+    OutputOpened(OutputInfo),
+    /// The output stream failed or its device went away; it is being rebuilt
+    OutputLost(String),
     /// Error occurred
     Error(String),
 }
@@ -227,499 +438,569 @@ pub enum AudioEvent {
 /// Sized for 2048 stereo samples (typical maximum)
 const MAX_BUFFER_SIZE: usize = 4096;
 
+/// A deck action held back until the next grid line (quantize)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QuantizedAction {
+    Play,
+    JumpCue(u8),
+    Beatjump(i32),
+}
+
+impl QuantizedAction {
+    fn apply(self, deck: &mut Deck) {
+        match self {
+            QuantizedAction::Play => deck.play(),
+            QuantizedAction::JumpCue(num) => deck.jump_cue(num),
+            QuantizedAction::Beatjump(beats) => deck.beatjump(beats),
+        }
+    }
+}
+
+/// A quantized action waiting for its grid line
+#[derive(Debug, Clone, Copy)]
+struct ScheduledAction {
+    action: QuantizedAction,
+    /// Output frames until it fires
+    frames: usize,
+}
+
+/// A deck's effect chain
+///
+/// Slots start as Deck → Tape Stop → Vinyl → Bitcrusher → Filter → Flanger →
+/// Delay → Reverb → Mixer and can be reordered, added and removed. The
+/// per-effect commands find their effect by type and do nothing if it was
+/// removed.
+pub struct DeckEffects {
+    pub chain: EffectChain,
+    // Current delay level (0 = off, 1-5)
+    delay_level: u8,
+    delay_modulation: DelayModulation,
+}
+
+impl DeckEffects {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            chain: EffectChain::with_effects(&EffectKind::ALL, sample_rate),
+            delay_level: 0,
+            delay_modulation: DelayModulation::Off,
+        }
+    }
+
+    /// Lookup table for delay level (1-5) to delay time in ms
+    /// Index 0 is default, indices 1-5 map to levels 1-5
+    const DELAY_LEVEL_MS: [f32; 6] = [250.0, 100.0, 200.0, 300.0, 400.0, 500.0];
+
+    /// Lookup table for delay level (1-5) to delay time in beats, used
+    /// once the deck has a tempo (the ms table covers decks without one)
+    const DELAY_LEVEL_BEATS: [f32; 6] = [0.5, 0.25, 0.5, 0.75, 1.0, 2.0];
+
+    /// Map delay level (1-5) to delay time in ms
+    #[inline]
+    fn delay_level_to_ms(level: u8) -> f32 {
+        Self::DELAY_LEVEL_MS
+            .get(level as usize)
+            .copied()
+            .unwrap_or(250.0)
+    }
+
+    /// Map delay level (1-5) to delay time in beats
+    #[inline]
+    fn delay_level_to_beats(level: u8) -> f32 {
+        Self::DELAY_LEVEL_BEATS
+            .get(level as usize)
+            .copied()
+            .unwrap_or(0.5)
+    }
+
+    /// The chain's first effect of type `T`
+    pub fn get<T: Effect>(&self) -> Option<&T> {
+        self.chain.find()
+    }
+
+    /// The chain's first effect of type `T`, for changes
+    pub fn get_mut<T: Effect>(&mut self) -> Option<&mut T> {
+        self.chain.find_mut()
+    }
+
+    /// Change the effect of type `T`, if the chain has one
+    fn with<T: Effect>(&mut self, f: impl FnOnce(&mut T)) {
+        if let Some(effect) = self.chain.find_mut() {
+            f(effect);
+        }
+    }
+
+    /// Flip an effect on or off
+    fn toggle<T: Effect>(&mut self) {
+        self.with(|effect: &mut T| effect.set_enabled(!effect.is_enabled()));
+    }
+
+    /// Whether the filter of the current mode is on
+    pub fn filter_enabled(&self) -> bool {
+        self.get::<DeckFilter>().is_some_and(|f| f.is_enabled())
+    }
+
+    fn set_delay_level(&mut self, level: u8) {
+        let Some(delay) = self.chain.find_mut::<Delay>() else {
+            return;
+        };
+        if level == 0 {
+            delay.set_enabled(false);
+        } else {
+            delay.set_delay_ms(Self::delay_level_to_ms(level));
+            delay.set_sync_beats(Some(Self::delay_level_to_beats(level)));
+            delay.set_enabled(true);
+        }
+        self.delay_level = level;
+    }
+
+    fn set_reverb_level(&mut self, level: u8) {
+        self.with(|reverb: &mut Reverb| {
+            if level == 0 {
+                reverb.set_enabled(false);
+            } else {
+                reverb.set_level(level);
+            }
+        });
+    }
+
+    fn set_delay_modulation(&mut self, mode: DelayModulation) {
+        self.with(|delay: &mut Delay| delay.set_modulation(mode));
+        self.delay_modulation = mode;
+    }
+
+    /// Rebuild the chain for a new sample rate, keeping its settings
+    /// (allocates - keep off the audio thread)
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let modulation = self.delay_modulation;
+        self.chain.rebuild(sample_rate, |old, new| {
+            let (old, new) = (old as &dyn Any, new as &mut dyn Any);
+            if let (Some(old), Some(new)) = (
+                old.downcast_ref::<DeckFilter>(),
+                new.downcast_mut::<DeckFilter>(),
+            ) {
+                new.set_preset(old.filter_type(), old.level());
+                new.set_mode(old.mode());
+                new.set_type(old.filter_type());
+            } else if let (Some(old), Some(new)) =
+                (old.downcast_ref::<Reverb>(), new.downcast_mut::<Reverb>())
+            {
+                if old.level() > 0 {
+                    new.set_level(old.level());
+                }
+            } else if let (Some(old), Some(new)) = (
+                old.downcast_ref::<VinylEmulator>(),
+                new.downcast_mut::<VinylEmulator>(),
+            ) {
+                new.set_preset(old.preset());
+            } else if let Some(new) = new.downcast_mut::<Delay>() {
+                new.set_modulation(modulation);
+            } else if let (Some(old), Some(new)) = (
+                old.downcast_ref::<ConvolutionReverb>(),
+                new.downcast_mut::<ConvolutionReverb>(),
+            ) {
+                if let Some(Ok(impulse)) = old.impulse().map(|ir| ir.resampled(sample_rate)) {
+                    new.set_impulse(Box::new(impulse));
+                }
+            }
+        });
+    }
+
+    /// Follow the deck's tempo (BPM, 0.0 if unknown) in beat-synced effects
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.chain.set_tempo(bpm);
+    }
+
+    /// Follow the deck's beat grid position in grid-locked effects
+    pub fn set_beat(&mut self, beat: Option<f64>) {
+        self.chain.set_beat(beat);
+    }
+
+    /// Run a deck's output through the chain
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        self.chain.process(buffer);
+    }
+}
+
 /// Audio engine state (held in audio thread)
 pub struct EngineState {
-    pub deck_a: Deck,
-    pub deck_b: Deck,
+    /// Decks, indexed by `DeckId::index`
+    pub decks: [Deck; DECK_COUNT],
     pub mixer: Mixer,
-    // Original biquad filters
-    pub filter_a: Filter,
-    pub filter_b: Filter,
-    // New ladder filters (Moog-style)
-    pub ladder_a: LadderFilter,
-    pub ladder_b: LadderFilter,
-    // New SVF filters
-    pub svf_a: StateVariableFilter,
-    pub svf_b: StateVariableFilter,
-    // Other effects
-    pub delay_a: Delay,
-    pub reverb_a: Reverb,
-    pub delay_b: Delay,
-    pub reverb_b: Reverb,
-    // Vinyl emulation
-    pub vinyl_a: VinylEmulator,
-    pub vinyl_b: VinylEmulator,
-    // Phase vocoder for time stretching
-    pub phase_vocoder_a: PhaseVocoder,
-    pub phase_vocoder_b: PhaseVocoder,
+    /// Each deck's effect chain
+    pub effects: [DeckEffects; DECK_COUNT],
     // Mastering chain (EQ, compressor, saturation, stereo enhancement)
     pub mastering: MasteringChain,
     // Master limiter (brickwall, always on for safety)
     pub master_limiter: Limiter,
-    // Tape stop effect
-    pub tape_stop_a: TapeStop,
-    pub tape_stop_b: TapeStop,
-    // Flanger effect
-    pub flanger_a: Flanger,
-    pub flanger_b: Flanger,
-    // Bitcrusher effect
-    pub bitcrusher_a: Bitcrusher,
-    pub bitcrusher_b: Bitcrusher,
     sample_rate: u32,
-    // Current effect levels (0 = off, 1-5 for delay/reverb, 1-10 for filter)
-    filter_a_level: u8,
-    filter_b_level: u8,
-    delay_a_level: u8,
-    delay_b_level: u8,
-    delay_a_modulation: DelayModulation,
-    delay_b_modulation: DelayModulation,
-    // Filter mode selection
-    filter_mode_a: FilterMode,
-    filter_mode_b: FilterMode,
+    // Quantize (global for all decks)
+    quantize: bool,
+    quantize_resolution: QuantizeResolution,
+    scheduled: [Option<ScheduledAction>; DECK_COUNT],
+    // Sync lock
+    sync_locks: [bool; DECK_COUNT],
+    sync_leader: SyncLeader,
+    master_clock: MasterClock,
+    clock_leads: bool,
+    // Recording of the master output
+    // (kept after stopping, so its status stays readable)
+    recorder: Option<Box<Recorder>>,
+    /// Why the last recording couldn't start
+    recording_error: Option<Label>,
     // Pre-allocated processing buffers (avoids allocation in audio callback)
-    buffer_a: Vec<f32>,
-    buffer_b: Vec<f32>,
+    buffers: [Vec<f32>; DECK_COUNT],
+    /// Where things the engine lets go of are sent to be freed
+    retired: Option<HeapProd<Retired>>,
 }
 
 impl EngineState {
     pub fn new(sample_rate: u32) -> Self {
         Self {
-            deck_a: Deck::new(sample_rate),
-            deck_b: Deck::new(sample_rate),
-            mixer: Mixer::new(),
-            // Original biquad filters
-            filter_a: Filter::new(sample_rate as f32),
-            filter_b: Filter::new(sample_rate as f32),
-            // New ladder filters (Moog-style)
-            ladder_a: LadderFilter::new(sample_rate as f32),
-            ladder_b: LadderFilter::new(sample_rate as f32),
-            // New SVF filters
-            svf_a: StateVariableFilter::new(sample_rate as f32),
-            svf_b: StateVariableFilter::new(sample_rate as f32),
-            // Other effects
-            delay_a: Delay::new(sample_rate),
-            reverb_a: Reverb::new(sample_rate),
-            delay_b: Delay::new(sample_rate),
-            reverb_b: Reverb::new(sample_rate),
-            // Vinyl emulation (disabled by default)
-            vinyl_a: VinylEmulator::new(sample_rate as f32),
-            vinyl_b: VinylEmulator::new(sample_rate as f32),
-            // Phase vocoder (disabled by default, medium FFT size for balance)
-            phase_vocoder_a: PhaseVocoder::new(FftSize::Medium),
-            phase_vocoder_b: PhaseVocoder::new(FftSize::Medium),
+            decks: std::array::from_fn(|_| Deck::new(sample_rate)),
+            mixer: Mixer::new(sample_rate as f32),
+            effects: std::array::from_fn(|_| DeckEffects::new(sample_rate)),
             // Mastering chain (enabled by default with Clean preset)
             mastering: MasteringChain::new(sample_rate as f32),
             // Master limiter (always on for safety, -0.1dBFS ceiling)
             master_limiter: Limiter::new(sample_rate as f32),
-            // Tape stop effects
-            tape_stop_a: TapeStop::new(sample_rate as f32),
-            tape_stop_b: TapeStop::new(sample_rate as f32),
-            // Flanger effects
-            flanger_a: Flanger::new(sample_rate as f32),
-            flanger_b: Flanger::new(sample_rate as f32),
-            // Bitcrusher effects
-            bitcrusher_a: Bitcrusher::new(sample_rate as f32),
-            bitcrusher_b: Bitcrusher::new(sample_rate as f32),
             sample_rate,
-            filter_a_level: 0,
-            filter_b_level: 0,
-            delay_a_level: 0,
-            delay_b_level: 0,
-            delay_a_modulation: DelayModulation::Off,
-            delay_b_modulation: DelayModulation::Off,
-            filter_mode_a: FilterMode::default(),
-            filter_mode_b: FilterMode::default(),
+            quantize: false,
+            quantize_resolution: QuantizeResolution::default(),
+            scheduled: [None; DECK_COUNT],
+            sync_locks: [false; DECK_COUNT],
+            sync_leader: SyncLeader::default(),
+            master_clock: MasterClock::default(),
+            clock_leads: false,
+            recorder: None,
+            recording_error: None,
             // Pre-allocate buffers to avoid allocation in audio callback
-            buffer_a: vec![0.0f32; MAX_BUFFER_SIZE],
-            buffer_b: vec![0.0f32; MAX_BUFFER_SIZE],
+            buffers: std::array::from_fn(|_| vec![0.0f32; MAX_BUFFER_SIZE]),
+            retired: None,
         }
     }
 
-    /// Lookup table for delay level (1-5) to delay time in ms
-    /// Index 0 is default, indices 1-5 map to levels 1-5
-    const DELAY_LEVEL_MS: [f32; 6] = [250.0, 100.0, 200.0, 300.0, 400.0, 500.0];
+    /// Output sample rate the engine runs at
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
 
-    /// Lookup table for filter level (1-10) to cutoff frequency in Hz
-    /// Index 0 is default, indices 1-10 map to levels 1-10
-    const FILTER_LEVEL_CUTOFF: [f32; 11] = [
-        1000.0, // default (index 0)
-        200.0, 400.0, 600.0, 1000.0, 2000.0, // levels 1-5
-        4000.0, 6000.0, 10000.0, 15000.0, 20000.0, // levels 6-10
-    ];
+    /// Rebuild the engine for a new output sample rate (allocates - keep
+    /// off the audio thread)
+    ///
+    /// Decks, mixer, effect chains (with their settings and impulse
+    /// responses), mastering, quantize and sync settings carry over.
+    /// Quantized actions still waiting are dropped, and a running
+    /// recording stops: its file is at the old rate.
+    pub fn reset_sample_rate(&mut self, sample_rate: u32) {
+        for deck in &mut self.decks {
+            deck.set_output_rate(sample_rate);
+        }
+        self.mixer.set_sample_rate(sample_rate as f32);
+        for effects in &mut self.effects {
+            effects.set_sample_rate(sample_rate);
+        }
+        let mut mastering = MasteringChain::new(sample_rate as f32);
+        mastering.set_preset(self.mastering.preset());
+        mastering.set_enabled(self.mastering.is_enabled());
+        self.mastering = mastering;
+        self.master_limiter = Limiter::new(sample_rate as f32);
+        self.scheduled = [None; DECK_COUNT];
+        self.stop_recording();
+        self.sample_rate = sample_rate;
+    }
 
-    /// Map delay level (1-5) to delay time in ms
-    #[inline]
-    fn delay_level_to_ms(level: u8) -> f32 {
-        Self::DELAY_LEVEL_MS
-            .get(level as usize)
-            .copied()
-            .unwrap_or(250.0)
+    /// Send what the engine lets go of (replaced tracks, ...) to `bin`,
+    /// so the host frees it off the audio thread
+    ///
+    /// Without a bin, or with a full one, it's freed where it's let go.
+    pub fn set_retired(&mut self, bin: HeapProd<Retired>) {
+        self.retired = Some(bin);
     }
 
-    /// Map filter level (1-10) to cutoff frequency in Hz
-    #[inline]
-    fn filter_level_to_cutoff(level: u8) -> f32 {
-        Self::FILTER_LEVEL_CUTOFF
-            .get(level as usize)
-            .copied()
-            .unwrap_or(1000.0)
+    /// Hand something over to be freed off the audio thread
+    fn retire(&mut self, item: Retired) {
+        if let Some(bin) = self.retired.as_mut() {
+            let _ = bin.try_push(item);
+        }
     }
 
-    /// Process a command
-    pub fn handle_command(&mut self, cmd: AudioCommand) {
-        match cmd {
-            // Deck A commands
-            AudioCommand::LoadDeckA(samples, sr, name, waveform, enhanced, key) => {
-                self.deck_a.load(samples, sr, name, waveform, enhanced, key)
-            }
-            AudioCommand::PlayA => self.deck_a.play(),
-            AudioCommand::PauseA => self.deck_a.pause(),
-            AudioCommand::StopA => self.deck_a.stop(),
-            AudioCommand::ToggleA => self.deck_a.toggle(),
-            AudioCommand::SeekA(pos) => self.deck_a.seek(pos),
-            AudioCommand::NudgeA(delta) => self.deck_a.nudge(delta),
-            AudioCommand::BeatNudgeA(beats) => self.deck_a.beat_nudge(beats),
-            AudioCommand::BeatjumpA(beats) => self.deck_a.beatjump(beats),
-            AudioCommand::SetCueA(num) => self.deck_a.set_cue(num),
-            AudioCommand::JumpCueA(num) => self.deck_a.jump_cue(num),
-            AudioCommand::SetTempoA(tempo) => self.deck_a.set_tempo(tempo),
-            AudioCommand::AdjustTempoA(delta) => self.deck_a.adjust_tempo(delta),
-            AudioCommand::SetGainA(gain) => self.deck_a.set_gain(gain),
-            AudioCommand::AdjustGainA(delta) => self.deck_a.adjust_gain(delta),
-
-            // Deck B commands
-            AudioCommand::LoadDeckB(samples, sr, name, waveform, enhanced, key) => {
-                self.deck_b.load(samples, sr, name, waveform, enhanced, key)
-            }
-            AudioCommand::PlayB => self.deck_b.play(),
-            AudioCommand::PauseB => self.deck_b.pause(),
-            AudioCommand::StopB => self.deck_b.stop(),
-            AudioCommand::ToggleB => self.deck_b.toggle(),
-            AudioCommand::SeekB(pos) => self.deck_b.seek(pos),
-            AudioCommand::NudgeB(delta) => self.deck_b.nudge(delta),
-            AudioCommand::BeatNudgeB(beats) => self.deck_b.beat_nudge(beats),
-            AudioCommand::BeatjumpB(beats) => self.deck_b.beatjump(beats),
-            AudioCommand::SetCueB(num) => self.deck_b.set_cue(num),
-            AudioCommand::JumpCueB(num) => self.deck_b.jump_cue(num),
-            AudioCommand::SetTempoB(tempo) => self.deck_b.set_tempo(tempo),
-            AudioCommand::AdjustTempoB(delta) => self.deck_b.adjust_tempo(delta),
-            AudioCommand::SetGainB(gain) => self.deck_b.set_gain(gain),
-            AudioCommand::AdjustGainB(delta) => self.deck_b.adjust_gain(delta),
+    /// Add an effect to a deck's chain; a full chain hands it to the
+    /// retire bin
+    fn insert_effect(&mut self, deck: DeckId, slot: usize, effect: Box<dyn Effect>) {
+        let chain = &mut self.effects[deck.index()].chain;
+        if chain.len() < MAX_EFFECT_SLOTS {
+            chain.insert(slot, effect);
+        } else {
+            self.retire(Retired::Effect(effect));
+        }
+    }
 
-            // Sync commands - smart sync with phase alignment
-            AudioCommand::SyncBToA => {
-                self.smart_sync_b_to_a();
-            }
-            AudioCommand::SyncAToB => {
-                self.smart_sync_a_to_b();
-            }
+    /// Get a deck
+    pub fn deck(&self, deck: DeckId) -> &Deck {
+        &self.decks[deck.index()]
+    }
 
-            // Mixer commands
-            AudioCommand::SetCrossfader(pos) => self.mixer.set_crossfader(pos),
-            AudioCommand::MoveCrossfader(delta) => self.mixer.move_crossfader(delta),
-            AudioCommand::CenterCrossfader => self.mixer.center_crossfader(),
-            AudioCommand::SetMasterVolume(vol) => self.mixer.set_master_volume(vol),
+    /// Get a deck for changes
+    pub fn deck_mut(&mut self, deck: DeckId) -> &mut Deck {
+        &mut self.decks[deck.index()]
+    }
 
-            // Effect commands - Deck A
-            AudioCommand::ToggleFilterA => {
-                // Toggle the currently selected filter mode
-                match self.filter_mode_a {
-                    FilterMode::Biquad => {
-                        let enabled = !self.filter_a.is_enabled();
-                        self.filter_a.set_enabled(enabled);
-                    }
-                    FilterMode::Ladder => {
-                        let enabled = !self.ladder_a.is_enabled();
-                        self.ladder_a.set_enabled(enabled);
-                    }
-                    FilterMode::SVF => {
-                        let enabled = !self.svf_a.is_enabled();
-                        self.svf_a.set_enabled(enabled);
-                    }
+    /// Process a command
+    pub fn handle_command(&mut self, cmd: AudioCommand) {
+        match cmd {
+            // Deck commands
+            AudioCommand::LoadDeck(deck, samples, sr, name, waveform, enhanced, key) => {
+                self.scheduled[deck.index()] = None;
+                self.deck_mut(deck)
+                    .load(samples, sr, name, waveform, enhanced, key)
+            }
+            AudioCommand::LoadAnalyzed(deck, track) => {
+                self.scheduled[deck.index()] = None;
+                let replaced = self.deck_mut(deck).load_analyzed(track);
+                self.retire(Retired::Track(replaced));
+            }
+            AudioCommand::Play(deck) => self.schedule(deck, QuantizedAction::Play),
+            AudioCommand::Pause(deck) => {
+                self.scheduled[deck.index()] = None;
+                self.deck_mut(deck).pause()
+            }
+            AudioCommand::Stop(deck) => {
+                self.scheduled[deck.index()] = None;
+                self.deck_mut(deck).stop()
+            }
+            AudioCommand::Toggle(deck) => {
+                let play_pending =
+                    self.scheduled[deck.index()].is_some_and(|s| s.action == QuantizedAction::Play);
+                if self.deck(deck).is_playing() || play_pending {
+                    // Playing, or a quantized play is pending: stop/cancel now
+                    self.scheduled[deck.index()] = None;
+                    self.deck_mut(deck).pause()
+                } else {
+                    self.schedule(deck, QuantizedAction::Play)
                 }
             }
-            AudioCommand::SetFilterTypeA(ft) => {
-                self.filter_a.set_type(ft);
-                // Also update SVF output type if in SVF mode
-                if self.filter_mode_a == FilterMode::SVF {
-                    self.svf_a.set_output_type(match ft {
-                        FilterType::LowPass => SvfOutputType::LowPass,
-                        FilterType::HighPass => SvfOutputType::HighPass,
-                        FilterType::BandPass => SvfOutputType::BandPass,
-                    });
+            AudioCommand::Seek(deck, pos) => self.deck_mut(deck).seek(pos),
+            AudioCommand::Nudge(deck, delta) => self.deck_mut(deck).nudge(delta),
+            AudioCommand::BeatNudge(deck, beats) => self.deck_mut(deck).beat_nudge(beats),
+            AudioCommand::Beatjump(deck, beats) => {
+                self.schedule(deck, QuantizedAction::Beatjump(beats))
+            }
+            AudioCommand::SetCue(deck, num) => self.deck_mut(deck).set_cue(num),
+            AudioCommand::JumpCue(deck, num) => self.schedule(deck, QuantizedAction::JumpCue(num)),
+            AudioCommand::LoopIn(deck) => self.deck_mut(deck).loop_in(),
+            AudioCommand::LoopOut(deck) => self.deck_mut(deck).loop_out(),
+            AudioCommand::AutoLoop(deck, beats) => self.deck_mut(deck).auto_loop(beats),
+            AudioCommand::LoopHalve(deck) => self.deck_mut(deck).loop_halve(),
+            AudioCommand::LoopDouble(deck) => self.deck_mut(deck).loop_double(),
+            AudioCommand::LoopMove(deck, beats) => self.deck_mut(deck).loop_move(beats),
+            AudioCommand::Reloop(deck) => self.deck_mut(deck).reloop(),
+            AudioCommand::LoopRoll(deck, beats) => self.deck_mut(deck).loop_roll(beats),
+            AudioCommand::LoopRollRelease(deck) => self.deck_mut(deck).loop_roll_release(),
+            AudioCommand::ToggleSlip(deck) => self.deck_mut(deck).toggle_slip(),
+            AudioCommand::SlipRelease(deck) => self.deck_mut(deck).slip_release(),
+            AudioCommand::SetReverse(deck, enabled) => self.deck_mut(deck).set_reverse(enabled),
+            AudioCommand::SetBeatGrid(deck, grid) => {
+                if let Some(replaced) = self.deck_mut(deck).set_beat_grid(grid) {
+                    self.retire(Retired::Grid(replaced));
                 }
             }
-            AudioCommand::SetFilterCutoffA(cutoff) => {
-                // Update biquad (source of truth) and active filter only
-                self.filter_a.set_cutoff(cutoff);
-                match self.filter_mode_a {
-                    FilterMode::Ladder => self.ladder_a.set_cutoff(cutoff),
-                    FilterMode::SVF => self.svf_a.set_cutoff(cutoff),
-                    FilterMode::Biquad => {} // Already updated above
+            AudioCommand::EditGrid(deck, grid) => {
+                let replaced = self.deck_mut(deck).apply_grid_edit(grid);
+                self.retire(Retired::Grid(replaced));
+            }
+            // Unprepared grid corrections (offline hosts) are worked out here
+            AudioCommand::ShiftGrid(deck, _)
+            | AudioCommand::SetDownbeat(deck)
+            | AudioCommand::ScaleGridTempo(deck, _)
+            | AudioCommand::SetGridBpm(deck, _) => {
+                let deck_ref = self.deck(deck);
+                let edited = deck_ref
+                    .beat_grid()
+                    .and_then(|grid| cmd.edit_grid(grid, deck_ref.position_secs()));
+                if let Some(grid) = edited {
+                    self.handle_command(AudioCommand::EditGrid(deck, Box::new(grid)));
                 }
             }
-            AudioCommand::AdjustFilterCutoffA(delta) => {
-                let current = self.filter_a.cutoff();
-                // Exponential adjustment for more natural feel
-                let factor: f32 = if delta > 0.0 { 1.1 } else { 0.9 };
-                let new_cutoff = current * factor.powf(delta.abs());
-                // Update biquad (source of truth) and active filter only
-                self.filter_a.set_cutoff(new_cutoff);
-                match self.filter_mode_a {
-                    FilterMode::Ladder => self.ladder_a.set_cutoff(new_cutoff),
-                    FilterMode::SVF => self.svf_a.set_cutoff(new_cutoff),
-                    FilterMode::Biquad => {} // Already updated above
+            AudioCommand::SetTempo(deck, tempo) => self.deck_mut(deck).set_tempo(tempo),
+            AudioCommand::AdjustTempo(deck, delta) => {
+                if self.sync_locks[deck.index()] && self.sync_leader != SyncLeader::Deck(deck) {
+                    // Followers take their tempo from the leader: move that instead
+                    self.adjust_leader_tempo(delta);
+                } else {
+                    self.deck_mut(deck).adjust_tempo(delta);
                 }
             }
-            AudioCommand::ToggleDelayA => {
-                let enabled = !self.delay_a.is_enabled();
-                self.delay_a.set_enabled(enabled);
-            }
-            AudioCommand::SetDelayTimeA(ms) => self.delay_a.set_delay_ms(ms),
-            AudioCommand::SetDelayFeedbackA(fb) => self.delay_a.set_feedback(fb),
+            AudioCommand::SetGain(deck, gain) => self.deck_mut(deck).set_gain(gain),
+            AudioCommand::AdjustGain(deck, delta) => self.deck_mut(deck).adjust_gain(delta),
 
-            // Effect commands - Deck B
-            AudioCommand::ToggleFilterB => {
-                // Toggle the currently selected filter mode
-                match self.filter_mode_b {
-                    FilterMode::Biquad => {
-                        let enabled = !self.filter_b.is_enabled();
-                        self.filter_b.set_enabled(enabled);
-                    }
-                    FilterMode::Ladder => {
-                        let enabled = !self.ladder_b.is_enabled();
-                        self.ladder_b.set_enabled(enabled);
-                    }
-                    FilterMode::SVF => {
-                        let enabled = !self.svf_b.is_enabled();
-                        self.svf_b.set_enabled(enabled);
-                    }
+            // Sync commands - smart sync with phase alignment
+            AudioCommand::Sync(deck) => {
+                if let Some(source) = self.sync_source(deck) {
+                    self.smart_sync(deck, source);
                 }
             }
-            AudioCommand::SetFilterTypeB(ft) => {
-                self.filter_b.set_type(ft);
-                // Also update SVF output type if in SVF mode
-                if self.filter_mode_b == FilterMode::SVF {
-                    self.svf_b.set_output_type(match ft {
-                        FilterType::LowPass => SvfOutputType::LowPass,
-                        FilterType::HighPass => SvfOutputType::HighPass,
-                        FilterType::BandPass => SvfOutputType::BandPass,
-                    });
-                }
+            AudioCommand::ToggleSyncLock(deck) => {
+                self.sync_locks[deck.index()] = !self.sync_locks[deck.index()]
             }
-            AudioCommand::SetFilterCutoffB(cutoff) => {
-                // Update biquad (source of truth) and active filter only
-                self.filter_b.set_cutoff(cutoff);
-                match self.filter_mode_b {
-                    FilterMode::Ladder => self.ladder_b.set_cutoff(cutoff),
-                    FilterMode::SVF => self.svf_b.set_cutoff(cutoff),
-                    FilterMode::Biquad => {} // Already updated above
+            AudioCommand::SetMasterClock(bpm) => {
+                if let Some(bpm) = bpm {
+                    self.master_clock.set_bpm(bpm);
                 }
+                self.clock_leads = bpm.is_some();
             }
-            AudioCommand::AdjustFilterCutoffB(delta) => {
-                let current = self.filter_b.cutoff();
-                let factor: f32 = if delta > 0.0 { 1.1 } else { 0.9 };
-                let new_cutoff = current * factor.powf(delta.abs());
-                // Update biquad (source of truth) and active filter only
-                self.filter_b.set_cutoff(new_cutoff);
-                match self.filter_mode_b {
-                    FilterMode::Ladder => self.ladder_b.set_cutoff(new_cutoff),
-                    FilterMode::SVF => self.svf_b.set_cutoff(new_cutoff),
-                    FilterMode::Biquad => {} // Already updated above
+
+            // Mixer commands
+            AudioCommand::SetEq(deck, band, db) => self.mixer.eq_mut(deck).set_gain(band, db),
+            AudioCommand::AdjustEq(deck, band, delta) => {
+                self.mixer.eq_mut(deck).adjust_gain(band, delta)
+            }
+            AudioCommand::ToggleEqKill(deck, band) => self.mixer.eq_mut(deck).toggle_kill(band),
+            AudioCommand::SetEqMode(mode) => self.mixer.set_eq_mode(mode),
+            AudioCommand::SetUpfader(deck, pos) => self.mixer.set_upfader(deck, pos),
+            AudioCommand::AdjustUpfader(deck, delta) => self
+                .mixer
+                .set_upfader(deck, self.mixer.upfader(deck) + delta),
+            AudioCommand::SetTrim(deck, db) => self.mixer.set_trim(deck, db),
+            AudioCommand::AdjustTrim(deck, delta) => {
+                self.mixer.set_trim(deck, self.mixer.trim(deck) + delta)
+            }
+            AudioCommand::SetUpfaderCurve(curve) => self.mixer.set_upfader_curve(curve),
+            AudioCommand::SetCrossfader(pos) => self.mixer.set_crossfader(pos),
+            AudioCommand::MoveCrossfader(delta) => self.mixer.move_crossfader(delta),
+            AudioCommand::CenterCrossfader => self.mixer.center_crossfader(),
+            AudioCommand::SetCrossfaderCurve(curve) => self.mixer.set_curve(curve),
+            AudioCommand::SetCrossfaderSharpness(s) => self.mixer.set_sharpness(s),
+            AudioCommand::SetHamster(on) => self.mixer.set_hamster(on),
+            AudioCommand::SetCrossfaderAssign(deck, assign) => self.mixer.set_assign(deck, assign),
+            AudioCommand::ToggleCue(deck) => self.mixer.set_cue(deck, !self.mixer.cue(deck)),
+            AudioCommand::SetCueMix(mix) => self.mixer.set_cue_mix(mix),
+            AudioCommand::SetHeadphoneVolume(vol) => self.mixer.set_headphone_volume(vol),
+
+            // Recording
+            AudioCommand::StartRecording(path) => {
+                self.stop_recording();
+                match Recorder::start(path, self.sample_rate) {
+                    Ok(recorder) => self.attach_recorder(Box::new(recorder)),
+                    Err(e) => {
+                        self.recording_error =
+                            Some(Label::from(format!("Recording: {}", e).as_str()))
+                    }
                 }
             }
-            AudioCommand::ToggleDelayB => {
-                let enabled = !self.delay_b.is_enabled();
-                self.delay_b.set_enabled(enabled);
-            }
-            AudioCommand::SetDelayTimeB(ms) => self.delay_b.set_delay_ms(ms),
-            AudioCommand::SetDelayFeedbackB(fb) => self.delay_b.set_feedback(fb),
+            AudioCommand::AttachRecorder(recorder) => self.attach_recorder(recorder),
+            AudioCommand::StopRecording => self.stop_recording(),
+            AudioCommand::SetMasterVolume(vol) => self.mixer.set_master_volume(vol),
 
-            // Reverb toggle commands
-            AudioCommand::ToggleReverbA => {
-                let enabled = !self.reverb_a.is_enabled();
-                self.reverb_a.set_enabled(enabled);
+            // Effect commands
+            AudioCommand::ToggleFilter(deck) => self.effects[deck.index()].toggle::<DeckFilter>(),
+            AudioCommand::SetFilterType(deck, ft) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.set_type(ft))
             }
-            AudioCommand::ToggleReverbB => {
-                let enabled = !self.reverb_b.is_enabled();
-                self.reverb_b.set_enabled(enabled);
+            AudioCommand::SetFilterCutoff(deck, cutoff) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.set_cutoff(cutoff))
             }
-
-            // Preset-based effect commands
-            AudioCommand::SetDelayLevelA(level) => {
-                if level == 0 {
-                    self.delay_a.set_enabled(false);
-                    self.delay_a_level = 0;
-                } else {
-                    self.delay_a.set_delay_ms(Self::delay_level_to_ms(level));
-                    self.delay_a.set_enabled(true);
-                    self.delay_a_level = level;
-                }
+            AudioCommand::AdjustFilterCutoff(deck, delta) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.adjust_cutoff(delta))
             }
-            AudioCommand::SetDelayLevelB(level) => {
-                if level == 0 {
-                    self.delay_b.set_enabled(false);
-                    self.delay_b_level = 0;
-                } else {
-                    self.delay_b.set_delay_ms(Self::delay_level_to_ms(level));
-                    self.delay_b.set_enabled(true);
-                    self.delay_b_level = level;
-                }
+            AudioCommand::ToggleDelay(deck) => self.effects[deck.index()].toggle::<Delay>(),
+            AudioCommand::SetDelayTime(deck, ms) => {
+                self.effects[deck.index()].with(|d: &mut Delay| d.set_delay_ms(ms))
             }
-            AudioCommand::SetFilterPresetA(filter_type, level) => {
-                if level == 0 {
-                    self.filter_a.set_enabled(false);
-                    self.filter_a_level = 0;
-                } else {
-                    self.filter_a.set_type(filter_type);
-                    self.filter_a
-                        .set_cutoff(Self::filter_level_to_cutoff(level));
-                    self.filter_a.set_enabled(true);
-                    self.filter_a_level = level;
-                }
+            AudioCommand::SetDelayFeedback(deck, fb) => {
+                self.effects[deck.index()].with(|d: &mut Delay| d.set_feedback(fb))
             }
-            AudioCommand::SetFilterPresetB(filter_type, level) => {
-                if level == 0 {
-                    self.filter_b.set_enabled(false);
-                    self.filter_b_level = 0;
-                } else {
-                    self.filter_b.set_type(filter_type);
-                    self.filter_b
-                        .set_cutoff(Self::filter_level_to_cutoff(level));
-                    self.filter_b.set_enabled(true);
-                    self.filter_b_level = level;
-                }
+            AudioCommand::ToggleReverb(deck) => self.effects[deck.index()].toggle::<Reverb>(),
+
+            // Preset-based effect commands
+            AudioCommand::SetDelayLevel(deck, level) => {
+                self.effects[deck.index()].set_delay_level(level)
             }
-            AudioCommand::SetReverbLevelA(level) => {
-                if level == 0 {
-                    self.reverb_a.set_enabled(false);
-                } else {
-                    self.reverb_a.set_level(level);
-                }
-            }
-            AudioCommand::SetReverbLevelB(level) => {
-                if level == 0 {
-                    self.reverb_b.set_enabled(false);
-                } else {
-                    self.reverb_b.set_level(level);
-                }
+            AudioCommand::SetFilterPreset(deck, filter_type, level) => self.effects[deck.index()]
+                .with(|f: &mut DeckFilter| f.set_preset(filter_type, level)),
+            AudioCommand::SetReverbLevel(deck, level) => {
+                self.effects[deck.index()].set_reverb_level(level)
             }
 
             // Filter mode and parameter commands
-            AudioCommand::SetFilterModeA(mode) => {
-                self.filter_mode_a = mode;
-                // Sync cutoff and resonance to the new filter
-                let cutoff = self.filter_a.cutoff();
-                let resonance = self.filter_a.resonance();
-                match mode {
-                    FilterMode::Ladder => {
-                        self.ladder_a.set_cutoff(cutoff);
-                        self.ladder_a.set_resonance(resonance / 20.0); // Scale Q to 0-1
-                    }
-                    FilterMode::SVF => {
-                        self.svf_a.set_cutoff(cutoff);
-                        self.svf_a.set_resonance(resonance / 20.0);
-                    }
-                    FilterMode::Biquad => {}
-                }
+            AudioCommand::SetFilterMode(deck, mode) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.set_mode(mode))
             }
-            AudioCommand::SetFilterModeB(mode) => {
-                self.filter_mode_b = mode;
-                let cutoff = self.filter_b.cutoff();
-                let resonance = self.filter_b.resonance();
-                match mode {
-                    FilterMode::Ladder => {
-                        self.ladder_b.set_cutoff(cutoff);
-                        self.ladder_b.set_resonance(resonance / 20.0);
-                    }
-                    FilterMode::SVF => {
-                        self.svf_b.set_cutoff(cutoff);
-                        self.svf_b.set_resonance(resonance / 20.0);
-                    }
-                    FilterMode::Biquad => {}
-                }
-            }
-            AudioCommand::SetFilterResonanceA(res) => {
-                let res_clamped = res.clamp(0.0, 1.0);
-                match self.filter_mode_a {
-                    FilterMode::Biquad => self.filter_a.set_resonance(0.5 + res_clamped * 19.5),
-                    FilterMode::Ladder => self.ladder_a.set_resonance(res_clamped),
-                    FilterMode::SVF => self.svf_a.set_resonance(res_clamped),
-                }
-            }
-            AudioCommand::SetFilterResonanceB(res) => {
-                let res_clamped = res.clamp(0.0, 1.0);
-                match self.filter_mode_b {
-                    FilterMode::Biquad => self.filter_b.set_resonance(0.5 + res_clamped * 19.5),
-                    FilterMode::Ladder => self.ladder_b.set_resonance(res_clamped),
-                    FilterMode::SVF => self.svf_b.set_resonance(res_clamped),
-                }
-            }
-            AudioCommand::SetFilterDriveA(drive) => {
-                self.ladder_a.set_drive(drive);
+            AudioCommand::SetFilterResonance(deck, res) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.set_resonance(res))
             }
-            AudioCommand::SetFilterDriveB(drive) => {
-                self.ladder_b.set_drive(drive);
+            AudioCommand::SetFilterDrive(deck, drive) => {
+                self.effects[deck.index()].with(|f: &mut DeckFilter| f.set_drive(drive))
             }
 
             // Vinyl emulation commands
-            AudioCommand::ToggleVinylA => {
-                let enabled = !self.vinyl_a.is_enabled();
-                self.vinyl_a.set_enabled(enabled);
+            AudioCommand::ToggleVinyl(deck) => self.effects[deck.index()].toggle::<VinylEmulator>(),
+            AudioCommand::SetVinylPreset(deck, preset) => {
+                self.effects[deck.index()].with(|v: &mut VinylEmulator| v.set_preset(preset))
             }
-            AudioCommand::ToggleVinylB => {
-                let enabled = !self.vinyl_b.is_enabled();
-                self.vinyl_b.set_enabled(enabled);
+            AudioCommand::SetVinylWow(deck, amount) => {
+                self.effects[deck.index()].with(|v: &mut VinylEmulator| v.set_wow_amount(amount))
             }
-            AudioCommand::SetVinylPresetA(preset) => {
-                self.vinyl_a.set_preset(preset);
+            AudioCommand::SetVinylNoise(deck, amount) => {
+                self.effects[deck.index()].with(|v: &mut VinylEmulator| v.set_noise_amount(amount))
             }
-            AudioCommand::SetVinylPresetB(preset) => {
-                self.vinyl_b.set_preset(preset);
+            AudioCommand::SetVinylWarmth(deck, amount) => {
+                self.effects[deck.index()].with(|v: &mut VinylEmulator| v.set_warmth_amount(amount))
             }
-            AudioCommand::SetVinylWowA(amount) => {
-                self.vinyl_a.set_wow_amount(amount);
-            }
-            AudioCommand::SetVinylWowB(amount) => {
-                self.vinyl_b.set_wow_amount(amount);
+
+            // Effect chain commands (a full chain drops the new effect)
+            AudioCommand::AddEffect(deck, slot, kind) => {
+                let effect = kind.build(self.sample_rate);
+                self.insert_effect(deck, slot, effect);
             }
-            AudioCommand::SetVinylNoiseA(amount) => {
-                self.vinyl_a.set_noise_amount(amount);
+            AudioCommand::InsertEffect(deck, slot, effect) => {
+                self.insert_effect(deck, slot, effect)
             }
-            AudioCommand::SetVinylNoiseB(amount) => {
-                self.vinyl_b.set_noise_amount(amount);
+            AudioCommand::RemoveEffect(deck, slot) => {
+                if let Some(effect) = self.effects[deck.index()].chain.remove(slot) {
+                    self.retire(Retired::Effect(effect));
+                }
             }
-            AudioCommand::SetVinylWarmthA(amount) => {
-                self.vinyl_a.set_warmth_amount(amount);
+            AudioCommand::MoveEffect(deck, from, to) => {
+                self.effects[deck.index()].chain.move_slot(from, to)
             }
-            AudioCommand::SetVinylWarmthB(amount) => {
-                self.vinyl_b.set_warmth_amount(amount);
+            AudioCommand::ToggleEffectSlot(deck, slot) => {
+                if let Some(slot) = self.effects[deck.index()].chain.slot_mut(slot) {
+                    let effect = slot.effect_mut();
+                    effect.set_enabled(!effect.is_enabled());
+                }
             }
-
-            // Time stretching commands
-            AudioCommand::ToggleTimeStretchA => {
-                let enabled = !self.phase_vocoder_a.is_enabled();
-                self.phase_vocoder_a.set_enabled(enabled);
+            AudioCommand::ToggleEffectKind(deck, kind) => {
+                let chain = &mut self.effects[deck.index()].chain;
+                if let Some(slot) = chain.position(kind).and_then(|i| chain.slot_mut(i)) {
+                    let effect = slot.effect_mut();
+                    effect.set_enabled(!effect.is_enabled());
+                }
             }
-            AudioCommand::ToggleTimeStretchB => {
-                let enabled = !self.phase_vocoder_b.is_enabled();
-                self.phase_vocoder_b.set_enabled(enabled);
+            AudioCommand::SetEffectMix(deck, slot, mix) => {
+                if let Some(slot) = self.effects[deck.index()].chain.slot_mut(slot) {
+                    slot.set_mix(mix);
+                }
             }
-            AudioCommand::SetTimeStretchRatioA(ratio) => {
-                self.phase_vocoder_a.set_stretch_ratio(ratio);
+            AudioCommand::SetEffectParam(deck, slot, param, value) => {
+                if let Some(slot) = self.effects[deck.index()].chain.slot_mut(slot) {
+                    slot.effect_mut().set_param(param, value);
+                }
             }
-            AudioCommand::SetTimeStretchRatioB(ratio) => {
-                self.phase_vocoder_b.set_stretch_ratio(ratio);
+
+            // Key lock commands
+            AudioCommand::ToggleKeyLock(deck) => self.deck_mut(deck).toggle_key_lock(),
+            AudioCommand::SetKeyLock(deck, enabled) => self.deck_mut(deck).set_key_lock(enabled),
+            AudioCommand::SetInterpolation(interpolation) => {
+                for deck in &mut self.decks {
+                    deck.set_interpolation(interpolation);
+                }
             }
+            AudioCommand::SetPitch(deck, semitones) => self.deck_mut(deck).set_pitch(semitones),
+            AudioCommand::AdjustPitch(deck, delta) => self.deck_mut(deck).adjust_pitch(delta),
 
             // Delay modulation commands
-            AudioCommand::SetDelayModulationA(mode) => {
-                self.delay_a.set_modulation(mode);
-                self.delay_a_modulation = mode;
-            }
-            AudioCommand::SetDelayModulationB(mode) => {
-                self.delay_b.set_modulation(mode);
-                self.delay_b_modulation = mode;
+            AudioCommand::SetDelayModulation(deck, mode) => {
+                self.effects[deck.index()].set_delay_modulation(mode)
             }
 
             // Mastering chain commands
@@ -735,345 +1016,536 @@ impl EngineState {
             }
 
             // Tape Stop commands
-            AudioCommand::ToggleTapeStopA => {
-                let enabled = !self.tape_stop_a.is_enabled();
-                self.tape_stop_a.set_enabled(enabled);
-            }
-            AudioCommand::ToggleTapeStopB => {
-                let enabled = !self.tape_stop_b.is_enabled();
-                self.tape_stop_b.set_enabled(enabled);
+            AudioCommand::ToggleTapeStop(deck) => self.effects[deck.index()].toggle::<TapeStop>(),
+            AudioCommand::TriggerTapeStop(deck) => {
+                self.deck_mut(deck).slip_begin();
+                self.effects[deck.index()].with(|tape_stop: &mut TapeStop| {
+                    tape_stop.set_enabled(true);
+                    tape_stop.trigger_stop();
+                });
+            }
+            AudioCommand::TriggerTapeStart(deck) => {
+                if self.deck(deck).is_slipping() {
+                    // Slip: drop straight back in where the track would have been
+                    self.deck_mut(deck).slip_release();
+                    self.effects[deck.index()].with(|t: &mut TapeStop| t.set_enabled(false));
+                } else {
+                    self.effects[deck.index()].with(|t: &mut TapeStop| t.trigger_start());
+                }
             }
-            AudioCommand::TriggerTapeStopA => {
-                self.tape_stop_a.set_enabled(true);
-                self.tape_stop_a.trigger_stop();
+            AudioCommand::SetTapeStopTime(deck, time) => {
+                self.effects[deck.index()].with(|t: &mut TapeStop| t.set_stop_time(time))
             }
-            AudioCommand::TriggerTapeStopB => {
-                self.tape_stop_b.set_enabled(true);
-                self.tape_stop_b.trigger_stop();
+
+            // Transition commands
+            AudioCommand::TriggerEchoOut(deck) => {
+                if let Some(echo) = self.effects[deck.index()].get_mut::<EchoOut>() {
+                    echo.trigger();
+                    self.deck_mut(deck).pause();
+                }
             }
-            AudioCommand::TriggerTapeStartA => {
-                self.tape_stop_a.trigger_start();
+            AudioCommand::TriggerNoiseSweep(deck) => {
+                self.effects[deck.index()].with(|sweep: &mut NoiseSweep| sweep.trigger())
             }
-            AudioCommand::TriggerTapeStartB => {
-                self.tape_stop_b.trigger_start();
+
+            // Convolution reverb commands (without a slot the response is
+            // retired; a response that won't prepare is reported by `prepare`)
+            AudioCommand::LoadImpulse(deck, samples, sr, channels, name) => {
+                if let Ok(impulse) =
+                    ImpulseResponse::new(name, &samples, channels, sr, self.sample_rate)
+                {
+                    self.handle_command(AudioCommand::SetImpulse(deck, Box::new(impulse)));
+                }
             }
-            AudioCommand::SetTapeStopTimeA(time) => {
-                self.tape_stop_a.set_stop_time(time);
+            AudioCommand::SetImpulse(deck, impulse) => {
+                let chain = &mut self.effects[deck.index()].chain;
+                let unused = match chain.find_mut::<ConvolutionReverb>() {
+                    Some(convolution) => convolution.set_impulse(impulse),
+                    None => Some(impulse),
+                };
+                if let Some(impulse) = unused {
+                    self.retire(Retired::Impulse(impulse));
+                }
             }
-            AudioCommand::SetTapeStopTimeB(time) => {
-                self.tape_stop_b.set_stop_time(time);
+            AudioCommand::SetConvolutionWet(deck, wet) => {
+                self.effects[deck.index()].with(|c: &mut ConvolutionReverb| c.set_wet(wet))
             }
 
             // Flanger commands
-            AudioCommand::ToggleFlangerA => {
-                let enabled = !self.flanger_a.is_enabled();
-                self.flanger_a.set_enabled(enabled);
+            AudioCommand::ToggleFlanger(deck) => self.effects[deck.index()].toggle::<Flanger>(),
+            AudioCommand::SetFlangerRate(deck, rate) => {
+                self.effects[deck.index()].with(|f: &mut Flanger| f.set_rate(rate))
             }
-            AudioCommand::ToggleFlangerB => {
-                let enabled = !self.flanger_b.is_enabled();
-                self.flanger_b.set_enabled(enabled);
+            AudioCommand::SetFlangerDepth(deck, depth) => {
+                self.effects[deck.index()].with(|f: &mut Flanger| f.set_depth(depth))
             }
-            AudioCommand::SetFlangerRateA(rate) => {
-                self.flanger_a.set_rate(rate);
-            }
-            AudioCommand::SetFlangerRateB(rate) => {
-                self.flanger_b.set_rate(rate);
-            }
-            AudioCommand::SetFlangerDepthA(depth) => {
-                self.flanger_a.set_depth(depth);
-            }
-            AudioCommand::SetFlangerDepthB(depth) => {
-                self.flanger_b.set_depth(depth);
-            }
-            AudioCommand::SetFlangerFeedbackA(fb) => {
-                self.flanger_a.set_feedback(fb);
-            }
-            AudioCommand::SetFlangerFeedbackB(fb) => {
-                self.flanger_b.set_feedback(fb);
+            AudioCommand::SetFlangerFeedback(deck, fb) => {
+                self.effects[deck.index()].with(|f: &mut Flanger| f.set_feedback(fb))
             }
 
             // Bitcrusher commands
-            AudioCommand::ToggleBitcrusherA => {
-                let enabled = !self.bitcrusher_a.is_enabled();
-                self.bitcrusher_a.set_enabled(enabled);
+            AudioCommand::ToggleBitcrusher(deck) => {
+                self.effects[deck.index()].toggle::<Bitcrusher>()
             }
-            AudioCommand::ToggleBitcrusherB => {
-                let enabled = !self.bitcrusher_b.is_enabled();
-                self.bitcrusher_b.set_enabled(enabled);
+            AudioCommand::SetBitcrusherBits(deck, bits) => {
+                self.effects[deck.index()].with(|b: &mut Bitcrusher| b.set_bits(bits))
             }
-            AudioCommand::SetBitcrusherBitsA(bits) => {
-                self.bitcrusher_a.set_bits(bits);
+            AudioCommand::SetBitcrusherDownsample(deck, ds) => {
+                self.effects[deck.index()].with(|b: &mut Bitcrusher| b.set_downsample(ds))
             }
-            AudioCommand::SetBitcrusherBitsB(bits) => {
-                self.bitcrusher_b.set_bits(bits);
+
+            // Quantize
+            AudioCommand::ToggleQuantize => {
+                self.quantize = !self.quantize;
+                self.update_quantize();
             }
-            AudioCommand::SetBitcrusherDownsampleA(ds) => {
-                self.bitcrusher_a.set_downsample(ds);
+            AudioCommand::SetQuantizeResolution(resolution) => {
+                self.quantize_resolution = resolution;
+                self.update_quantize();
             }
-            AudioCommand::SetBitcrusherDownsampleB(ds) => {
-                self.bitcrusher_b.set_downsample(ds);
+            AudioCommand::CycleQuantizeResolution => {
+                self.quantize_resolution = self.quantize_resolution.next();
+                self.update_quantize();
             }
 
-            AudioCommand::Shutdown => {} // Handled at higher level
+            AudioCommand::ListOutputs | AudioCommand::SetOutput(_) | AudioCommand::Shutdown => {} // Handled at higher level
         }
     }
 
+    /// A deck's channel for the UI
+    fn channel_state(&self, deck: DeckId) -> ChannelState {
+        let mut state = ChannelState::default();
+        self.write_channel_state(deck, &mut state);
+        state
+    }
+
+    /// Update a deck's channel for the UI in place
+    ///
+    /// Returns the waveforms it replaced (see `Deck::write_state`).
+    fn write_channel_state(
+        &self,
+        deck: DeckId,
+        state: &mut ChannelState,
+    ) -> Option<(Arc<Vec<f32>>, Arc<EnhancedWaveform>)> {
+        let effects = &self.effects[deck.index()];
+        let filter = effects.get::<DeckFilter>();
+        let reverb = effects.get::<Reverb>();
+        let vinyl = effects.get::<VinylEmulator>();
+        let defaults = ChannelState::default();
+        let eq = self.mixer.eq(deck);
+        state.eq_gains = eq.gains();
+        state.eq_kills = eq.kills();
+        state.upfader = self.mixer.upfader(deck);
+        state.trim = self.mixer.trim(deck);
+        state.cue = self.mixer.cue(deck);
+        state.assign = self.mixer.assign(deck);
+        // Enabled state follows the current filter mode
+        state.filter_enabled = effects.filter_enabled();
+        state.filter_cutoff = filter.map_or(defaults.filter_cutoff, DeckFilter::cutoff);
+        state.filter_type = filter.map_or(defaults.filter_type, DeckFilter::filter_type);
+        state.filter_level = filter.map_or(0, DeckFilter::level);
+        state.filter_mode = filter.map_or(defaults.filter_mode, DeckFilter::mode);
+        state.delay_enabled = effects.get::<Delay>().is_some_and(|d| d.is_enabled());
+        state.delay_level = effects.delay_level;
+        state.delay_modulation = effects.delay_modulation;
+        state.reverb_enabled = reverb.is_some_and(|r| r.is_enabled());
+        state.reverb_level = reverb.map_or(0, Reverb::level);
+        state.impulse = effects
+            .get::<ConvolutionReverb>()
+            .and_then(ConvolutionReverb::impulse)
+            .map(|ir| Label::from(ir.name()));
+        state.vinyl_enabled = vinyl.is_some_and(|v| v.is_enabled());
+        state.vinyl_preset = vinyl.map_or(defaults.vinyl_preset, VinylEmulator::preset);
+        state.effects = effects.chain.state();
+        self.deck(deck).write_state(&mut state.deck)
+    }
+
     /// Generate current state for UI
     pub fn get_state(&self) -> AudioEvent {
-        // Get enabled state based on current filter mode
-        let filter_a_enabled = match self.filter_mode_a {
-            FilterMode::Biquad => self.filter_a.is_enabled(),
-            FilterMode::Ladder => self.ladder_a.is_enabled(),
-            FilterMode::SVF => self.svf_a.is_enabled(),
-        };
-        let filter_b_enabled = match self.filter_mode_b {
-            FilterMode::Biquad => self.filter_b.is_enabled(),
-            FilterMode::Ladder => self.ladder_b.is_enabled(),
-            FilterMode::SVF => self.svf_b.is_enabled(),
+        let channels = Box::new(DeckId::ALL.map(|deck| self.channel_state(deck)));
+        self.state_with(channels, Box::default())
+    }
+
+    /// Update a state from `get_state` in place (for the audio callback)
+    ///
+    /// Nothing is allocated or freed: waveforms of tracks the state stops
+    /// showing go to the retire bin.
+    pub fn write_state(&mut self, event: &mut AudioEvent) {
+        let placeholder = AudioEvent::TrackLoaded { deck: DeckId::A };
+        let (mut channels, recording) = match std::mem::replace(event, placeholder) {
+            AudioEvent::StateUpdate {
+                channels,
+                recording,
+                ..
+            } => (channels, recording),
+            // Not a state yet (allocates, but only the first time)
+            _ => Default::default(),
         };
+        for deck in DeckId::ALL {
+            let channel = &mut channels[deck.index()];
+            if let Some((overview, enhanced)) = self.write_channel_state(deck, channel) {
+                self.retire(Retired::Waveforms(overview, enhanced));
+            }
+        }
+        *event = self.state_with(channels, recording);
+    }
 
+    /// The state with `channels`, the current mixer, mastering and sync
+    /// settings, and the recording status written into `recording`
+    fn state_with(
+        &self,
+        channels: Box<[ChannelState; DECK_COUNT]>,
+        mut recording: Box<RecordingStatus>,
+    ) -> AudioEvent {
+        *recording = match (&self.recording_error, &self.recorder) {
+            (Some(error), _) => RecordingStatus::Failed(*error),
+            (None, Some(recorder)) => recorder.status(),
+            (None, None) => RecordingStatus::Idle,
+        };
         AudioEvent::StateUpdate {
-            deck_a: Box::new(self.deck_a.state()),
-            deck_b: Box::new(self.deck_b.state()),
+            channels,
             crossfader: self.mixer.crossfader(),
             master_volume: self.mixer.master_volume(),
-            // Filter state
-            filter_a_enabled,
-            filter_a_cutoff: self.filter_a.cutoff(),
-            filter_a_type: self.filter_a.filter_type(),
-            filter_a_level: self.filter_a_level,
-            filter_a_mode: self.filter_mode_a,
-            filter_b_enabled,
-            filter_b_cutoff: self.filter_b.cutoff(),
-            filter_b_type: self.filter_b.filter_type(),
-            filter_b_level: self.filter_b_level,
-            filter_b_mode: self.filter_mode_b,
-            // Delay state
-            delay_a_enabled: self.delay_a.is_enabled(),
-            delay_a_level: self.delay_a_level,
-            delay_a_modulation: self.delay_a_modulation,
-            delay_b_enabled: self.delay_b.is_enabled(),
-            delay_b_level: self.delay_b_level,
-            delay_b_modulation: self.delay_b_modulation,
-            // Reverb state
-            reverb_a_enabled: self.reverb_a.is_enabled(),
-            reverb_a_level: self.reverb_a.level(),
-            reverb_b_enabled: self.reverb_b.is_enabled(),
-            reverb_b_level: self.reverb_b.level(),
-            // Vinyl emulation state
-            vinyl_a_enabled: self.vinyl_a.is_enabled(),
-            vinyl_a_preset: self.vinyl_a.preset(),
-            vinyl_b_enabled: self.vinyl_b.is_enabled(),
-            vinyl_b_preset: self.vinyl_b.preset(),
-            // Time stretch state
-            time_stretch_a_enabled: self.phase_vocoder_a.is_enabled(),
-            time_stretch_a_ratio: self.phase_vocoder_a.stretch_ratio(),
-            time_stretch_b_enabled: self.phase_vocoder_b.is_enabled(),
-            time_stretch_b_ratio: self.phase_vocoder_b.stretch_ratio(),
+            eq_mode: self.mixer.eq_mode(),
+            upfader_curve: self.mixer.upfader_curve(),
+            crossfader_curve: self.mixer.curve(),
+            crossfader_sharpness: self.mixer.sharpness(),
+            hamster: self.mixer.hamster(),
+            cue_mix: self.mixer.cue_mix(),
+            headphone_volume: self.mixer.headphone_volume(),
+            recording,
+            interpolation: self.decks[0].interpolation(),
             // Mastering state
             mastering_enabled: self.mastering.is_enabled(),
             mastering_preset: self.mastering.preset(),
             mastering_lufs: self.mastering.lufs(),
             mastering_gain_reduction: self.mastering.gain_reduction_db(),
+            // Quantize state
+            quantize: self.quantize,
+            quantize_resolution: self.quantize_resolution,
+            // Sync lock state
+            sync_leader: self.sync_leader,
+            master_bpm: self.master_clock.bpm(),
         }
     }
 
-    /// Smart sync: sync Deck B's tempo and phase to Deck A
+    /// Start capturing the master with `recorder` (a running one stops
+    /// and goes to the retire bin)
+    fn attach_recorder(&mut self, recorder: Box<Recorder>) {
+        self.stop_recording();
+        if let Some(old) = self.recorder.replace(recorder) {
+            self.retire(Retired::Recorder(old));
+        }
+        self.recording_error = None;
+    }
+
+    /// Stop recording; the file is finished in the background
+    fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.stop();
+        }
+    }
+
+    /// Stop recording and wait until the file is written (for shutdown)
+    pub fn finish_recording(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.finish();
+        }
+    }
+
+    /// Pick the sync leader
     ///
-    /// This performs professional-style beat sync:
-    /// 1. Matches tempo so both decks play at the same BPM
-    /// 2. Aligns beat phases so transients (kicks) land together
-    /// 3. Uses smooth transition to avoid jarring jumps
-    fn smart_sync_b_to_a(&mut self) {
-        // Get beat grids from both decks
-        let (source_grid, source_phase) = match (self.deck_a.beat_grid(), self.deck_a.beat_phase())
-        {
-            (Some(g), Some(p)) => (g, p),
-            _ => {
-                // Fallback to tempo-only sync if no beat grid
-                self.tempo_only_sync_b_to_a();
-                return;
-            }
+    /// A sync-locked deck that plays leads; with several playing the
+    /// crossfader decides, and the lead only changes hands once the fader
+    /// is clearly away from the leader's side. Otherwise the master clock
+    /// leads.
+    fn choose_sync_leader(&self) -> SyncLeader {
+        if self.clock_leads {
+            return SyncLeader::Clock;
+        }
+        let can_lead = |deck: DeckId| {
+            let d = self.deck(deck);
+            self.sync_locks[deck.index()] && d.is_playing() && d.base_bpm().is_some()
         };
+        if let SyncLeader::Deck(leader) = self.sync_leader {
+            if can_lead(leader) && self.mixer.crossfader_lean(leader) >= -0.5 {
+                return self.sync_leader;
+            }
+        }
+        // The deck the crossfader leans toward most (the first on a tie)
+        let mut best: Option<(DeckId, f32)> = None;
+        for deck in DeckId::ALL.into_iter().filter(|&deck| can_lead(deck)) {
+            let lean = self.mixer.crossfader_lean(deck);
+            if best.is_none_or(|(_, best_lean)| lean > best_lean) {
+                best = Some((deck, lean));
+            }
+        }
+        best.map_or(SyncLeader::Clock, |(deck, _)| SyncLeader::Deck(deck))
+    }
+
+    /// Keep sync-locked decks on the leader's tempo and bar phase
+    fn update_sync_lock(&mut self, frames: usize) {
+        self.sync_leader = self.choose_sync_leader();
 
-        let target_grid = match self.deck_b.beat_grid() {
-            Some(g) => g,
+        // Leader tempo and bar phase at the start of this buffer
+        let leader = match self.sync_leader {
+            SyncLeader::Deck(deck) => Self::deck_beat(self.deck(deck)),
+            SyncLeader::Clock => None,
+        };
+        let (bpm, phase) = match leader {
+            Some((bpm, phase)) => {
+                self.master_clock.follow(bpm, phase);
+                (bpm, phase)
+            }
             None => {
-                self.tempo_only_sync_b_to_a();
-                return;
+                let beat = (self.master_clock.bpm(), self.master_clock.bar_phase());
+                self.master_clock.advance(frames, self.sample_rate);
+                beat
             }
         };
 
-        // Step 1: Calculate target tempo to match BPMs
-        let source_effective_bpm = source_grid.bpm * self.deck_a.state().tempo;
-        // Use target deck's original BPM (from beat grid, not adjusted for tempo)
-        let target_original_bpm = target_grid.bpm;
-        let new_tempo = (source_effective_bpm / target_original_bpm).clamp(0.5, 2.0);
+        let duration = (self.sample_rate as f64 * 0.5) as u64;
+        for id in DeckId::ALL {
+            let role = Self::sync_role(
+                self.sync_locks[id.index()],
+                self.sync_leader == SyncLeader::Deck(id),
+            );
+            Self::follow_leader(&mut self.decks[id.index()], role, bpm, phase, duration);
+        }
+    }
 
-        // Step 2: Calculate phase offset needed to align beats
-        let phase_offset = self
-            .deck_b
-            .phase_offset_to_align(source_phase)
-            .unwrap_or(0.0);
+    fn sync_role(locked: bool, leads: bool) -> SyncRole {
+        match (locked, leads) {
+            (false, _) => SyncRole::Off,
+            (true, true) => SyncRole::Leader,
+            (true, false) => SyncRole::Follower,
+        }
+    }
 
-        // Step 3: Start smooth transition (~500ms at 44.1kHz)
-        let transition_duration = (self.sample_rate as f64 * 0.5) as u64;
-        self.deck_b
-            .start_sync_transition(new_tempo, phase_offset, transition_duration);
+    /// Current BPM and bar phase of a deck (phase 0 without a grid)
+    fn deck_beat(deck: &Deck) -> Option<(f32, f32)> {
+        let bpm = deck.base_bpm()? * deck.tempo();
+        Some((bpm, deck.bar_phase().unwrap_or(0.0)))
     }
 
-    /// Smart sync: sync Deck A's tempo and phase to Deck B
-    fn smart_sync_a_to_b(&mut self) {
-        // Get beat grids from both decks
-        let (source_grid, source_phase) = match (self.deck_b.beat_grid(), self.deck_b.beat_phase())
-        {
-            (Some(g), Some(p)) => (g, p),
-            _ => {
-                self.tempo_only_sync_a_to_b();
-                return;
-            }
+    /// Lock a follower to the leader's tempo and bar phase
+    ///
+    /// Small beat phase errors are pulled in by bending the tempo; large ones
+    /// (a deck just started, or was jumped) get a smooth sync transition that
+    /// also lines up the bars.
+    fn follow_leader(deck: &mut Deck, role: SyncRole, bpm: f32, bar_phase: f32, duration: u64) {
+        /// Phase error (in beats) above which the follower re-syncs
+        const RESYNC_BEATS: f32 = 0.1;
+
+        if role != SyncRole::Follower {
+            deck.set_sync_status(role, None);
+            return;
+        }
+        let Some(base_bpm) = deck.base_bpm().filter(|b| *b > 0.0) else {
+            deck.set_sync_status(role, None);
+            return;
         };
+        let tempo = (bpm / base_bpm).clamp(0.5, 2.0);
+        let error = deck
+            .beat_phase()
+            .filter(|_| deck.is_playing() && !deck.reverse())
+            .map(|p| sync::phase_error(p, (bar_phase * BeatGrid::BEATS_PER_BAR as f32).fract()));
+        deck.set_sync_status(role, error);
+
+        if deck.is_syncing() {
+            return;
+        }
+        match error {
+            Some(error) if error.abs() > RESYNC_BEATS => {
+                let offset = deck.phase_offset_to_align(bar_phase).unwrap_or(0.0);
+                deck.start_sync_transition(tempo, offset, duration);
+            }
+            Some(error) => deck.set_tempo(tempo * sync::phase_correction(error, bpm)),
+            None => deck.set_tempo(tempo),
+        }
+    }
 
-        let target_grid = match self.deck_a.beat_grid() {
-            Some(g) => g,
-            None => {
-                self.tempo_only_sync_a_to_b();
-                return;
+    /// Change the sync leader's tempo (a follower's tempo was adjusted)
+    fn adjust_leader_tempo(&mut self, delta: f32) {
+        match self.sync_leader {
+            SyncLeader::Deck(deck) => self.deck_mut(deck).adjust_tempo(delta),
+            SyncLeader::Clock => {
+                let bpm = self.master_clock.bpm();
+                self.master_clock.set_bpm(bpm * (1.0 + delta));
             }
-        };
+        }
+    }
 
-        // Calculate target tempo
-        let source_effective_bpm = source_grid.bpm * self.deck_b.state().tempo;
-        let target_original_bpm = target_grid.bpm;
-        let new_tempo = (source_effective_bpm / target_original_bpm).clamp(0.5, 2.0);
+    /// Push the quantize grid step to every deck
+    fn update_quantize(&mut self) {
+        let step = self.quantize.then(|| self.quantize_resolution.beats());
+        for deck in &mut self.decks {
+            deck.set_quantize(step);
+        }
+    }
 
-        // Calculate phase offset
-        let phase_offset = self
-            .deck_a
-            .phase_offset_to_align(source_phase)
-            .unwrap_or(0.0);
+    /// Run a deck action now, or hold it until the next grid line
+    ///
+    /// The grid comes from the deck itself while it plays, otherwise from
+    /// the first other deck that plays, so a quantized play lands on the
+    /// playing deck's beat.
+    fn schedule(&mut self, deck: DeckId, action: QuantizedAction) {
+        let i = deck.index();
+        let frames = self.decks[i].frames_to_grid_line().or_else(|| {
+            self.decks
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .find_map(|(_, other)| other.frames_to_grid_line())
+        });
+        match frames {
+            Some(frames) if frames > 0 => {
+                self.scheduled[i] = Some(ScheduledAction { action, frames })
+            }
+            _ => {
+                self.scheduled[i] = None;
+                action.apply(&mut self.decks[i]);
+            }
+        }
+    }
 
-        // Start smooth transition
-        let transition_duration = (self.sample_rate as f64 * 0.5) as u64;
-        self.deck_a
-            .start_sync_transition(new_tempo, phase_offset, transition_duration);
+    /// Process a deck, firing its scheduled action on the exact frame
+    fn process_deck(deck: &mut Deck, scheduled: &mut Option<ScheduledAction>, output: &mut [f32]) {
+        let frames = output.len() / 2;
+        match *scheduled {
+            Some(pending) if pending.frames < frames => {
+                let (before, after) = output.split_at_mut(pending.frames * 2);
+                if !before.is_empty() {
+                    deck.process(before);
+                }
+                pending.action.apply(deck);
+                *scheduled = None;
+                deck.process(after);
+            }
+            Some(pending) => {
+                *scheduled = Some(ScheduledAction {
+                    frames: pending.frames - frames,
+                    ..pending
+                });
+                deck.process(output);
+            }
+            None => deck.process(output),
+        }
     }
 
-    /// Fallback tempo-only sync (no phase alignment)
-    fn tempo_only_sync_b_to_a(&mut self) {
-        if let (Some(bpm_a), Some(_bpm_b)) = (self.deck_a.current_bpm(), self.deck_b.current_bpm())
+    /// Deck a sync of `deck` follows: the sync leader, else the loudest
+    /// other playing deck, else the first other deck with a tempo
+    fn sync_source(&self, deck: DeckId) -> Option<DeckId> {
+        if let SyncLeader::Deck(leader) = self.sync_leader {
+            if leader != deck {
+                return Some(leader);
+            }
+        }
+        let gains = self.mixer.audible_gains();
+        let mut others = DeckId::ALL
+            .into_iter()
+            .filter(|&other| other != deck && self.deck(other).current_bpm().is_some());
+        let mut loudest: Option<(DeckId, f32)> = None;
+        for other in others
+            .clone()
+            .filter(|&other| self.deck(other).is_playing())
         {
-            if let Some(original_b) = self
-                .deck_b
-                .state()
-                .bpm
-                .map(|b| b / self.deck_b.state().tempo)
-            {
-                let new_tempo = bpm_a / original_b;
-                self.deck_b.set_tempo(new_tempo);
+            let gain = gains[other.index()] * self.deck(other).gain();
+            if loudest.is_none_or(|(_, loudest_gain)| gain > loudest_gain) {
+                loudest = Some((other, gain));
             }
         }
+        loudest.map(|(other, _)| other).or_else(|| others.next())
+    }
+
+    /// Smart sync: sync a deck's tempo and phase to the `source` deck
+    ///
+    /// This performs professional-style beat sync:
+    /// 1. Matches tempo so both decks play at the same BPM
+    /// 2. Aligns bar phases so transients (kicks) and downbeats land together
+    /// 3. Uses smooth transition to avoid jarring jumps
+    fn smart_sync(&mut self, deck: DeckId, source: DeckId) {
+        // Get beat grids from both decks
+        let source_deck = self.deck(source);
+        let source_beat = source_deck
+            .beat_grid()
+            .zip(source_deck.bar_phase())
+            .map(|(grid, phase)| (source_deck.current_bpm().unwrap_or(grid.bpm), phase));
+        let target = self.deck(deck);
+        let (Some((source_bpm, source_phase)), Some(target_grid_bpm)) =
+            (source_beat, target.beat_grid().map(|grid| grid.bpm))
+        else {
+            // Fallback to tempo-only sync if no beat grid
+            self.tempo_only_sync(deck, source);
+            return;
+        };
+
+        // Step 1: Calculate target tempo to match BPMs
+        // Use target deck's original BPM (from beat grid, not adjusted for tempo)
+        let target_original_bpm = target.base_bpm().unwrap_or(target_grid_bpm);
+        let new_tempo = (source_bpm / target_original_bpm).clamp(0.5, 2.0);
+
+        // Step 2: Calculate phase offset needed to align bars
+        let phase_offset = target.phase_offset_to_align(source_phase).unwrap_or(0.0);
+
+        // Step 3: Start smooth transition (~500ms)
+        let transition_duration = (self.sample_rate as f64 * 0.5) as u64;
+        self.deck_mut(deck)
+            .start_sync_transition(new_tempo, phase_offset, transition_duration);
     }
 
     /// Fallback tempo-only sync (no phase alignment)
-    fn tempo_only_sync_a_to_b(&mut self) {
-        if let (Some(_bpm_a), Some(bpm_b)) = (self.deck_a.current_bpm(), self.deck_b.current_bpm())
-        {
-            if let Some(original_a) = self
-                .deck_a
-                .state()
-                .bpm
-                .map(|b| b / self.deck_a.state().tempo)
-            {
-                let new_tempo = bpm_b / original_a;
-                self.deck_a.set_tempo(new_tempo);
+    fn tempo_only_sync(&mut self, deck: DeckId, source: DeckId) {
+        let source_bpm = self.deck(source).current_bpm();
+        let target = self.deck_mut(deck);
+        if let (Some(source_bpm), Some(_)) = (source_bpm, target.current_bpm()) {
+            if let Some(original) = target.current_bpm().map(|b| b / target.tempo()) {
+                target.set_tempo(source_bpm / original);
             }
         }
     }
 
     /// Process audio for output buffer
-    pub fn process(&mut self, output: &mut [f32]) {
+    ///
+    /// `output` gets the master mix. With a `cue` buffer of the same length,
+    /// it gets the headphone feed: the cued decks blended with the master.
+    pub fn process(&mut self, output: &mut [f32], mut cue: Option<&mut [f32]>) {
         let len = output.len();
 
         // Ensure pre-allocated buffers are large enough
         // This should rarely happen after the first call
-        if len > self.buffer_a.len() {
-            self.buffer_a.resize(len, 0.0);
-            self.buffer_b.resize(len, 0.0);
-        }
-
-        // Zero the buffers (no allocation - just memset)
-        self.buffer_a[..len].fill(0.0);
-        self.buffer_b[..len].fill(0.0);
-
-        // Use slices of pre-allocated buffers
-        let (buf_a, buf_b) = {
-            let (a, _) = self.buffer_a.split_at_mut(len);
-            let (b, _) = self.buffer_b.split_at_mut(len);
-            (a, b)
-        };
-
-        // Process each deck
-        self.deck_a.process(buf_a);
-        self.deck_b.process(buf_b);
-
-        // Apply effects chain:
-        // Deck → Tape Stop → Vinyl → Bitcrusher → Filter → Flanger → Delay → Reverb → Mixer
-
-        // Deck A chain
-        // 1. Tape stop (pitch slowdown effect)
-        self.tape_stop_a.process(buf_a);
-
-        // 2. Vinyl emulation (adds warmth, noise, wow/flutter)
-        self.vinyl_a.process(buf_a);
-
-        // 3. Bitcrusher (lo-fi crunch)
-        self.bitcrusher_a.process(buf_a);
-
-        // 4. Filter (mode-selected)
-        match self.filter_mode_a {
-            FilterMode::Biquad => self.filter_a.process(buf_a),
-            FilterMode::Ladder => self.ladder_a.process(buf_a),
-            FilterMode::SVF => self.svf_a.process(buf_a),
+        if len > self.buffers[0].len() {
+            for buffer in &mut self.buffers {
+                buffer.resize(len, 0.0);
+            }
         }
 
-        // 5. Flanger (sweeping comb filter)
-        self.flanger_a.process(buf_a);
-
-        // 6. Delay
-        self.delay_a.process(buf_a);
-
-        // 7. Reverb
-        self.reverb_a.process(buf_a);
-
-        // Deck B chain
-        // 1. Tape stop
-        self.tape_stop_b.process(buf_b);
-
-        // 2. Vinyl emulation
-        self.vinyl_b.process(buf_b);
+        // Lock followers to the sync leader
+        self.update_sync_lock(len / 2);
 
-        // 3. Bitcrusher
-        self.bitcrusher_b.process(buf_b);
-
-        // 4. Filter (mode-selected)
-        match self.filter_mode_b {
-            FilterMode::Biquad => self.filter_b.process(buf_b),
-            FilterMode::Ladder => self.ladder_b.process(buf_b),
-            FilterMode::SVF => self.svf_b.process(buf_b),
+        // Process each deck (quantized actions fire mid-buffer), then its effect chain
+        for (((deck, scheduled), effects), buffer) in self
+            .decks
+            .iter_mut()
+            .zip(&mut self.scheduled)
+            .zip(&mut self.effects)
+            .zip(&mut self.buffers)
+        {
+            // Zero the buffer (no allocation - just memset)
+            let buffer = &mut buffer[..len];
+            buffer.fill(0.0);
+            // Grid position as the buffer starts, for effects locked to the beat
+            let beat = deck.beat_position().filter(|_| deck.is_playing());
+            Self::process_deck(deck, scheduled, buffer);
+            // Tempo as it ends the buffer (sync transitions move it)
+            effects.set_tempo(deck.current_bpm().unwrap_or(0.0));
+            effects.set_beat(beat);
+            effects.process(buffer);
         }
 
-        // 5. Flanger
-        self.flanger_b.process(buf_b);
-
-        // 6. Delay
-        self.delay_b.process(buf_b);
-
-        // 7. Reverb
-        self.reverb_b.process(buf_b);
-
-        // Mix to output
-        self.mixer.mix(buf_a, buf_b, output);
+        // Mix to output (cued decks also go to the cue bus)
+        let channels: [&[f32]; DECK_COUNT] = std::array::from_fn(|i| &self.buffers[i][..len]);
+        self.mixer.mix(&channels, output, cue.as_deref_mut());
 
         // Mastering chain - EQ, compression, saturation, stereo enhancement
         // Applied before the limiter for transparent processing
@@ -1088,6 +1560,29 @@ impl EngineState {
         for sample in output.iter_mut() {
             *sample = sample.clamp(-CEILING, CEILING);
         }
+
+        // Headphones: cue bus blended with the finished master
+        if let Some(cue) = cue {
+            self.mixer.mix_headphones(output, cue);
+            for sample in cue.iter_mut() {
+                *sample = sample.clamp(-CEILING, CEILING);
+            }
+        }
+
+        // Recording: hand the master to the writer thread, noting which track is audible
+        if let Some(recorder) = self.recorder.as_mut() {
+            let gains = self.mixer.audible_gains();
+            let decks: [(Option<&str>, f32); DECK_COUNT] = std::array::from_fn(|i| {
+                let deck = &self.decks[i];
+                let gain = if deck.is_playing() {
+                    deck.gain() * gains[i]
+                } else {
+                    0.0
+                };
+                (deck.track_name(), gain)
+            });
+            recorder.record(output, &decks);
+        }
     }
 }
 
@@ -1140,3 +1635,667 @@ impl AudioEngine {
         let _ = self.command_tx.try_send(AudioCommand::Shutdown);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ole_analysis::BeatGrid;
+    use ringbuf::traits::{Consumer, Split};
+    use ringbuf::HeapRb;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Engine with a 10s 120 BPM track (grid at 0) on decks A and B
+    fn engine_with_tracks() -> EngineState {
+        let samples: Vec<f32> = (0..SAMPLE_RATE * 10)
+            .flat_map(|i| {
+                let s = (i as f32 * 0.05).sin() * 0.5;
+                [s, s]
+            })
+            .collect();
+        let samples = Arc::new(samples);
+        let mut engine = EngineState::new(SAMPLE_RATE);
+        for deck in &mut engine.decks[..2] {
+            deck.load(
+                samples.clone(),
+                SAMPLE_RATE,
+                None,
+                Arc::new(Vec::new()),
+                Arc::new(EnhancedWaveform::default()),
+                None,
+            );
+            deck.set_beat_grid(Box::new(BeatGrid::new(120.0, 0, SAMPLE_RATE, 1.0)));
+        }
+        engine
+    }
+
+    /// Process `frames` output frames in 512-frame buffers
+    fn run(engine: &mut EngineState, frames: usize) {
+        let mut buffer = [0.0f32; 1024];
+        let mut left = frames;
+        while left > 0 {
+            let n = left.min(512);
+            engine.process(&mut buffer[..n * 2], None);
+            left -= n;
+        }
+    }
+
+    #[test]
+    fn test_quantized_cue_jump_fires_on_grid_line() {
+        let mut engine = engine_with_tracks();
+        engine.deck_mut(DeckId::A).seek(4.0);
+        engine.handle_command(AudioCommand::SetCue(DeckId::A, 1));
+        engine.deck_mut(DeckId::A).seek(1.2);
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::ToggleQuantize);
+
+        // Cue placement snaps to the nearest beat
+        engine.handle_command(AudioCommand::SetCue(DeckId::A, 2));
+        assert_eq!(
+            engine.deck(DeckId::A).get_cue(2),
+            Some(SAMPLE_RATE as f64 * 2.0)
+        );
+
+        // The jump waits for the next beat at 1.5s, then lands exactly
+        engine.handle_command(AudioCommand::JumpCue(DeckId::A, 1));
+        run(&mut engine, (SAMPLE_RATE as f64 * 0.2) as usize);
+        assert!((engine.deck(DeckId::A).position_secs() - 1.4).abs() < 1e-3);
+        run(&mut engine, (SAMPLE_RATE as f64 * 0.2) as usize);
+        let pos = engine.deck(DeckId::A).position_secs();
+        assert!((pos - 4.1).abs() < 1e-3, "position {}", pos);
+    }
+
+    #[test]
+    fn test_quantized_play_starts_on_other_decks_beat() {
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::ToggleQuantize);
+        engine.deck_mut(DeckId::A).seek(1.2);
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::Play(DeckId::B));
+        assert!(!engine.deck(DeckId::B).is_playing());
+
+        run(&mut engine, (SAMPLE_RATE as f64 * 0.5) as usize);
+        assert!(engine.deck(DeckId::B).is_playing());
+        // B started when A crossed 1.5s, 0.3s in
+        let pos = engine.deck(DeckId::B).position_secs();
+        assert!((pos - 0.2).abs() < 1e-3, "position {}", pos);
+    }
+
+    #[test]
+    fn test_sync_lock_keeps_follower_on_leader_beat() {
+        let mut engine = engine_with_tracks();
+        engine
+            .deck_mut(DeckId::B)
+            .set_beat_grid(Box::new(BeatGrid::new(125.0, 0, SAMPLE_RATE, 1.0)));
+        engine.deck_mut(DeckId::A).seek(1.2);
+        engine.deck_mut(DeckId::B).seek(3.33);
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::A));
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::B));
+        engine.handle_command(AudioCommand::Play(DeckId::B));
+        run(&mut engine, SAMPLE_RATE as usize * 2);
+
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::A));
+        let b = engine.deck(DeckId::B).state();
+        assert_eq!(b.sync_role, SyncRole::Follower);
+        assert!((b.tempo - 120.0 / 125.0).abs() < 0.01, "tempo {}", b.tempo);
+        let error = b.phase_error.unwrap();
+        assert!(error.abs() < 0.01, "phase error {}", error);
+
+        // Drift is pulled back in gradually
+        engine
+            .deck_mut(DeckId::B)
+            .nudge_samples(0.05 * 44100.0 * 2.0 * 60.0 / 125.0);
+        run(&mut engine, SAMPLE_RATE as usize * 3);
+        let error = engine.deck(DeckId::B).state().phase_error.unwrap();
+        assert!(error.abs() < 0.01, "phase error {}", error);
+    }
+
+    #[test]
+    fn test_sync_leader_follows_crossfader_and_falls_back_to_clock() {
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::A));
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::B));
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::Play(DeckId::B));
+        run(&mut engine, 4096);
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::A));
+
+        engine.handle_command(AudioCommand::SetCrossfader(1.0));
+        run(&mut engine, 4096);
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::B));
+        assert_eq!(engine.deck(DeckId::A).state().sync_role, SyncRole::Follower);
+
+        // Leader tempo changes carry over to the follower
+        engine.handle_command(AudioCommand::AdjustTempo(DeckId::A, 0.05));
+        run(&mut engine, 4096);
+        assert!((engine.deck(DeckId::B).tempo() - 1.05).abs() < 1e-3);
+        assert!((engine.deck(DeckId::A).tempo() - 1.05).abs() < 0.011);
+
+        // With the leader stopped the other deck takes over, and the clock
+        // keeps its tempo once nothing plays
+        engine.handle_command(AudioCommand::Stop(DeckId::B));
+        run(&mut engine, 8192);
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::A));
+        engine.handle_command(AudioCommand::Stop(DeckId::A));
+        run(&mut engine, 8192);
+        assert_eq!(engine.sync_leader, SyncLeader::Clock);
+        assert!((engine.master_clock.bpm() - 126.0).abs() < 1.5);
+
+        // The master clock can be made to lead
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::SetMasterClock(Some(128.0)));
+        run(&mut engine, SAMPLE_RATE as usize);
+        assert_eq!(engine.sync_leader, SyncLeader::Clock);
+        let a = engine.deck(DeckId::A).state();
+        assert!((a.tempo - 128.0 / 120.0).abs() < 0.011, "tempo {}", a.tempo);
+        assert!(a.phase_error.unwrap().abs() < 0.01);
+    }
+
+    #[test]
+    fn test_sync_lines_up_bars() {
+        let mut engine = engine_with_tracks();
+        // A on the downbeat of bar 2, B on beat 3 of a bar with the same beat phase
+        engine.deck_mut(DeckId::A).seek(4.0);
+        engine.deck_mut(DeckId::B).seek(5.0);
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::Play(DeckId::B));
+        engine.handle_command(AudioCommand::Sync(DeckId::B));
+        run(&mut engine, SAMPLE_RATE as usize);
+
+        let (a, b) = (
+            engine.deck(DeckId::A).state(),
+            engine.deck(DeckId::B).state(),
+        );
+        assert_eq!(a.beat_in_bar, b.beat_in_bar);
+        let bar_phase_a = engine.deck(DeckId::A).bar_phase().unwrap();
+        let bar_phase_b = engine.deck(DeckId::B).bar_phase().unwrap();
+        assert!(sync::phase_error(bar_phase_b, bar_phase_a).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_third_deck_plays_and_syncs_like_the_others() {
+        let mut engine = engine_with_tracks();
+        let samples = Arc::new(vec![0.1f32; SAMPLE_RATE as usize * 20]);
+        engine.handle_command(AudioCommand::LoadDeck(
+            DeckId::C,
+            samples,
+            SAMPLE_RATE,
+            Some("Deck C".into()),
+            Arc::new(Vec::new()),
+            Arc::new(EnhancedWaveform::default()),
+            None,
+        ));
+        engine
+            .deck_mut(DeckId::C)
+            .set_beat_grid(Box::new(BeatGrid::new(125.0, 0, SAMPLE_RATE, 1.0)));
+
+        // Quantized play on C waits for A's beat
+        engine.handle_command(AudioCommand::ToggleQuantize);
+        engine.deck_mut(DeckId::A).seek(1.2);
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::Play(DeckId::C));
+        assert!(!engine.deck(DeckId::C).is_playing());
+        run(&mut engine, (SAMPLE_RATE as f64 * 0.5) as usize);
+        assert!(engine.deck(DeckId::C).is_playing());
+
+        // With nothing sync-locked C syncs to the playing deck A
+        engine.handle_command(AudioCommand::Sync(DeckId::C));
+        run(&mut engine, SAMPLE_RATE as usize);
+        let c = engine.deck(DeckId::C).state();
+        assert!((c.tempo - 120.0 / 125.0).abs() < 0.01, "tempo {}", c.tempo);
+
+        // Sync lock: A leads until it stops, then the lead passes to C
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::A));
+        engine.handle_command(AudioCommand::ToggleSyncLock(DeckId::C));
+        run(&mut engine, 4096);
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::A));
+        engine.handle_command(AudioCommand::Stop(DeckId::A));
+        run(&mut engine, 4096);
+        assert_eq!(engine.sync_leader, SyncLeader::Deck(DeckId::C));
+
+        let AudioEvent::StateUpdate { channels, .. } = engine.get_state() else {
+            panic!("expected a state update");
+        };
+        assert_eq!(channels[2].deck.track_name.as_deref(), Some("Deck C"));
+        assert_eq!(channels[2].assign, CrossfaderAssign::A);
+        assert_eq!(channels[2].deck.sync_role, SyncRole::Leader);
+    }
+
+    #[test]
+    fn test_unquantized_actions_are_immediate() {
+        let mut engine = engine_with_tracks();
+        engine.deck_mut(DeckId::A).seek(1.2);
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::Beatjump(DeckId::A, 2));
+        assert!((engine.deck(DeckId::A).position_secs() - 2.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_recording_writes_file_and_cue_sheet() {
+        let path = std::env::temp_dir().join(format!("ole-test-{}.wav", std::process::id()));
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::StartRecording(path.clone()));
+        run(&mut engine, SAMPLE_RATE as usize);
+        engine.finish_recording();
+
+        let AudioEvent::StateUpdate { recording, .. } = engine.get_state() else {
+            panic!("expected a state update");
+        };
+        assert_eq!(
+            *recording,
+            RecordingStatus::Saved {
+                path: Label::from(path.to_str().unwrap()),
+                tracks: 0
+            }
+        );
+        // 44-byte header, 24-bit stereo frames
+        let len = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(len, 44 + SAMPLE_RATE as u64 * 6);
+        let cue = std::fs::read_to_string(path.with_extension("cue")).unwrap();
+        assert!(cue.contains("FILE \"ole-test-"));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("cue"));
+    }
+
+    #[test]
+    fn test_new_sample_rate_keeps_the_decks() {
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        engine.handle_command(AudioCommand::ToggleQuantize);
+        engine.handle_command(AudioCommand::SetCrossfader(1.0));
+        engine.handle_command(AudioCommand::ToggleEqKill(DeckId::A, EqBand::Low));
+        engine.handle_command(AudioCommand::SetFilterPreset(
+            DeckId::B,
+            FilterType::HighPass,
+            4,
+        ));
+        engine.handle_command(AudioCommand::SetReverbLevel(DeckId::B, 3));
+        engine.handle_command(AudioCommand::MoveEffect(DeckId::B, 0, 5));
+        run(&mut engine, SAMPLE_RATE as usize);
+        let channel = engine.channel_state(DeckId::B);
+
+        engine.reset_sample_rate(96000);
+        assert_eq!(engine.sample_rate(), 96000);
+        assert!(engine.deck(DeckId::A).is_playing());
+        assert!((engine.deck(DeckId::A).position_secs() - 1.0).abs() < 1e-3);
+        assert!(engine.deck(DeckId::B).beat_grid().is_some());
+        assert!(engine.quantize);
+        // The mixer and effects carry over too
+        assert_eq!(engine.mixer.crossfader(), 1.0);
+        assert!(engine.mixer.eq(DeckId::A).kill(EqBand::Low));
+        let rebuilt = engine.channel_state(DeckId::B);
+        assert_eq!(rebuilt.effects, channel.effects);
+        assert_eq!(rebuilt.filter_level, 4);
+        assert_eq!(rebuilt.filter_type, FilterType::HighPass);
+        assert_eq!(rebuilt.reverb_level, 3);
+    }
+
+    fn recording_status(engine: &EngineState) -> RecordingStatus {
+        match engine.get_state() {
+            AudioEvent::StateUpdate { recording, .. } => *recording,
+            _ => panic!("expected a state update"),
+        }
+    }
+
+    #[test]
+    fn test_prepared_recording_attaches_and_stops() {
+        let path = std::env::temp_dir().join(format!("ole-test-{}-prep.flac", std::process::id()));
+        let mut engine = engine_with_tracks();
+        let cmd = AudioCommand::StartRecording(path.clone())
+            .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+            .unwrap();
+        assert!(matches!(cmd, AudioCommand::AttachRecorder(_)));
+        engine.handle_command(cmd);
+        run(&mut engine, SAMPLE_RATE as usize / 2);
+        assert!(recording_status(&engine).is_recording());
+
+        engine.handle_command(AudioCommand::StopRecording);
+        assert!(!recording_status(&engine).is_recording());
+        // Stopped audio isn't captured any more
+        run(&mut engine, SAMPLE_RATE as usize / 2);
+        engine.finish_recording();
+        assert_eq!(
+            recording_status(&engine),
+            RecordingStatus::Saved {
+                path: Label::from(path.to_str().unwrap()),
+                tracks: 0
+            }
+        );
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("cue"));
+
+        let bad = AudioCommand::StartRecording(path.with_extension("mp3"))
+            .prepare(SAMPLE_RATE, &mut DeckGrids::default());
+        assert!(bad.is_err());
+    }
+
+    #[test]
+    fn test_replaced_recorder_is_retired() {
+        let dir = std::env::temp_dir();
+        let paths = ["first", "second"]
+            .map(|name| dir.join(format!("ole-test-{}-{}.wav", std::process::id(), name)));
+        let mut engine = engine_with_tracks();
+        let (bin, mut retired) = HeapRb::<Retired>::new(4).split();
+        engine.set_retired(bin);
+        for path in &paths {
+            let cmd = AudioCommand::StartRecording(path.clone())
+                .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+                .unwrap();
+            engine.handle_command(cmd);
+            run(&mut engine, SAMPLE_RATE as usize / 10);
+        }
+        assert!(recording_status(&engine).is_recording());
+
+        // The first recorder comes back stopped, to be freed off the audio thread
+        let Some(Retired::Recorder(mut first)) = retired.try_pop() else {
+            panic!("replaced recorder not retired");
+        };
+        assert!(retired.try_pop().is_none());
+        first.finish();
+        assert_eq!(
+            first.status(),
+            RecordingStatus::Saved {
+                path: Label::from(paths[0].to_str().unwrap()),
+                tracks: 0
+            }
+        );
+
+        engine.finish_recording();
+        for path in &paths {
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_file(path.with_extension("cue"));
+        }
+    }
+
+    #[test]
+    fn test_grid_edits_are_prepared_off_the_audio_thread() {
+        let mut engine = engine_with_tracks();
+        let (bin, mut retired) = HeapRb::<Retired>::new(8).split();
+        engine.set_retired(bin);
+        let mut grids = DeckGrids::default();
+        let send = |engine: &mut EngineState, grids: &mut DeckGrids, cmd: AudioCommand| {
+            let cmd = cmd.prepare(SAMPLE_RATE, grids).unwrap();
+            engine.handle_command(cmd);
+        };
+        let grid = Box::new(BeatGrid::new(80.0, 0, SAMPLE_RATE, 1.0));
+        send(&mut engine, &mut grids, AudioCommand::SetBeatGrid(DeckId::A, grid));
+        assert!(matches!(retired.try_pop(), Some(Retired::Grid(old)) if old.bpm == 120.0));
+        let state = |engine: &EngineState| engine.deck(DeckId::A).state().beat_grid_info.unwrap();
+        assert_eq!(state(&engine).edits, 0);
+
+        let cmd = AudioCommand::ScaleGridTempo(DeckId::A, 2.0).prepare(SAMPLE_RATE, &mut grids);
+        assert!(matches!(cmd, Ok(AudioCommand::EditGrid(DeckId::A, _))));
+        engine.handle_command(cmd.unwrap());
+        assert_eq!(engine.deck(DeckId::A).current_bpm(), Some(160.0));
+        assert!(matches!(retired.try_pop(), Some(Retired::Grid(old)) if old.bpm == 80.0));
+
+        // Playhead a third of a beat past beat 5 becomes a downbeat, once
+        // the command thread has seen it there
+        engine.deck_mut(DeckId::A).seek(2.0);
+        grids.follow(&engine.get_state());
+        send(&mut engine, &mut grids, AudioCommand::SetDownbeat(DeckId::A));
+        let deck = engine.deck(DeckId::A);
+        assert!(deck.beat_phase().unwrap() < 1e-3);
+        assert_eq!(deck.current_bar().map(|(_, beat)| beat), Some(0));
+
+        send(&mut engine, &mut grids, AudioCommand::ShiftGrid(DeckId::A, 0.01));
+        let phase = engine.deck(DeckId::A).beat_phase().unwrap();
+        assert!((phase - (1.0 - 0.01 * 160.0 / 60.0)).abs() < 1e-3);
+
+        send(&mut engine, &mut grids, AudioCommand::SetGridBpm(DeckId::A, 170.0));
+        assert_eq!(engine.deck(DeckId::A).current_bpm(), Some(170.0));
+        assert_eq!(state(&engine).edits, 4);
+
+        // Hosts that don't prepare get the same correction
+        engine.handle_command(AudioCommand::SetGridBpm(DeckId::B, 170.0));
+        assert_eq!(engine.deck(DeckId::B).current_bpm(), Some(170.0));
+        assert_eq!(engine.deck(DeckId::B).state().beat_grid_info.unwrap().edits, 1);
+    }
+
+    #[test]
+    fn test_prepared_load_keeps_the_analysis() {
+        let mut engine = EngineState::new(SAMPLE_RATE);
+        let (bin, mut retired) = HeapRb::<Retired>::new(4).split();
+        engine.set_retired(bin);
+        let load = |name: &str| {
+            AudioCommand::LoadDeck(
+                DeckId::B,
+                Arc::new(vec![0.0; SAMPLE_RATE as usize * 2]),
+                SAMPLE_RATE,
+                Some(name.into()),
+                Arc::new(Vec::new()),
+                Arc::new(EnhancedWaveform::default()),
+                Some("8A".into()),
+            )
+            .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+            .unwrap()
+        };
+        let cmd = load("Silence");
+        assert!(matches!(cmd, AudioCommand::LoadAnalyzed(DeckId::B, _)));
+        engine.handle_command(cmd);
+        assert_eq!(engine.deck(DeckId::B).track_name(), Some("Silence"));
+        assert!((engine.deck(DeckId::B).duration() - 1.0).abs() < 1e-6);
+
+        // The replaced track comes back to be freed off the audio thread
+        // (after the empty deck's)
+        engine.handle_command(load("More silence"));
+        retired.try_pop();
+        let Some(Retired::Track(old)) = retired.try_pop() else {
+            panic!("expected the replaced track");
+        };
+        assert_eq!(old.name.as_deref(), Some("Silence"));
+        assert_eq!(old.samples.len(), SAMPLE_RATE as usize * 2);
+    }
+
+    #[test]
+    fn test_write_state_updates_in_place() {
+        let mut engine = EngineState::new(SAMPLE_RATE);
+        let (bin, mut retired) = HeapRb::<Retired>::new(8).split();
+        engine.set_retired(bin);
+        let mut event = engine.get_state();
+        let AudioEvent::StateUpdate { channels, .. } = &event else {
+            panic!("expected a state update");
+        };
+        let channels_ptr: *const ChannelState = &channels[0];
+
+        let overview = Arc::new(vec![0.5; 16]);
+        engine.handle_command(AudioCommand::LoadDeck(
+            DeckId::C,
+            Arc::new(vec![0.0; SAMPLE_RATE as usize * 2]),
+            SAMPLE_RATE,
+            Some("Silence".into()),
+            overview.clone(),
+            Arc::new(EnhancedWaveform::default()),
+            Some("8A".into()),
+        ));
+        engine.handle_command(AudioCommand::SetCrossfader(0.25));
+        engine.write_state(&mut event);
+
+        let AudioEvent::StateUpdate {
+            channels,
+            crossfader,
+            ..
+        } = &event
+        else {
+            panic!("expected a state update");
+        };
+        // Same box, new contents
+        assert!(std::ptr::eq(&channels[0], channels_ptr));
+        assert_eq!(*crossfader, 0.25);
+        let deck = &channels[DeckId::C.index()].deck;
+        assert_eq!(deck.track_name.as_deref(), Some("Silence"));
+        assert_eq!(deck.key.as_deref(), Some("8A"));
+        assert!(Arc::ptr_eq(&deck.waveform_overview, &overview));
+
+        // The empty deck's waveforms went to the bin, not the allocator
+        assert!(matches!(retired.try_pop(), Some(Retired::Waveforms(..))));
+        assert!(retired.try_pop().is_none());
+    }
+
+    /// Names of a deck's effect slots, in order
+    fn slot_names(engine: &EngineState, deck: DeckId) -> Vec<&'static str> {
+        engine
+            .channel_state(deck)
+            .effects
+            .iter()
+            .map(|slot| slot.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_effect_chain_commands() {
+        let mut engine = EngineState::new(SAMPLE_RATE);
+        let (bin, mut retired) = HeapRb::<Retired>::new(4).split();
+        engine.set_retired(bin);
+        let names = slot_names(&engine, DeckId::C);
+        assert_eq!(names[0], "Tape Stop");
+        assert_eq!(names.last(), Some(&"Reverb"));
+
+        // Reverb first, delay gone: the delay commands do nothing
+        let delay = names.iter().position(|&n| n == "Delay").unwrap();
+        engine.handle_command(AudioCommand::MoveEffect(DeckId::C, names.len() - 1, 0));
+        engine.handle_command(AudioCommand::RemoveEffect(DeckId::C, delay + 1));
+        engine.handle_command(AudioCommand::ToggleDelay(DeckId::C));
+        let names = slot_names(&engine, DeckId::C);
+        assert_eq!(names[0], "Reverb");
+        assert!(!names.contains(&"Delay"));
+        assert!(!engine.channel_state(DeckId::C).delay_enabled);
+        // The removed delay is freed off the audio thread
+        let Some(Retired::Effect(removed)) = retired.try_pop() else {
+            panic!("expected the removed effect");
+        };
+        assert_eq!(removed.name(), "Delay");
+
+        // Added back (built off the audio thread) at the end
+        let cmd = AudioCommand::AddEffect(DeckId::C, usize::MAX, EffectKind::Delay)
+            .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+            .unwrap();
+        assert!(matches!(cmd, AudioCommand::InsertEffect(DeckId::C, _, _)));
+        engine.handle_command(cmd);
+        let last = slot_names(&engine, DeckId::C).len() - 1;
+        engine.handle_command(AudioCommand::ToggleEffectKind(DeckId::C, EffectKind::Delay));
+        engine.handle_command(AudioCommand::SetEffectMix(DeckId::C, last, 0.25));
+        engine.handle_command(AudioCommand::SetEffectParam(DeckId::C, last, 0, 375.0));
+
+        let channel = engine.channel_state(DeckId::C);
+        let slot = &channel.effects[last];
+        assert_eq!(slot.name, "Delay");
+        assert!(slot.enabled && channel.delay_enabled);
+        assert_eq!(slot.mix, 0.25);
+        assert_eq!(slot.values[0], 375.0);
+        // Other decks keep the default chain
+        assert_eq!(slot_names(&engine, DeckId::A)[0], "Tape Stop");
+    }
+
+    #[test]
+    fn test_delay_level_follows_deck_tempo() {
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::SetDelayLevel(DeckId::A, 2)); // half a beat
+        let delay_ms = |engine: &EngineState| {
+            let channel = engine.channel_state(DeckId::A);
+            let slot = channel.effects.iter().find(|s| s.name == "Delay").unwrap();
+            slot.values[0]
+        };
+
+        run(&mut engine, 512);
+        assert!((delay_ms(&engine) - 250.0).abs() < 0.01); // 120 BPM
+        engine.handle_command(AudioCommand::SetTempo(DeckId::A, 1.25));
+        run(&mut engine, 512);
+        assert!((delay_ms(&engine) - 200.0).abs() < 0.01); // 150 BPM
+
+        // A deck without a track keeps the fixed time
+        engine.handle_command(AudioCommand::SetDelayLevel(DeckId::C, 2));
+        run(&mut engine, 512);
+        let channel = engine.channel_state(DeckId::C);
+        let slot = channel.effects.iter().find(|s| s.name == "Delay").unwrap();
+        assert_eq!(slot.values[0], 200.0);
+    }
+
+    #[test]
+    fn test_echo_out_rings_on_after_the_deck_stops() {
+        let mut engine = engine_with_tracks();
+        engine.handle_command(AudioCommand::Play(DeckId::A));
+        run(&mut engine, SAMPLE_RATE as usize);
+
+        engine.handle_command(AudioCommand::TriggerEchoOut(DeckId::A));
+        run(&mut engine, SAMPLE_RATE as usize / 2);
+        assert!(!engine.deck(DeckId::A).is_playing());
+        let echo = engine.effects[0].get::<EchoOut>().unwrap();
+        assert!(echo.is_echoing());
+
+        // The tail is still audible with the deck stopped
+        let mut buffer = [0.0f32; 1024];
+        let mut peak = 0.0f32;
+        for _ in 0..20 {
+            engine.process(&mut buffer, None);
+            peak = buffer.iter().fold(peak, |m, s| m.max(s.abs()));
+        }
+        assert!(peak > 0.01, "peak {}", peak);
+    }
+
+    #[test]
+    fn test_impulse_response_is_prepared_off_the_audio_thread() {
+        let mut engine = engine_with_tracks();
+        assert_eq!(engine.channel_state(DeckId::B).impulse, None);
+
+        // A mono 44.1 kHz response, transformed before it reaches the engine
+        let samples: Vec<f32> = (0..4410).map(|i| (-(i as f32) / 500.0).exp()).collect();
+        let cmd = AudioCommand::LoadImpulse(DeckId::B, samples, 44100, 1, "hall.wav".into())
+            .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+            .unwrap();
+        assert!(matches!(cmd, AudioCommand::SetImpulse(DeckId::B, _)));
+        engine.handle_command(cmd);
+        engine.handle_command(AudioCommand::ToggleEffectKind(
+            DeckId::B,
+            EffectKind::Convolution,
+        ));
+        engine.handle_command(AudioCommand::SetConvolutionWet(DeckId::B, 0.8));
+
+        let channel = engine.channel_state(DeckId::B);
+        assert_eq!(channel.impulse.as_deref(), Some("hall.wav"));
+        let slot = channel
+            .effects
+            .iter()
+            .find(|s| s.name == "Convolution")
+            .unwrap();
+        assert!(slot.enabled);
+        assert_eq!(slot.values[0], 0.8);
+        assert_eq!(engine.channel_state(DeckId::A).impulse, None);
+
+        // The replaced response, and one without a slot, are freed elsewhere
+        let (bin, mut retired) = HeapRb::<Retired>::new(4).split();
+        engine.set_retired(bin);
+        let room = || {
+            let samples: Vec<f32> = (0..480).map(|i| (-(i as f32) / 50.0).exp()).collect();
+            AudioCommand::LoadImpulse(DeckId::B, samples, 48000, 1, "room.wav".into())
+                .prepare(SAMPLE_RATE, &mut DeckGrids::default())
+                .unwrap()
+        };
+        engine.handle_command(room());
+        assert_eq!(
+            engine.channel_state(DeckId::B).impulse.as_deref(),
+            Some("room.wav")
+        );
+        let Some(Retired::Impulse(old)) = retired.try_pop() else {
+            panic!("replaced impulse response not retired");
+        };
+        assert_eq!(old.name(), "hall.wav");
+
+        let slot = engine
+            .channel_state(DeckId::B)
+            .effects
+            .iter()
+            .position(|s| s.name == "Convolution")
+            .unwrap();
+        engine.handle_command(AudioCommand::RemoveEffect(DeckId::B, slot));
+        assert!(matches!(retired.try_pop(), Some(Retired::Effect(_))));
+        engine.handle_command(room());
+        assert!(matches!(retired.try_pop(), Some(Retired::Impulse(_))));
+
+        // Silence doesn't prepare
+        let silent = AudioCommand::LoadImpulse(DeckId::B, vec![0.0; 64], 48000, 2, "x".into());
+        assert!(silent.prepare(SAMPLE_RATE, &mut DeckGrids::default()).is_err());
+    }
+}
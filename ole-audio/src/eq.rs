@@ -0,0 +1,440 @@
+//! Channel EQ - per-deck low/mid/high with kill switches
+//!
+//! Two flavours, as on DJ mixers:
+//! - Classic: low shelf, mid bell and high shelf around the dry signal.
+//!   Kills cut hard but not completely.
+//! - Isolator: the signal is split into three bands with Linkwitz-Riley
+//!   (LR4) crossovers and the bands are summed back at their own gains, so
+//!   a kill removes the band entirely.
+//!
+//! Filters run at fixed frequencies and the band gains are smoothed per
+//! sample, so turning a knob or hitting a kill never clicks.
+
+use std::f32::consts::{PI, SQRT_2};
+
+/// EQ band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqBand {
+    Low,
+    Mid,
+    High,
+}
+
+impl EqBand {
+    /// All bands, lowest first
+    pub const ALL: [EqBand; 3] = [EqBand::Low, EqBand::Mid, EqBand::High];
+
+    fn index(self) -> usize {
+        match self {
+            EqBand::Low => 0,
+            EqBand::Mid => 1,
+            EqBand::High => 2,
+        }
+    }
+
+    /// Get display name for the band
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            EqBand::Low => "LO",
+            EqBand::Mid => "MID",
+            EqBand::High => "HI",
+        }
+    }
+
+    /// Parse a band as typed in command mode ("low", "mid", "hi")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" | "lo" | "bass" => Some(EqBand::Low),
+            "mid" => Some(EqBand::Mid),
+            "high" | "hi" | "treble" => Some(EqBand::High),
+            _ => None,
+        }
+    }
+}
+
+/// How the EQ shapes the bands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EqMode {
+    /// Shelving/bell EQ around the dry signal
+    #[default]
+    Classic,
+    /// Full band split: kills remove the band completely
+    Isolator,
+}
+
+impl EqMode {
+    /// Get display name for the mode
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            EqMode::Classic => "CLASSIC",
+            EqMode::Isolator => "ISO",
+        }
+    }
+
+    /// Switch to the other mode
+    pub fn toggle(self) -> Self {
+        match self {
+            EqMode::Classic => EqMode::Isolator,
+            EqMode::Isolator => EqMode::Classic,
+        }
+    }
+}
+
+/// State variable filter with fixed coefficients (Cytomic trapezoidal SVF)
+#[derive(Debug, Clone, Copy, Default)]
+struct Svf {
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    fn new(freq: f32, q: f32, sample_rate: f32) -> Self {
+        let g = (PI * freq / sample_rate).tan();
+        let k = 1.0 / q;
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        Self {
+            k,
+            a1,
+            a2,
+            a3,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        }
+    }
+
+    /// Butterworth response (two in series make a Linkwitz-Riley filter)
+    fn butterworth(freq: f32, sample_rate: f32) -> Self {
+        Self::new(freq, 1.0 / SQRT_2, sample_rate)
+    }
+
+    /// Filter one sample, returning (low pass, band pass, high pass)
+    #[inline]
+    fn tick(&mut self, input: f32) -> (f32, f32, f32) {
+        let v3 = input - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        (v2, v1, input - self.k * v1 - v2)
+    }
+
+    /// Band pass with 0 dB gain at the center frequency
+    #[inline]
+    fn band_pass(&mut self, input: f32) -> f32 {
+        let (_, bp, _) = self.tick(input);
+        self.k * bp
+    }
+
+    /// All pass matching the phase of a Linkwitz-Riley crossover at the same frequency
+    #[inline]
+    fn all_pass(&mut self, input: f32) -> f32 {
+        let (_, bp, _) = self.tick(input);
+        input - 2.0 * self.k * bp
+    }
+
+    fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+}
+
+/// One audio channel's filters for both modes
+#[derive(Debug, Clone, Copy)]
+struct EqFilters {
+    // Classic
+    low_shelf: Svf,
+    mid_bell: Svf,
+    high_shelf: Svf,
+    // Isolator: LR4 split at the low and high crossovers
+    low_split: Svf,
+    low_lp: Svf,
+    low_hp: Svf,
+    high_split: Svf,
+    high_lp: Svf,
+    high_hp: Svf,
+    low_all_pass: Svf,
+}
+
+impl EqFilters {
+    fn new(sample_rate: f32) -> Self {
+        let low = ChannelEq::ISOLATOR_LOW_HZ;
+        let high = ChannelEq::ISOLATOR_HIGH_HZ;
+        Self {
+            low_shelf: Svf::butterworth(ChannelEq::CLASSIC_LOW_HZ, sample_rate),
+            mid_bell: Svf::new(
+                ChannelEq::CLASSIC_MID_HZ,
+                ChannelEq::CLASSIC_MID_Q,
+                sample_rate,
+            ),
+            high_shelf: Svf::butterworth(ChannelEq::CLASSIC_HIGH_HZ, sample_rate),
+            low_split: Svf::butterworth(low, sample_rate),
+            low_lp: Svf::butterworth(low, sample_rate),
+            low_hp: Svf::butterworth(low, sample_rate),
+            high_split: Svf::butterworth(high, sample_rate),
+            high_lp: Svf::butterworth(high, sample_rate),
+            high_hp: Svf::butterworth(high, sample_rate),
+            low_all_pass: Svf::butterworth(high, sample_rate),
+        }
+    }
+
+    #[inline]
+    fn classic(&mut self, x: f32, gains: [f32; 3]) -> f32 {
+        // Second order shelves around the dry signal: H = (s² + kAs + A²) / (s² + ks + 1)
+        // for the low shelf (mirrored for the high one), where A² is the band gain
+        let (low, low_bp, _) = self.low_shelf.tick(x);
+        let mid = self.mid_bell.band_pass(x);
+        let (_, high_bp, high) = self.high_shelf.tick(x);
+        let (low_a, high_a) = (gains[0].sqrt(), gains[2].sqrt());
+        x + self.low_shelf.k * (low_a - 1.0) * low_bp
+            + (gains[0] - 1.0) * low
+            + (gains[1] - 1.0) * mid
+            + self.high_shelf.k * (high_a - 1.0) * high_bp
+            + (gains[2] - 1.0) * high
+    }
+
+    #[inline]
+    fn isolator(&mut self, x: f32, gains: [f32; 3]) -> f32 {
+        let (lp, _, hp) = self.low_split.tick(x);
+        let (low, _, _) = self.low_lp.tick(lp);
+        let (_, _, rest) = self.low_hp.tick(hp);
+        let (lp, _, hp) = self.high_split.tick(rest);
+        let (mid, _, _) = self.high_lp.tick(lp);
+        let (_, _, high) = self.high_hp.tick(hp);
+        // The low band skips the high crossover, so give it the same phase
+        let low = self.low_all_pass.all_pass(low);
+        gains[0] * low + gains[1] * mid + gains[2] * high
+    }
+
+    fn reset(&mut self) {
+        for svf in [
+            &mut self.low_shelf,
+            &mut self.mid_bell,
+            &mut self.high_shelf,
+            &mut self.low_split,
+            &mut self.low_lp,
+            &mut self.low_hp,
+            &mut self.high_split,
+            &mut self.high_lp,
+            &mut self.high_hp,
+            &mut self.low_all_pass,
+        ] {
+            svf.reset();
+        }
+    }
+}
+
+/// 3-band channel EQ with kill switches (stereo interleaved)
+#[derive(Debug, Clone)]
+pub struct ChannelEq {
+    mode: EqMode,
+    /// Band gains in dB (low, mid, high)
+    gains_db: [f32; 3],
+    /// Band kills (low, mid, high)
+    kills: [bool; 3],
+    /// Linear gains the smoothed gains move toward
+    targets: [f32; 3],
+    /// Smoothed linear gains
+    smoothed: [f32; 3],
+    left: EqFilters,
+    right: EqFilters,
+}
+
+impl ChannelEq {
+    /// Gain range of a band in dB (kills go below it, to silence)
+    pub const MIN_DB: f32 = -26.0;
+    pub const MAX_DB: f32 = 6.0;
+
+    /// Classic shelf and bell frequencies
+    const CLASSIC_LOW_HZ: f32 = 150.0;
+    const CLASSIC_MID_HZ: f32 = 1000.0;
+    const CLASSIC_MID_Q: f32 = 0.7;
+    const CLASSIC_HIGH_HZ: f32 = 6000.0;
+
+    /// Isolator crossover frequencies
+    const ISOLATOR_LOW_HZ: f32 = 300.0;
+    const ISOLATOR_HIGH_HZ: f32 = 3000.0;
+
+    /// Smoothing coefficient for band gains (~5ms at 48kHz, like the crossfader)
+    const GAIN_SMOOTH_COEFF: f32 = 0.995;
+
+    /// Create a flat EQ
+    pub fn new(sample_rate: f32) -> Self {
+        let filters = EqFilters::new(sample_rate);
+        Self {
+            mode: EqMode::default(),
+            gains_db: [0.0; 3],
+            kills: [false; 3],
+            targets: [1.0; 3],
+            smoothed: [1.0; 3],
+            left: filters,
+            right: filters,
+        }
+    }
+
+    /// Redesign the filters for a new sample rate, keeping gains, kills
+    /// and mode
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let filters = EqFilters::new(sample_rate);
+        self.left = filters;
+        self.right = filters;
+    }
+
+    /// Set a band's gain in dB (-26 to +6)
+    pub fn set_gain(&mut self, band: EqBand, gain_db: f32) {
+        self.gains_db[band.index()] = gain_db.clamp(Self::MIN_DB, Self::MAX_DB);
+        self.update_target(band);
+    }
+
+    /// Adjust a band's gain by `delta` dB
+    pub fn adjust_gain(&mut self, band: EqBand, delta: f32) {
+        self.set_gain(band, self.gain(band) + delta);
+    }
+
+    /// Get a band's gain in dB
+    pub fn gain(&self, band: EqBand) -> f32 {
+        self.gains_db[band.index()]
+    }
+
+    /// Kill (silence) a band or bring it back
+    pub fn set_kill(&mut self, band: EqBand, killed: bool) {
+        self.kills[band.index()] = killed;
+        self.update_target(band);
+    }
+
+    /// Toggle a band's kill
+    pub fn toggle_kill(&mut self, band: EqBand) {
+        self.set_kill(band, !self.kill(band));
+    }
+
+    /// Check if a band is killed
+    pub fn kill(&self, band: EqBand) -> bool {
+        self.kills[band.index()]
+    }
+
+    /// Band gains in dB (low, mid, high)
+    pub fn gains(&self) -> [f32; 3] {
+        self.gains_db
+    }
+
+    /// Band kills (low, mid, high)
+    pub fn kills(&self) -> [bool; 3] {
+        self.kills
+    }
+
+    /// Switch between classic and isolator EQ
+    pub fn set_mode(&mut self, mode: EqMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.left.reset();
+            self.right.reset();
+        }
+    }
+
+    /// Get the EQ mode
+    pub fn mode(&self) -> EqMode {
+        self.mode
+    }
+
+    fn update_target(&mut self, band: EqBand) {
+        let i = band.index();
+        self.targets[i] = if self.kills[i] {
+            0.0
+        } else {
+            10.0_f32.powf(self.gains_db[i] / 20.0)
+        };
+    }
+
+    /// Process one stereo frame
+    #[inline]
+    pub fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        for (smoothed, target) in self.smoothed.iter_mut().zip(self.targets) {
+            *smoothed =
+                Self::GAIN_SMOOTH_COEFF * *smoothed + (1.0 - Self::GAIN_SMOOTH_COEFF) * target;
+        }
+        let gains = self.smoothed;
+        match self.mode {
+            EqMode::Classic => (
+                self.left.classic(left, gains),
+                self.right.classic(right, gains),
+            ),
+            EqMode::Isolator => (
+                self.left.isolator(left, gains),
+                self.right.isolator(right, gains),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// RMS level of a sine at `freq` through the EQ (after it settles)
+    fn level(eq: &mut ChannelEq, freq: f32) -> f32 {
+        let frames = SAMPLE_RATE as usize;
+        let mut sum = 0.0;
+        for n in 0..frames {
+            let x = (2.0 * PI * freq * n as f32 / SAMPLE_RATE).sin();
+            let (l, _) = eq.process_frame(x, x);
+            if n >= frames / 2 {
+                sum += l * l;
+            }
+        }
+        (sum / (frames / 2) as f32).sqrt() * SQRT_2
+    }
+
+    #[test]
+    fn test_flat_eq_passes_signal() {
+        for mode in [EqMode::Classic, EqMode::Isolator] {
+            for freq in [50.0, 1000.0, 10000.0] {
+                let mut eq = ChannelEq::new(SAMPLE_RATE);
+                eq.set_mode(mode);
+                let gain = level(&mut eq, freq);
+                assert!(
+                    (gain - 1.0).abs() < 0.02,
+                    "{:?} at {} Hz: {}",
+                    mode,
+                    freq,
+                    gain
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_isolator_kill_removes_band() {
+        let mut eq = ChannelEq::new(SAMPLE_RATE);
+        eq.set_mode(EqMode::Isolator);
+        eq.set_kill(EqBand::Low, true);
+        assert!(level(&mut eq, 50.0) < 0.01);
+        assert!((level(&mut eq, 10000.0) - 1.0).abs() < 0.02);
+
+        eq.set_kill(EqBand::Low, false);
+        eq.toggle_kill(EqBand::High);
+        assert!(level(&mut eq, 12000.0) < 0.01);
+        assert!((level(&mut eq, 50.0) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_classic_shelves_and_bell() {
+        let mut eq = ChannelEq::new(SAMPLE_RATE);
+        eq.set_gain(EqBand::Low, -26.0);
+        assert!(level(&mut eq, 40.0) < 0.15);
+        assert!((level(&mut eq, 5000.0) - 1.0).abs() < 0.05);
+
+        let mut eq = ChannelEq::new(SAMPLE_RATE);
+        eq.set_gain(EqBand::Mid, 6.0);
+        let boost = 10.0_f32.powf(6.0 / 20.0);
+        assert!((level(&mut eq, 1000.0) - boost).abs() < 0.05);
+        assert!(level(&mut eq, 40.0) < 1.1);
+        assert_eq!(eq.gains(), [0.0, 6.0, 0.0]);
+    }
+}
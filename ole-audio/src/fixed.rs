@@ -0,0 +1,241 @@
+//! Fixed-capacity containers for state published from the audio callback
+//!
+//! `FixedVec` and `FixedStr` keep their contents inline, so state built out
+//! of them is plain data: it can be updated in place and copied without
+//! touching the allocator. Anything past the capacity is dropped (strings
+//! are cut at a character boundary).
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Capacity of a `Label` in bytes
+pub const LABEL_CAPACITY: usize = 256;
+
+/// Text published from the audio callback: track and impulse response
+/// names, keys, recording paths and errors
+pub type Label = FixedStr<LABEL_CAPACITY>;
+
+/// A vector of at most `N` items, stored inline
+#[derive(Clone, Copy)]
+pub struct FixedVec<T: Copy + Default, const N: usize> {
+    len: usize,
+    items: [T; N],
+}
+
+impl<T: Copy + Default, const N: usize> FixedVec<T, N> {
+    /// Most items it holds
+    pub const CAPACITY: usize = N;
+
+    /// An empty vector
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            items: [T::default(); N],
+        }
+    }
+
+    /// Add an item; returns false (and drops it) when full
+    pub fn push(&mut self, item: T) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.items[self.len] = item;
+        self.len += 1;
+        true
+    }
+
+    /// Remove every item
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Replace the contents with (the first `N` of) `items`
+    pub fn assign(&mut self, items: impl IntoIterator<Item = T>) {
+        self.clear();
+        for item in items.into_iter().take(N) {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items[..self.len]
+    }
+}
+
+impl<T: Copy + Default, const N: usize> FromIterator<T> for FixedVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.assign(iter);
+        vec
+    }
+}
+
+impl<'a, T: Copy + Default, const N: usize> IntoIterator for &'a FixedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for FixedVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Copy + Default + fmt::Debug, const N: usize> fmt::Debug for FixedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A string of at most `N` bytes, stored inline
+#[derive(Clone, Copy)]
+pub struct FixedStr<const N: usize> {
+    len: usize,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> FixedStr<N> {
+    /// An empty string
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; N],
+        }
+    }
+
+    /// The contents
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are ever copied in
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+
+    /// Remove the contents
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Add as much of `s` as fits (whole characters only); returns false
+    /// if it was cut
+    pub fn push_str(&mut self, s: &str) -> bool {
+        let room = N - self.len;
+        let mut end = s.len().min(room);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.bytes[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        end == s.len()
+    }
+}
+
+impl<const N: usize> Default for FixedStr<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> From<&str> for FixedStr<N> {
+    fn from(s: &str) -> Self {
+        let mut fixed = Self::new();
+        fixed.push_str(s);
+        fixed
+    }
+}
+
+impl<const N: usize> Deref for FixedStr<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Write for FixedStr<N> {
+    /// Cut rather than fail when full, so `write!` always leaves the start
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<const N: usize> PartialEq for FixedStr<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<str> for FixedStr<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for FixedStr<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for FixedStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_fixed_vec_drops_past_capacity() {
+        let mut vec: FixedVec<u32, 3> = (1..=5).collect();
+        assert_eq!(&*vec, &[1, 2, 3]);
+        assert!(!vec.push(4));
+
+        vec.assign([7, 8]);
+        assert_eq!(&*vec, &[7, 8]);
+        vec[0] = 9;
+        assert_eq!(vec.iter().sum::<u32>(), 17);
+    }
+
+    #[test]
+    fn test_fixed_str_cuts_at_char_boundary() {
+        let mut s = FixedStr::<5>::from("abcd");
+        assert_eq!(s, "abcd");
+        // "é" is two bytes: only one is left, so it's left out
+        assert!(!s.push_str("é"));
+        assert_eq!(s, "abcd");
+
+        s.clear();
+        let (number, letter) = (12, 'B');
+        write!(s, "{}{}", number, letter).unwrap();
+        assert_eq!(s.to_string(), "12B");
+    }
+}
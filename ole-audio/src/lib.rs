@@ -1,32 +1,67 @@
 //! Audio engine for OLE - decks, mixer, and effects
 //!
 //! This module provides the core audio processing pipeline:
-//! - Deck: Track playback with pitch/tempo control
-//! - Mixer: Crossfader and channel routing
-//! - Effects: Filter, delay, and other DSP effects
+//! - Deck: Track playback with pitch/tempo control (up to `DECK_COUNT` decks)
+//! - Interpolation: Linear, Hermite or windowed-sinc reads between samples
+//! - Mixer: Upfaders, trim, crossfader curves and channel routing
+//! - EQ: Per-channel 3-band EQ with kills (classic or isolator)
+//! - Effects: Per-deck chains of filter, delay, and other DSP effects
+//! - Quantize: Beat grid resolution for quantized actions
+//! - Sync: Sync lock to a tempo leader or master clock
+//! - Recorder: Master output to WAV/FLAC with a CUE sheet tracklist
+//! - Output: Listing and configuring output hosts and devices
 //! - Vinyl: Turntable emulation (motor, wow/flutter, warmth, noise)
 //! - Timestretcher: Phase vocoder for pitch-independent tempo
+//! - Triple buffer: Lock-free state handoff out of the audio callback
+//! - Fixed: Inline vectors and strings for state built in the audio callback
 
 mod deck;
 mod effects;
 mod engine;
+mod eq;
+mod fixed;
+mod interpolation;
 pub mod mastering;
 mod mixer;
+pub mod output;
+mod quantize;
+mod recorder;
+mod sync;
 pub mod timestretcher;
+pub mod triple_buffer;
 mod vinyl;
 
-pub use deck::{BeatGridInfo, Deck, DeckState, PlaybackState, SyncTransition, SCOPE_SAMPLES_SIZE};
+pub use deck::{
+    AnalyzedTrack, BeatGridInfo, Deck, DeckId, DeckState, LoopInfo, PlaybackState, SyncTransition,
+    DECK_COUNT, SCOPE_SAMPLES_SIZE,
+};
 pub use effects::{
-    Delay, DelayInterpolation, DelayModulation, Effect, Filter, FilterMode, FilterType,
-    LadderFilter, Reverb, StateVariableFilter, SvfOutputType,
+    AutoPan, Bitcrusher, Chorus, ConvolutionReverb, DeckFilter, Delay, DelayInterpolation,
+    DelayModulation, EchoOut, Effect, EffectChain, EffectKind, EffectParam, EffectSlot,
+    EffectSlotState, Filter, FilterMode, FilterType, Flanger, Gate, ImpulseResponse, LadderFilter,
+    LfoShape, NoiseSweep, Phaser, Reverb, StateVariableFilter, Stutter, SvfOutputType, TapeStop,
+    MAX_EFFECT_PARAMS, MAX_EFFECT_SLOTS, MAX_IMPULSE_SECS,
+};
+pub use engine::{
+    AudioCommand, AudioEngine, AudioEvent, ChannelState, DeckEffects, DeckGrids, EngineState,
+    Retired,
 };
-pub use engine::{AudioCommand, AudioEngine, AudioEvent, EngineState};
+pub use eq::{ChannelEq, EqBand, EqMode};
+pub use fixed::{FixedStr, FixedVec, Label, LABEL_CAPACITY};
+pub use interpolation::Interpolation;
 pub use mastering::{
     LoudnessMeter, LufsValues, MasteringChain, MasteringCompressor, MasteringEQ, MasteringPreset,
     MasteringSaturation, SaturationMode, StereoEnhancer,
 };
-pub use mixer::{CrossfaderCurve, Mixer};
+pub use mixer::{CrossfaderAssign, CrossfaderCurve, Mixer, UpfaderCurve};
+pub use output::{OutputDevice, OutputInfo, OutputSettings};
+pub use quantize::QuantizeResolution;
+pub use recorder::{
+    cue_sheet, FlacWriter, RecordFormat, Recorder, RecordingStatus, TrackEntry, WavWriter,
+};
+pub use sync::{MasterClock, SyncLeader, SyncRole};
 pub use timestretcher::{FftSize, PhaseLockMode, PhaseVocoder, TimeStretchParams};
+pub use triple_buffer::triple_buffer;
 pub use vinyl::{
     AnalogWarmth, SaturationType, TurntableMotor, VinylEmulator, VinylNoise, VinylPreset,
     WowFlutter,
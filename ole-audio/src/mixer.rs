@@ -1,7 +1,11 @@
-//! Mixer implementation - crossfader and channel routing
+//! Mixer implementation - channel strips (trim, EQ, upfader), crossfader, channel routing
+//! and the headphone cue (PFL) bus
 
 use std::f32::consts::FRAC_PI_4;
 
+use crate::deck::{DeckId, DECK_COUNT};
+use crate::eq::{ChannelEq, EqMode};
+
 /// Number of entries in the crossfader lookup table
 const CROSSFADER_LUT_SIZE: usize = 256;
 
@@ -17,6 +21,214 @@ pub enum CrossfaderCurve {
     Cut,
 }
 
+impl CrossfaderCurve {
+    /// All curves, in cycling order
+    pub const ALL: [CrossfaderCurve; 3] = [
+        CrossfaderCurve::Linear,
+        CrossfaderCurve::ConstantPower,
+        CrossfaderCurve::Cut,
+    ];
+
+    /// Get display name for the curve
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CrossfaderCurve::Linear => "LIN",
+            CrossfaderCurve::ConstantPower => "POWER",
+            CrossfaderCurve::Cut => "CUT",
+        }
+    }
+
+    /// Name used in commands and the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrossfaderCurve::Linear => "linear",
+            CrossfaderCurve::ConstantPower => "power",
+            CrossfaderCurve::Cut => "cut",
+        }
+    }
+
+    /// Parse a curve name ("linear", "power", "cut")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "linear" | "lin" => Some(CrossfaderCurve::Linear),
+            "power" | "constant" | "smooth" => Some(CrossfaderCurve::ConstantPower),
+            "cut" | "scratch" | "sharp" => Some(CrossfaderCurve::Cut),
+            _ => None,
+        }
+    }
+
+    /// Next curve (wraps around)
+    pub fn next(self) -> Self {
+        match self {
+            CrossfaderCurve::Linear => CrossfaderCurve::ConstantPower,
+            CrossfaderCurve::ConstantPower => CrossfaderCurve::Cut,
+            CrossfaderCurve::Cut => CrossfaderCurve::Linear,
+        }
+    }
+
+    /// Share of the travel toward the other side that fades a deck out, before sharpness
+    fn fade_width(self) -> f32 {
+        match self {
+            CrossfaderCurve::Linear | CrossfaderCurve::ConstantPower => 1.0,
+            CrossfaderCurve::Cut => 0.05,
+        }
+    }
+}
+
+/// Crossfader side a channel plays on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrossfaderAssign {
+    /// Left side
+    #[default]
+    A,
+    /// Right side
+    B,
+    /// Bypasses the crossfader
+    Thru,
+}
+
+impl CrossfaderAssign {
+    /// All assignments, in cycling order
+    pub const ALL: [CrossfaderAssign; 3] = [
+        CrossfaderAssign::A,
+        CrossfaderAssign::B,
+        CrossfaderAssign::Thru,
+    ];
+
+    /// Get display name for the assignment
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CrossfaderAssign::A => "A",
+            CrossfaderAssign::B => "B",
+            CrossfaderAssign::Thru => "THRU",
+        }
+    }
+
+    /// Name used in commands and the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrossfaderAssign::A => "a",
+            CrossfaderAssign::B => "b",
+            CrossfaderAssign::Thru => "thru",
+        }
+    }
+
+    /// Parse an assignment name ("a", "b", "thru")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "a" | "left" => Some(CrossfaderAssign::A),
+            "b" | "right" => Some(CrossfaderAssign::B),
+            "thru" | "through" | "off" => Some(CrossfaderAssign::Thru),
+            _ => None,
+        }
+    }
+
+    /// Next assignment (wraps around)
+    pub fn next(self) -> Self {
+        match self {
+            CrossfaderAssign::A => CrossfaderAssign::B,
+            CrossfaderAssign::B => CrossfaderAssign::Thru,
+            CrossfaderAssign::Thru => CrossfaderAssign::A,
+        }
+    }
+
+    /// Factory assignment: A and C on the left, B and D on the right
+    pub fn default_for(deck: DeckId) -> Self {
+        match deck {
+            DeckId::A | DeckId::C => CrossfaderAssign::A,
+            DeckId::B | DeckId::D => CrossfaderAssign::B,
+        }
+    }
+}
+
+/// Channel upfader curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpfaderCurve {
+    /// Gain follows the fader
+    #[default]
+    Linear,
+    /// Level comes in late (most of it in the top half)
+    Slow,
+    /// Level comes in early (most of it in the bottom half)
+    Fast,
+}
+
+impl UpfaderCurve {
+    /// Get display name for the curve
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            UpfaderCurve::Linear => "LIN",
+            UpfaderCurve::Slow => "SLOW",
+            UpfaderCurve::Fast => "FAST",
+        }
+    }
+
+    /// Name used in commands and the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpfaderCurve::Linear => "linear",
+            UpfaderCurve::Slow => "slow",
+            UpfaderCurve::Fast => "fast",
+        }
+    }
+
+    /// Parse a curve name ("linear", "slow", "fast")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "linear" | "lin" => Some(UpfaderCurve::Linear),
+            "slow" | "log" => Some(UpfaderCurve::Slow),
+            "fast" | "exp" => Some(UpfaderCurve::Fast),
+            _ => None,
+        }
+    }
+
+    /// Next curve (wraps around)
+    pub fn next(self) -> Self {
+        match self {
+            UpfaderCurve::Linear => UpfaderCurve::Slow,
+            UpfaderCurve::Slow => UpfaderCurve::Fast,
+            UpfaderCurve::Fast => UpfaderCurve::Linear,
+        }
+    }
+
+    /// Gain for a fader position (0.0 - 1.0)
+    #[inline]
+    pub fn gain(self, position: f32) -> f32 {
+        let p = position.clamp(0.0, 1.0);
+        match self {
+            UpfaderCurve::Linear => p,
+            UpfaderCurve::Slow => p * p,
+            UpfaderCurve::Fast => 1.0 - (1.0 - p) * (1.0 - p),
+        }
+    }
+}
+
+/// Linear gains of a channel strip stage by stage
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ChannelGains {
+    /// Trim (pre-fader, feeds the cue bus too)
+    trim: f32,
+    /// Upfader through its curve
+    fader: f32,
+    /// Cue bus send (1.0 while cued)
+    cue: f32,
+}
+
+impl ChannelGains {
+    const UNITY: Self = Self {
+        trim: 1.0,
+        fader: 1.0,
+        cue: 0.0,
+    };
+
+    #[inline]
+    fn smooth_toward(&mut self, target: Self, coeff: f32) {
+        self.trim = coeff * self.trim + (1.0 - coeff) * target.trim;
+        self.fader = coeff * self.fader + (1.0 - coeff) * target.fader;
+        self.cue = coeff * self.cue + (1.0 - coeff) * target.cue;
+    }
+}
+
 /// Mixer for combining deck outputs
 pub struct Mixer {
     /// Crossfader position (-1.0 = full A, 0.0 = center, 1.0 = full B)
@@ -25,6 +237,10 @@ pub struct Mixer {
     smoothed_crossfader: f32,
     /// Crossfader curve
     curve: CrossfaderCurve,
+    /// Crossfader curve sharpness (0.0 = as the curve is, 1.0 = shortest fade)
+    sharpness: f32,
+    /// Reversed crossfader ("hamster" mode): the left side plays side B
+    hamster: bool,
     /// Master volume
     master_volume: f32,
     /// Smoothed master volume (interpolates toward master_volume to prevent clicks)
@@ -32,6 +248,28 @@ pub struct Mixer {
     /// Pre-computed crossfader gains [gain_a, gain_b] for ConstantPower curve
     /// 256 entries covering -1.0 to 1.0 range
     crossfader_lut: Box<[(f32, f32); CROSSFADER_LUT_SIZE]>,
+    /// Channel EQ per deck
+    eqs: [ChannelEq; DECK_COUNT],
+    /// Upfader positions per deck (0.0 - 1.0)
+    upfaders: [f32; DECK_COUNT],
+    /// Trim per deck in dB
+    trims_db: [f32; DECK_COUNT],
+    /// Upfader curve (all channels)
+    upfader_curve: UpfaderCurve,
+    /// Cue (PFL) toggles per deck
+    cues: [bool; DECK_COUNT],
+    /// Crossfader side per deck
+    assigns: [CrossfaderAssign; DECK_COUNT],
+    /// Channel gains the smoothed gains move toward
+    channel_targets: [ChannelGains; DECK_COUNT],
+    /// Smoothed channel gains
+    smoothed_channel_gains: [ChannelGains; DECK_COUNT],
+    /// Headphone mix (0.0 = cue only, 1.0 = master only)
+    cue_mix: f32,
+    smoothed_cue_mix: f32,
+    /// Headphone volume
+    headphone_volume: f32,
+    smoothed_headphone_volume: f32,
 }
 
 impl Mixer {
@@ -39,10 +277,26 @@ impl Mixer {
     const CROSSFADER_SMOOTH_COEFF: f32 = 0.995;
     /// Smoothing coefficient for master volume (~5ms at 48kHz)
     const MASTER_VOLUME_SMOOTH_COEFF: f32 = 0.995;
+    /// Smoothing coefficient for upfaders and trims (~5ms at 48kHz)
+    const CHANNEL_SMOOTH_COEFF: f32 = 0.995;
+
+    /// Trim range in dB
+    pub const TRIM_MIN_DB: f32 = -12.0;
+    pub const TRIM_MAX_DB: f32 = 12.0;
+
+    /// Fade width left at full sharpness, relative to the curve's own
+    const MIN_FADE_SCALE: f32 = 0.1;
 }
 
 impl Default for Mixer {
     fn default() -> Self {
+        Self::new(48000.0)
+    }
+}
+
+impl Mixer {
+    /// Create a new mixer
+    pub fn new(sample_rate: f32) -> Self {
         // Pre-compute constant power crossfader gains
         let mut lut = Box::new([(0.0f32, 0.0f32); CROSSFADER_LUT_SIZE]);
         for i in 0..CROSSFADER_LUT_SIZE {
@@ -56,18 +310,25 @@ impl Default for Mixer {
             crossfader: 0.0,
             smoothed_crossfader: 0.0,
             curve: CrossfaderCurve::Linear,
+            sharpness: 0.0,
+            hamster: false,
             master_volume: 1.0,
             smoothed_master_volume: 1.0,
             crossfader_lut: lut,
+            eqs: std::array::from_fn(|_| ChannelEq::new(sample_rate)),
+            upfaders: [1.0; DECK_COUNT],
+            trims_db: [0.0; DECK_COUNT],
+            upfader_curve: UpfaderCurve::Linear,
+            cues: [false; DECK_COUNT],
+            assigns: DeckId::ALL.map(CrossfaderAssign::default_for),
+            channel_targets: [ChannelGains::UNITY; DECK_COUNT],
+            smoothed_channel_gains: [ChannelGains::UNITY; DECK_COUNT],
+            cue_mix: 0.0,
+            smoothed_cue_mix: 0.0,
+            headphone_volume: 1.0,
+            smoothed_headphone_volume: 1.0,
         }
     }
-}
-
-impl Mixer {
-    /// Create a new mixer
-    pub fn new() -> Self {
-        Self::default()
-    }
 
     /// Set crossfader position (-1.0 to 1.0)
     pub fn set_crossfader(&mut self, position: f32) {
@@ -94,6 +355,119 @@ impl Mixer {
         self.curve = curve;
     }
 
+    /// Get crossfader curve
+    pub fn curve(&self) -> CrossfaderCurve {
+        self.curve
+    }
+
+    /// Set crossfader curve sharpness (0.0 - 1.0)
+    ///
+    /// Sharper curves fade a deck out over less of the fader's travel.
+    pub fn set_sharpness(&mut self, sharpness: f32) {
+        self.sharpness = sharpness.clamp(0.0, 1.0);
+    }
+
+    /// Get crossfader curve sharpness
+    pub fn sharpness(&self) -> f32 {
+        self.sharpness
+    }
+
+    /// Reverse the crossfader (hamster mode)
+    pub fn set_hamster(&mut self, hamster: bool) {
+        self.hamster = hamster;
+    }
+
+    /// Check if the crossfader is reversed
+    pub fn hamster(&self) -> bool {
+        self.hamster
+    }
+
+    /// Set a deck's upfader (0.0 - 1.0)
+    pub fn set_upfader(&mut self, deck: DeckId, position: f32) {
+        let channel = deck.index();
+        self.upfaders[channel] = position.clamp(0.0, 1.0);
+        self.update_channel_gain(channel);
+    }
+
+    /// Get a deck's upfader position
+    pub fn upfader(&self, deck: DeckId) -> f32 {
+        self.upfaders[deck.index()]
+    }
+
+    /// Set a deck's trim in dB (-12 to +12)
+    pub fn set_trim(&mut self, deck: DeckId, trim_db: f32) {
+        let channel = deck.index();
+        self.trims_db[channel] = trim_db.clamp(Self::TRIM_MIN_DB, Self::TRIM_MAX_DB);
+        self.update_channel_gain(channel);
+    }
+
+    /// Get a deck's trim in dB
+    pub fn trim(&self, deck: DeckId) -> f32 {
+        self.trims_db[deck.index()]
+    }
+
+    /// Set the upfader curve (all channels)
+    pub fn set_upfader_curve(&mut self, curve: UpfaderCurve) {
+        self.upfader_curve = curve;
+        for channel in 0..DECK_COUNT {
+            self.update_channel_gain(channel);
+        }
+    }
+
+    /// Get the upfader curve
+    pub fn upfader_curve(&self) -> UpfaderCurve {
+        self.upfader_curve
+    }
+
+    fn update_channel_gain(&mut self, channel: usize) {
+        self.channel_targets[channel] = ChannelGains {
+            trim: 10.0_f32.powf(self.trims_db[channel] / 20.0),
+            fader: self.upfader_curve.gain(self.upfaders[channel]),
+            cue: if self.cues[channel] { 1.0 } else { 0.0 },
+        };
+    }
+
+    /// Send a deck to the cue bus or take it off
+    pub fn set_cue(&mut self, deck: DeckId, cued: bool) {
+        self.cues[deck.index()] = cued;
+        self.update_channel_gain(deck.index());
+    }
+
+    /// Check if a deck is cued
+    pub fn cue(&self, deck: DeckId) -> bool {
+        self.cues[deck.index()]
+    }
+
+    /// Put a deck on a crossfader side (or through)
+    pub fn set_assign(&mut self, deck: DeckId, assign: CrossfaderAssign) {
+        self.assigns[deck.index()] = assign;
+    }
+
+    /// Get a deck's crossfader side
+    pub fn assign(&self, deck: DeckId) -> CrossfaderAssign {
+        self.assigns[deck.index()]
+    }
+
+    /// Set the headphone mix (0.0 = cue only, 1.0 = master only)
+    pub fn set_cue_mix(&mut self, mix: f32) {
+        self.cue_mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Get the headphone mix
+    pub fn cue_mix(&self) -> f32 {
+        self.cue_mix
+    }
+
+    /// Set headphone volume
+    pub fn set_headphone_volume(&mut self, volume: f32) {
+        self.headphone_volume = volume.clamp(0.0, 2.0);
+    }
+
+    /// Get headphone volume
+    pub fn headphone_volume(&self) -> f32 {
+        self.headphone_volume
+    }
+
     /// Set master volume
     pub fn set_master_volume(&mut self, volume: f32) {
         self.master_volume = volume.clamp(0.0, 2.0);
@@ -104,44 +478,94 @@ impl Mixer {
         self.master_volume
     }
 
-    /// Calculate gain for deck A based on crossfader position
+    /// Follow a new sample rate (only the EQ filters depend on it)
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        for eq in &mut self.eqs {
+            eq.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Get a deck's channel EQ
+    pub fn eq(&self, deck: DeckId) -> &ChannelEq {
+        &self.eqs[deck.index()]
+    }
+
+    /// Get a deck's channel EQ for changes
+    pub fn eq_mut(&mut self, deck: DeckId) -> &mut ChannelEq {
+        &mut self.eqs[deck.index()]
+    }
+
+    /// Switch all channel EQs between classic and isolator
+    pub fn set_eq_mode(&mut self, mode: EqMode) {
+        for eq in &mut self.eqs {
+            eq.set_mode(mode);
+        }
+    }
+
+    /// Get the channel EQ mode
+    pub fn eq_mode(&self) -> EqMode {
+        self.eqs[0].mode()
+    }
+
+    /// Crossfader gains (side A, side B) for a crossfader position
     #[inline]
-    fn gain_a_for(&self, cf: f32) -> f32 {
-        match self.curve {
-            CrossfaderCurve::Linear => {
-                // -1.0 -> 1.0, 0.0 -> 0.5, 1.0 -> 0.0
-                (1.0 - cf) * 0.5
-            }
-            CrossfaderCurve::ConstantPower => {
-                // Use pre-computed LUT with linear interpolation
-                self.crossfader_lookup(cf).0
-            }
-            CrossfaderCurve::Cut => {
-                // Sharp cut at edges
-                if cf < 0.9 {
-                    1.0
-                } else {
-                    (1.0 - cf) * 10.0
-                }
-            }
+    fn crossfader_gains(&self, cf: f32) -> (f32, f32) {
+        let cf = if self.hamster { -cf } else { cf };
+        // How far toward the other side the fader is, for each side
+        let toward_b = (cf.clamp(-1.0, 1.0) + 1.0) * 0.5;
+        (self.fade_gain(toward_b), self.fade_gain(1.0 - toward_b))
+    }
+
+    /// Crossfader gain of a channel on `assign`, given the side gains
+    #[inline]
+    fn assigned_gain(assign: CrossfaderAssign, (xf_a, xf_b): (f32, f32)) -> f32 {
+        match assign {
+            CrossfaderAssign::A => xf_a,
+            CrossfaderAssign::B => xf_b,
+            CrossfaderAssign::Thru => 1.0,
+        }
+    }
+
+    /// How far the crossfader leans toward a deck's side (-1.0 - 1.0,
+    /// 0.0 for decks that bypass it)
+    pub fn crossfader_lean(&self, deck: DeckId) -> f32 {
+        let cf = if self.hamster {
+            -self.crossfader
+        } else {
+            self.crossfader
+        };
+        match self.assigns[deck.index()] {
+            CrossfaderAssign::A => -cf,
+            CrossfaderAssign::B => cf,
+            CrossfaderAssign::Thru => 0.0,
         }
     }
 
-    /// Calculate gain for deck B based on crossfader position
+    /// How loud each deck currently is in the master (crossfader, trim and
+    /// upfader at their set positions)
+    pub fn audible_gains(&self) -> [f32; DECK_COUNT] {
+        let sides = self.crossfader_gains(self.crossfader);
+        std::array::from_fn(|channel| {
+            let gains = self.channel_targets[channel];
+            Self::assigned_gain(self.assigns[channel], sides) * gains.trim * gains.fader
+        })
+    }
+
+    /// Gain of a deck with the fader `distance` (0.0 - 1.0) toward the other side
+    ///
+    /// The deck stays at full level until the fade region at the far end,
+    /// which covers the whole travel for the linear and constant power
+    /// curves and narrows as the sharpness goes up.
     #[inline]
-    fn gain_b_for(&self, cf: f32) -> f32 {
+    fn fade_gain(&self, distance: f32) -> f32 {
+        let scale = 1.0 - self.sharpness * (1.0 - Self::MIN_FADE_SCALE);
+        let width = self.curve.fade_width() * scale;
+        let t = ((distance - (1.0 - width)) / width).clamp(0.0, 1.0);
         match self.curve {
-            CrossfaderCurve::Linear => (1.0 + cf) * 0.5,
+            CrossfaderCurve::Linear | CrossfaderCurve::Cut => 1.0 - t,
             CrossfaderCurve::ConstantPower => {
                 // Use pre-computed LUT with linear interpolation
-                self.crossfader_lookup(cf).1
-            }
-            CrossfaderCurve::Cut => {
-                if cf > -0.9 {
-                    1.0
-                } else {
-                    (1.0 + cf) * 10.0
-                }
+                self.crossfader_lookup(t * 2.0 - 1.0).0
             }
         }
     }
@@ -162,14 +586,27 @@ impl Mixer {
         (a0 + frac * (a1 - a0), b0 + frac * (b1 - b0))
     }
 
-    /// Mix two stereo buffers according to crossfader position
-    /// Both inputs and output are interleaved stereo
-    /// Uses per-sample smoothing to prevent clicks during crossfader and volume changes
-    pub fn mix(&mut self, deck_a: &[f32], deck_b: &[f32], output: &mut [f32]) {
-        let len = output.len().min(deck_a.len()).min(deck_b.len());
+    /// Mix the decks' stereo buffers through the channel strips according
+    /// to crossfader position and each channel's crossfader side
+    /// Inputs (one per deck, in deck order) and output are interleaved stereo
+    /// Uses per-sample smoothing to prevent clicks during EQ, fader, trim and volume changes
+    ///
+    /// With a `cue` buffer, the cued channels are summed into it before their
+    /// upfaders and the crossfader (pre-fader listen).
+    pub fn mix(&mut self, decks: &[&[f32]], output: &mut [f32], mut cue: Option<&mut [f32]>) {
+        let decks = &decks[..decks.len().min(DECK_COUNT)];
+        let mut len = decks
+            .iter()
+            .map(|deck| deck.len())
+            .fold(output.len(), usize::min);
+        if let Some(ref cue) = cue {
+            len = len.min(cue.len());
+        }
 
         // Process in stereo frames (2 samples per frame)
         for i in (0..len).step_by(2) {
+            let right = |buf: &[f32]| if i + 1 < len { buf[i + 1] } else { 0.0 };
+
             // Smooth crossfader toward target position
             self.smoothed_crossfader = Self::CROSSFADER_SMOOTH_COEFF * self.smoothed_crossfader
                 + (1.0 - Self::CROSSFADER_SMOOTH_COEFF) * self.crossfader;
@@ -179,16 +616,48 @@ impl Mixer {
                 * self.smoothed_master_volume
                 + (1.0 - Self::MASTER_VOLUME_SMOOTH_COEFF) * self.master_volume;
 
-            // Calculate gains from smoothed crossfader and smoothed master volume
-            let gain_a = self.gain_a_for(self.smoothed_crossfader) * self.smoothed_master_volume;
-            let gain_b = self.gain_b_for(self.smoothed_crossfader) * self.smoothed_master_volume;
+            // Smooth channel gains (trim, upfader and cue send) toward their targets
+            for (smoothed, target) in self
+                .smoothed_channel_gains
+                .iter_mut()
+                .zip(self.channel_targets)
+            {
+                smoothed.smooth_toward(target, Self::CHANNEL_SMOOTH_COEFF);
+            }
+
+            // Gains from smoothed crossfader, channel gains and master volume
+            let sides = self.crossfader_gains(self.smoothed_crossfader);
+            let (mut out_l, mut out_r) = (0.0, 0.0);
+            let (mut cue_l, mut cue_r) = (0.0, 0.0);
+            for (channel, deck) in decks.iter().enumerate() {
+                let (l, r) = self.eqs[channel].process_frame(deck[i], right(deck));
+                let gains = self.smoothed_channel_gains[channel];
+                let gain = Self::assigned_gain(self.assigns[channel], sides)
+                    * gains.trim
+                    * gains.fader
+                    * self.smoothed_master_volume;
+                out_l += l * gain;
+                out_r += r * gain;
+
+                // Pre-fader cue bus
+                let cue_gain = gains.trim * gains.cue;
+                cue_l += l * cue_gain;
+                cue_r += r * cue_gain;
+            }
+
+            if let Some(cue) = cue.as_deref_mut() {
+                cue[i] = cue_l;
+                if i + 1 < len {
+                    cue[i + 1] = cue_r;
+                }
+            }
 
             // Mix left channel
-            output[i] = deck_a[i] * gain_a + deck_b[i] * gain_b;
+            output[i] = out_l;
 
             // Mix right channel (if present)
             if i + 1 < len {
-                output[i + 1] = deck_a[i + 1] * gain_a + deck_b[i + 1] * gain_b;
+                output[i + 1] = out_r;
             }
         }
 
@@ -197,6 +666,25 @@ impl Mixer {
             *sample = soft_clip(*sample);
         }
     }
+
+    /// Turn the cue bus into the headphone feed: blend in the finished
+    /// master by the cue mix and apply the headphone volume
+    pub fn mix_headphones(&mut self, master: &[f32], cue: &mut [f32]) {
+        let len = cue.len().min(master.len());
+        for i in (0..len).step_by(2) {
+            self.smoothed_cue_mix = Self::CHANNEL_SMOOTH_COEFF * self.smoothed_cue_mix
+                + (1.0 - Self::CHANNEL_SMOOTH_COEFF) * self.cue_mix;
+            self.smoothed_headphone_volume = Self::CHANNEL_SMOOTH_COEFF
+                * self.smoothed_headphone_volume
+                + (1.0 - Self::CHANNEL_SMOOTH_COEFF) * self.headphone_volume;
+
+            let cue_gain = (1.0 - self.smoothed_cue_mix) * self.smoothed_headphone_volume;
+            let master_gain = self.smoothed_cue_mix * self.smoothed_headphone_volume;
+            for j in i..(i + 2).min(len) {
+                cue[j] = soft_clip(cue[j] * cue_gain + master[j] * master_gain);
+            }
+        }
+    }
 }
 
 /// Soft clip threshold - lower value gives limiter more time to react
@@ -241,3 +729,107 @@ fn soft_clip(x: f32) -> f32 {
     let compressed = SOFT_CLIP_THRESHOLD + knee_width * (1.0 - fast_exp_neg(ratio * 3.0));
     sign * compressed.min(SOFT_CLIP_CEILING)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossfader_sharpness_and_hamster() {
+        let mut mixer = Mixer::default();
+        let (a, b) = mixer.crossfader_gains(0.0);
+        assert!((a - 0.5).abs() < 1e-6 && (b - 0.5).abs() < 1e-6);
+
+        // Full sharpness keeps both decks up until the last 10% of travel
+        mixer.set_sharpness(1.0);
+        assert_eq!(mixer.crossfader_gains(0.0), (1.0, 1.0));
+        assert_eq!(mixer.crossfader_gains(0.79).1, 1.0);
+        assert_eq!(mixer.crossfader_gains(-1.0), (1.0, 0.0));
+
+        mixer.set_curve(CrossfaderCurve::ConstantPower);
+        mixer.set_sharpness(0.0);
+        let (a, b) = mixer.crossfader_gains(0.0);
+        assert!((a * a + b * b - 1.0).abs() < 1e-3);
+
+        // Hamster mode: the left side plays side B
+        mixer.set_hamster(true);
+        let (a, b) = mixer.crossfader_gains(-1.0);
+        assert!(a < 1e-3 && (b - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_upfader_and_trim_scale_channel() {
+        let mut mixer = Mixer::default();
+        mixer.set_crossfader(-1.0);
+        mixer.set_upfader(DeckId::A, 0.5);
+        mixer.set_trim(DeckId::A, 6.0);
+        mixer.set_upfader_curve(UpfaderCurve::Slow);
+        assert_eq!(mixer.trim(DeckId::A), 6.0);
+
+        let input = vec![0.1; 9600];
+        let silence = vec![0.0; 9600];
+        let mut output = vec![0.0; 9600];
+        for _ in 0..5 {
+            mixer.mix(&[&input, &silence], &mut output, None);
+        }
+        let expected = 0.1 * 0.25 * 10.0_f32.powf(6.0 / 20.0);
+        assert!((output[9598] - expected).abs() < 1e-3, "{}", output[9598]);
+
+        mixer.set_upfader(DeckId::A, 0.0);
+        for _ in 0..5 {
+            mixer.mix(&[&input, &silence], &mut output, None);
+        }
+        assert!(output[9598].abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_crossfader_assign_per_deck() {
+        let mut mixer = Mixer::default();
+        assert_eq!(mixer.assign(DeckId::C), CrossfaderAssign::A);
+        assert_eq!(mixer.assign(DeckId::D), CrossfaderAssign::B);
+        mixer.set_assign(DeckId::D, CrossfaderAssign::Thru);
+        mixer.set_crossfader(1.0);
+
+        // Side A (decks A and C) is faded out, deck D bypasses the crossfader
+        let gains = mixer.audible_gains();
+        assert_eq!(gains, [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(mixer.crossfader_lean(DeckId::C), -1.0);
+        assert_eq!(mixer.crossfader_lean(DeckId::D), 0.0);
+
+        let input = vec![0.1; 9600];
+        let silence = vec![0.0; 9600];
+        let mut output = vec![0.0; 9600];
+        for _ in 0..5 {
+            mixer.mix(&[&silence, &silence, &input, &input], &mut output, None);
+        }
+        assert!((output[9598] - 0.1).abs() < 1e-3, "{}", output[9598]);
+    }
+
+    #[test]
+    fn test_cue_bus_is_pre_fader() {
+        let mut mixer = Mixer::default();
+        mixer.set_crossfader(1.0);
+        mixer.set_upfader(DeckId::A, 0.0);
+        mixer.set_cue(DeckId::A, true);
+
+        let input = vec![0.1; 9600];
+        let silence = vec![0.0; 9600];
+        let mut output = vec![0.0; 9600];
+        let mut cue = vec![0.0; 9600];
+        for _ in 0..5 {
+            mixer.mix(&[&input, &silence], &mut output, Some(&mut cue));
+            mixer.mix_headphones(&output, &mut cue);
+        }
+        // Deck A is faded out of the master but still in the headphones
+        assert!(output[9598].abs() < 1e-4);
+        assert!((cue[9598] - 0.1).abs() < 1e-3, "{}", cue[9598]);
+
+        // Cue mix all the way to master: headphones follow the (silent) master
+        mixer.set_cue_mix(1.0);
+        for _ in 0..5 {
+            mixer.mix(&[&input, &silence], &mut output, Some(&mut cue));
+            mixer.mix_headphones(&output, &mut cue);
+        }
+        assert!(cue[9598].abs() < 1e-3);
+    }
+}
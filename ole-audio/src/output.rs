@@ -0,0 +1,233 @@
+//! Output devices - listing, picking and configuring cpal outputs
+//!
+//! Settings name the host and device the way cpal reports them, so they can
+//! be kept in the config file; `None` stands for the system default. The
+//! stream itself is built by the application, which owns the callback.
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{BufferSize, StreamConfig, SupportedBufferSize};
+
+/// Sample rates offered when listing devices
+const COMMON_RATES: [u32; 6] = [44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Which output to open, and how
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputSettings {
+    /// Audio host (e.g. "ALSA", "JACK", "CoreAudio")
+    pub host: Option<String>,
+    /// Output device on that host
+    pub device: Option<String>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Buffer size in frames
+    pub buffer_size: Option<u32>,
+}
+
+impl OutputSettings {
+    /// The same rate and buffer size on the default host and device
+    pub fn default_device(&self) -> Self {
+        Self {
+            host: None,
+            device: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// An output device, as listed for the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputDevice {
+    pub host: String,
+    pub name: String,
+    /// The host's default output
+    pub is_default: bool,
+    pub channels: u16,
+    /// Common sample rates the device supports
+    pub sample_rates: Vec<u32>,
+}
+
+/// The output that is playing
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    pub host: String,
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Fixed buffer size in frames (`None` = device default)
+    pub buffer_size: Option<u32>,
+    /// Opened in place of the configured device, which is missing
+    pub fallback: bool,
+    /// The engine was rebuilt for a new sample rate (mixer and effects reset)
+    pub engine_restarted: bool,
+}
+
+/// List the output devices of every available host
+pub fn list_outputs() -> Vec<OutputDevice> {
+    let mut outputs = Vec::new();
+    for id in cpal::available_hosts() {
+        let Ok(host) = cpal::host_from_id(id) else {
+            continue;
+        };
+        let default_name = host.default_output_device().and_then(|d| d.name().ok());
+        let Ok(devices) = host.output_devices() else {
+            continue;
+        };
+        for device in devices {
+            let Ok(name) = device.name() else {
+                continue;
+            };
+            let Ok(config) = device.default_output_config() else {
+                continue;
+            };
+            let ranges = rate_ranges(&device, config.channels());
+            outputs.push(OutputDevice {
+                host: id.name().to_string(),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                channels: config.channels(),
+                sample_rates: COMMON_RATES
+                    .into_iter()
+                    .filter(|&rate| supports_rate(&ranges, rate))
+                    .collect(),
+            });
+        }
+    }
+    outputs
+}
+
+/// Find the host and device that `settings` name
+pub fn find_output(settings: &OutputSettings) -> Result<(cpal::Host, cpal::Device), String> {
+    let host = match settings.host {
+        Some(ref name) => {
+            let id = cpal::available_hosts()
+                .into_iter()
+                .find(|id| id.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("no audio host named '{}'", name))?;
+            cpal::host_from_id(id).map_err(|e| format!("{}: {}", name, e))?
+        }
+        None => cpal::default_host(),
+    };
+    let device = match settings.device {
+        Some(ref name) => host
+            .output_devices()
+            .map_err(|e| e.to_string())?
+            .find(|d| d.name().is_ok_and(|n| n == *name))
+            .ok_or_else(|| format!("no output device named '{}'", name))?,
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "no audio output device found".to_string())?,
+    };
+    Ok((host, device))
+}
+
+/// Stream config for `device`
+///
+/// The sample rate is the requested one, else `preferred` (the running
+/// engine's) when the device supports it, else the device default. A fixed
+/// buffer size is clamped to what the device allows.
+pub fn stream_config(
+    device: &cpal::Device,
+    settings: &OutputSettings,
+    preferred_rate: Option<u32>,
+) -> Result<StreamConfig, String> {
+    let default = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get audio config: {}", e))?;
+    let ranges = rate_ranges(device, default.channels());
+    let sample_rate = pick_sample_rate(
+        &ranges,
+        settings.sample_rate,
+        preferred_rate,
+        default.sample_rate().0,
+    )?;
+    let buffer_size = match (settings.buffer_size, default.buffer_size()) {
+        (Some(frames), SupportedBufferSize::Range { min, max }) => {
+            BufferSize::Fixed(frames.clamp(*min, *max))
+        }
+        (Some(frames), SupportedBufferSize::Unknown) => BufferSize::Fixed(frames),
+        (None, _) => BufferSize::Default,
+    };
+    Ok(StreamConfig {
+        channels: default.channels(),
+        sample_rate: cpal::SampleRate(sample_rate),
+        buffer_size,
+    })
+}
+
+/// Supported sample rate ranges at `channels` (all of them if none match)
+fn rate_ranges(device: &cpal::Device, channels: u16) -> Vec<(u32, u32)> {
+    let Ok(configs) = device.supported_output_configs() else {
+        return Vec::new();
+    };
+    let configs: Vec<_> = configs.collect();
+    let matching = configs.iter().any(|c| c.channels() == channels);
+    configs
+        .iter()
+        .filter(|c| !matching || c.channels() == channels)
+        .map(|c| (c.min_sample_rate().0, c.max_sample_rate().0))
+        .collect()
+}
+
+/// Whether `rate` falls in one of the ranges (unknown ranges allow anything)
+fn supports_rate(ranges: &[(u32, u32)], rate: u32) -> bool {
+    ranges.is_empty() || ranges.iter().any(|&(min, max)| (min..=max).contains(&rate))
+}
+
+/// Requested rate (must be supported), else preferred if supported, else the default
+fn pick_sample_rate(
+    ranges: &[(u32, u32)],
+    requested: Option<u32>,
+    preferred: Option<u32>,
+    default: u32,
+) -> Result<u32, String> {
+    match requested {
+        Some(rate) if supports_rate(ranges, rate) => Ok(rate),
+        Some(rate) => Err(format!("the device doesn't support {} Hz", rate)),
+        None => Ok(preferred
+            .filter(|&rate| supports_rate(ranges, rate))
+            .unwrap_or(default)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_sample_rate() {
+        let ranges = [(44100, 48000), (96000, 96000)];
+        // A requested rate wins, but only if the device has it
+        assert_eq!(
+            pick_sample_rate(&ranges, Some(96000), Some(44100), 48000),
+            Ok(96000)
+        );
+        assert!(pick_sample_rate(&ranges, Some(192000), None, 48000).is_err());
+        // Otherwise keep the engine's rate when possible
+        assert_eq!(
+            pick_sample_rate(&ranges, None, Some(44100), 48000),
+            Ok(44100)
+        );
+        assert_eq!(
+            pick_sample_rate(&ranges, None, Some(88200), 48000),
+            Ok(48000)
+        );
+        assert_eq!(pick_sample_rate(&ranges, None, None, 48000), Ok(48000));
+        // Devices that don't report ranges take anything
+        assert_eq!(pick_sample_rate(&[], Some(22050), None, 48000), Ok(22050));
+    }
+
+    #[test]
+    fn test_default_device_keeps_stream_settings() {
+        let settings = OutputSettings {
+            host: Some("JACK".into()),
+            device: Some("system".into()),
+            sample_rate: Some(96000),
+            buffer_size: Some(256),
+        };
+        let fallback = settings.default_device();
+        assert_eq!(fallback.host, None);
+        assert_eq!(fallback.device, None);
+        assert_eq!(fallback.sample_rate, Some(96000));
+        assert_eq!(fallback.buffer_size, Some(256));
+    }
+}
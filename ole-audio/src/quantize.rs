@@ -0,0 +1,100 @@
+//! Quantize - snaps cue, loop and beatjump actions to the beat grid
+
+/// Grid resolution for quantized actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuantizeResolution {
+    /// 1/8 beat
+    Eighth,
+    /// 1/4 beat
+    Quarter,
+    /// 1/2 beat
+    Half,
+    /// One beat
+    #[default]
+    Beat,
+    /// One bar (4 beats)
+    Bar,
+}
+
+impl QuantizeResolution {
+    /// All resolutions, finest first
+    pub const ALL: [QuantizeResolution; 5] = [
+        QuantizeResolution::Eighth,
+        QuantizeResolution::Quarter,
+        QuantizeResolution::Half,
+        QuantizeResolution::Beat,
+        QuantizeResolution::Bar,
+    ];
+
+    /// Grid step in beats
+    pub fn beats(self) -> f64 {
+        match self {
+            QuantizeResolution::Eighth => 0.125,
+            QuantizeResolution::Quarter => 0.25,
+            QuantizeResolution::Half => 0.5,
+            QuantizeResolution::Beat => 1.0,
+            QuantizeResolution::Bar => 4.0,
+        }
+    }
+
+    /// Get display name for the resolution
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            QuantizeResolution::Eighth => "1/8",
+            QuantizeResolution::Quarter => "1/4",
+            QuantizeResolution::Half => "1/2",
+            QuantizeResolution::Beat => "1",
+            QuantizeResolution::Bar => "BAR",
+        }
+    }
+
+    /// Parse a resolution as typed in command mode ("1/8" ... "1", "bar")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bar" | "4" => Some(QuantizeResolution::Bar),
+            name => Self::ALL
+                .into_iter()
+                .find(|r| r.display_name().eq_ignore_ascii_case(name)),
+        }
+    }
+
+    /// Cycle to the next resolution
+    pub fn next(self) -> Self {
+        match self {
+            QuantizeResolution::Eighth => QuantizeResolution::Quarter,
+            QuantizeResolution::Quarter => QuantizeResolution::Half,
+            QuantizeResolution::Half => QuantizeResolution::Beat,
+            QuantizeResolution::Beat => QuantizeResolution::Bar,
+            QuantizeResolution::Bar => QuantizeResolution::Eighth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips_display_name() {
+        for resolution in QuantizeResolution::ALL {
+            assert_eq!(
+                QuantizeResolution::parse(resolution.display_name()),
+                Some(resolution)
+            );
+        }
+        assert_eq!(
+            QuantizeResolution::parse("bar"),
+            Some(QuantizeResolution::Bar)
+        );
+        assert_eq!(QuantizeResolution::parse("1/3"), None);
+    }
+
+    #[test]
+    fn test_next_cycles_through_all() {
+        let mut resolution = QuantizeResolution::default();
+        for _ in 0..QuantizeResolution::ALL.len() {
+            resolution = resolution.next();
+        }
+        assert_eq!(resolution, QuantizeResolution::default());
+    }
+}
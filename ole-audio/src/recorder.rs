@@ -0,0 +1,1076 @@
+//! Recorder - captures the master output to WAV or FLAC
+//!
+//! The audio thread only pushes samples into a lock-free ring buffer and
+//! never blocks; a writer thread encodes them to disk. Both formats are
+//! 24-bit stereo PCM, picked by the file extension.
+//!
+//! While recording, the recorder follows which track leads the mix (the
+//! loudest playing deck through the crossfader and channel gains). When
+//! the recording stops that log is written next to the audio file as a
+//! CUE sheet.
+
+use crate::fixed::Label;
+use crate::triple_buffer::{self, triple_buffer};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb, Obs};
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Recording file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Wav,
+    Flac,
+}
+
+impl RecordFormat {
+    /// Pick the format from a file extension (.wav or .flac)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "wav" | "wave" => Some(RecordFormat::Wav),
+            "flac" => Some(RecordFormat::Flac),
+            _ => None,
+        }
+    }
+
+    /// Get display name for the format
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            RecordFormat::Wav => "WAV",
+            RecordFormat::Flac => "FLAC",
+        }
+    }
+}
+
+/// Recording state for the UI (plain data, so the audio thread can copy it)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RecordingStatus {
+    /// Not recording
+    #[default]
+    Idle,
+    /// Capturing the master output
+    Recording {
+        path: Label,
+        secs: f64,
+        /// Samples lost because the writer fell behind
+        dropped: u64,
+    },
+    /// Stopped, the writer is finishing the file
+    Finishing { path: Label },
+    /// File and CUE sheet written
+    Saved { path: Label, tracks: usize },
+    /// Could not start or write the recording
+    Failed(Label),
+}
+
+impl RecordingStatus {
+    pub fn is_recording(&self) -> bool {
+        matches!(self, RecordingStatus::Recording { .. })
+    }
+}
+
+/// A track in the recording's tracklist
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackEntry {
+    /// When the track took the lead, in seconds into the recording
+    pub start_secs: f64,
+    pub title: String,
+}
+
+/// Follows which deck leads the mix and logs track changes
+///
+/// A deck has to stay loudest for `MIN_LEAD_SECS` before its track gets
+/// an entry, so short cuts and scratches to another deck don't count.
+/// The entry starts where the deck took the lead. Entries go to the writer
+/// thread as (start frame, title) through a ring buffer, so following the
+/// lead never allocates.
+struct Tracklist {
+    entries_tx: HeapProd<(u64, Label)>,
+    /// Title of the last entry
+    last: Option<Label>,
+    /// Deck (0 - 3 = A - D) that took the lead, the frame it did and its track
+    candidate: Option<(usize, u64, Label)>,
+}
+
+impl Tracklist {
+    const MIN_LEAD_SECS: f64 = 8.0;
+
+    /// Entries the writer thread can fall behind by
+    const CAPACITY: usize = 64;
+
+    fn new(entries_tx: HeapProd<(u64, Label)>) -> Self {
+        Self {
+            entries_tx,
+            last: None,
+            candidate: None,
+        }
+    }
+
+    /// Note the leading deck (index and title) at `frame`
+    fn follow(&mut self, leader: Option<(usize, &str)>, frame: u64, sample_rate: u32) {
+        let Some((deck, title)) = leader else {
+            self.candidate = None;
+            return;
+        };
+        let title = Label::from(title);
+        if self.last == Some(title) {
+            self.candidate = None;
+            return;
+        }
+        match self.candidate {
+            Some((candidate, since, candidate_title))
+                if candidate == deck && candidate_title == title =>
+            {
+                let min_frames = (Self::MIN_LEAD_SECS * sample_rate as f64) as u64;
+                if frame - since >= min_frames {
+                    self.confirm_candidate();
+                }
+            }
+            _ => self.candidate = Some((deck, frame, title)),
+        }
+    }
+
+    /// Log the candidate (a track still leading when the recording stops
+    /// counts too)
+    fn confirm_candidate(&mut self) {
+        if let Some((_, since, title)) = self.candidate.take() {
+            let _ = self.entries_tx.try_push((since, title));
+            self.last = Some(title);
+        }
+    }
+}
+
+/// Writer-thread side of the tracklist: turn logged (start frame, title)
+/// pairs into entries
+fn collect_tracklist(
+    entries_rx: &mut HeapCons<(u64, Label)>,
+    sample_rate: u32,
+    entries: &mut Vec<TrackEntry>,
+) {
+    while let Some((since, title)) = entries_rx.try_pop() {
+        entries.push(TrackEntry {
+            start_secs: since as f64 / sample_rate as f64,
+            title: title.to_string(),
+        });
+    }
+}
+
+/// Channel gain below which a deck doesn't count as audible (-12 dB)
+const AUDIBLE_GAIN: f32 = 0.25;
+
+/// Seconds of audio the ring buffer holds while the writer catches up
+const BUFFER_SECS: usize = 4;
+
+/// Audio-thread side of a recording
+///
+/// Nothing here locks: the writer thread publishes its status through a
+/// triple buffer. Dropping the recorder stops it (hosts running the engine
+/// on the audio thread get replaced recorders back through the retire bin).
+pub struct Recorder {
+    /// `None` once stopped
+    samples_tx: Option<HeapProd<f32>>,
+    /// Keeps the sample ring alive until the recorder is dropped, so
+    /// stopping never frees it on the audio thread (even after the writer
+    /// thread is gone)
+    _samples_ring: Obs<Arc<HeapRb<f32>>>,
+    writer: Option<JoinHandle<()>>,
+    /// What the writer thread reports
+    status: triple_buffer::Output<RecordingStatus>,
+    path: Label,
+    sample_rate: u32,
+    frames: u64,
+    dropped: u64,
+    tracklist: Tracklist,
+}
+
+impl Recorder {
+    /// Create the file and start the writer thread
+    pub fn start(path: PathBuf, sample_rate: u32) -> io::Result<Self> {
+        let format = RecordFormat::from_path(&path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported format (use .wav or .flac)",
+            )
+        })?;
+        let file = BufWriter::new(File::create(&path)?);
+        let encoder = match format {
+            RecordFormat::Wav => Encoder::Wav(WavWriter::new(file, sample_rate)?),
+            RecordFormat::Flac => Encoder::Flac(FlacWriter::new(file, sample_rate)?),
+        };
+
+        let (samples_tx, samples_rx) =
+            HeapRb::<f32>::new(sample_rate as usize * 2 * BUFFER_SECS).split();
+        let (entries_tx, entries_rx) = HeapRb::new(Tracklist::CAPACITY).split();
+
+        let label = Label::from(path.to_string_lossy().as_ref());
+        let (mut status_tx, status) = triple_buffer(RecordingStatus::Recording {
+            path: label,
+            secs: 0.0,
+            dropped: 0,
+        });
+        let writer = thread::Builder::new()
+            .name("ole-recorder".into())
+            .spawn(move || {
+                let result = write_recording(
+                    encoder,
+                    samples_rx,
+                    entries_rx,
+                    sample_rate,
+                    &path,
+                    &mut status_tx,
+                );
+                status_tx.write(match result {
+                    Ok(tracks) => RecordingStatus::Saved {
+                        path: label,
+                        tracks,
+                    },
+                    Err(e) => {
+                        RecordingStatus::Failed(Label::from(format!("Recording: {}", e).as_str()))
+                    }
+                });
+            })?;
+
+        Ok(Self {
+            _samples_ring: samples_tx.observe(),
+            samples_tx: Some(samples_tx),
+            writer: Some(writer),
+            status,
+            path: label,
+            sample_rate,
+            frames: 0,
+            dropped: 0,
+            tracklist: Tracklist::new(entries_tx),
+        })
+    }
+
+    /// Capture an interleaved stereo buffer (never blocks)
+    ///
+    /// `decks` are each deck's title and audible gain, for the tracklist.
+    pub fn record(&mut self, samples: &[f32], decks: &[(Option<&str>, f32)]) {
+        let Some(samples_tx) = self.samples_tx.as_mut() else {
+            return;
+        };
+        // Loudest deck with a track, if it is audible at all
+        let mut leader = None;
+        let mut loudest = AUDIBLE_GAIN;
+        for (i, &(title, gain)) in decks.iter().enumerate() {
+            if let Some(title) = title {
+                if gain >= loudest {
+                    leader = Some((i, title));
+                    loudest = gain;
+                }
+            }
+        }
+        self.tracklist.follow(leader, self.frames, self.sample_rate);
+
+        let pushed = samples_tx.push_slice(samples);
+        self.dropped += (samples.len() - pushed) as u64;
+        self.frames += (samples.len() / 2) as u64;
+    }
+
+    /// Seconds recorded so far
+    pub fn secs(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+
+    /// Current state for the UI
+    ///
+    /// The writer's own status wins once it failed or finished.
+    pub fn status(&self) -> RecordingStatus {
+        match self.status.read() {
+            RecordingStatus::Recording { .. } if self.samples_tx.is_some() => {
+                RecordingStatus::Recording {
+                    path: self.path,
+                    secs: self.secs(),
+                    dropped: self.dropped,
+                }
+            }
+            // Stopped, but the writer hasn't noticed yet
+            RecordingStatus::Recording { .. } => RecordingStatus::Finishing { path: self.path },
+            other => other,
+        }
+    }
+
+    /// Stop capturing; the writer finishes the file and CUE sheet in the
+    /// background (never blocks, does nothing once stopped)
+    pub fn stop(&mut self) {
+        if let Some(samples_tx) = self.samples_tx.take() {
+            self.tracklist.confirm_candidate();
+            // Dropping the producer tells the writer no more samples are coming
+            drop(samples_tx);
+        }
+    }
+
+    /// Stop and wait until the file is written (for shutdown and tests)
+    pub fn finish(&mut self) {
+        self.stop();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("path", &self.path)
+            .field("secs", &self.secs())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writer thread: drain the ring buffer into the file, then write the CUE sheet
+///
+/// Returns the number of tracks in the tracklist.
+fn write_recording(
+    mut encoder: Encoder,
+    mut samples_rx: HeapCons<f32>,
+    mut entries_rx: HeapCons<(u64, Label)>,
+    sample_rate: u32,
+    path: &Path,
+    status_tx: &mut triple_buffer::Input<RecordingStatus>,
+) -> io::Result<usize> {
+    let mut buffer = vec![0.0f32; 8192];
+    let mut entries = Vec::new();
+    loop {
+        collect_tracklist(&mut entries_rx, sample_rate, &mut entries);
+        let n = samples_rx.pop_slice(&mut buffer);
+        if n > 0 {
+            encoder.write(&buffer[..n])?;
+        } else if !samples_rx.write_is_held() {
+            // Producer gone: take what it pushed last, then finish
+            status_tx.write(RecordingStatus::Finishing {
+                path: Label::from(path.to_string_lossy().as_ref()),
+            });
+            while !samples_rx.is_empty() {
+                let n = samples_rx.pop_slice(&mut buffer);
+                encoder.write(&buffer[..n])?;
+            }
+            break;
+        } else {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+    encoder.finish()?;
+
+    // The last entry goes in before the samples producer is dropped
+    collect_tracklist(&mut entries_rx, sample_rate, &mut entries);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    std::fs::write(path.with_extension("cue"), cue_sheet(&file_name, &entries))?;
+    Ok(entries.len())
+}
+
+/// Format a tracklist as a CUE sheet for `file_name`
+pub fn cue_sheet(file_name: &str, entries: &[TrackEntry]) -> String {
+    let quote = |s: &str| s.replace('"', "'");
+    let mut sheet = String::from("REM COMMENT \"Recorded with OLE\"\n");
+    sheet.push_str(&format!("FILE \"{}\" WAVE\n", quote(file_name)));
+    for (i, entry) in entries.iter().enumerate() {
+        // CUE time is minutes:seconds:frames, 75 frames per second
+        let frames = (entry.start_secs.max(0.0) * 75.0).round() as u64;
+        sheet.push_str(&format!("  TRACK {:02} AUDIO\n", i + 1));
+        sheet.push_str(&format!("    TITLE \"{}\"\n", quote(&entry.title)));
+        sheet.push_str(&format!(
+            "    INDEX 01 {:02}:{:02}:{:02}\n",
+            frames / 75 / 60,
+            frames / 75 % 60,
+            frames % 75
+        ));
+    }
+    sheet
+}
+
+/// Convert a sample to 24-bit PCM
+#[inline]
+fn to_pcm24(sample: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) * 8_388_607.0).round() as i32
+}
+
+enum Encoder {
+    Wav(WavWriter<BufWriter<File>>),
+    Flac(FlacWriter<BufWriter<File>>),
+}
+
+impl Encoder {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        match self {
+            Encoder::Wav(w) => w.write(samples),
+            Encoder::Flac(w) => w.write(samples),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Encoder::Wav(w) => w.finish().map(|_| ()),
+            Encoder::Flac(w) => w.finish().map(|_| ()),
+        }
+    }
+}
+
+/// 24-bit stereo PCM WAV writer
+///
+/// The RIFF and data sizes are patched in when finishing.
+pub struct WavWriter<W: Write + Seek> {
+    out: W,
+    data_bytes: u64,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    const HEADER_BYTES: u32 = 44;
+
+    pub fn new(mut out: W, sample_rate: u32) -> io::Result<Self> {
+        let block_align = 2 * 3;
+        out.write_all(b"RIFF")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&(sample_rate * block_align).to_le_bytes())?;
+        out.write_all(&(block_align as u16).to_le_bytes())?;
+        out.write_all(&24u16.to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        Ok(Self { out, data_bytes: 0 })
+    }
+
+    /// Append interleaved stereo samples
+    pub fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        for &sample in samples {
+            self.out.write_all(&to_pcm24(sample).to_le_bytes()[..3])?;
+        }
+        self.data_bytes += samples.len() as u64 * 3;
+        Ok(())
+    }
+
+    /// Patch the header sizes and flush
+    pub fn finish(mut self) -> io::Result<W> {
+        let data_bytes = self.data_bytes.min((u32::MAX - Self::HEADER_BYTES) as u64) as u32;
+        self.out.seek(SeekFrom::Start(4))?;
+        self.out
+            .write_all(&(data_bytes + Self::HEADER_BYTES - 8).to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(40))?;
+        self.out.write_all(&data_bytes.to_le_bytes())?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// 24-bit stereo FLAC writer
+///
+/// Fixed 4096-frame blocks, each channel pair coded as left/right,
+/// left/side, right/side or mid/side (whichever is smallest) with the best
+/// fixed predictor and partitioned Rice residuals. The total sample count
+/// in STREAMINFO is patched in when finishing; the MD5 is left unset.
+pub struct FlacWriter<W: Write + Seek> {
+    out: W,
+    sample_rate: u32,
+    /// Buffered frames for the next block, per channel
+    block: [Vec<i32>; 2],
+    frame_number: u64,
+    total_frames: u64,
+}
+
+/// Frames per FLAC block (all but the last)
+const FLAC_BLOCK_SIZE: usize = 4096;
+
+impl<W: Write + Seek> FlacWriter<W> {
+    const BITS_PER_SAMPLE: u32 = 24;
+    /// Byte offset of the sample rate / channels / bits / total samples field
+    const STREAMINFO_SAMPLES_OFFSET: u64 = 18;
+
+    pub fn new(mut out: W, sample_rate: u32) -> io::Result<Self> {
+        out.write_all(b"fLaC")?;
+        // Last metadata block, STREAMINFO, 34 bytes
+        out.write_all(&[0x80, 0, 0, 34])?;
+        let block_size = (FLAC_BLOCK_SIZE as u16).to_be_bytes();
+        out.write_all(&block_size)?;
+        out.write_all(&block_size)?;
+        // Min/max frame size unknown
+        out.write_all(&[0; 6])?;
+        out.write_all(&Self::stream_info_samples(sample_rate, 0))?;
+        // MD5 unset
+        out.write_all(&[0; 16])?;
+        Ok(Self {
+            out,
+            sample_rate,
+            block: [
+                Vec::with_capacity(FLAC_BLOCK_SIZE),
+                Vec::with_capacity(FLAC_BLOCK_SIZE),
+            ],
+            frame_number: 0,
+            total_frames: 0,
+        })
+    }
+
+    /// Sample rate (20 bits), channels - 1 (3), bits - 1 (5), total samples (36)
+    fn stream_info_samples(sample_rate: u32, total_frames: u64) -> [u8; 8] {
+        let packed = ((sample_rate as u64 & 0xF_FFFF) << 44)
+            | (1 << 41)
+            | (((Self::BITS_PER_SAMPLE - 1) as u64) << 36)
+            | (total_frames & 0xF_FFFF_FFFF);
+        packed.to_be_bytes()
+    }
+
+    /// Append interleaved stereo samples
+    pub fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        for frame in samples.chunks_exact(2) {
+            self.block[0].push(to_pcm24(frame[0]));
+            self.block[1].push(to_pcm24(frame[1]));
+            if self.block[0].len() == FLAC_BLOCK_SIZE {
+                self.write_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the last (short) block, patch STREAMINFO and flush
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.block[0].is_empty() {
+            self.write_block()?;
+        }
+        self.out
+            .seek(SeekFrom::Start(Self::STREAMINFO_SAMPLES_OFFSET))?;
+        self.out.write_all(&Self::stream_info_samples(
+            self.sample_rate,
+            self.total_frames,
+        ))?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let frame = encode_flac_frame(
+            &self.block[0],
+            &self.block[1],
+            self.frame_number,
+            self.sample_rate,
+        );
+        self.out.write_all(&frame)?;
+        self.frame_number += 1;
+        self.total_frames += self.block[0].len() as u64;
+        self.block[0].clear();
+        self.block[1].clear();
+        Ok(())
+    }
+}
+
+/// MSB-first bit writer for FLAC frames
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Write the low `n` bits of `value` (n <= 32)
+    fn write(&mut self, value: u64, n: u32) {
+        if n == 0 {
+            return;
+        }
+        self.acc = (self.acc << n) | (value & ((1u64 << n) - 1));
+        self.bits += n;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, n: u32) {
+        self.write(value as u64, n);
+    }
+
+    /// `q` zero bits then a one
+    fn write_unary(&mut self, mut q: u64) {
+        while q >= 32 {
+            self.write(0, 32);
+            q -= 32;
+        }
+        self.write(1, q as u32 + 1);
+    }
+
+    /// Pad with zero bits to the next byte
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+/// How the two channels of a frame are coded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChannelAssignment {
+    LeftRight,
+    LeftSide,
+    RightSide,
+    MidSide,
+}
+
+impl ChannelAssignment {
+    fn code(self) -> u64 {
+        match self {
+            ChannelAssignment::LeftRight => 0b0001,
+            ChannelAssignment::LeftSide => 0b1000,
+            ChannelAssignment::RightSide => 0b1001,
+            ChannelAssignment::MidSide => 0b1010,
+        }
+    }
+}
+
+/// Encode one FLAC frame from left and right 24-bit samples
+fn encode_flac_frame(left: &[i32], right: &[i32], frame_number: u64, sample_rate: u32) -> Vec<u8> {
+    const BPS: u32 = 24;
+    let n = left.len();
+    let side: Vec<i64> = left
+        .iter()
+        .zip(right)
+        .map(|(&l, &r)| l as i64 - r as i64)
+        .collect();
+    let mid: Vec<i64> = left
+        .iter()
+        .zip(right)
+        .map(|(&l, &r)| (l as i64 + r as i64) >> 1)
+        .collect();
+    let left: Vec<i64> = left.iter().map(|&s| s as i64).collect();
+    let right: Vec<i64> = right.iter().map(|&s| s as i64).collect();
+
+    let [l, r, m, s] = [
+        Subframe::best(&left, BPS),
+        Subframe::best(&right, BPS),
+        Subframe::best(&mid, BPS),
+        Subframe::best(&side, BPS + 1),
+    ];
+    let (assignment, first, second) = [
+        (ChannelAssignment::LeftRight, &l, &r),
+        (ChannelAssignment::LeftSide, &l, &s),
+        (ChannelAssignment::RightSide, &s, &r),
+        (ChannelAssignment::MidSide, &m, &s),
+    ]
+    .into_iter()
+    .min_by_key(|(_, a, b)| a.bits + b.bits)
+    .unwrap_or((ChannelAssignment::LeftRight, &l, &r));
+
+    let mut bw = BitWriter::default();
+    // Sync code, fixed blocking
+    bw.write(0xFFF8, 16);
+    let block_size_code = if n == FLAC_BLOCK_SIZE { 0b1100 } else { 0b0111 };
+    bw.write(block_size_code, 4);
+    let sample_rate_code = match sample_rate {
+        88200 => 0b0001,
+        176400 => 0b0010,
+        192000 => 0b0011,
+        8000 => 0b0100,
+        16000 => 0b0101,
+        22050 => 0b0110,
+        24000 => 0b0111,
+        32000 => 0b1000,
+        44100 => 0b1001,
+        48000 => 0b1010,
+        96000 => 0b1011,
+        // From STREAMINFO
+        _ => 0b0000,
+    };
+    bw.write(sample_rate_code, 4);
+    bw.write(assignment.code(), 4);
+    // 24 bits per sample, reserved bit
+    bw.write(0b1100, 4);
+    write_utf8_number(&mut bw, frame_number);
+    if block_size_code == 0b0111 {
+        bw.write(n as u64 - 1, 16);
+    }
+    let crc = crc8(&bw.bytes);
+    bw.write(crc as u64, 8);
+
+    first.write(&mut bw);
+    second.write(&mut bw);
+    bw.align();
+    let crc = crc16(&bw.bytes);
+    bw.write(crc as u64, 16);
+    bw.bytes
+}
+
+/// FLAC's UTF-8-like coding of the frame number
+fn write_utf8_number(bw: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        bw.write(value, 8);
+        return;
+    }
+    let bytes = match value {
+        0..=0x7FF => 2,
+        0x800..=0xFFFF => 3,
+        0x1_0000..=0x1F_FFFF => 4,
+        0x20_0000..=0x3FF_FFFF => 5,
+        0x400_0000..=0x7FFF_FFFF => 6,
+        _ => 7,
+    };
+    let prefix = (0xFF00u64 >> bytes) & 0xFF;
+    bw.write(prefix | (value >> (6 * (bytes - 1))), 8);
+    for i in (0..bytes - 1).rev() {
+        bw.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+/// A channel's coding within a frame
+enum SubframeKind {
+    Constant,
+    Verbatim,
+    /// Fixed predictor order, Rice partition order and parameters
+    Fixed {
+        order: usize,
+        partition_order: u32,
+        params: Vec<u32>,
+    },
+}
+
+/// A coded channel with its size in bits
+struct Subframe<'a> {
+    samples: &'a [i64],
+    bps: u32,
+    kind: SubframeKind,
+    bits: u64,
+}
+
+impl<'a> Subframe<'a> {
+    const MAX_ORDER: usize = 4;
+    const MAX_PARTITION_ORDER: u32 = 8;
+    /// Rice parameters above this need the 5-bit parameter coding
+    const MAX_RICE4_PARAM: u32 = 14;
+
+    /// Smallest of constant, verbatim and the fixed predictors
+    fn best(samples: &'a [i64], bps: u32) -> Self {
+        let header = 8;
+        if samples.iter().all(|&s| s == samples[0]) {
+            return Self {
+                samples,
+                bps,
+                kind: SubframeKind::Constant,
+                bits: header + bps as u64,
+            };
+        }
+        let mut best = Self {
+            samples,
+            bps,
+            kind: SubframeKind::Verbatim,
+            bits: header + bps as u64 * samples.len() as u64,
+        };
+        for order in 0..=Self::MAX_ORDER.min(samples.len() - 1) {
+            let residual = fixed_residual(samples, order);
+            let (partition_order, params, residual_bits) =
+                best_rice_partitioning(&residual, samples.len(), order, Self::MAX_PARTITION_ORDER);
+            let param_bits = if params.iter().any(|&k| k > Self::MAX_RICE4_PARAM) {
+                5
+            } else {
+                4
+            };
+            let bits = header
+                + bps as u64 * order as u64
+                + 6
+                + param_bits * params.len() as u64
+                + residual_bits;
+            if bits < best.bits {
+                best = Self {
+                    samples,
+                    bps,
+                    kind: SubframeKind::Fixed {
+                        order,
+                        partition_order,
+                        params,
+                    },
+                    bits,
+                };
+            }
+        }
+        best
+    }
+
+    fn write(&self, bw: &mut BitWriter) {
+        match &self.kind {
+            SubframeKind::Constant => {
+                bw.write(0b0000_0000, 8);
+                bw.write_signed(self.samples[0], self.bps);
+            }
+            SubframeKind::Verbatim => {
+                bw.write(0b0000_0010, 8);
+                for &s in self.samples {
+                    bw.write_signed(s, self.bps);
+                }
+            }
+            SubframeKind::Fixed {
+                order,
+                partition_order,
+                params,
+            } => {
+                bw.write(0b0001_0000 | ((*order as u64) << 1), 8);
+                for &s in &self.samples[..*order] {
+                    bw.write_signed(s, self.bps);
+                }
+                let rice2 = params.iter().any(|&k| k > Self::MAX_RICE4_PARAM);
+                bw.write(rice2 as u64, 2);
+                bw.write(*partition_order as u64, 4);
+                let param_bits = if rice2 { 5 } else { 4 };
+
+                let residual = fixed_residual(self.samples, *order);
+                let partition_len = self.samples.len() >> partition_order;
+                let mut start = 0;
+                for (p, &k) in params.iter().enumerate() {
+                    let end = (p + 1) * partition_len - order;
+                    bw.write(k as u64, param_bits);
+                    for &r in &residual[start..end] {
+                        let u = zigzag(r);
+                        bw.write_unary(u >> k);
+                        bw.write(u, k);
+                    }
+                    start = end;
+                }
+            }
+        }
+    }
+}
+
+/// Residual of the fixed predictor of `order` (after the warm-up samples)
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    let s = samples;
+    (order..s.len())
+        .map(|i| match order {
+            0 => s[i],
+            1 => s[i] - s[i - 1],
+            2 => s[i] - 2 * s[i - 1] + s[i - 2],
+            3 => s[i] - 3 * s[i - 1] + 3 * s[i - 2] - s[i - 3],
+            _ => s[i] - 4 * s[i - 1] + 6 * s[i - 2] - 4 * s[i - 3] + s[i - 4],
+        })
+        .collect()
+}
+
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Best Rice partition order for a residual, with each partition's
+/// parameter and the residual's size in bits
+fn best_rice_partitioning(
+    residual: &[i64],
+    block_size: usize,
+    order: usize,
+    max_partition_order: u32,
+) -> (u32, Vec<u32>, u64) {
+    let mut best: Option<(u32, Vec<u32>, u64)> = None;
+    for partition_order in 0..=max_partition_order {
+        let partitions = 1usize << partition_order;
+        // Partitions (a power of two) must split the block evenly
+        if block_size & (partitions - 1) != 0 || block_size / partitions <= order {
+            break;
+        }
+        let partition_len = block_size / partitions;
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 0;
+        let mut start = 0;
+        for p in 0..partitions {
+            let end = (p + 1) * partition_len - order;
+            let part = &residual[start..end];
+            let sum: u64 = part.iter().map(|&r| zigzag(r)).sum();
+            let (k, part_bits) = rice_parameter(sum, part.len() as u64);
+            params.push(k);
+            bits += part_bits;
+            start = end;
+        }
+        match best {
+            Some((_, _, best_bits)) if best_bits <= bits => {}
+            _ => best = Some((partition_order, params, bits)),
+        }
+    }
+    best.unwrap_or((0, vec![0], 0))
+}
+
+/// Rice parameter for a partition from the sum of its zigzagged residuals,
+/// with the estimated size in bits
+fn rice_parameter(sum: u64, count: u64) -> (u32, u64) {
+    const MAX_PARAM: u32 = 30;
+    (0..=MAX_PARAM)
+        .map(|k| (k, count * (k as u64 + 1) + (sum >> k)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+/// CRC-8 (polynomial 0x07) of a frame header
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16 (polynomial 0x8005) of a whole frame
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    /// A few seconds of stereo test signal: a tone, silence and noise
+    fn test_signal(frames: usize) -> Vec<f32> {
+        let mut seed = 1u32;
+        (0..frames)
+            .flat_map(|i| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1 << 24) as f32 - 0.5;
+                let tone = (i as f32 * 0.03).sin() * 0.5;
+                match i / 5000 {
+                    0 => [tone, tone * 0.5],
+                    1 => [0.0, 0.0],
+                    _ => [tone + noise * 0.1, noise],
+                }
+            })
+            .collect()
+    }
+
+    /// Decode a file with symphonia to 24-bit samples
+    fn decode(bytes: Vec<u8>, extension: &str) -> (u32, Vec<i32>) {
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap()
+            .format;
+        let track = format.default_track().unwrap();
+        let sample_rate = track.codec_params.sample_rate.unwrap();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .unwrap();
+        let mut samples = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buf = SampleBuffer::<i32>::new(decoded.capacity() as u64, *decoded.spec());
+            buf.copy_interleaved_ref(decoded);
+            samples.extend(buf.samples().iter().map(|&s| s >> 8));
+        }
+        (sample_rate, samples)
+    }
+
+    #[test]
+    fn test_wav_roundtrip() {
+        let signal = test_signal(12000);
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), 48000).unwrap();
+        writer.write(&signal).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let (sample_rate, decoded) = decode(bytes, "wav");
+        assert_eq!(sample_rate, 48000);
+        let expected: Vec<i32> = signal.iter().map(|&s| to_pcm24(s)).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_flac_roundtrip_is_lossless() {
+        // Several full blocks and a short last one
+        let signal = test_signal(15000);
+        let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 44100).unwrap();
+        for chunk in signal.chunks(1000) {
+            writer.write(chunk).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(bytes.len() < signal.len() * 3, "no compression");
+
+        let (sample_rate, decoded) = decode(bytes, "flac");
+        assert_eq!(sample_rate, 44100);
+        let expected: Vec<i32> = signal.iter().map(|&s| to_pcm24(s)).collect();
+        assert_eq!(decoded.len(), expected.len());
+        assert!(decoded == expected, "FLAC decode differs");
+    }
+
+    #[test]
+    fn test_tracklist_needs_a_steady_lead() {
+        let sr = 1000;
+        let (entries_tx, mut entries_rx) = HeapRb::new(Tracklist::CAPACITY).split();
+        let mut tracklist = Tracklist::new(entries_tx);
+        let mut frame = 0;
+        let mut run = |tracklist: &mut Tracklist, leader, secs: u64| {
+            for _ in 0..secs * 10 {
+                tracklist.follow(leader, frame, sr);
+                frame += 100;
+            }
+        };
+        run(&mut tracklist, Some((0, "Intro")), 20);
+        // A quick cut to the other deck doesn't count
+        run(&mut tracklist, Some((1, "Next")), 2);
+        run(&mut tracklist, Some((0, "Intro")), 10);
+        run(&mut tracklist, Some((1, "Next")), 30);
+        tracklist.confirm_candidate();
+        let mut entries = Vec::new();
+        collect_tracklist(&mut entries_rx, sr, &mut entries);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Intro");
+        assert_eq!(entries[0].start_secs, 0.0);
+        assert_eq!(entries[1].title, "Next");
+        assert_eq!(entries[1].start_secs, 32.0);
+    }
+
+    #[test]
+    fn test_cue_sheet_format() {
+        let entries = [
+            TrackEntry {
+                start_secs: 0.0,
+                title: "First".into(),
+            },
+            TrackEntry {
+                start_secs: 125.4,
+                title: "Say \"Hi\"".into(),
+            },
+        ];
+        let sheet = cue_sheet("mix.flac", &entries);
+        assert!(sheet.contains("FILE \"mix.flac\" WAVE\n"));
+        assert!(sheet.contains("  TRACK 01 AUDIO\n    TITLE \"First\"\n    INDEX 01 00:00:00\n"));
+        assert!(sheet.contains("  TRACK 02 AUDIO\n    TITLE \"Say 'Hi'\"\n    INDEX 01 02:05:30\n"));
+    }
+}
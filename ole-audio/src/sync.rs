@@ -0,0 +1,167 @@
+//! Sync lock - followers keep tempo and beat phase locked to a leader
+//!
+//! The leader is a sync-locked deck or the internal master clock. The
+//! clock tracks the leading deck while one plays, so handing the lead to
+//! it (e.g. when the leading deck stops) keeps the beat going. Followers
+//! lock to the leader's bars, not just its beats.
+
+use crate::deck::DeckId;
+use ole_analysis::BeatGrid;
+
+/// A deck's part in sync lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncRole {
+    /// Not sync-locked
+    #[default]
+    Off,
+    /// Sets tempo and phase for the followers
+    Leader,
+    /// Locked to the leader
+    Follower,
+}
+
+impl SyncRole {
+    /// Get display name for the role
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SyncRole::Off => "",
+            SyncRole::Leader => "LEAD",
+            SyncRole::Follower => "SYNC",
+        }
+    }
+}
+
+/// Tempo leader for sync lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncLeader {
+    Deck(DeckId),
+    #[default]
+    Clock,
+}
+
+impl SyncLeader {
+    /// Get display name for the leader
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SyncLeader::Deck(deck) => deck.name(),
+            SyncLeader::Clock => "CLOCK",
+        }
+    }
+}
+
+/// Internal master clock
+#[derive(Debug, Clone, Copy)]
+pub struct MasterClock {
+    bpm: f32,
+    /// Position within the bar in beats (0.0 - 4.0)
+    beats: f64,
+}
+
+impl MasterClock {
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            bpm: bpm.clamp(Self::MIN_BPM, Self::MAX_BPM),
+            beats: 0.0,
+        }
+    }
+
+    const MIN_BPM: f32 = 40.0;
+    const MAX_BPM: f32 = 300.0;
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    pub fn set_bpm(&mut self, bpm: f32) {
+        self.bpm = bpm.clamp(Self::MIN_BPM, Self::MAX_BPM);
+    }
+
+    /// Position within the beat (0.0 - 1.0)
+    pub fn phase(&self) -> f32 {
+        self.beats.fract() as f32
+    }
+
+    /// Position within the bar (0.0 - 1.0)
+    pub fn bar_phase(&self) -> f32 {
+        (self.beats / Self::BEATS_PER_BAR) as f32
+    }
+
+    const BEATS_PER_BAR: f64 = BeatGrid::BEATS_PER_BAR as f64;
+
+    /// Run the clock for `frames` output frames
+    pub fn advance(&mut self, frames: usize, sample_rate: u32) {
+        let beats = frames as f64 * self.bpm as f64 / 60.0 / sample_rate as f64;
+        self.beats = (self.beats + beats).rem_euclid(Self::BEATS_PER_BAR);
+    }
+
+    /// Take over tempo and bar phase from the leading deck
+    pub fn follow(&mut self, bpm: f32, bar_phase: f32) {
+        self.set_bpm(bpm);
+        self.beats = bar_phase as f64 * Self::BEATS_PER_BAR;
+    }
+}
+
+impl Default for MasterClock {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+/// Phase error of `phase` against the leader's, in beats (-0.5 - 0.5)
+///
+/// Positive when ahead of the leader.
+pub fn phase_error(phase: f32, leader_phase: f32) -> f32 {
+    let error = (phase - leader_phase).rem_euclid(1.0);
+    if error >= 0.5 {
+        error - 1.0
+    } else {
+        error
+    }
+}
+
+/// Tempo multiplier that slowly pulls a phase error back to zero
+///
+/// Bends the tempo so the error decays over about a second, by at most
+/// 1% (a DJ riding the pitch fader rather than an audible jump).
+pub fn phase_correction(error_beats: f32, bpm: f32) -> f32 {
+    const DECAY_SECS: f32 = 1.0;
+    const MAX_BEND: f32 = 0.01;
+    if bpm <= 0.0 {
+        return 1.0;
+    }
+    let beats_per_sec = bpm / 60.0;
+    let bend = error_beats / DECAY_SECS / beats_per_sec;
+    1.0 - bend.clamp(-MAX_BEND, MAX_BEND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_error_takes_shortest_way() {
+        assert!((phase_error(0.1, 0.0) - 0.1).abs() < 1e-6);
+        assert!((phase_error(0.0, 0.1) + 0.1).abs() < 1e-6);
+        assert!((phase_error(0.95, 0.05) + 0.1).abs() < 1e-6);
+        assert!((phase_error(0.05, 0.95) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_phase_correction_slows_deck_ahead() {
+        assert!(phase_correction(0.05, 120.0) < 1.0);
+        assert!(phase_correction(-0.05, 120.0) > 1.0);
+        assert_eq!(phase_correction(0.0, 120.0), 1.0);
+        assert!((phase_correction(0.4, 120.0) - 0.99).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_master_clock_wraps_each_beat() {
+        // 120 BPM: a beat every 22050 frames at 44.1kHz
+        let mut clock = MasterClock::new(120.0);
+        clock.advance(11025, 44100);
+        assert!((clock.phase() - 0.5).abs() < 1e-6);
+        clock.advance(11025, 44100);
+        assert!(clock.phase() < 1e-6);
+        assert!((clock.bar_phase() - 0.25).abs() < 1e-6);
+    }
+}
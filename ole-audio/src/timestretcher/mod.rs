@@ -47,17 +47,28 @@ mod stft;
 pub use phase::{PhaseLockMode, PhaseVocoder, TimeStretchParams};
 pub use stft::{Complex, FftSize, Stft};
 
-/// Pitch shift without tempo change (future feature)
-/// Uses phase vocoder + resampling
+/// Pitch shift without tempo change
+///
+/// The phase vocoder stretches time by the pitch ratio, then the result is
+/// resampled by the same ratio: duration is restored and pitch moves. An
+/// extra stretch ratio can be stacked on top, so a deck can change tempo and
+/// key independently through a single vocoder.
+///
+/// Meant to be driven pull-style: `pop` output frames and `push` one input
+/// frame whenever `pop` returns None.
 pub struct PitchShifter {
     vocoder: PhaseVocoder,
     /// Pitch shift in semitones
     semitones: f32,
-    /// Resampling ratio to compensate for time stretch
+    /// Time stretch applied on top of the pitch compensation (1.0 = none)
+    stretch_ratio: f32,
+    /// Resampling ratio (vocoder output frames per output frame)
     resample_ratio: f32,
-    /// Resampling filter state
-    resample_state_l: f32,
-    resample_state_r: f32,
+    /// Fractional read position between the two buffered vocoder frames
+    resample_pos: f32,
+    /// Resampling interpolation state (previous and next vocoder frames)
+    resample_prev: (f32, f32),
+    resample_next: (f32, f32),
 }
 
 impl Default for PitchShifter {
@@ -72,9 +83,11 @@ impl PitchShifter {
         Self {
             vocoder: PhaseVocoder::new(FftSize::Medium),
             semitones: 0.0,
+            stretch_ratio: 1.0,
             resample_ratio: 1.0,
-            resample_state_l: 0.0,
-            resample_state_r: 0.0,
+            resample_pos: 1.0,
+            resample_prev: (0.0, 0.0),
+            resample_next: (0.0, 0.0),
         }
     }
 
@@ -84,9 +97,8 @@ impl PitchShifter {
 
         // Calculate ratio: 2^(semitones/12)
         // Positive semitones = higher pitch = faster playback = stretch to compensate
-        let pitch_ratio = 2.0f32.powf(self.semitones / 12.0);
-        self.resample_ratio = pitch_ratio;
-        self.vocoder.set_stretch_ratio(pitch_ratio);
+        self.resample_ratio = 2.0f32.powf(self.semitones / 12.0);
+        self.update_vocoder_ratio();
     }
 
     /// Get current pitch shift
@@ -94,18 +106,66 @@ impl PitchShifter {
         self.semitones
     }
 
-    /// Process sample (simplified - full resampling would need interpolation)
+    /// Set additional time stretch ratio (> 1.0 = slower, same pitch)
+    pub fn set_stretch_ratio(&mut self, ratio: f32) {
+        self.stretch_ratio = ratio;
+        self.update_vocoder_ratio();
+    }
+
+    /// Get additional time stretch ratio
+    pub fn stretch_ratio(&self) -> f32 {
+        self.stretch_ratio
+    }
+
+    fn update_vocoder_ratio(&mut self) {
+        self.vocoder
+            .set_stretch_ratio(self.stretch_ratio * self.resample_ratio);
+    }
+
+    /// Processing latency in input samples
+    pub fn latency(&self) -> f32 {
+        // Vocoder latency plus, on average, half a vocoder output frame
+        // held back by the resampler
+        let vocoder_ratio = self.stretch_ratio * self.resample_ratio;
+        self.vocoder.latency() + 0.5 / vocoder_ratio
+    }
+
+    /// Feed one stereo input sample
+    #[inline]
+    pub fn push(&mut self, left: f32, right: f32) {
+        self.vocoder.push(left, right);
+    }
+
+    /// Pop one output sample, or None if more input is needed
+    pub fn pop(&mut self) -> Option<(f32, f32)> {
+        while self.resample_pos >= 1.0 {
+            let next = self.vocoder.pop()?;
+            self.resample_prev = self.resample_next;
+            self.resample_next = next;
+            self.resample_pos -= 1.0;
+        }
+
+        // Linear interpolation between the buffered vocoder frames
+        let t = self.resample_pos;
+        let (l0, r0) = self.resample_prev;
+        let (l1, r1) = self.resample_next;
+        self.resample_pos += self.resample_ratio;
+
+        Some((l0 + t * (l1 - l0), r0 + t * (r1 - r0)))
+    }
+
+    /// Process sample (push one input, pop at most one output)
     pub fn process(&mut self, left: f32, right: f32) -> Option<(f32, f32)> {
-        // For now, just delegate to vocoder
-        // Full implementation would add variable-rate resampling here
-        self.vocoder.process(left, right)
+        self.push(left, right);
+        self.pop()
     }
 
     /// Reset state
     pub fn reset(&mut self) {
         self.vocoder.reset();
-        self.resample_state_l = 0.0;
-        self.resample_state_r = 0.0;
+        self.resample_pos = 1.0;
+        self.resample_prev = (0.0, 0.0);
+        self.resample_next = (0.0, 0.0);
     }
 }
 
@@ -126,4 +186,49 @@ mod tests {
         ps.set_semitones(3.0);
         assert_eq!(ps.semitones(), 3.0);
     }
+
+    #[test]
+    fn test_pitch_shifter_octave_up_keeps_duration() {
+        use std::f32::consts::PI;
+
+        let mut ps = PitchShifter::new();
+        ps.set_semitones(12.0);
+
+        let mut pushed = 0usize;
+        let mut pushed_halfway = 0usize;
+        let mut output = Vec::new();
+        while output.len() < 48000 {
+            match ps.pop() {
+                Some((l, _)) => {
+                    output.push(l);
+                    if output.len() == 24000 {
+                        pushed_halfway = pushed;
+                    }
+                }
+                None => {
+                    let t = pushed as f32 / 48000.0;
+                    let s = (2.0 * PI * 220.0 * t).sin() * 0.5;
+                    ps.push(s, s);
+                    pushed += 1;
+                }
+            }
+        }
+
+        // Input is consumed at the same rate output is produced
+        let consumed = (pushed - pushed_halfway) as f32;
+        assert!(
+            (consumed / 24000.0 - 1.0).abs() < 0.02,
+            "consumed {}",
+            consumed
+        );
+
+        // Pitch doubled: 220 Hz in, 440 Hz out
+        let tail = &output[output.len() / 2..];
+        let crossings = tail
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        let freq = crossings as f32 / 2.0 / (tail.len() as f32 / 48000.0);
+        assert!((freq / 440.0 - 1.0).abs() < 0.02, "frequency {}", freq);
+    }
 }
@@ -49,6 +49,8 @@ pub struct PhaseVocoder {
     transient_threshold: f32,
     /// Whether transient was detected in last frame
     transient_detected: bool,
+    /// Seed phase accumulators from the next analysis frame (after new/reset)
+    phase_reset_pending: bool,
     /// Phase lock mode
     phase_lock_mode: PhaseLockMode,
     /// Enabled state
@@ -104,6 +106,7 @@ impl PhaseVocoder {
             peaks: Vec::with_capacity(num_bins / 4),
             transient_threshold: 1.5,
             transient_detected: false,
+            phase_reset_pending: true,
             phase_lock_mode: PhaseLockMode::PeakLocked,
             enabled: true,
             samples_since_frame: 0,
@@ -158,6 +161,16 @@ impl PhaseVocoder {
             return Some((left, right));
         }
 
+        self.push(left, right);
+
+        // Pop output sample
+        self.stft.pop_sample()
+    }
+
+    /// Feed one stereo input sample without popping output
+    ///
+    /// Runs the analysis/synthesis step whenever a hop of input is complete.
+    pub fn push(&mut self, left: f32, right: f32) {
         // Feed input to STFT
         let frame_ready = self.stft.push_samples(left, right);
 
@@ -183,11 +196,29 @@ impl PhaseVocoder {
                 self.prev_mag_r[k] = self.bins_r[k].magnitude();
             }
         }
+    }
 
-        // Pop output sample
+    /// Pop an already synthesized output sample without feeding new input
+    ///
+    /// Lets callers pull output at their own rate: drain with `pop` and only
+    /// `push` more input once it returns None.
+    #[inline]
+    pub fn pop(&mut self) -> Option<(f32, f32)> {
         self.stft.pop_sample()
     }
 
+    /// Average processing latency in input samples when driven pull-style
+    ///
+    /// Each frame is overlap-added at the current read point of the output,
+    /// so its centre is heard half a (stretched) frame later; add the half
+    /// frame of input still ahead of the centre, minus half a hop because
+    /// input is pushed one hop at a time. One frame minus half a hop at 1.0.
+    #[inline]
+    pub fn latency(&self) -> f32 {
+        let half_frame = self.stft.size() as f32 * 0.5;
+        half_frame + half_frame / self.stretch_ratio - self.hop_size as f32 * 0.5
+    }
+
     /// Core phase processing algorithm
     fn process_phase(&mut self) {
         // Find spectral peaks for phase locking
@@ -268,51 +299,50 @@ impl PhaseVocoder {
     }
 
     /// Apply phase locking around spectral peaks
+    ///
+    /// Bins around a peak are rotated with the peak, keeping the phase
+    /// offset they had in the analysis frame. Locking them to the peak's
+    /// phase directly would drop the alternating signs of the window's
+    /// main lobe and partially cancel the sinusoid.
     fn apply_peak_phase_lock(&mut self) {
-        for &peak in &self.peaks {
-            // Lock nearby bins to peak's phase
-            let peak_phase_l = self.out_bins_l[peak].phase();
-            let peak_phase_r = self.out_bins_r[peak].phase();
+        for i in 0..self.peaks.len() {
+            let peak = self.peaks[i];
 
             // Influence radius based on peak strength
             let peak_mag = self.bins_l[peak].magnitude() + self.bins_r[peak].magnitude();
             let radius = ((peak_mag * 10.0) as usize).clamp(1, 5);
 
             for j in 1..=radius {
+                let weight = 1.0 - j as f32 / (radius + 1) as f32;
+
                 if peak >= j {
-                    let k = peak - j;
-                    let weight = 1.0 - j as f32 / (radius + 1) as f32;
-
-                    // Blend phase towards peak phase
-                    let mag_l = self.out_bins_l[k].magnitude();
-                    let phase_l = self.out_bins_l[k].phase();
-                    let blended_phase_l = Self::blend_phase(phase_l, peak_phase_l, weight);
-                    self.out_bins_l[k] = Complex::from_polar(mag_l, blended_phase_l);
-
-                    let mag_r = self.out_bins_r[k].magnitude();
-                    let phase_r = self.out_bins_r[k].phase();
-                    let blended_phase_r = Self::blend_phase(phase_r, peak_phase_r, weight);
-                    self.out_bins_r[k] = Complex::from_polar(mag_r, blended_phase_r);
+                    self.lock_bin_to_peak(peak - j, peak, weight);
                 }
-
                 if peak + j < self.num_bins {
-                    let k = peak + j;
-                    let weight = 1.0 - j as f32 / (radius + 1) as f32;
-
-                    let mag_l = self.out_bins_l[k].magnitude();
-                    let phase_l = self.out_bins_l[k].phase();
-                    let blended_phase_l = Self::blend_phase(phase_l, peak_phase_l, weight);
-                    self.out_bins_l[k] = Complex::from_polar(mag_l, blended_phase_l);
-
-                    let mag_r = self.out_bins_r[k].magnitude();
-                    let phase_r = self.out_bins_r[k].phase();
-                    let blended_phase_r = Self::blend_phase(phase_r, peak_phase_r, weight);
-                    self.out_bins_r[k] = Complex::from_polar(mag_r, blended_phase_r);
+                    self.lock_bin_to_peak(peak + j, peak, weight);
                 }
             }
         }
     }
 
+    /// Blend bin `k` towards the phase it would have if it rotated with `peak`
+    #[inline]
+    fn lock_bin_to_peak(&mut self, k: usize, peak: usize, weight: f32) {
+        let target_l =
+            self.out_bins_l[peak].phase() + (self.bins_l[k].phase() - self.bins_l[peak].phase());
+        let mag_l = self.out_bins_l[k].magnitude();
+        let phase_l = self.out_bins_l[k].phase();
+        let blended_phase_l = Self::blend_phase(phase_l, target_l, weight);
+        self.out_bins_l[k] = Complex::from_polar(mag_l, blended_phase_l);
+
+        let target_r =
+            self.out_bins_r[peak].phase() + (self.bins_r[k].phase() - self.bins_r[peak].phase());
+        let mag_r = self.out_bins_r[k].magnitude();
+        let phase_r = self.out_bins_r[k].phase();
+        let blended_phase_r = Self::blend_phase(phase_r, target_r, weight);
+        self.out_bins_r[k] = Complex::from_polar(mag_r, blended_phase_r);
+    }
+
     /// Detect transients using spectral flux
     fn detect_transients(&mut self) {
         let mut flux_l = 0.0f32;
@@ -342,8 +372,13 @@ impl PhaseVocoder {
         let total = (total_l + total_r).sqrt();
         let flux = (flux_l + flux_r).sqrt();
 
-        // Transient if flux exceeds threshold relative to total energy
-        self.transient_detected = total > 0.001 && flux / total > self.transient_threshold;
+        // Transient if flux exceeds threshold relative to total energy.
+        // The first frame after a reset is treated the same way so the
+        // accumulators start from the analysed phases instead of zero,
+        // which would otherwise break the phase relation between bins.
+        self.transient_detected =
+            self.phase_reset_pending || (total > 0.001 && flux / total > self.transient_threshold);
+        self.phase_reset_pending = false;
 
         // Reset phase accumulators on transient
         if self.transient_detected {
@@ -392,6 +427,7 @@ impl PhaseVocoder {
         self.fractional_pos = 0.0;
         self.samples_since_frame = 0;
         self.transient_detected = false;
+        self.phase_reset_pending = true;
     }
 }
 
@@ -435,7 +471,7 @@ mod tests {
             let t = i as f32 / 48000.0;
             let sample = (2.0 * PI * 440.0 * t).sin() * 0.5;
 
-            if let Some(_) = pv.process(sample, sample) {
+            if pv.process(sample, sample).is_some() {
                 output_count += 1;
             }
         }
@@ -444,6 +480,59 @@ mod tests {
         assert!(output_count > 0);
     }
 
+    #[test]
+    fn test_gain_and_pitch_preserved_across_ratios() {
+        for ratio in [1.0, 1.5, 0.75, 0.5] {
+            let mut pv = PhaseVocoder::new(FftSize::Small);
+            pv.set_stretch_ratio(ratio);
+
+            let mut input_pos = 0usize;
+            let mut outputs = Vec::new();
+
+            // Pull model: drain output, feed input only when none is ready
+            while outputs.len() < 16384 {
+                let out = match pv.pop() {
+                    Some(out) => out,
+                    None => {
+                        let t = input_pos as f32 / 48000.0;
+                        let sample = (2.0 * PI * 440.0 * t).sin() * 0.5;
+                        input_pos += 1;
+                        match pv.process(sample, sample) {
+                            Some(out) => out,
+                            None => continue,
+                        }
+                    }
+                };
+                outputs.push(out.0);
+            }
+
+            // Skip the latency warm-up, then compare RMS to the input sine
+            let tail = &outputs[4096..];
+            let rms = (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt();
+            let expected = 0.5 / 2.0f32.sqrt();
+            assert!(
+                (rms / expected - 1.0).abs() < 0.2,
+                "ratio {}: rms {} expected {}",
+                ratio,
+                rms,
+                expected
+            );
+
+            // Pitch must not follow the ratio: 440 Hz has 880 zero crossings/s
+            let crossings = tail
+                .windows(2)
+                .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+                .count();
+            let freq = crossings as f32 / 2.0 / (tail.len() as f32 / 48000.0);
+            assert!(
+                (freq / 440.0 - 1.0).abs() < 0.02,
+                "ratio {}: frequency {}",
+                ratio,
+                freq
+            );
+        }
+    }
+
     #[test]
     fn test_stretch_ratio_clamping() {
         let mut pv = PhaseVocoder::new(FftSize::Medium);
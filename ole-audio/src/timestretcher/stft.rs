@@ -164,6 +164,10 @@ pub struct Stft {
     work: Vec<Complex>,
     /// Normalization factor for IFFT
     norm_factor: f32,
+    /// Sum of squared window values (analysis * synthesis window energy)
+    window_power: f32,
+    /// Fractional output hop carried over between frames
+    hop_remainder: f32,
 }
 
 impl Stft {
@@ -181,6 +185,9 @@ impl Stft {
             })
             .collect();
 
+        // Overlap-add of two Hann windows sums to window_power / hop per sample
+        let window_power: f32 = window.iter().map(|w| w * w).sum();
+
         // Pre-compute twiddle factors
         let twiddles = Self::compute_twiddles(size, false);
         let twiddles_inv = Self::compute_twiddles(size, true);
@@ -207,6 +214,8 @@ impl Stft {
             output_available: 0,
             work: vec![Complex::default(); size],
             norm_factor: 1.0 / size as f32,
+            window_power,
+            hop_remainder: 0.0,
         }
     }
 
@@ -298,19 +307,27 @@ impl Stft {
         debug_assert!(input_l.len() >= self.num_bins());
         debug_assert!(input_r.len() >= self.num_bins());
 
+        // Frames overlap more (or less) as the output hop changes, so scale
+        // each frame to keep unity gain regardless of stretch ratio
+        let exact_hop = self.hop_size as f32 * time_stretch;
+        let gain = self.norm_factor * exact_hop / self.window_power;
+
         // Synthesize left channel
-        self.synthesize_channel(input_l, true);
+        self.synthesize_channel(input_l, true, gain);
 
         // Synthesize right channel
-        self.synthesize_channel(input_r, false);
-
-        // Advance output position based on time stretch
-        let output_hop = (self.hop_size as f32 * time_stretch) as usize;
-        self.output_available += output_hop.max(1);
+        self.synthesize_channel(input_r, false, gain);
+
+        // Advance output position based on time stretch, carrying the
+        // fractional part so the average hop matches the requested ratio
+        let hop = exact_hop + self.hop_remainder;
+        let output_hop = (hop as usize).max(1);
+        self.hop_remainder = hop - output_hop as f32;
+        self.output_available += output_hop;
     }
 
     /// Synthesize single channel
-    fn synthesize_channel(&mut self, input: &[Complex], is_left: bool) {
+    fn synthesize_channel(&mut self, input: &[Complex], is_left: bool, gain: f32) {
         // Reconstruct full spectrum from positive frequencies (Hermitian symmetry)
         let num_bins = self.num_bins();
         self.work[..num_bins].copy_from_slice(&input[..num_bins]);
@@ -331,7 +348,7 @@ impl Stft {
         let out_len = output_buf.len();
         for i in 0..self.size {
             let idx = (self.output_pos + i) % out_len;
-            output_buf[idx] += self.work[i].re * self.window[i] * self.norm_factor;
+            output_buf[idx] += self.work[i].re * self.window[i] * gain;
         }
     }
 
@@ -404,6 +421,7 @@ impl Stft {
         self.input_pos = 0;
         self.output_pos = 0;
         self.output_available = 0;
+        self.hop_remainder = 0.0;
     }
 }
 
@@ -0,0 +1,162 @@
+//! Triple buffer - lock-free latest-value handoff between two threads
+//!
+//! The writer always has a slot to write into and the reader always has a
+//! slot to read from; the third slot is swapped between them with a single
+//! atomic. Neither side ever waits, and the reader only sees the newest
+//! value (older ones are overwritten, not queued). Used to publish engine
+//! state from the audio callback.
+
+use std::cell::{Cell, UnsafeCell};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Set in the back slot index when it holds a value the reader hasn't taken
+const FRESH: u8 = 0b100;
+const INDEX: u8 = 0b011;
+
+struct Shared<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// Index of the slot between writer and reader, with the FRESH flag
+    back: AtomicU8,
+}
+
+// Each slot is only ever accessed by the side that currently owns its index
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+/// Create a triple buffer starting out with `initial` in every slot
+pub fn triple_buffer<T: Clone + Send>(initial: T) -> (Input<T>, Output<T>) {
+    let shared = Arc::new(Shared {
+        slots: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial),
+        ],
+        back: AtomicU8::new(1),
+    });
+    (
+        Input {
+            shared: shared.clone(),
+            index: 0,
+        },
+        Output {
+            shared,
+            index: Cell::new(2),
+        },
+    )
+}
+
+/// Writing side of a triple buffer
+pub struct Input<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+impl<T: Send> Input<T> {
+    /// Publish a new value (never blocks)
+    pub fn write(&mut self, value: T) {
+        // Safety: the writer owns `index` until it is swapped out below
+        unsafe {
+            *self.shared.slots[self.index as usize].get() = value;
+        }
+        self.publish();
+    }
+
+    /// Publish a value updated in place (never blocks)
+    ///
+    /// `update` gets the writer's slot, which holds an older value (not
+    /// necessarily the last one written): it has to set all of it. Nothing
+    /// is dropped, so a value that owns memory can be refreshed without
+    /// allocating.
+    pub fn write_with(&mut self, update: impl FnOnce(&mut T)) {
+        // Safety: the writer owns `index` until it is swapped out below
+        unsafe {
+            update(&mut *self.shared.slots[self.index as usize].get());
+        }
+        self.publish();
+    }
+
+    /// Hand the writer's slot to the reader and take the back slot
+    fn publish(&mut self) {
+        let previous = self.shared.back.swap(self.index | FRESH, Ordering::AcqRel);
+        self.index = previous & INDEX;
+    }
+}
+
+/// Reading side of a triple buffer
+pub struct Output<T> {
+    shared: Arc<Shared<T>>,
+    index: Cell<u8>,
+}
+
+impl<T: Clone + Send> Output<T> {
+    /// Whether a value was published since the last read
+    pub fn updated(&self) -> bool {
+        self.shared.back.load(Ordering::Relaxed) & FRESH != 0
+    }
+
+    /// The newest published value (never blocks)
+    pub fn read(&self) -> T {
+        if self.updated() {
+            let previous = self.shared.back.swap(self.index.get(), Ordering::AcqRel);
+            self.index.set(previous & INDEX);
+        }
+        // Safety: the reader owns `index`, and no reference outlives this call
+        unsafe { (*self.shared.slots[self.index.get() as usize].get()).clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_sees_latest_value() {
+        let (mut input, output) = triple_buffer(0);
+        assert!(!output.updated());
+        assert_eq!(output.read(), 0);
+
+        input.write(1);
+        input.write(2);
+        assert!(output.updated());
+        assert_eq!(output.read(), 2);
+        assert!(!output.updated());
+        assert_eq!(output.read(), 2);
+
+        input.write(3);
+        assert_eq!(output.read(), 3);
+    }
+
+    #[test]
+    fn test_write_with_reuses_the_slot() {
+        let (mut input, output) = triple_buffer(vec![0u32; 8]);
+        for i in 1..=10 {
+            input.write_with(|slot| {
+                let capacity = slot.capacity();
+                slot.clear();
+                slot.extend([i; 8]);
+                // Filled in place: no new allocation
+                assert_eq!(slot.capacity(), capacity);
+            });
+        }
+        assert_eq!(output.read(), vec![10; 8]);
+    }
+
+    #[test]
+    fn test_values_arrive_whole_across_threads() {
+        // Each value is a run of equal numbers: a torn read would mix them
+        let (mut input, output) = triple_buffer(vec![0u32; 64]);
+        let writer = std::thread::spawn(move || {
+            for i in 1..=20_000u32 {
+                input.write(vec![i; 64]);
+            }
+        });
+        let mut last = 0;
+        while last < 20_000 {
+            let value = output.read();
+            assert!(value.iter().all(|&v| v == value[0]), "torn read");
+            assert!(value[0] >= last, "went back in time");
+            last = value[0];
+        }
+        writer.join().unwrap();
+    }
+}
@@ -16,6 +16,14 @@ pub use noise::VinylNoise;
 pub use warmth::{AnalogWarmth, SaturationType};
 pub use wow_flutter::WowFlutter;
 
+use crate::effects::{Effect, EffectParam};
+
+const PARAMS: &[EffectParam] = &[
+    EffectParam::new("wow", 0.0, 1.0),
+    EffectParam::new("noise", 0.0, 1.0),
+    EffectParam::new("warmth", 0.0, 1.0),
+];
+
 /// Complete vinyl emulation system
 ///
 /// Combines all vinyl effects into a cohesive simulation.
@@ -39,6 +47,9 @@ pub struct VinylEmulator {
     /// Current preset
     current_preset: VinylPreset,
 
+    /// Wow/flutter, noise and warmth amounts (0.0-1.0) last applied
+    amounts: [f32; 3],
+
     /// Wet envelope for click-free enable/disable
     wet_target: f32,
     wet_current: f32,
@@ -50,16 +61,20 @@ impl VinylEmulator {
 
     /// Create a new vinyl emulator
     pub fn new(sample_rate: f32) -> Self {
-        Self {
+        let mut vinyl = Self {
             enabled: false,
             motor: TurntableMotor::new(sample_rate),
             wow_flutter: WowFlutter::new(sample_rate),
             warmth: AnalogWarmth::new(sample_rate),
             noise: VinylNoise::new(sample_rate),
             current_preset: VinylPreset::default(),
+            amounts: [0.0; 3],
             wet_target: 0.0,
             wet_current: 0.0,
-        }
+        };
+        // Sound like the preset it reports
+        vinyl.set_preset(VinylPreset::default());
+        vinyl
     }
 
     /// Enable/disable all vinyl effects (with smooth crossfade)
@@ -98,6 +113,8 @@ impl VinylEmulator {
 
         // Scale noise
         self.noise.set_intensity(i * 0.5);
+
+        self.amounts = [i, i * 0.5, i * 0.5];
     }
 
     /// Get current preset (if any)
@@ -115,6 +132,7 @@ impl VinylEmulator {
                 self.warmth.set_drive(0.1);
                 self.warmth.set_riaa_amount(0.2);
                 self.noise.set_intensity(0.1);
+                self.amounts = [0.2, 0.1, 0.2];
             }
             VinylPreset::Warm => {
                 // Warm but clean
@@ -122,6 +140,7 @@ impl VinylEmulator {
                 self.warmth.set_drive(0.3);
                 self.warmth.set_riaa_amount(0.4);
                 self.noise.set_intensity(0.2);
+                self.amounts = [0.4, 0.2, 0.4];
             }
             VinylPreset::Vintage => {
                 // Classic vinyl sound
@@ -129,6 +148,7 @@ impl VinylEmulator {
                 self.warmth.set_drive(0.4);
                 self.warmth.set_riaa_amount(0.6);
                 self.noise.set_intensity(0.4);
+                self.amounts = [0.6, 0.4, 0.6];
             }
             VinylPreset::Worn => {
                 // Old, worn record
@@ -136,6 +156,7 @@ impl VinylEmulator {
                 self.warmth.set_drive(0.5);
                 self.warmth.set_riaa_amount(0.7);
                 self.noise.set_intensity(0.7);
+                self.amounts = [0.8, 0.7, 0.7];
             }
             VinylPreset::Extreme => {
                 // Maximum vinyl character
@@ -143,23 +164,27 @@ impl VinylEmulator {
                 self.warmth.set_drive(0.7);
                 self.warmth.set_riaa_amount(0.8);
                 self.noise.set_intensity(1.0);
+                self.amounts = [1.0, 1.0, 0.8];
             }
         }
     }
 
     /// Set wow/flutter amount (0.0-1.0)
     pub fn set_wow_amount(&mut self, amount: f32) {
-        self.wow_flutter.set_intensity(amount.clamp(0.0, 1.0));
+        self.amounts[0] = amount.clamp(0.0, 1.0);
+        self.wow_flutter.set_intensity(self.amounts[0]);
     }
 
     /// Set noise amount (0.0-1.0)
     pub fn set_noise_amount(&mut self, amount: f32) {
-        self.noise.set_intensity(amount.clamp(0.0, 1.0));
+        self.amounts[1] = amount.clamp(0.0, 1.0);
+        self.noise.set_intensity(self.amounts[1]);
     }
 
     /// Set warmth amount (0.0-1.0)
     pub fn set_warmth_amount(&mut self, amount: f32) {
         let a = amount.clamp(0.0, 1.0);
+        self.amounts[2] = a;
         self.warmth.set_drive(a * 0.5);
         self.warmth.set_riaa_amount(a);
     }
@@ -227,8 +252,6 @@ impl VinylEmulator {
     }
 
     /// Process audio samples (alias for process_audio)
-    ///
-    /// Implements the same interface as Effect trait for consistency.
     #[inline]
     pub fn process(&mut self, samples: &mut [f32]) {
         self.process_audio(samples);
@@ -245,6 +268,45 @@ impl VinylEmulator {
     }
 }
 
+impl Effect for VinylEmulator {
+    fn process(&mut self, samples: &mut [f32]) {
+        self.process_audio(samples);
+    }
+
+    fn reset(&mut self) {
+        VinylEmulator::reset(self);
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        VinylEmulator::set_enabled(self, enabled);
+    }
+
+    fn name(&self) -> &'static str {
+        "Vinyl"
+    }
+
+    fn params(&self) -> &'static [EffectParam] {
+        PARAMS
+    }
+
+    fn param(&self, index: usize) -> Option<f32> {
+        self.amounts.get(index).copied()
+    }
+
+    fn set_param(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_wow_amount(value),
+            1 => self.set_noise_amount(value),
+            2 => self.set_warmth_amount(value),
+            _ => {}
+        }
+    }
+}
+
 /// Vinyl emulation presets
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum VinylPreset {
@@ -4,15 +4,28 @@ use std::sync::Arc;
 use crossbeam_channel::Sender;
 use eframe::egui;
 
-use ole_audio::{AudioCommand, AudioEvent};
+use ole_audio::{
+    AudioCommand, AudioEvent, CrossfaderAssign, CrossfaderCurve, Interpolation, Mixer,
+    OutputSettings, UpfaderCurve, DECK_COUNT,
+};
 use ole_input::{Command, DeckId, Direction, EffectType};
-use ole_library::{AnalysisCache, Config, LibraryScanner, ScanConfig, ScanProgress, TrackLoader};
+use ole_library::{
+    AnalysisCache, Config, GridEdit, LibraryScanner, MixerConfig, ScanConfig, ScanProgress,
+    TrackLoader,
+};
 
 use crate::input::handle_keyboard;
 use crate::state::{FocusedPane, GuiState};
 use crate::theme::CyberTheme;
 use crate::widgets;
 
+/// The file loaded on a deck, for saving beat grid corrections
+struct DeckTrack {
+    path: PathBuf,
+    /// Grid edits already saved (None until the deck reports the new track)
+    saved_edits: Option<u32>,
+}
+
 pub struct OleApp {
     state: GuiState,
     cmd_tx: Sender<AudioCommand>,
@@ -22,6 +35,10 @@ pub struct OleApp {
     config: Config,
     scan_progress_rx: Option<crossbeam_channel::Receiver<ScanProgress>>,
     current_scan_folder: Option<PathBuf>,
+    /// Files loaded on the decks, indexed by DeckId
+    tracks: [Option<DeckTrack>; DECK_COUNT],
+    /// Engine has reported the mixer setup from the config (saving starts then)
+    mixer_config_synced: bool,
     theme_applied: bool,
 }
 
@@ -31,7 +48,10 @@ impl OleApp {
         event_rx: crossbeam_channel::Receiver<AudioEvent>,
     ) -> Self {
         let track_loader = TrackLoader::new();
-        let config = Config::load();
+        let mut config = Config::load();
+        config.mixer = apply_mixer_config(&config.mixer, &cmd_tx);
+        let interpolation = config.audio.interpolation.as_deref().and_then(Interpolation::parse);
+        let _ = cmd_tx.send(AudioCommand::SetInterpolation(interpolation.unwrap_or_default()));
 
         let cache_path = dirs::data_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -74,6 +94,8 @@ impl OleApp {
             config,
             scan_progress_rx: None,
             current_scan_folder: None,
+            tracks: Default::default(),
+            mixer_config_synced: false,
             theme_applied: false,
         }
     }
@@ -84,8 +106,94 @@ impl OleApp {
 
     fn drain_audio_events(&mut self) {
         while let Ok(event) = self.event_rx.try_recv() {
+            if let AudioEvent::OutputOpened(ref info) = event {
+                if info.engine_restarted && self.state.output.is_some() {
+                    // The engine starts over at the new rate: restore the saved mixer setup
+                    self.config.mixer = apply_mixer_config(&self.config.mixer, &self.cmd_tx);
+                    self.mixer_config_synced = false;
+                }
+                // Decode new tracks at the device rate (decks resample the rest)
+                self.track_loader = TrackLoader::with_sample_rate(info.sample_rate);
+            }
             self.state.handle_audio_event(event);
         }
+        for deck in DeckId::ALL {
+            self.save_grid_edits(deck);
+        }
+    }
+
+    /// Save a deck's beat grid to the library cache once it has been corrected
+    fn save_grid_edits(&mut self, deck: DeckId) {
+        let (track, deck_state) = (&mut self.tracks[deck.index()], self.state.deck(deck));
+        let (Some(track), Some(info)) = (track, &deck_state.beat_grid_info) else {
+            return;
+        };
+        match track.saved_edits {
+            // Still showing the previous track until the deck reports no edits
+            None if info.edits == 0 => track.saved_edits = Some(0),
+            Some(saved) if info.edits > saved => {
+                let Some((first_beat_secs, _, _)) = info.tempo_map.first().copied() else {
+                    return;
+                };
+                let edit = GridEdit {
+                    bpm: info.bpm,
+                    first_beat_secs,
+                    tempo_map: info.tempo_map.iter().map(|&(_, beat, bpm)| (beat, bpm)).collect(),
+                    downbeat: info.downbeat,
+                    phrases: info.phrases.to_vec(),
+                };
+                track.saved_edits = Some(info.edits);
+                if let Some(ref scanner) = self.scanner {
+                    if let Err(e) = scanner.store_grid_edit(&track.path, &edit) {
+                        self.state.set_error(format!("Failed to save beat grid: {}", e));
+                        return;
+                    }
+                }
+                let library = &mut self.state.library.tracks;
+                if let Some(cached) = library.iter_mut().find(|t| t.path == track.path) {
+                    cached.apply_grid_edit(&edit);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Save the audio setup and reopen the output with it
+    fn apply_audio_config(&mut self) {
+        if let Err(e) = self.config.save() {
+            self.state.set_error(format!("Failed to save config: {}", e));
+        }
+        let audio = &self.config.audio;
+        self.send_audio(AudioCommand::SetOutput(OutputSettings {
+            host: audio.host.clone(),
+            device: audio.device.clone(),
+            sample_rate: audio.sample_rate,
+            buffer_size: audio.buffer_size,
+        }));
+    }
+
+    /// Save the mixer setup to the config when it changes
+    fn save_mixer_config(&mut self) {
+        let state = &self.state;
+        let mixer = MixerConfig {
+            crossfader_curve: state.crossfader_curve.name().to_string(),
+            crossfader_sharpness: state.crossfader_sharpness,
+            hamster: state.hamster,
+            upfader_curve: state.upfader_curve.name().to_string(),
+            trims: state.channels.each_ref().map(|channel| channel.trim),
+            assigns: state.channels.each_ref().map(|channel| channel.assign.name().to_string()),
+        };
+        if !self.mixer_config_synced {
+            // Still showing the engine's defaults until it reports the loaded setup
+            self.mixer_config_synced = mixer == self.config.mixer;
+            return;
+        }
+        if mixer != self.config.mixer {
+            self.config.mixer = mixer;
+            if let Err(e) = self.config.save() {
+                self.state.set_error(format!("Failed to save config: {}", e));
+            }
+        }
     }
 
     fn process_scan_progress(&mut self) {
@@ -134,52 +242,97 @@ impl OleApp {
     pub fn handle_command(&mut self, cmd: Command) {
         match cmd {
             // Transport
-            Command::Play(DeckId::A) => self.send_audio(AudioCommand::PlayA),
-            Command::Play(DeckId::B) => self.send_audio(AudioCommand::PlayB),
-            Command::Pause(DeckId::A) => self.send_audio(AudioCommand::PauseA),
-            Command::Pause(DeckId::B) => self.send_audio(AudioCommand::PauseB),
-            Command::Stop(DeckId::A) => self.send_audio(AudioCommand::StopA),
-            Command::Stop(DeckId::B) => self.send_audio(AudioCommand::StopB),
-            Command::Toggle(DeckId::A) => self.send_audio(AudioCommand::ToggleA),
-            Command::Toggle(DeckId::B) => self.send_audio(AudioCommand::ToggleB),
+            Command::Play(deck) => self.send_audio(AudioCommand::Play(deck)),
+            Command::Pause(deck) => self.send_audio(AudioCommand::Pause(deck)),
+            Command::Stop(deck) => self.send_audio(AudioCommand::Stop(deck)),
+            Command::Toggle(deck) => self.send_audio(AudioCommand::Toggle(deck)),
 
             // Seeking
-            Command::Seek(DeckId::A, pos) => self.send_audio(AudioCommand::SeekA(pos)),
-            Command::Seek(DeckId::B, pos) => self.send_audio(AudioCommand::SeekB(pos)),
-            Command::Nudge(DeckId::A, d) => self.send_audio(AudioCommand::NudgeA(d)),
-            Command::Nudge(DeckId::B, d) => self.send_audio(AudioCommand::NudgeB(d)),
-            Command::BeatNudge(DeckId::A, b) => self.send_audio(AudioCommand::BeatNudgeA(b)),
-            Command::BeatNudge(DeckId::B, b) => self.send_audio(AudioCommand::BeatNudgeB(b)),
-            Command::Beatjump(DeckId::A, b) => self.send_audio(AudioCommand::BeatjumpA(b)),
-            Command::Beatjump(DeckId::B, b) => self.send_audio(AudioCommand::BeatjumpB(b)),
+            Command::Seek(deck, pos) => self.send_audio(AudioCommand::Seek(deck, pos)),
+            Command::Nudge(deck, d) => self.send_audio(AudioCommand::Nudge(deck, d)),
+            Command::BeatNudge(deck, b) => self.send_audio(AudioCommand::BeatNudge(deck, b)),
+            Command::Beatjump(deck, b) => self.send_audio(AudioCommand::Beatjump(deck, b)),
 
             // Cue points
-            Command::SetCue(DeckId::A, n) => {
-                self.send_audio(AudioCommand::SetCueA(n));
-                self.state.set_success(format!("Deck A CUE {} set", n));
+            Command::SetCue(deck, n) => {
+                self.send_audio(AudioCommand::SetCue(deck, n));
+                self.state.set_success(format!("Deck {} CUE {} set", deck.name(), n));
+            }
+            Command::JumpCue(deck, n) => self.send_audio(AudioCommand::JumpCue(deck, n)),
+
+            // Loops
+            Command::LoopIn(deck) => self.send_audio(AudioCommand::LoopIn(deck)),
+            Command::LoopOut(deck) => self.send_audio(AudioCommand::LoopOut(deck)),
+            Command::AutoLoop(deck, b) => self.send_audio(AudioCommand::AutoLoop(deck, b)),
+            Command::LoopHalve(deck) => self.send_audio(AudioCommand::LoopHalve(deck)),
+            Command::LoopDouble(deck) => self.send_audio(AudioCommand::LoopDouble(deck)),
+            Command::LoopMove(deck, b) => self.send_audio(AudioCommand::LoopMove(deck, b)),
+            Command::Reloop(deck) => self.send_audio(AudioCommand::Reloop(deck)),
+            Command::LoopRoll(deck, b) => self.send_audio(AudioCommand::LoopRoll(deck, b)),
+            Command::LoopRollRelease(deck) => {
+                self.send_audio(AudioCommand::LoopRollRelease(deck))
+            }
+
+            // Slip
+            Command::ToggleSlip(deck) => self.send_audio(AudioCommand::ToggleSlip(deck)),
+            Command::SlipRelease(deck) => self.send_audio(AudioCommand::SlipRelease(deck)),
+            Command::SetReverse(deck, on) => self.send_audio(AudioCommand::SetReverse(deck, on)),
+
+            // Quantize
+            Command::ToggleQuantize => self.send_audio(AudioCommand::ToggleQuantize),
+            Command::SetQuantizeResolution(resolution) => {
+                self.send_audio(AudioCommand::SetQuantizeResolution(resolution))
+            }
+            Command::CycleQuantizeResolution => {
+                self.send_audio(AudioCommand::CycleQuantizeResolution)
+            }
+
+            // Beat grid
+            Command::ShiftGrid(deck, s) => self.send_audio(AudioCommand::ShiftGrid(deck, s)),
+            Command::SetDownbeat(deck) => self.send_audio(AudioCommand::SetDownbeat(deck)),
+            Command::ScaleGridTempo(deck, f) => {
+                self.send_audio(AudioCommand::ScaleGridTempo(deck, f))
             }
-            Command::SetCue(DeckId::B, n) => {
-                self.send_audio(AudioCommand::SetCueB(n));
-                self.state.set_success(format!("Deck B CUE {} set", n));
+            Command::SetGridBpm(deck, bpm) => {
+                self.send_audio(AudioCommand::SetGridBpm(deck, bpm));
+                self.state.set_message(format!("Deck {} grid: {:.2} BPM", deck.name(), bpm));
             }
-            Command::JumpCue(DeckId::A, n) => self.send_audio(AudioCommand::JumpCueA(n)),
-            Command::JumpCue(DeckId::B, n) => self.send_audio(AudioCommand::JumpCueB(n)),
 
             // Tempo
-            Command::SetTempo(DeckId::A, t) => self.send_audio(AudioCommand::SetTempoA(t)),
-            Command::SetTempo(DeckId::B, t) => self.send_audio(AudioCommand::SetTempoB(t)),
-            Command::AdjustTempo(DeckId::A, d) => self.send_audio(AudioCommand::AdjustTempoA(d)),
-            Command::AdjustTempo(DeckId::B, d) => self.send_audio(AudioCommand::AdjustTempoB(d)),
+            Command::SetTempo(deck, t) => self.send_audio(AudioCommand::SetTempo(deck, t)),
+            Command::AdjustTempo(deck, d) => self.send_audio(AudioCommand::AdjustTempo(deck, d)),
 
             // Gain
-            Command::SetGain(DeckId::A, g) => self.send_audio(AudioCommand::SetGainA(g)),
-            Command::SetGain(DeckId::B, g) => self.send_audio(AudioCommand::SetGainB(g)),
-            Command::AdjustGain(DeckId::A, d) => self.send_audio(AudioCommand::AdjustGainA(d)),
-            Command::AdjustGain(DeckId::B, d) => self.send_audio(AudioCommand::AdjustGainB(d)),
+            Command::SetGain(deck, g) => self.send_audio(AudioCommand::SetGain(deck, g)),
+            Command::AdjustGain(deck, d) => self.send_audio(AudioCommand::AdjustGain(deck, d)),
+
+            // Channel EQ
+            Command::SetEqGain(deck, band, db) => {
+                self.send_audio(AudioCommand::SetEq(deck, band, db))
+            }
+            Command::AdjustEqGain(deck, band, d) => {
+                self.send_audio(AudioCommand::AdjustEq(deck, band, d))
+            }
+            Command::ToggleEqKill(deck, band) => {
+                self.send_audio(AudioCommand::ToggleEqKill(deck, band))
+            }
+            Command::SetEqMode(mode) => self.send_audio(AudioCommand::SetEqMode(mode)),
+
+            // Channel strips
+            Command::SetUpfader(deck, pos) => self.send_audio(AudioCommand::SetUpfader(deck, pos)),
+            Command::AdjustUpfader(deck, d) => {
+                self.send_audio(AudioCommand::AdjustUpfader(deck, d))
+            }
+            Command::SetTrim(deck, db) => self.send_audio(AudioCommand::SetTrim(deck, db)),
+            Command::AdjustTrim(deck, d) => self.send_audio(AudioCommand::AdjustTrim(deck, d)),
+            Command::SetUpfaderCurve(curve) => {
+                self.send_audio(AudioCommand::SetUpfaderCurve(curve))
+            }
 
             // Sync
-            Command::Sync(DeckId::A) => self.send_audio(AudioCommand::SyncAToB),
-            Command::Sync(DeckId::B) => self.send_audio(AudioCommand::SyncBToA),
+            Command::Sync(deck) => self.send_audio(AudioCommand::Sync(deck)),
+            Command::ToggleSyncLock(deck) => self.send_audio(AudioCommand::ToggleSyncLock(deck)),
+            Command::SetMasterClock(bpm) => self.send_audio(AudioCommand::SetMasterClock(bpm)),
 
             // Crossfader
             Command::SetCrossfader(pos) => self.send_audio(AudioCommand::SetCrossfader(pos)),
@@ -191,58 +344,97 @@ impl OleApp {
             }
             Command::MoveCrossfader(_) => {}
             Command::CenterCrossfader => self.send_audio(AudioCommand::CenterCrossfader),
+            Command::SetCrossfaderCurve(curve) => {
+                self.send_audio(AudioCommand::SetCrossfaderCurve(curve))
+            }
+            Command::SetCrossfaderSharpness(s) => {
+                self.send_audio(AudioCommand::SetCrossfaderSharpness(s))
+            }
+            Command::ToggleHamster => {
+                self.send_audio(AudioCommand::SetHamster(!self.state.hamster))
+            }
+            Command::SetCrossfaderAssign(deck, assign) => {
+                self.send_audio(AudioCommand::SetCrossfaderAssign(deck, assign))
+            }
+            Command::CycleCrossfaderAssign(deck) => {
+                let assign = self.state.channel(deck).assign.next();
+                self.send_audio(AudioCommand::SetCrossfaderAssign(deck, assign));
+                self.state.set_message(format!("Deck {} crossfader: {}", deck.name(), assign.display_name()));
+            }
 
-            // Effects - toggle
-            Command::ToggleEffect(DeckId::A, EffectType::Filter) => {
-                self.send_audio(AudioCommand::ToggleFilterA)
+            // Headphone cue
+            Command::ToggleCue(deck) => self.send_audio(AudioCommand::ToggleCue(deck)),
+            Command::SetCueMix(mix) => self.send_audio(AudioCommand::SetCueMix(mix)),
+            Command::SetHeadphoneVolume(vol) => {
+                self.send_audio(AudioCommand::SetHeadphoneVolume(vol))
             }
-            Command::ToggleEffect(DeckId::B, EffectType::Filter) => {
-                self.send_audio(AudioCommand::ToggleFilterB)
+
+            // Recording
+            Command::StartRecording(path) => self.send_audio(AudioCommand::StartRecording(path)),
+            Command::StopRecording => self.send_audio(AudioCommand::StopRecording),
+
+            // Audio output
+            Command::ShowAudioOutput => {
+                let desc = self.state.output_description();
+                self.state.set_message(desc);
             }
-            Command::ToggleEffect(DeckId::A, EffectType::Delay) => {
-                self.send_audio(AudioCommand::ToggleDelayA)
+            Command::ListAudioOutputs => self.send_audio(AudioCommand::ListOutputs),
+            Command::SetAudioHost(host) => {
+                // Device names belong to a host
+                self.config.audio.host = host;
+                self.config.audio.device = None;
+                self.apply_audio_config();
             }
-            Command::ToggleEffect(DeckId::B, EffectType::Delay) => {
-                self.send_audio(AudioCommand::ToggleDelayB)
+            Command::SetAudioDevice(device) => {
+                self.config.audio.device = device;
+                self.apply_audio_config();
             }
-            Command::ToggleEffect(DeckId::A, EffectType::Reverb) => {
-                self.send_audio(AudioCommand::ToggleReverbA)
+            Command::SetSampleRate(rate) => {
+                self.config.audio.sample_rate = rate;
+                self.apply_audio_config();
             }
-            Command::ToggleEffect(DeckId::B, EffectType::Reverb) => {
-                self.send_audio(AudioCommand::ToggleReverbB)
+            Command::SetBufferSize(frames) => {
+                self.config.audio.buffer_size = frames;
+                self.apply_audio_config();
             }
-            Command::ToggleEffect(DeckId::A, EffectType::TapeStop) => {
-                self.send_audio(AudioCommand::ToggleTapeStopA)
+            Command::SetInterpolation(mode) => {
+                // Switches in place: no need to reopen the output
+                self.send_audio(AudioCommand::SetInterpolation(mode));
+                self.config.audio.interpolation = Some(mode.name().to_string());
+                if let Err(e) = self.config.save() {
+                    self.state.set_error(format!("Failed to save config: {}", e));
+                } else {
+                    self.state.set_message(format!("Interpolation: {}", mode.name()));
+                }
             }
-            Command::ToggleEffect(DeckId::B, EffectType::TapeStop) => {
-                self.send_audio(AudioCommand::ToggleTapeStopB)
+
+            // Effects - toggle
+            Command::ToggleEffect(deck, EffectType::Filter) => {
+                self.send_audio(AudioCommand::ToggleFilter(deck))
             }
-            Command::ToggleEffect(DeckId::A, EffectType::Flanger) => {
-                self.send_audio(AudioCommand::ToggleFlangerA)
+            Command::ToggleEffect(deck, EffectType::Delay) => {
+                self.send_audio(AudioCommand::ToggleDelay(deck))
             }
-            Command::ToggleEffect(DeckId::B, EffectType::Flanger) => {
-                self.send_audio(AudioCommand::ToggleFlangerB)
+            Command::ToggleEffect(deck, EffectType::Reverb) => {
+                self.send_audio(AudioCommand::ToggleReverb(deck))
             }
-            Command::ToggleEffect(DeckId::A, EffectType::Bitcrusher) => {
-                self.send_audio(AudioCommand::ToggleBitcrusherA)
+            Command::ToggleEffect(deck, EffectType::TapeStop) => {
+                self.send_audio(AudioCommand::ToggleTapeStop(deck))
             }
-            Command::ToggleEffect(DeckId::B, EffectType::Bitcrusher) => {
-                self.send_audio(AudioCommand::ToggleBitcrusherB)
+            Command::ToggleEffect(deck, EffectType::Flanger) => {
+                self.send_audio(AudioCommand::ToggleFlanger(deck))
             }
-            Command::AdjustFilterCutoff(DeckId::A, d) => {
-                self.send_audio(AudioCommand::AdjustFilterCutoffA(d))
+            Command::ToggleEffect(deck, EffectType::Bitcrusher) => {
+                self.send_audio(AudioCommand::ToggleBitcrusher(deck))
             }
-            Command::AdjustFilterCutoff(DeckId::B, d) => {
-                self.send_audio(AudioCommand::AdjustFilterCutoffB(d))
+            Command::AdjustFilterCutoff(deck, d) => {
+                self.send_audio(AudioCommand::AdjustFilterCutoff(deck, d))
             }
 
             // Effects - preset levels
             Command::SetDelayLevel(deck, level) => {
-                let ch = match deck { DeckId::A => 'A', DeckId::B => 'B' };
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::SetDelayLevelA(level)),
-                    DeckId::B => self.send_audio(AudioCommand::SetDelayLevelB(level)),
-                }
+                let ch = deck.name();
+                self.send_audio(AudioCommand::SetDelayLevel(deck, level));
                 if level == 0 {
                     self.state.set_message(format!("Deck {} DELAY OFF", ch));
                 } else {
@@ -250,16 +442,13 @@ impl OleApp {
                 }
             }
             Command::SetFilterPreset(deck, filter_type, level) => {
-                let ch = match deck { DeckId::A => 'A', DeckId::B => 'B' };
+                let ch = deck.name();
                 let ft = match filter_type {
                     ole_audio::FilterType::LowPass => "LOW",
                     ole_audio::FilterType::BandPass => "BAND",
                     ole_audio::FilterType::HighPass => "HIGH",
                 };
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::SetFilterPresetA(filter_type, level)),
-                    DeckId::B => self.send_audio(AudioCommand::SetFilterPresetB(filter_type, level)),
-                }
+                self.send_audio(AudioCommand::SetFilterPreset(deck, filter_type, level));
                 if level == 0 {
                     self.state.set_message(format!("Deck {} FILTER OFF", ch));
                 } else {
@@ -267,11 +456,8 @@ impl OleApp {
                 }
             }
             Command::SetReverbLevel(deck, level) => {
-                let ch = match deck { DeckId::A => 'A', DeckId::B => 'B' };
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::SetReverbLevelA(level)),
-                    DeckId::B => self.send_audio(AudioCommand::SetReverbLevelB(level)),
-                }
+                let ch = deck.name();
+                self.send_audio(AudioCommand::SetReverbLevel(deck, level));
                 if level == 0 {
                     self.state.set_message(format!("Deck {} REVERB OFF", ch));
                 } else {
@@ -288,21 +474,25 @@ impl OleApp {
             Command::ToggleHelp => self.state.toggle_help(),
             Command::ToggleScope => self.state.toggle_scope(),
             Command::CycleScopeMode => self.state.cycle_scope_mode(),
-            Command::ZoomIn(deck) => match deck {
-                DeckId::A => self.state.zoom_a = self.state.zoom_a.zoom_in(),
-                DeckId::B => self.state.zoom_b = self.state.zoom_b.zoom_in(),
-            },
-            Command::ZoomOut(deck) => match deck {
-                DeckId::A => self.state.zoom_a = self.state.zoom_a.zoom_out(),
-                DeckId::B => self.state.zoom_b = self.state.zoom_b.zoom_out(),
-            },
+            Command::ZoomIn(deck) => {
+                let view = self.state.view_mut(deck);
+                view.zoom = view.zoom.zoom_in();
+            }
+            Command::ZoomOut(deck) => {
+                let view = self.state.view_mut(deck);
+                view.zoom = view.zoom.zoom_out();
+            }
             Command::SetTheme(_) => {} // Single theme in GUI
             Command::CycleFocus => self.state.cycle_focus(),
             Command::Focus(deck) => {
-                self.state.focused = match deck {
-                    DeckId::A => FocusedPane::DeckA,
-                    DeckId::B => FocusedPane::DeckB,
-                };
+                if deck.index() >= self.state.visible_decks {
+                    self.state.set_visible_decks(DECK_COUNT);
+                }
+                self.state.focused = FocusedPane::Deck(deck);
+            }
+            Command::SetVisibleDecks(count) => {
+                self.state.set_visible_decks(count);
+                self.state.set_message(format!("{} decks", self.state.visible_decks));
             }
             Command::Quit => self.state.should_quit = true,
 
@@ -389,114 +579,63 @@ impl OleApp {
             }
 
             // Filter mode
-            Command::SetFilterMode(DeckId::A, mode) => {
-                self.send_audio(AudioCommand::SetFilterModeA(mode))
-            }
-            Command::SetFilterMode(DeckId::B, mode) => {
-                self.send_audio(AudioCommand::SetFilterModeB(mode))
-            }
-            Command::CycleFilterMode(DeckId::A) => {
-                let next = match self.state.filter_a_mode {
-                    ole_audio::FilterMode::Biquad => ole_audio::FilterMode::Ladder,
-                    ole_audio::FilterMode::Ladder => ole_audio::FilterMode::SVF,
-                    ole_audio::FilterMode::SVF => ole_audio::FilterMode::Biquad,
-                };
-                self.state.filter_a_mode = next;
-                self.send_audio(AudioCommand::SetFilterModeA(next));
+            Command::SetFilterMode(deck, mode) => {
+                self.send_audio(AudioCommand::SetFilterMode(deck, mode))
             }
-            Command::CycleFilterMode(DeckId::B) => {
-                let next = match self.state.filter_b_mode {
+            Command::CycleFilterMode(deck) => {
+                let channel = &mut self.state.channels[deck.index()];
+                let next = match channel.filter_mode {
                     ole_audio::FilterMode::Biquad => ole_audio::FilterMode::Ladder,
                     ole_audio::FilterMode::Ladder => ole_audio::FilterMode::SVF,
                     ole_audio::FilterMode::SVF => ole_audio::FilterMode::Biquad,
                 };
-                self.state.filter_b_mode = next;
-                self.send_audio(AudioCommand::SetFilterModeB(next));
+                channel.filter_mode = next;
+                self.send_audio(AudioCommand::SetFilterMode(deck, next));
             }
 
             // Vinyl
-            Command::ToggleVinyl(DeckId::A) => self.send_audio(AudioCommand::ToggleVinylA),
-            Command::ToggleVinyl(DeckId::B) => self.send_audio(AudioCommand::ToggleVinylB),
-            Command::SetVinylPreset(DeckId::A, preset) => {
+            Command::ToggleVinyl(deck) => self.send_audio(AudioCommand::ToggleVinyl(deck)),
+            Command::SetVinylPreset(deck, preset) => {
                 let p = vinyl_preset_to_audio(preset);
-                self.send_audio(AudioCommand::SetVinylPresetA(p));
-            }
-            Command::SetVinylPreset(DeckId::B, preset) => {
-                let p = vinyl_preset_to_audio(preset);
-                self.send_audio(AudioCommand::SetVinylPresetB(p));
+                self.send_audio(AudioCommand::SetVinylPreset(deck, p));
             }
             Command::CycleVinylPreset(deck) => {
-                let next = match (deck, self.state.vinyl_a_preset, self.state.vinyl_b_preset) {
-                    (DeckId::A, p, _) | (DeckId::B, _, p) => {
-                        match p {
-                            ole_audio::VinylPreset::Clean => ole_audio::VinylPreset::Warm,
-                            ole_audio::VinylPreset::Warm => ole_audio::VinylPreset::Vintage,
-                            ole_audio::VinylPreset::Vintage => ole_audio::VinylPreset::Worn,
-                            ole_audio::VinylPreset::Worn => ole_audio::VinylPreset::Extreme,
-                            ole_audio::VinylPreset::Extreme => ole_audio::VinylPreset::Clean,
-                        }
-                    }
+                let channel = &mut self.state.channels[deck.index()];
+                let next = match channel.vinyl_preset {
+                    ole_audio::VinylPreset::Clean => ole_audio::VinylPreset::Warm,
+                    ole_audio::VinylPreset::Warm => ole_audio::VinylPreset::Vintage,
+                    ole_audio::VinylPreset::Vintage => ole_audio::VinylPreset::Worn,
+                    ole_audio::VinylPreset::Worn => ole_audio::VinylPreset::Extreme,
+                    ole_audio::VinylPreset::Extreme => ole_audio::VinylPreset::Clean,
                 };
-                match deck {
-                    DeckId::A => {
-                        self.state.vinyl_a_preset = next;
-                        self.send_audio(AudioCommand::SetVinylPresetA(next));
-                    }
-                    DeckId::B => {
-                        self.state.vinyl_b_preset = next;
-                        self.send_audio(AudioCommand::SetVinylPresetB(next));
-                    }
-                }
+                channel.vinyl_preset = next;
+                self.send_audio(AudioCommand::SetVinylPreset(deck, next));
             }
-            Command::SetVinylWow(DeckId::A, a) => self.send_audio(AudioCommand::SetVinylWowA(a)),
-            Command::SetVinylWow(DeckId::B, a) => self.send_audio(AudioCommand::SetVinylWowB(a)),
-            Command::SetVinylNoise(DeckId::A, a) => self.send_audio(AudioCommand::SetVinylNoiseA(a)),
-            Command::SetVinylNoise(DeckId::B, a) => self.send_audio(AudioCommand::SetVinylNoiseB(a)),
-            Command::SetVinylWarmth(DeckId::A, a) => self.send_audio(AudioCommand::SetVinylWarmthA(a)),
-            Command::SetVinylWarmth(DeckId::B, a) => self.send_audio(AudioCommand::SetVinylWarmthB(a)),
+            Command::SetVinylWow(deck, a) => self.send_audio(AudioCommand::SetVinylWow(deck, a)),
+            Command::SetVinylNoise(deck, a) => self.send_audio(AudioCommand::SetVinylNoise(deck, a)),
+            Command::SetVinylWarmth(deck, a) => self.send_audio(AudioCommand::SetVinylWarmth(deck, a)),
 
-            // Time stretch
-            Command::ToggleTimeStretch(DeckId::A) => {
-                self.send_audio(AudioCommand::ToggleTimeStretchA)
-            }
-            Command::ToggleTimeStretch(DeckId::B) => {
-                self.send_audio(AudioCommand::ToggleTimeStretchB)
-            }
-            Command::SetTimeStretchRatio(DeckId::A, r) => {
-                self.send_audio(AudioCommand::SetTimeStretchRatioA(r))
-            }
-            Command::SetTimeStretchRatio(DeckId::B, r) => {
-                self.send_audio(AudioCommand::SetTimeStretchRatioB(r))
-            }
+            // Key lock
+            Command::ToggleKeyLock(deck) => self.send_audio(AudioCommand::ToggleKeyLock(deck)),
+
+            // Key shift
+            Command::SetPitch(deck, s) => self.send_audio(AudioCommand::SetPitch(deck, s)),
+            Command::AdjustPitch(deck, d) => self.send_audio(AudioCommand::AdjustPitch(deck, d)),
 
             // Delay modulation
-            Command::SetDelayModulation(DeckId::A, m) => {
-                self.send_audio(AudioCommand::SetDelayModulationA(m))
-            }
-            Command::SetDelayModulation(DeckId::B, m) => {
-                self.send_audio(AudioCommand::SetDelayModulationB(m))
+            Command::SetDelayModulation(deck, m) => {
+                self.send_audio(AudioCommand::SetDelayModulation(deck, m))
             }
             Command::CycleDelayModulation(deck) => {
-                let current = match deck {
-                    DeckId::A => self.state.delay_a_modulation,
-                    DeckId::B => self.state.delay_b_modulation,
-                };
-                let next = match current {
+                let channel = &mut self.state.channels[deck.index()];
+                let next = match channel.delay_modulation {
                     ole_audio::DelayModulation::Off => ole_audio::DelayModulation::Subtle,
                     ole_audio::DelayModulation::Subtle => ole_audio::DelayModulation::Classic,
                     ole_audio::DelayModulation::Classic => ole_audio::DelayModulation::Heavy,
                     ole_audio::DelayModulation::Heavy => ole_audio::DelayModulation::Off,
                 };
-                match deck {
-                    DeckId::A => {
-                        self.state.delay_a_modulation = next;
-                        self.send_audio(AudioCommand::SetDelayModulationA(next));
-                    }
-                    DeckId::B => {
-                        self.state.delay_b_modulation = next;
-                        self.send_audio(AudioCommand::SetDelayModulationB(next));
-                    }
-                }
+                channel.delay_modulation = next;
+                self.send_audio(AudioCommand::SetDelayModulation(deck, next));
             }
 
             // Mode changes (handled by input handler)
@@ -551,43 +690,63 @@ impl OleApp {
             }
 
             // Tape Stop
-            Command::ToggleTapeStop(deck) => match deck {
-                DeckId::A => self.send_audio(AudioCommand::ToggleTapeStopA),
-                DeckId::B => self.send_audio(AudioCommand::ToggleTapeStopB),
-            },
+            Command::ToggleTapeStop(deck) => self.send_audio(AudioCommand::ToggleTapeStop(deck)),
             Command::TriggerTapeStop(deck) => {
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::TriggerTapeStopA),
-                    DeckId::B => self.send_audio(AudioCommand::TriggerTapeStopB),
-                }
+                self.send_audio(AudioCommand::TriggerTapeStop(deck));
                 self.state.set_message("Tape Stop");
             }
             Command::TriggerTapeStart(deck) => {
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::TriggerTapeStartA),
-                    DeckId::B => self.send_audio(AudioCommand::TriggerTapeStartB),
-                }
+                self.send_audio(AudioCommand::TriggerTapeStart(deck));
                 self.state.set_message("Tape Start");
             }
 
+            // Transitions
+            Command::TriggerEchoOut(deck) => {
+                self.send_audio(AudioCommand::TriggerEchoOut(deck));
+                self.state.set_message("Echo Out");
+            }
+            Command::TriggerNoiseSweep(deck) => {
+                self.send_audio(AudioCommand::TriggerNoiseSweep(deck));
+                self.state.set_message("Noise Sweep");
+            }
+
+            // Convolution reverb
+            Command::LoadImpulse(deck, path) => self.load_impulse(deck, &path),
+            Command::SetImpulseWet(deck, wet) => {
+                let wet = wet.clamp(0.0, 1.0);
+                self.send_audio(AudioCommand::SetConvolutionWet(deck, wet));
+                self.state.set_message(format!("Deck {} IR WET:{:.0}%", deck.name(), wet * 100.0));
+            }
+
             // Flanger
             Command::ToggleFlanger(deck) => {
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::ToggleFlangerA),
-                    DeckId::B => self.send_audio(AudioCommand::ToggleFlangerB),
-                }
+                self.send_audio(AudioCommand::ToggleFlanger(deck));
                 self.state.set_message("Flanger toggled");
             }
 
             // Bitcrusher
             Command::ToggleBitcrusher(deck) => {
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::ToggleBitcrusherA),
-                    DeckId::B => self.send_audio(AudioCommand::ToggleBitcrusherB),
-                }
+                self.send_audio(AudioCommand::ToggleBitcrusher(deck));
                 self.state.set_message("Bitcrusher toggled");
             }
 
+            // Effect chain
+            Command::AddEffect(deck, slot, kind) => {
+                self.send_audio(AudioCommand::AddEffect(deck, slot, kind));
+                self.state.set_message(format!("Deck {}: {} added", deck.name(), kind.name()));
+            }
+            Command::RemoveEffect(deck, slot) => self.send_audio(AudioCommand::RemoveEffect(deck, slot)),
+            Command::MoveEffect(deck, from, to) => self.send_audio(AudioCommand::MoveEffect(deck, from, to)),
+            Command::ToggleEffectSlot(deck, slot) => self.send_audio(AudioCommand::ToggleEffectSlot(deck, slot)),
+            Command::ToggleEffectKind(deck, kind) => {
+                self.send_audio(AudioCommand::ToggleEffectKind(deck, kind));
+                self.state.set_message(format!("Deck {}: {} toggled", deck.name(), kind.name()));
+            }
+            Command::SetEffectMix(deck, slot, mix) => self.send_audio(AudioCommand::SetEffectMix(deck, slot, mix)),
+            Command::SetEffectParam(deck, slot, param, value) => {
+                self.send_audio(AudioCommand::SetEffectParam(deck, slot, param, value))
+            }
+
             // Help scrolling
             Command::HelpScrollUp => self.state.help_scroll = (self.state.help_scroll - 30.0).max(0.0),
             Command::HelpScrollDown => self.state.help_scroll = (self.state.help_scroll + 30.0).min(2000.0),
@@ -606,17 +765,23 @@ impl OleApp {
                 let samples = Arc::new(track.samples);
                 let waveform = Arc::new(track.waveform_overview);
                 let enhanced_waveform = Arc::new(track.enhanced_waveform);
-                match deck {
-                    DeckId::A => self.send_audio(AudioCommand::LoadDeckA(
-                        samples, track.sample_rate, name, waveform, enhanced_waveform, key,
-                    )),
-                    DeckId::B => self.send_audio(AudioCommand::LoadDeckB(
-                        samples, track.sample_rate, name, waveform, enhanced_waveform, key,
-                    )),
+                // Beat grid corrected by hand earlier replaces the analyzed one
+                let grid = self
+                    .scanner
+                    .as_ref()
+                    .and_then(|scanner| scanner.get_grid_edit(path))
+                    .map(|edit| Box::new(edit.to_grid(track.sample_rate)));
+                let deck_track = Some(DeckTrack { path: path.to_path_buf(), saved_edits: None });
+                self.send_audio(AudioCommand::LoadDeck(
+                    deck, samples, track.sample_rate, name, waveform, enhanced_waveform, key,
+                ));
+                if let Some(grid) = grid {
+                    self.send_audio(AudioCommand::SetBeatGrid(deck, grid));
                 }
+                self.tracks[deck.index()] = deck_track;
                 self.state.set_message(format!(
                     "Loaded to deck {}: {}",
-                    match deck { DeckId::A => 'A', DeckId::B => 'B' },
+                    deck.name(),
                     path.file_name().unwrap_or_default().to_string_lossy(),
                 ));
             }
@@ -625,6 +790,63 @@ impl OleApp {
             }
         }
     }
+
+    /// Decode an impulse response for a deck's convolution reverb (the
+    /// engine resamples and transforms it off the audio thread)
+    fn load_impulse(&mut self, deck: DeckId, path: &std::path::Path) {
+        if !self.state.channel(deck).effects.iter().any(|slot| slot.name == "Convolution") {
+            self.state.set_error(format!("Deck {} has no convolution slot (:fx {} add ir)", deck.name(), deck.name().to_lowercase()));
+            return;
+        }
+        match self.track_loader.load(path) {
+            Ok(track) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                self.send_audio(AudioCommand::LoadImpulse(
+                    deck, track.samples, track.sample_rate, track.channels, name.clone(),
+                ));
+                self.state.set_message(format!("Deck {} IR: {}", deck.name(), name));
+            }
+            Err(e) => {
+                self.state.set_error(format!("Failed to load impulse response: {}", e));
+            }
+        }
+    }
+}
+
+/// Send the saved mixer setup to the engine
+///
+/// Returns the setup as the engine will report it (unknown curve names
+/// become the defaults, values are clamped), for comparing against later.
+fn apply_mixer_config(mixer: &MixerConfig, cmd_tx: &Sender<AudioCommand>) -> MixerConfig {
+    let crossfader_curve = CrossfaderCurve::parse(&mixer.crossfader_curve).unwrap_or_default();
+    let upfader_curve = UpfaderCurve::parse(&mixer.upfader_curve).unwrap_or_default();
+    let trim = |db: f32| db.clamp(Mixer::TRIM_MIN_DB, Mixer::TRIM_MAX_DB);
+    // Unknown or missing sides fall back to A for decks A/C and B for decks B/D
+    let assigns = DeckId::ALL.map(|deck| {
+        CrossfaderAssign::parse(&mixer.assigns[deck.index()])
+            .unwrap_or_else(|| CrossfaderAssign::default_for(deck))
+    });
+    let applied = MixerConfig {
+        crossfader_curve: crossfader_curve.name().to_string(),
+        crossfader_sharpness: mixer.crossfader_sharpness.clamp(0.0, 1.0),
+        hamster: mixer.hamster,
+        upfader_curve: upfader_curve.name().to_string(),
+        trims: mixer.trims.map(trim),
+        assigns: assigns.map(|assign| assign.name().to_string()),
+    };
+    for cmd in [
+        AudioCommand::SetCrossfaderCurve(crossfader_curve),
+        AudioCommand::SetCrossfaderSharpness(applied.crossfader_sharpness),
+        AudioCommand::SetHamster(applied.hamster),
+        AudioCommand::SetUpfaderCurve(upfader_curve),
+    ] {
+        let _ = cmd_tx.send(cmd);
+    }
+    for deck in DeckId::ALL {
+        let _ = cmd_tx.send(AudioCommand::SetTrim(deck, applied.trims[deck.index()]));
+        let _ = cmd_tx.send(AudioCommand::SetCrossfaderAssign(deck, assigns[deck.index()]));
+    }
+    applied
 }
 
 impl eframe::App for OleApp {
@@ -638,6 +860,11 @@ impl eframe::App for OleApp {
         // Drain audio events
         self.drain_audio_events();
 
+        // Save mixer setup changes once knobs and faders are let go
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.save_mixer_config();
+        }
+
         // Process scan progress
         self.process_scan_progress();
 
@@ -700,26 +927,32 @@ fn render_ui(ctx: &egui::Context, state: &mut GuiState, cmd_tx: &Sender<AudioCom
 
     // Main content
     egui::CentralPanel::default().show(ctx, |ui| {
-        // Decks row
-        ui.columns(2, |cols| {
-            if let Some(cmd) = widgets::DeckPanel::show(&mut cols[0], state, true) {
-                commands.push(cmd);
-            }
-            if let Some(cmd) = widgets::DeckPanel::show(&mut cols[1], state, false) {
-                commands.push(cmd);
-            }
-        });
+        // Decks: A B, with C D in a second row when four decks are shown
+        let decks: Vec<DeckId> = state.visible_deck_ids().collect();
+        for pair in decks.chunks(2) {
+            ui.columns(2, |cols| {
+                for (col, &deck) in cols.iter_mut().zip(pair) {
+                    if let Some(cmd) = widgets::DeckPanel::show(col, state, deck) {
+                        commands.push(cmd);
+                    }
+                }
+            });
+        }
 
         // Energy bridge between decks
         widgets::EnergyBridge::show(ui, state);
 
         ui.separator();
 
-        // Effects + Mixer row
+        // Effects + Mixer row: left-hand decks (A, C) and right-hand decks (B, D) either side
         ui.columns(3, |cols| {
-            widgets::FxRack::show(&mut cols[0], state, cmd_tx, true);
+            for &deck in decks.iter().step_by(2) {
+                widgets::FxRack::show(&mut cols[0], state, cmd_tx, deck);
+            }
             widgets::MixerPanel::show(&mut cols[1], state, cmd_tx);
-            widgets::FxRack::show(&mut cols[2], state, cmd_tx, false);
+            for &deck in decks.iter().skip(1).step_by(2) {
+                widgets::FxRack::show(&mut cols[2], state, cmd_tx, deck);
+            }
         });
 
         ui.separator();
@@ -1,6 +1,9 @@
 use egui::{Context, Key};
 
-use ole_input::{Command, DeckId, Direction};
+use ole_input::{
+    Command, CrossfaderAssign, CrossfaderCurve, DeckId, Direction, EffectKind, EqBand, EqMode,
+    Interpolation, QuantizeResolution, UpfaderCurve,
+};
 use crate::state::{FocusedPane, GuiState};
 
 pub fn handle_keyboard(ctx: &Context, state: &mut GuiState) -> Vec<Command> {
@@ -38,8 +41,7 @@ pub fn handle_keyboard(ctx: &Context, state: &mut GuiState) -> Vec<Command> {
 
 fn focused_deck(state: &GuiState) -> DeckId {
     match state.focused {
-        FocusedPane::DeckA => DeckId::A,
-        FocusedPane::DeckB => DeckId::B,
+        FocusedPane::Deck(deck) => deck,
         _ => DeckId::A,
     }
 }
@@ -160,6 +162,60 @@ fn handle_normal_mode(
         cmds.push(Command::Beatjump(fd, 8));
     }
 
+    // Slip mode: cue jumps and beatjumps last while the key is held
+    let slip = state.deck(fd).slip;
+    let released = [Key::J, Key::K, Key::ArrowDown, Key::ArrowUp, Key::Num1, Key::Num2,
+                    Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8];
+    if slip && released.iter().any(|key| input.key_released(*key)) {
+        cmds.push(Command::SlipRelease(fd));
+    }
+    if input.key_pressed(Key::Y) && !input.modifiers.shift {
+        cmds.push(Command::ToggleSlip(fd));
+    }
+
+    // Reverse while held
+    if input.key_pressed(Key::Q) && !input.modifiers.shift && !input.modifiers.command
+        && state.reverse_held.is_none()
+    {
+        state.reverse_held = Some(fd);
+        cmds.push(Command::SetReverse(fd, true));
+    }
+    if input.key_released(Key::Q) {
+        if let Some(deck) = state.reverse_held.take() {
+            cmds.push(Command::SetReverse(deck, false));
+        }
+    }
+
+    // Quantize (global)
+    if input.key_pressed(Key::Q) && input.modifiers.shift {
+        cmds.push(Command::ToggleQuantize);
+    }
+
+    // Beat grid: H/L = shift grid earlier/later, ; = tap tempo
+    if input.key_pressed(Key::H) && input.modifiers.shift {
+        cmds.push(Command::ShiftGrid(fd, -0.005));
+    }
+    if input.key_pressed(Key::L) && input.modifiers.shift {
+        cmds.push(Command::ShiftGrid(fd, 0.005));
+    }
+    if input.key_pressed(Key::Semicolon) && !input.modifiers.shift {
+        if let Some(bpm) = state.view_mut(fd).tap.tap(input.time) {
+            cmds.push(Command::SetGridBpm(fd, bpm));
+        }
+    }
+
+    // EQ kills: F1/F2/F3 = low/mid/high
+    for (key, band) in [(Key::F1, EqBand::Low), (Key::F2, EqBand::Mid), (Key::F3, EqBand::High)] {
+        if input.key_pressed(key) {
+            cmds.push(Command::ToggleEqKill(fd, band));
+        }
+    }
+
+    // Headphone cue: F4
+    if input.key_pressed(Key::F4) {
+        cmds.push(Command::ToggleCue(fd));
+    }
+
     // Beat nudge
     if input.key_pressed(Key::D) && !input.modifiers.shift {
         cmds.push(Command::BeatNudge(fd, 0.0625));
@@ -203,6 +259,31 @@ fn handle_normal_mode(
     if input.key_pressed(Key::B) && input.modifiers.shift {
         cmds.push(Command::Sync(DeckId::A));
     }
+    if input.key_pressed(Key::Y) && input.modifiers.shift {
+        cmds.push(Command::ToggleSyncLock(fd));
+    }
+
+    // Key lock (master tempo): m = A, M = B
+    if input.key_pressed(Key::M) && !input.modifiers.shift {
+        cmds.push(Command::ToggleKeyLock(DeckId::A));
+    }
+    if input.key_pressed(Key::M) && input.modifiers.shift {
+        cmds.push(Command::ToggleKeyLock(DeckId::B));
+    }
+
+    // Key shift (focused deck): u/i = -/+ 1 semitone, U/I = -/+ 10 cents
+    if input.key_pressed(Key::U) && !input.modifiers.shift {
+        cmds.push(Command::AdjustPitch(fd, -1.0));
+    }
+    if input.key_pressed(Key::I) && !input.modifiers.shift {
+        cmds.push(Command::AdjustPitch(fd, 1.0));
+    }
+    if input.key_pressed(Key::U) && input.modifiers.shift {
+        cmds.push(Command::AdjustPitch(fd, -0.1));
+    }
+    if input.key_pressed(Key::I) && input.modifiers.shift {
+        cmds.push(Command::AdjustPitch(fd, 0.1));
+    }
 
     // Gain A: - =
     if input.key_pressed(Key::Minus) && !input.modifiers.shift {
@@ -253,6 +334,54 @@ fn handle_normal_mode(
             cmds.push(Command::SetCue(fd, (i + 1) as u8));
         }
     }
+
+    // Loops (focused deck)
+    if input.key_pressed(Key::N) && !input.modifiers.shift {
+        cmds.push(Command::LoopIn(fd));
+    }
+    if input.key_pressed(Key::N) && input.modifiers.shift {
+        cmds.push(Command::LoopOut(fd));
+    }
+    if input.key_pressed(Key::G) && !input.modifiers.shift {
+        cmds.push(Command::AutoLoop(fd, 4.0));
+    }
+    if input.key_pressed(Key::G) && input.modifiers.shift {
+        cmds.push(Command::Reloop(fd));
+    }
+    if input.key_pressed(Key::T) && !input.modifiers.shift {
+        cmds.push(Command::LoopHalve(fd));
+    }
+    if input.key_pressed(Key::T) && input.modifiers.shift {
+        cmds.push(Command::LoopDouble(fd));
+    }
+    if input.key_pressed(Key::R) && !input.modifiers.shift {
+        cmds.push(Command::LoopMove(fd, -1.0));
+    }
+    if input.key_pressed(Key::R) && input.modifiers.shift {
+        cmds.push(Command::LoopMove(fd, 1.0));
+    }
+
+    // Loop roll while held: f = 1/4 beat, F = 1/16 beat
+    if input.key_pressed(Key::F) && !input.modifiers.shift {
+        cmds.push(Command::LoopRoll(fd, 0.25));
+    }
+    if input.key_pressed(Key::F) && input.modifiers.shift {
+        cmds.push(Command::LoopRoll(fd, 0.0625));
+    }
+    if input.key_released(Key::F) {
+        cmds.push(Command::LoopRollRelease(fd));
+    }
+}
+
+/// Parse a length in beats: "4", "0.5" or "1/8"
+fn parse_beats(s: &str) -> Option<f32> {
+    match s.split_once('/') {
+        Some((num, den)) => {
+            let den: f32 = den.parse().ok()?;
+            (den != 0.0).then_some(num.parse::<f32>().ok()? / den)
+        }
+        None => s.parse().ok(),
+    }
 }
 
 fn handle_command_mode(
@@ -278,16 +407,336 @@ fn handle_command_mode(
             Some("help") => cmds.push(Command::ToggleHelp),
             Some("sync") => {
                 // Sync: if a deck arg provided, sync that deck; otherwise sync focused
+                // :sync lock [a-d]  - toggle sync lock
+                // :sync clock [bpm] - master clock leads, :sync auto - decks lead
                 if parts.len() > 1 {
                     match parts[1] {
-                        "a" | "A" => cmds.push(Command::Sync(DeckId::A)),
-                        "b" | "B" => cmds.push(Command::Sync(DeckId::B)),
-                        _ => cmds.push(Command::Sync(focused_deck(state))),
+                        "lock" => {
+                            let deck = parts.get(2).copied().and_then(DeckId::parse);
+                            cmds.push(Command::ToggleSyncLock(deck.unwrap_or(focused_deck(state))))
+                        }
+                        "clock" => match parts.get(2).map(|v| v.parse::<f32>()) {
+                            None => cmds.push(Command::SetMasterClock(Some(state.master_bpm))),
+                            Some(Ok(bpm)) if bpm > 0.0 => {
+                                cmds.push(Command::SetMasterClock(Some(bpm)))
+                            }
+                            _ => state.set_error("Usage: :sync clock [bpm]"),
+                        },
+                        "auto" => cmds.push(Command::SetMasterClock(None)),
+                        deck => {
+                            let deck = DeckId::parse(deck).unwrap_or(focused_deck(state));
+                            cmds.push(Command::Sync(deck))
+                        }
                     }
                 } else {
                     cmds.push(Command::Sync(focused_deck(state)));
                 }
             }
+            Some("pitch") => {
+                // :pitch <a-d> <semitones>  - "+1"/"-0.5" adjust, "2" sets, "0" resets
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let value = parts.get(2).and_then(|v| v.parse::<f32>().ok());
+                match (deck, value, parts.get(2)) {
+                    (Some(deck), Some(v), Some(raw)) if raw.starts_with(['+', '-']) => {
+                        cmds.push(Command::AdjustPitch(deck, v))
+                    }
+                    (Some(deck), Some(v), _) => cmds.push(Command::SetPitch(deck, v)),
+                    _ => state.set_error("Usage: :pitch <a-d> <semitones>"),
+                }
+            }
+            Some("loop") => {
+                // :loop <a-d> <beats|off>  - e.g. "4", "1/8"
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                match (deck, parts.get(2).copied()) {
+                    (Some(deck), Some("off")) => {
+                        if state.deck(deck).loop_info.is_some_and(|l| l.active) {
+                            cmds.push(Command::Reloop(deck));
+                        }
+                    }
+                    (Some(deck), Some(beats)) => match parse_beats(beats) {
+                        Some(beats) if beats > 0.0 => cmds.push(Command::AutoLoop(deck, beats)),
+                        _ => state.set_error("Usage: :loop <a-d> <beats|off>"),
+                    },
+                    _ => state.set_error("Usage: :loop <a-d> <beats|off>"),
+                }
+            }
+            Some("grid") => {
+                // :grid <a-d> <half|double|down|shift <ms>|bpm <bpm>>
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let value = parts.get(3).and_then(|v| v.parse::<f64>().ok());
+                match (deck, parts.get(2).copied(), value) {
+                    (Some(deck), Some("half"), _) => {
+                        cmds.push(Command::ScaleGridTempo(deck, 0.5))
+                    }
+                    (Some(deck), Some("double"), _) => {
+                        cmds.push(Command::ScaleGridTempo(deck, 2.0))
+                    }
+                    (Some(deck), Some("down"), _) => cmds.push(Command::SetDownbeat(deck)),
+                    (Some(deck), Some("shift"), Some(ms)) => {
+                        cmds.push(Command::ShiftGrid(deck, ms / 1000.0))
+                    }
+                    (Some(deck), Some("bpm"), Some(bpm)) if bpm > 0.0 => {
+                        cmds.push(Command::SetGridBpm(deck, bpm as f32))
+                    }
+                    _ => state.set_error(
+                        "Usage: :grid <a-d> <half|double|down|shift <ms>|bpm <bpm>>",
+                    ),
+                }
+            }
+            Some("eq") => {
+                // :eq <a-d> <low|mid|high> <db|kill>  - "+3"/"-3" adjust, "0" resets
+                // :eq mode <classic|iso>
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let band = parts.get(2).and_then(|b| EqBand::parse(b));
+                let raw = parts.get(3).copied();
+                match (parts.get(1).copied(), deck, band, raw) {
+                    (Some("mode"), _, _, _) => match parts.get(2).copied() {
+                        Some("classic") => cmds.push(Command::SetEqMode(EqMode::Classic)),
+                        Some("iso") | Some("isolator") => {
+                            cmds.push(Command::SetEqMode(EqMode::Isolator))
+                        }
+                        None => cmds.push(Command::SetEqMode(state.eq_mode.toggle())),
+                        _ => state.set_error("Usage: :eq mode <classic|iso>"),
+                    },
+                    (_, Some(deck), Some(band), Some("kill")) => {
+                        cmds.push(Command::ToggleEqKill(deck, band))
+                    }
+                    (_, Some(deck), Some(band), Some(raw)) => match raw.parse::<f32>() {
+                        Ok(db) if raw.starts_with(['+', '-']) => {
+                            cmds.push(Command::AdjustEqGain(deck, band, db))
+                        }
+                        Ok(db) => cmds.push(Command::SetEqGain(deck, band, db)),
+                        Err(_) => state.set_error("Usage: :eq <a-d> <low|mid|high> <db|kill>"),
+                    },
+                    _ => state.set_error("Usage: :eq <a-d> <low|mid|high> <db|kill>"),
+                }
+            }
+            Some("fader") | Some("trim") => {
+                // :fader <a-d> <0-1>  :trim <a-d> <db>  - "+"/"-" adjust
+                // :fader curve <linear|slow|fast>
+                let trim = parts[0] == "trim";
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let raw = parts.get(2).copied();
+                let value = raw.and_then(|v| v.parse::<f32>().ok());
+                match (parts.get(1).copied(), deck, raw, value) {
+                    (Some("curve"), _, Some(name), _) if !trim => {
+                        match UpfaderCurve::parse(name) {
+                            Some(curve) => cmds.push(Command::SetUpfaderCurve(curve)),
+                            None => state.set_error("Usage: :fader curve <linear|slow|fast>"),
+                        }
+                    }
+                    (_, Some(deck), Some(raw), Some(v)) if raw.starts_with(['+', '-']) => {
+                        cmds.push(if trim {
+                            Command::AdjustTrim(deck, v)
+                        } else {
+                            Command::AdjustUpfader(deck, v)
+                        })
+                    }
+                    (_, Some(deck), _, Some(v)) => cmds.push(if trim {
+                        Command::SetTrim(deck, v)
+                    } else {
+                        Command::SetUpfader(deck, v)
+                    }),
+                    _ if trim => state.set_error("Usage: :trim <a-d> <db>"),
+                    _ => state.set_error("Usage: :fader <a-d> <0-1> or :fader curve <name>"),
+                }
+            }
+            Some("cue") => {
+                // :cue <a-d>  :cue mix <0-1>  :cue vol <0-2>
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let value = parts.get(2).and_then(|v| v.parse::<f32>().ok());
+                match (parts.get(1).copied(), deck, value) {
+                    (_, Some(deck), _) => cmds.push(Command::ToggleCue(deck)),
+                    (Some("mix"), _, Some(mix)) => cmds.push(Command::SetCueMix(mix)),
+                    (Some("vol"), _, Some(vol)) => cmds.push(Command::SetHeadphoneVolume(vol)),
+                    (None, _, _) => cmds.push(Command::ToggleCue(focused_deck(state))),
+                    _ => state.set_error("Usage: :cue <a-d> or :cue <mix 0-1|vol 0-2>"),
+                }
+            }
+            Some("record") | Some("rec") => {
+                // :record start <path.wav|path.flac>  :record stop
+                match parts.get(1).copied() {
+                    Some("start") if parts.len() > 2 => {
+                        let path = parts[2..].join(" ");
+                        let path = path.trim_matches(['\'', '"']);
+                        cmds.push(Command::StartRecording(std::path::PathBuf::from(path)));
+                    }
+                    Some("stop") => cmds.push(Command::StopRecording),
+                    _ => state.set_error("Usage: :record start <file.wav|file.flac> or :record stop"),
+                }
+            }
+            Some("audio") => {
+                // :audio [list]  :audio <host|device> <name|default>
+                // :audio rate <hz|default>  :audio buffer <frames|default>
+                // :audio quality <linear|hermite|sinc>
+                let name = (parts.len() > 2).then(|| parts[2..].join(" "));
+                let name = name.as_deref().map(|n| n.trim_matches(['\'', '"']));
+                let value = match name {
+                    Some("default") => Some(None),
+                    Some(v) => v.parse::<u32>().ok().filter(|&v| v > 0).map(Some),
+                    None => None,
+                };
+                match (parts.get(1).copied(), name, value) {
+                    (None, _, _) => cmds.push(Command::ShowAudioOutput),
+                    (Some("list"), _, _) => cmds.push(Command::ListAudioOutputs),
+                    (Some("host"), Some(name), _) => cmds.push(Command::SetAudioHost(
+                        (name != "default").then(|| name.to_string()),
+                    )),
+                    (Some("device"), Some(name), _) => cmds.push(Command::SetAudioDevice(
+                        (name != "default").then(|| name.to_string()),
+                    )),
+                    (Some("rate"), _, Some(rate)) => cmds.push(Command::SetSampleRate(rate)),
+                    (Some("buffer"), _, Some(frames)) => {
+                        cmds.push(Command::SetBufferSize(frames))
+                    }
+                    (Some("quality"), Some(name), _) => match Interpolation::parse(name) {
+                        Some(mode) => cmds.push(Command::SetInterpolation(mode)),
+                        None => state.set_error("Usage: :audio quality <linear|hermite|sinc>"),
+                    },
+                    _ => state.set_error(
+                        "Usage: :audio [list] or :audio <host|device|rate|buffer|quality> <value>",
+                    ),
+                }
+            }
+            Some("assign") => {
+                // :assign <a-d> [a|b|thru]  - crossfader side, no side cycles
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                match (deck, parts.get(2).copied().map(CrossfaderAssign::parse)) {
+                    (Some(deck), None) => cmds.push(Command::CycleCrossfaderAssign(deck)),
+                    (Some(deck), Some(Some(assign))) => {
+                        cmds.push(Command::SetCrossfaderAssign(deck, assign))
+                    }
+                    _ => state.set_error("Usage: :assign <a-d> [a|b|thru]"),
+                }
+            }
+            Some("fx") => {
+                // :fx <a-d>  - list the chain; slots count from 1
+                // :fx <a-d> add <effect> [slot]  :fx <a-d> rm <slot>  :fx <a-d> move <from> <to>
+                // :fx <a-d> <slot>  - toggle   :fx <a-d> <slot> <mix|param> <value>
+                const USAGE: &str = "Usage: :fx <a-d> [add <effect> [slot]|rm <slot>|move <from> <to>|<slot> [mix|param] [value]]";
+                let slot_arg = |i: usize| {
+                    parts.get(i).and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0).map(|n| n - 1)
+                };
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                let slots = deck.map(|deck| state.channel(deck).effects).unwrap_or_default();
+                match (deck, parts.get(2).copied(), parts.get(3).copied()) {
+                    (None, _, _) => state.set_error(USAGE),
+                    (Some(deck), None, _) => {
+                        let list: Vec<String> = slots
+                            .iter()
+                            .enumerate()
+                            .map(|(i, slot)| format!("{} {}{}", i + 1, slot.name, if slot.enabled { "*" } else { "" }))
+                            .collect();
+                        state.set_message(format!("Deck {} FX: {}", deck.name(), list.join(", ")));
+                    }
+                    (Some(deck), Some("add"), Some(name)) => match EffectKind::parse(name) {
+                        Some(kind) => cmds.push(Command::AddEffect(deck, slot_arg(4).unwrap_or(usize::MAX), kind)),
+                        None => {
+                            let names: Vec<&str> = EffectKind::ALL.iter().map(|k| k.name()).collect();
+                            state.set_error(format!("Effects: {}", names.join(", ")));
+                        }
+                    },
+                    (Some(deck), Some("rm"), _) => match slot_arg(3) {
+                        Some(slot) => cmds.push(Command::RemoveEffect(deck, slot)),
+                        None => state.set_error(USAGE),
+                    },
+                    (Some(deck), Some("move"), _) => match (slot_arg(3), slot_arg(4)) {
+                        (Some(from), Some(to)) => cmds.push(Command::MoveEffect(deck, from, to)),
+                        _ => state.set_error(USAGE),
+                    },
+                    (Some(deck), Some(_), param) => match (slot_arg(2), param) {
+                        (Some(slot), _) if slot >= slots.len() => {
+                            state.set_error(format!("Deck {} has {} FX slots", deck.name(), slots.len()))
+                        }
+                        (Some(slot), None) => cmds.push(Command::ToggleEffectSlot(deck, slot)),
+                        (Some(slot), Some(param)) => {
+                            // Beats and bars can be fractions ("1/16")
+                            let value = parts.get(4).and_then(|v| parse_beats(v));
+                            let index = slots[slot].param_index(param);
+                            match (param, index, value) {
+                                ("mix", _, Some(mix)) => cmds.push(Command::SetEffectMix(deck, slot, mix)),
+                                (_, Some(index), Some(value)) => {
+                                    cmds.push(Command::SetEffectParam(deck, slot, index, value))
+                                }
+                                (_, None, _) => {
+                                    let names: Vec<&str> = slots[slot].params.iter().map(|p| p.name).collect();
+                                    state.set_error(format!("{}: mix {}", slots[slot].name, names.join(" ")));
+                                }
+                                _ => state.set_error(USAGE),
+                            }
+                        }
+                        (None, _) => state.set_error(USAGE),
+                    },
+                }
+            }
+            Some("ir") => {
+                // :ir <a-d>  - show the response   :ir <a-d> <file.wav>  :ir <a-d> wet <0-1>
+                const USAGE: &str = "Usage: :ir <a-d> [<file.wav>|wet <0-1>]";
+                let deck = parts.get(1).copied().and_then(DeckId::parse);
+                match (deck, parts.get(2).copied(), parts.get(3)) {
+                    (None, _, _) => state.set_error(USAGE),
+                    (Some(deck), None, _) => {
+                        let impulse = state.channel(deck).impulse;
+                        state.set_message(format!(
+                            "Deck {} IR: {}",
+                            deck.name(),
+                            impulse.as_deref().unwrap_or("none"),
+                        ));
+                    }
+                    (Some(deck), Some("wet"), value) => match value.and_then(|v| v.parse::<f32>().ok()) {
+                        Some(wet) => cmds.push(Command::SetImpulseWet(deck, wet)),
+                        None => state.set_error(USAGE),
+                    },
+                    (Some(deck), Some(_), _) => {
+                        let path = parts[2..].join(" ");
+                        let path = path.trim_matches(['\'', '"']);
+                        cmds.push(Command::LoadImpulse(deck, std::path::PathBuf::from(path)));
+                    }
+                }
+            }
+            Some("decks") => {
+                // :decks <2|4>  - decks on screen
+                match parts.get(1).and_then(|v| v.parse::<usize>().ok()) {
+                    Some(count @ (2 | 4)) => cmds.push(Command::SetVisibleDecks(count)),
+                    _ => state.set_error("Usage: :decks <2|4>"),
+                }
+            }
+            Some("xf") | Some("crossfader") => {
+                // :xf curve <linear|power|cut>  :xf sharp <0-1>  :xf hamster
+                match (parts.get(1).copied(), parts.get(2).copied()) {
+                    (Some("curve"), Some(name)) => match CrossfaderCurve::parse(name) {
+                        Some(curve) => cmds.push(Command::SetCrossfaderCurve(curve)),
+                        None => state.set_error("Usage: :xf curve <linear|power|cut>"),
+                    },
+                    (Some("curve"), None) => {
+                        cmds.push(Command::SetCrossfaderCurve(state.crossfader_curve.next()))
+                    }
+                    (Some("sharp"), Some(v)) => match v.parse::<f32>() {
+                        Ok(s) => cmds.push(Command::SetCrossfaderSharpness(s)),
+                        Err(_) => state.set_error("Usage: :xf sharp <0-1>"),
+                    },
+                    (Some("hamster"), _) => cmds.push(Command::ToggleHamster),
+                    _ => state.set_error("Usage: :xf <curve <name>|sharp <0-1>|hamster>"),
+                }
+            }
+            Some("quantize") | Some("qz") => {
+                // :quantize [on|off|1/8|1/4|1/2|1|bar]  - no argument toggles
+                match parts.get(1).copied() {
+                    None => cmds.push(Command::ToggleQuantize),
+                    Some("on") if !state.quantize => cmds.push(Command::ToggleQuantize),
+                    Some("off") if state.quantize => cmds.push(Command::ToggleQuantize),
+                    Some("on") | Some("off") => {}
+                    Some(res) => match QuantizeResolution::parse(res) {
+                        Some(resolution) => {
+                            cmds.push(Command::SetQuantizeResolution(resolution));
+                            if !state.quantize {
+                                cmds.push(Command::ToggleQuantize);
+                            }
+                        }
+                        None => state.set_error("Usage: :quantize <on|off|1/8|1/4|1/2|1|bar>"),
+                    },
+                }
+            }
             Some("lib") | Some("library") => cmds.push(Command::LibraryToggle),
             Some("rescan") => cmds.push(Command::LibraryRescan),
             Some("scan") => {
@@ -300,12 +749,7 @@ fn handle_command_mode(
             }
             Some("load") => {
                 if parts.len() > 2 {
-                    let deck = match parts[1] {
-                        "a" | "A" => Some(DeckId::A),
-                        "b" | "B" => Some(DeckId::B),
-                        _ => None,
-                    };
-                    if let Some(deck) = deck {
+                    if let Some(deck) = DeckId::parse(parts[1]) {
                         let path = parts[2..].join(" ");
                         cmds.push(Command::LoadTrack(deck, std::path::PathBuf::from(path)));
                     }
@@ -354,6 +798,12 @@ fn handle_effects_mode(
     if input.key_pressed(Key::T) && input.modifiers.shift {
         cmds.push(Command::TriggerTapeStart(fd));
     }
+    if input.key_pressed(Key::O) && !input.modifiers.shift {
+        cmds.push(Command::TriggerEchoOut(fd));
+    }
+    if input.key_pressed(Key::W) && !input.modifiers.shift {
+        cmds.push(Command::TriggerNoiseSweep(fd));
+    }
     if input.key_pressed(Key::G) && !input.modifiers.shift {
         cmds.push(Command::ToggleFlanger(fd));
     }
@@ -366,6 +816,21 @@ fn handle_effects_mode(
     if input.key_pressed(Key::M) && !input.modifiers.shift {
         cmds.push(Command::CycleFilterMode(fd));
     }
+    if input.key_pressed(Key::P) && !input.modifiers.shift {
+        cmds.push(Command::ToggleEffectKind(fd, EffectKind::Phaser));
+    }
+    if input.key_pressed(Key::H) && !input.modifiers.shift {
+        cmds.push(Command::ToggleEffectKind(fd, EffectKind::Chorus));
+    }
+    if input.key_pressed(Key::N) && !input.modifiers.shift {
+        cmds.push(Command::ToggleEffectKind(fd, EffectKind::AutoPan));
+    }
+    if input.key_pressed(Key::X) && !input.modifiers.shift {
+        cmds.push(Command::ToggleEffectKind(fd, EffectKind::Gate));
+    }
+    if input.key_pressed(Key::S) && !input.modifiers.shift {
+        cmds.push(Command::ToggleEffectKind(fd, EffectKind::Stutter));
+    }
 
     // Delay levels d0-d5
     // Delay levels d0-d5, Reverb levels r0-r5, Filter presets
@@ -1,4 +1,4 @@
-use ole_audio::{AudioEvent, DeckState, DelayModulation, FilterMode, FilterType, LufsValues, MasteringPreset, VinylPreset};
+use ole_audio::{AudioEvent, ChannelState, CrossfaderAssign, CrossfaderCurve, DeckId, DeckState, EqMode, Interpolation, LufsValues, MasteringPreset, OutputInfo, QuantizeResolution, RecordingStatus, SyncLeader, UpfaderCurve, DECK_COUNT};
 use ole_library::CachedAnalysis;
 use ole_analysis::CamelotKey;
 
@@ -6,16 +6,20 @@ pub const SPECTRUM_BANDS: usize = 32;
 pub const AFTERGLOW_HISTORY: usize = 15;
 pub const WATERFALL_DEPTH: usize = 256;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FocusedPane {
-    #[default]
-    DeckA,
-    DeckB,
+    Deck(DeckId),
     Crossfader,
     Effects,
     Library,
 }
 
+impl Default for FocusedPane {
+    fn default() -> Self {
+        Self::Deck(DeckId::A)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MessageType {
     #[default]
@@ -69,6 +73,78 @@ impl WaveformZoom {
     }
 }
 
+/// Tap tempo: BPM from the average interval between key taps
+#[derive(Debug, Clone, Default)]
+pub struct TapTempo {
+    taps: Vec<f64>,
+}
+
+impl TapTempo {
+    /// Taps averaged over
+    const MAX_TAPS: usize = 8;
+    /// Taps needed before a BPM is given
+    const MIN_TAPS: usize = 4;
+    /// A longer pause starts a new measurement
+    const RESET_SECS: f64 = 2.0;
+
+    /// Record a tap at `time` (seconds), returning the tapped BPM once there are enough taps
+    pub fn tap(&mut self, time: f64) -> Option<f32> {
+        if self.taps.last().is_some_and(|&last| time - last > Self::RESET_SECS) {
+            self.taps.clear();
+        }
+        if self.taps.len() == Self::MAX_TAPS {
+            self.taps.remove(0);
+        }
+        self.taps.push(time);
+        if self.taps.len() < Self::MIN_TAPS {
+            return None;
+        }
+        let span = self.taps[self.taps.len() - 1] - self.taps[0];
+        (span > 0.0).then(|| (60.0 * (self.taps.len() - 1) as f64 / span) as f32)
+    }
+}
+
+/// Per-deck display state: animation, zoom, tap tempo and meter hold
+#[derive(Debug, Clone, Default)]
+pub struct DeckView {
+    pub beat_pulse: f32,
+    prev_beat_phase: f32,
+    pub zoom: WaveformZoom,
+    pub tap: TapTempo,
+    pub vu_peak: f32,
+    vu_peak_hold_frames: u16,
+}
+
+impl DeckView {
+    fn update_beat_pulse(&mut self, beat_phase: f32) {
+        const PULSE_DECAY: f32 = 0.85;
+        if self.prev_beat_phase > 0.9 && beat_phase < 0.1 {
+            self.beat_pulse = 1.0;
+        }
+        self.prev_beat_phase = beat_phase;
+
+        self.beat_pulse *= PULSE_DECAY;
+        if self.beat_pulse < 0.01 {
+            self.beat_pulse = 0.0;
+        }
+    }
+
+    fn update_peak_hold(&mut self, peak_level: f32) {
+        const HOLD_FRAMES: u16 = 20;
+        const DECAY_RATE: f32 = 0.92;
+
+        if peak_level > self.vu_peak {
+            self.vu_peak = peak_level;
+            self.vu_peak_hold_frames = HOLD_FRAMES;
+        } else if self.vu_peak_hold_frames > 0 {
+            self.vu_peak_hold_frames -= 1;
+        } else {
+            self.vu_peak *= DECAY_RATE;
+            if self.vu_peak < 0.001 { self.vu_peak = 0.0; }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ScopeMode {
     #[default]
@@ -243,51 +319,30 @@ pub struct EnergyParticle {
 }
 
 pub struct GuiState {
-    // Deck states
-    pub deck_a: DeckState,
-    pub deck_b: DeckState,
+    // Per-deck channel state (deck, EQ, channel strip, effects), indexed by DeckId
+    pub channels: [ChannelState; DECK_COUNT],
 
     // Mixer
     pub crossfader: f32,
     pub master_volume: f32,
+    pub eq_mode: EqMode,
+
+    // Crossfader setup
+    pub upfader_curve: UpfaderCurve,
+    pub crossfader_curve: CrossfaderCurve,
+    pub crossfader_sharpness: f32,
+    pub hamster: bool,
 
-    // Filter states
-    pub filter_a_enabled: bool,
-    pub filter_a_cutoff: f32,
-    pub filter_a_type: FilterType,
-    pub filter_a_level: u8,
-    pub filter_a_mode: FilterMode,
-    pub filter_b_enabled: bool,
-    pub filter_b_cutoff: f32,
-    pub filter_b_type: FilterType,
-    pub filter_b_level: u8,
-    pub filter_b_mode: FilterMode,
-
-    // Delay
-    pub delay_a_enabled: bool,
-    pub delay_a_level: u8,
-    pub delay_a_modulation: DelayModulation,
-    pub delay_b_enabled: bool,
-    pub delay_b_level: u8,
-    pub delay_b_modulation: DelayModulation,
-
-    // Reverb
-    pub reverb_a_enabled: bool,
-    pub reverb_a_level: u8,
-    pub reverb_b_enabled: bool,
-    pub reverb_b_level: u8,
-
-    // Vinyl
-    pub vinyl_a_enabled: bool,
-    pub vinyl_a_preset: VinylPreset,
-    pub vinyl_b_enabled: bool,
-    pub vinyl_b_preset: VinylPreset,
-
-    // Time stretch
-    pub time_stretch_a_enabled: bool,
-    pub time_stretch_a_ratio: f32,
-    pub time_stretch_b_enabled: bool,
-    pub time_stretch_b_ratio: f32,
+    // Headphone cue
+    pub cue_mix: f32,
+    pub headphone_volume: f32,
+
+    // Recording
+    pub recording: RecordingStatus,
+
+    // Audio output (None until the first stream opens)
+    pub output: Option<OutputInfo>,
+    pub interpolation: Interpolation,
 
     // Mastering
     pub mastering_enabled: bool,
@@ -295,9 +350,21 @@ pub struct GuiState {
     pub mastering_lufs: LufsValues,
     pub mastering_gain_reduction: f32,
 
+    // Quantize
+    pub quantize: bool,
+    pub quantize_resolution: QuantizeResolution,
+
+    // Sync lock
+    pub sync_leader: SyncLeader,
+    pub master_bpm: f32,
+
     // UI state
     pub mode: ole_input::Mode,
     pub focused: FocusedPane,
+    /// Deck reversed by a plain Q press, cleared when Q is released
+    pub reverse_held: Option<DeckId>,
+    /// Decks on screen: 2 (A and B) or 4
+    pub visible_decks: usize,
     pub command_buffer: String,
     pub message: Option<String>,
     pub message_type: MessageType,
@@ -314,14 +381,9 @@ pub struct GuiState {
 
     // Animation
     pub frame_count: u64,
-    pub beat_pulse_a: f32,
-    pub beat_pulse_b: f32,
-    prev_beat_phase_a: f32,
-    prev_beat_phase_b: f32,
 
-    // Waveform zoom
-    pub zoom_a: WaveformZoom,
-    pub zoom_b: WaveformZoom,
+    // Per-deck display state (beat pulse, zoom, tap tempo, VU peak hold)
+    pub views: [DeckView; DECK_COUNT],
 
     // Sync quality
     pub sync_quality: f32,
@@ -330,12 +392,6 @@ pub struct GuiState {
     pub spectrum_history: [[f32; AFTERGLOW_HISTORY]; SPECTRUM_BANDS],
     pub spectrum_history_idx: usize,
 
-    // VU peak hold
-    pub vu_peak_a: f32,
-    pub vu_peak_b: f32,
-    vu_peak_hold_frames_a: u16,
-    vu_peak_hold_frames_b: u16,
-
     // Waterfall (scrolling spectrogram)
     pub waterfall_a: Box<[[f32; SPECTRUM_BANDS]; WATERFALL_DEPTH]>,
     pub waterfall_b: Box<[[f32; SPECTRUM_BANDS]; WATERFALL_DEPTH]>,
@@ -363,44 +419,34 @@ pub struct GuiState {
 impl Default for GuiState {
     fn default() -> Self {
         Self {
-            deck_a: DeckState::default(),
-            deck_b: DeckState::default(),
+            channels: DeckId::ALL.map(|deck| ChannelState {
+                assign: CrossfaderAssign::default_for(deck),
+                ..ChannelState::default()
+            }),
             crossfader: 0.0,
             master_volume: 1.0,
-            filter_a_enabled: false,
-            filter_a_cutoff: 1000.0,
-            filter_a_type: FilterType::LowPass,
-            filter_a_level: 0,
-            filter_a_mode: FilterMode::default(),
-            filter_b_enabled: false,
-            filter_b_cutoff: 1000.0,
-            filter_b_type: FilterType::LowPass,
-            filter_b_level: 0,
-            filter_b_mode: FilterMode::default(),
-            delay_a_enabled: false,
-            delay_a_level: 0,
-            delay_a_modulation: DelayModulation::default(),
-            delay_b_enabled: false,
-            delay_b_level: 0,
-            delay_b_modulation: DelayModulation::default(),
-            reverb_a_enabled: false,
-            reverb_a_level: 0,
-            reverb_b_enabled: false,
-            reverb_b_level: 0,
-            vinyl_a_enabled: false,
-            vinyl_a_preset: VinylPreset::default(),
-            vinyl_b_enabled: false,
-            vinyl_b_preset: VinylPreset::default(),
-            time_stretch_a_enabled: false,
-            time_stretch_a_ratio: 1.0,
-            time_stretch_b_enabled: false,
-            time_stretch_b_ratio: 1.0,
+            eq_mode: EqMode::default(),
+            upfader_curve: UpfaderCurve::default(),
+            crossfader_curve: CrossfaderCurve::default(),
+            crossfader_sharpness: 0.0,
+            hamster: false,
+            cue_mix: 0.0,
+            headphone_volume: 1.0,
+            recording: RecordingStatus::Idle,
+            output: None,
+            interpolation: Interpolation::default(),
             mastering_enabled: true,
             mastering_preset: MasteringPreset::default(),
             mastering_lufs: LufsValues::default(),
             mastering_gain_reduction: 0.0,
+            quantize: false,
+            quantize_resolution: QuantizeResolution::default(),
+            sync_leader: SyncLeader::default(),
+            master_bpm: 120.0,
             mode: ole_input::Mode::Normal,
-            focused: FocusedPane::DeckA,
+            focused: FocusedPane::default(),
+            reverse_held: None,
+            visible_decks: 2,
             command_buffer: String::new(),
             message: None,
             message_type: MessageType::Info,
@@ -411,19 +457,10 @@ impl Default for GuiState {
             show_scope: false,
             scope_mode: ScopeMode::default(),
             frame_count: 0,
-            beat_pulse_a: 0.0,
-            beat_pulse_b: 0.0,
-            prev_beat_phase_a: 0.0,
-            prev_beat_phase_b: 0.0,
-            zoom_a: WaveformZoom::default(),
-            zoom_b: WaveformZoom::default(),
+            views: Default::default(),
             sync_quality: 0.0,
             spectrum_history: [[0.0; AFTERGLOW_HISTORY]; SPECTRUM_BANDS],
             spectrum_history_idx: 0,
-            vu_peak_a: 0.0,
-            vu_peak_b: 0.0,
-            vu_peak_hold_frames_a: 0,
-            vu_peak_hold_frames_b: 0,
             waterfall_a: Box::new([[0.0; SPECTRUM_BANDS]; WATERFALL_DEPTH]),
             waterfall_b: Box::new([[0.0; SPECTRUM_BANDS]; WATERFALL_DEPTH]),
             waterfall_idx: 0,
@@ -445,91 +482,119 @@ impl GuiState {
     pub fn handle_audio_event(&mut self, event: AudioEvent) {
         match event {
             AudioEvent::StateUpdate {
-                deck_a,
-                deck_b,
+                channels,
                 crossfader,
                 master_volume,
-                filter_a_enabled,
-                filter_a_cutoff,
-                filter_a_type,
-                filter_a_level,
-                filter_a_mode,
-                filter_b_enabled,
-                filter_b_cutoff,
-                filter_b_type,
-                filter_b_level,
-                filter_b_mode,
-                delay_a_enabled,
-                delay_a_level,
-                delay_a_modulation,
-                delay_b_enabled,
-                delay_b_level,
-                delay_b_modulation,
-                reverb_a_enabled,
-                reverb_a_level,
-                reverb_b_enabled,
-                reverb_b_level,
-                vinyl_a_enabled,
-                vinyl_a_preset,
-                vinyl_b_enabled,
-                vinyl_b_preset,
-                time_stretch_a_enabled,
-                time_stretch_a_ratio,
-                time_stretch_b_enabled,
-                time_stretch_b_ratio,
+                eq_mode,
+                upfader_curve,
+                crossfader_curve,
+                crossfader_sharpness,
+                hamster,
+                cue_mix,
+                headphone_volume,
+                recording,
+                interpolation,
                 mastering_enabled,
                 mastering_preset,
                 mastering_lufs,
                 mastering_gain_reduction,
+                quantize,
+                quantize_resolution,
+                sync_leader,
+                master_bpm,
             } => {
-                self.deck_a = *deck_a;
-                self.deck_b = *deck_b;
+                self.channels = *channels;
                 self.crossfader = crossfader;
                 self.master_volume = master_volume;
-                self.filter_a_enabled = filter_a_enabled;
-                self.filter_a_cutoff = filter_a_cutoff;
-                self.filter_a_type = filter_a_type;
-                self.filter_a_level = filter_a_level;
-                self.filter_a_mode = filter_a_mode;
-                self.filter_b_enabled = filter_b_enabled;
-                self.filter_b_cutoff = filter_b_cutoff;
-                self.filter_b_type = filter_b_type;
-                self.filter_b_level = filter_b_level;
-                self.filter_b_mode = filter_b_mode;
-                self.delay_a_enabled = delay_a_enabled;
-                self.delay_a_level = delay_a_level;
-                self.delay_a_modulation = delay_a_modulation;
-                self.delay_b_enabled = delay_b_enabled;
-                self.delay_b_level = delay_b_level;
-                self.delay_b_modulation = delay_b_modulation;
-                self.reverb_a_enabled = reverb_a_enabled;
-                self.reverb_a_level = reverb_a_level;
-                self.reverb_b_enabled = reverb_b_enabled;
-                self.reverb_b_level = reverb_b_level;
-                self.vinyl_a_enabled = vinyl_a_enabled;
-                self.vinyl_a_preset = vinyl_a_preset;
-                self.vinyl_b_enabled = vinyl_b_enabled;
-                self.vinyl_b_preset = vinyl_b_preset;
-                self.time_stretch_a_enabled = time_stretch_a_enabled;
-                self.time_stretch_a_ratio = time_stretch_a_ratio;
-                self.time_stretch_b_enabled = time_stretch_b_enabled;
-                self.time_stretch_b_ratio = time_stretch_b_ratio;
+                self.eq_mode = eq_mode;
+                self.upfader_curve = upfader_curve;
+                self.crossfader_curve = crossfader_curve;
+                self.crossfader_sharpness = crossfader_sharpness;
+                self.hamster = hamster;
+                self.cue_mix = cue_mix;
+                self.headphone_volume = headphone_volume;
+                self.update_recording(*recording);
+                self.interpolation = interpolation;
                 self.mastering_enabled = mastering_enabled;
                 self.mastering_preset = mastering_preset;
                 self.mastering_lufs = mastering_lufs;
                 self.mastering_gain_reduction = mastering_gain_reduction;
+                self.quantize = quantize;
+                self.quantize_resolution = quantize_resolution;
+                self.sync_leader = sync_leader;
+                self.master_bpm = master_bpm;
             }
             AudioEvent::TrackLoaded { deck } => {
-                self.set_success(format!("Track loaded to deck {}", deck));
+                self.set_success(format!("Track loaded to deck {}", deck.name()));
                 self.glitch_frames = 8;
                 self.glitch_intensity = 1.0;
             }
+            AudioEvent::Outputs(outputs) => {
+                if outputs.is_empty() {
+                    self.set_warning("No audio outputs found");
+                } else {
+                    let list: Vec<String> = outputs
+                        .iter()
+                        .map(|o| {
+                            let default = if o.is_default { "*" } else { "" };
+                            format!("{}: {}{}", o.host, o.name, default)
+                        })
+                        .collect();
+                    self.set_message(format!("Outputs: {}", list.join(" | ")));
+                }
+            }
+            AudioEvent::OutputOpened(info) => {
+                let desc = describe_output(&info);
+                if info.fallback {
+                    self.set_warning(format!("{} (configured device missing)", desc));
+                } else if info.engine_restarted && self.output.is_some() {
+                    self.set_warning(format!("{} (new sample rate: mixer and effects reset)", desc));
+                } else if self.output.is_some() {
+                    // Reconnected or switched; the first open stays quiet
+                    self.set_success(desc);
+                }
+                self.output = Some(info);
+            }
+            AudioEvent::OutputLost(msg) => {
+                self.set_error(format!("Audio output lost ({}), reconnecting...", msg));
+            }
             AudioEvent::Error(msg) => {
                 self.set_error(format!("Error: {}", msg));
             }
         }
     }
 
+    /// Describe the output that is playing
+    pub fn output_description(&self) -> String {
+        match self.output {
+            Some(ref info) => format!(
+                "{}, {} interpolation",
+                describe_output(info),
+                self.interpolation.name()
+            ),
+            None => "Audio: no output".to_string(),
+        }
+    }
+
+    /// Take the engine's recording status, reporting starts, saves and failures
+    fn update_recording(&mut self, recording: RecordingStatus) {
+        if std::mem::discriminant(&recording) != std::mem::discriminant(&self.recording) {
+            match &recording {
+                RecordingStatus::Recording { path, .. } => {
+                    self.set_message(format!("Recording to {}", path))
+                }
+                RecordingStatus::Saved { path, tracks } => self.set_success(format!(
+                    "Saved {} ({} tracks in the CUE sheet)",
+                    path,
+                    tracks
+                )),
+                RecordingStatus::Failed(msg) => self.set_error(msg.to_string()),
+                RecordingStatus::Idle | RecordingStatus::Finishing { .. } => {}
+            }
+        }
+        self.recording = recording;
+    }
+
     pub fn set_mode(&mut self, mode: ole_input::Mode) {
         self.mode = mode;
         if mode != ole_input::Mode::Command {
@@ -587,42 +652,57 @@ impl GuiState {
 
     pub fn cycle_focus(&mut self) {
         self.focused = match self.focused {
-            FocusedPane::DeckA => FocusedPane::DeckB,
-            FocusedPane::DeckB => FocusedPane::DeckA,
-            _ => FocusedPane::DeckA,
+            FocusedPane::Deck(deck) => {
+                let next = (deck.index() + 1) % self.visible_decks;
+                FocusedPane::Deck(DeckId::from_index(next).unwrap_or_default())
+            }
+            _ => FocusedPane::default(),
         };
     }
 
-    pub fn update_animations(&mut self) {
-        self.frame_count = self.frame_count.wrapping_add(1);
-
-        // Beat pulse
-        const PULSE_DECAY: f32 = 0.85;
-        if self.prev_beat_phase_a > 0.9 && self.deck_a.beat_phase < 0.1 {
-            self.beat_pulse_a = 1.0;
+    /// Show 2 or 4 decks, moving focus off a deck that is hidden
+    pub fn set_visible_decks(&mut self, count: usize) {
+        self.visible_decks = count.clamp(2, DECK_COUNT);
+        if let FocusedPane::Deck(deck) = self.focused {
+            if deck.index() >= self.visible_decks {
+                self.focused = FocusedPane::default();
+            }
         }
-        self.prev_beat_phase_a = self.deck_a.beat_phase;
+    }
 
-        if self.prev_beat_phase_b > 0.9 && self.deck_b.beat_phase < 0.1 {
-            self.beat_pulse_b = 1.0;
-        }
-        self.prev_beat_phase_b = self.deck_b.beat_phase;
+    /// Decks on screen, in order
+    pub fn visible_deck_ids(&self) -> impl Iterator<Item = DeckId> {
+        DeckId::ALL.into_iter().take(self.visible_decks)
+    }
 
-        self.beat_pulse_a *= PULSE_DECAY;
-        self.beat_pulse_b *= PULSE_DECAY;
-        if self.beat_pulse_a < 0.01 {
-            self.beat_pulse_a = 0.0;
-        }
-        if self.beat_pulse_b < 0.01 {
-            self.beat_pulse_b = 0.0;
+    pub fn channel(&self, deck: DeckId) -> &ChannelState {
+        &self.channels[deck.index()]
+    }
+
+    pub fn deck(&self, deck: DeckId) -> &DeckState {
+        &self.channels[deck.index()].deck
+    }
+
+    pub fn view(&self, deck: DeckId) -> &DeckView {
+        &self.views[deck.index()]
+    }
+
+    pub fn view_mut(&mut self, deck: DeckId) -> &mut DeckView {
+        &mut self.views[deck.index()]
+    }
+
+    pub fn update_animations(&mut self) {
+        self.frame_count = self.frame_count.wrapping_add(1);
+
+        // Beat pulse and VU peak hold
+        for (view, channel) in self.views.iter_mut().zip(&self.channels) {
+            view.update_beat_pulse(channel.deck.beat_phase);
+            view.update_peak_hold(channel.deck.peak_level);
         }
 
         // Sync quality
         self.sync_quality = self.calculate_sync_quality();
 
-        // VU peak hold
-        self.update_peak_hold();
-
         // Spectrum afterglow
         self.update_spectrum_history();
 
@@ -645,53 +725,31 @@ impl GuiState {
     }
 
     fn calculate_sync_quality(&self) -> f32 {
-        let has_grid_a = self.deck_a.beat_grid_info.as_ref().is_some_and(|g| g.has_grid);
-        let has_grid_b = self.deck_b.beat_grid_info.as_ref().is_some_and(|g| g.has_grid);
+        let (deck_a, deck_b) = (self.deck(DeckId::A), self.deck(DeckId::B));
+        let has_grid_a = deck_a.beat_grid_info.as_ref().is_some_and(|g| g.has_grid);
+        let has_grid_b = deck_b.beat_grid_info.as_ref().is_some_and(|g| g.has_grid);
         if !has_grid_a || !has_grid_b {
             return 0.0;
         }
-        let phase_diff = (self.deck_a.beat_phase - self.deck_b.beat_phase).abs();
+        let phase_diff = (deck_a.beat_phase - deck_b.beat_phase).abs();
         let normalized = if phase_diff > 0.5 { 1.0 - phase_diff } else { phase_diff };
         1.0 - (normalized * 2.0)
     }
 
-    fn update_peak_hold(&mut self) {
-        const HOLD_FRAMES: u16 = 20;
-        const DECAY_RATE: f32 = 0.92;
-
-        if self.deck_a.peak_level > self.vu_peak_a {
-            self.vu_peak_a = self.deck_a.peak_level;
-            self.vu_peak_hold_frames_a = HOLD_FRAMES;
-        } else if self.vu_peak_hold_frames_a > 0 {
-            self.vu_peak_hold_frames_a -= 1;
-        } else {
-            self.vu_peak_a *= DECAY_RATE;
-            if self.vu_peak_a < 0.001 { self.vu_peak_a = 0.0; }
-        }
-
-        if self.deck_b.peak_level > self.vu_peak_b {
-            self.vu_peak_b = self.deck_b.peak_level;
-            self.vu_peak_hold_frames_b = HOLD_FRAMES;
-        } else if self.vu_peak_hold_frames_b > 0 {
-            self.vu_peak_hold_frames_b -= 1;
-        } else {
-            self.vu_peak_b *= DECAY_RATE;
-            if self.vu_peak_b < 0.001 { self.vu_peak_b = 0.0; }
-        }
-    }
-
     fn update_spectrum_history(&mut self) {
-        for (i, &value) in self.deck_a.spectrum.bands.iter().take(SPECTRUM_BANDS).enumerate() {
+        let bands_a = &self.channels[DeckId::A.index()].deck.spectrum.bands;
+        let bands_b = &self.channels[DeckId::B.index()].deck.spectrum.bands;
+        for (i, &value) in bands_a.iter().take(SPECTRUM_BANDS).enumerate() {
             self.spectrum_history[i][self.spectrum_history_idx] = value;
         }
         self.spectrum_history_idx = (self.spectrum_history_idx + 1) % AFTERGLOW_HISTORY;
 
         // Update waterfall (scrolling spectrogram)
         let wf_idx = self.waterfall_idx;
-        for (i, &value) in self.deck_a.spectrum.bands.iter().take(SPECTRUM_BANDS).enumerate() {
+        for (i, &value) in bands_a.iter().take(SPECTRUM_BANDS).enumerate() {
             self.waterfall_a[wf_idx][i] = value;
         }
-        for (i, &value) in self.deck_b.spectrum.bands.iter().take(SPECTRUM_BANDS).enumerate() {
+        for (i, &value) in bands_b.iter().take(SPECTRUM_BANDS).enumerate() {
             self.waterfall_b[wf_idx][i] = value;
         }
         self.waterfall_idx = (self.waterfall_idx + 1) % WATERFALL_DEPTH;
@@ -699,15 +757,17 @@ impl GuiState {
 
     pub fn update_energy_particles(&mut self) {
         let crossfader_norm = (self.crossfader + 1.0) / 2.0; // -1..1 → 0..1
-        let energy_a = self.deck_a.peak_level;
-        let energy_b = self.deck_b.peak_level;
+        let energy_a = self.deck(DeckId::A).peak_level;
+        let energy_b = self.deck(DeckId::B).peak_level;
+        let pulse_a = self.view(DeckId::A).beat_pulse;
+        let pulse_b = self.view(DeckId::B).beat_pulse;
         let total_energy = (energy_a + energy_b).max(0.001);
 
         // Spawn particles on beat pulses
-        if self.beat_pulse_a > 0.8 || self.beat_pulse_b > 0.8 {
+        if pulse_a > 0.8 || pulse_b > 0.8 {
             let spawn_count = (total_energy * 4.0).ceil() as usize;
             for j in 0..spawn_count.min(8) {
-                let from_a = self.beat_pulse_a > self.beat_pulse_b;
+                let from_a = pulse_a > pulse_b;
                 let base_pos = if from_a { 0.0 } else { 1.0 };
                 self.energy_particles.push(EnergyParticle {
                     pos: base_pos,
@@ -754,3 +814,15 @@ impl GuiState {
         }
     }
 }
+
+/// "Audio: <device> (<host>) at <rate> Hz[, <n> frames]"
+fn describe_output(info: &OutputInfo) -> String {
+    let buffer = match info.buffer_size {
+        Some(frames) => format!(", {} frames", frames),
+        None => String::new(),
+    };
+    format!(
+        "Audio: {} ({}) at {} Hz{}",
+        info.device, info.host, info.sample_rate, buffer
+    )
+}
@@ -1,4 +1,5 @@
 use egui::{Color32, FontFamily, FontId, Stroke, TextStyle, Visuals};
+use ole_audio::DeckId;
 
 pub struct CyberTheme;
 
@@ -16,6 +17,8 @@ pub const WARNING: Color32 = Color32::from_rgb(0xff, 0xff, 0x00);
 pub const DANGER: Color32 = Color32::from_rgb(0xff, 0x32, 0x32);
 pub const DECK_A: Color32 = PRIMARY;
 pub const DECK_B: Color32 = ACCENT_CYAN;
+pub const DECK_C: Color32 = Color32::from_rgb(0xff, 0x99, 0x00);
+pub const DECK_D: Color32 = Color32::from_rgb(0xb0, 0x60, 0xff);
 
 impl CyberTheme {
     pub fn apply(ctx: &egui::Context) {
@@ -78,8 +81,13 @@ impl CyberTheme {
         ctx.set_style(style);
     }
 
-    pub fn deck_color(deck_a: bool) -> Color32 {
-        if deck_a { DECK_A } else { DECK_B }
+    pub fn deck_color(deck: DeckId) -> Color32 {
+        match deck {
+            DeckId::A => DECK_A,
+            DeckId::B => DECK_B,
+            DeckId::C => DECK_C,
+            DeckId::D => DECK_D,
+        }
     }
 
     pub fn meter_color(level: f32) -> Color32 {
@@ -1,8 +1,9 @@
-use egui::{Rect, Sense, Ui, Vec2};
+use egui::{Color32, Rect, Sense, Ui, Vec2};
 
 use crate::theme;
 
-pub fn draw_crossfader(ui: &mut Ui, value: &mut f32) -> bool {
+/// Horizontal crossfader; `reversed` (hamster mode) swaps the deck labels
+pub fn draw_crossfader(ui: &mut Ui, value: &mut f32, reversed: bool) -> bool {
     let desired_size = Vec2::new(ui.available_width().min(200.0), 20.0);
     let (response, painter) = ui.allocate_painter(desired_size, Sense::drag());
     let rect = response.rect;
@@ -45,20 +46,62 @@ pub fn draw_crossfader(ui: &mut Ui, value: &mut f32) -> bool {
     painter.rect_filled(handle_rect, 2.0, handle_color);
 
     // A/B labels
+    let (left, right) = if reversed {
+        (("B", theme::DECK_B), ("A", theme::DECK_A))
+    } else {
+        (("A", theme::DECK_A), ("B", theme::DECK_B))
+    };
     painter.text(
         egui::pos2(rect.left() + 2.0, rect.center().y),
         egui::Align2::LEFT_CENTER,
-        "A",
+        left.0,
         egui::FontId::monospace(10.0),
-        theme::DECK_A,
+        left.1,
     );
     painter.text(
         egui::pos2(rect.right() - 2.0, rect.center().y),
         egui::Align2::RIGHT_CENTER,
-        "B",
+        right.0,
         egui::FontId::monospace(10.0),
-        theme::DECK_B,
+        right.1,
     );
 
     changed
 }
+
+/// Vertical channel upfader (0.0 - 1.0)
+pub fn draw_upfader(ui: &mut Ui, value: &mut f32, color: Color32) -> bool {
+    let desired_size = Vec2::new(16.0, 64.0);
+    let (response, painter) = ui.allocate_painter(desired_size, Sense::drag());
+    let rect = response.rect;
+
+    let mut changed = false;
+
+    // Track
+    let track_rect = Rect::from_min_max(
+        egui::pos2(rect.center().x - 2.0, rect.top() + 4.0),
+        egui::pos2(rect.center().x + 2.0, rect.bottom() - 4.0),
+    );
+    painter.rect_filled(track_rect, 2.0, theme::DIM);
+
+    // Drag interaction
+    if response.dragged() {
+        let delta = -response.drag_delta().y / track_rect.height();
+        *value = (*value + delta).clamp(0.0, 1.0);
+        changed = true;
+    }
+
+    // Level fill and handle
+    let pos_y = track_rect.bottom() - *value * track_rect.height();
+    painter.rect_filled(
+        Rect::from_min_max(egui::pos2(track_rect.left(), pos_y), track_rect.max),
+        2.0,
+        color.gamma_multiply(0.4),
+    );
+    let handle_rect =
+        Rect::from_center_size(egui::pos2(rect.center().x, pos_y), Vec2::new(14.0, 6.0));
+    let handle_color = if response.dragged() { color } else { theme::TEXT };
+    painter.rect_filled(handle_rect, 2.0, handle_color);
+
+    changed
+}
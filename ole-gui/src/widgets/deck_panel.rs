@@ -10,17 +10,13 @@ use super::transport::draw_transport;
 pub struct DeckPanel;
 
 impl DeckPanel {
-    pub fn show(ui: &mut Ui, state: &mut GuiState, is_deck_a: bool) -> Option<Command> {
+    pub fn show(ui: &mut Ui, state: &mut GuiState, deck: DeckId) -> Option<Command> {
         let mut command = None;
-        let deck_color = theme::CyberTheme::deck_color(is_deck_a);
-        let focused = if is_deck_a {
-            state.focused == FocusedPane::DeckA
-        } else {
-            state.focused == FocusedPane::DeckB
-        };
+        let deck_color = theme::CyberTheme::deck_color(deck);
+        let focused = state.focused == FocusedPane::Deck(deck);
 
         let border_color = if focused { deck_color } else { theme::DIM };
-        let label = if is_deck_a { "DECK A" } else { "DECK B" };
+        let label = format!("DECK {}", deck.name());
 
         Frame::none()
             .stroke(egui::Stroke::new(1.0, border_color))
@@ -34,7 +30,7 @@ impl DeckPanel {
                             .strong()
                             .monospace(),
                     );
-                    let d = if is_deck_a { &state.deck_a } else { &state.deck_b };
+                    let d = state.deck(deck);
                     if let Some(ref name) = d.track_name {
                         ui.label(
                             egui::RichText::new(format!("\"{}\"", name))
@@ -44,7 +40,7 @@ impl DeckPanel {
                     }
                     if let Some(ref key) = d.key {
                         ui.label(
-                            egui::RichText::new(key)
+                            egui::RichText::new(key.as_str())
                                 .color(theme::ACCENT_CYAN)
                                 .monospace(),
                         );
@@ -57,38 +53,35 @@ impl DeckPanel {
                                 .monospace(),
                         );
                     }
+                    if let (Some(bar), Some(beat)) = (d.bar, d.beat_in_bar) {
+                        // Bar.beat, counting from 1 like a sequencer
+                        ui.label(
+                            egui::RichText::new(format!("{}.{}", bar + 1, beat + 1))
+                                .color(theme::TEXT_DIM)
+                                .monospace(),
+                        );
+                    }
                 });
 
                 // Waveform (click-to-seek)
-                if let Some(seek_frac) = draw_waveform(ui, state, is_deck_a) {
-                    let deck = if is_deck_a { DeckId::A } else { DeckId::B };
+                if let Some(seek_frac) = draw_waveform(ui, state, deck) {
                     command = Some(Command::Seek(deck, seek_frac));
                 }
 
                 // Transport + VU meter row
                 ui.horizontal(|ui| {
-                    let d = if is_deck_a { &state.deck_a } else { &state.deck_b };
+                    let d = state.deck(deck);
                     draw_transport(ui, d.playback, d.position, d.duration);
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        let peak = if is_deck_a { state.vu_peak_a } else { state.vu_peak_b };
-                        let clipping = if is_deck_a {
-                            state.deck_a.is_clipping
-                        } else {
-                            state.deck_b.is_clipping
-                        };
-                        let level = if is_deck_a {
-                            state.deck_a.peak_level
-                        } else {
-                            state.deck_b.peak_level
-                        };
-                        draw_vu_meter(ui, level, peak, clipping);
+                        let peak = state.view(deck).vu_peak;
+                        draw_vu_meter(ui, d.peak_level, peak, d.is_clipping);
                     });
                 });
 
                 // Tempo + Gain
                 ui.horizontal(|ui| {
-                    let d = if is_deck_a { &state.deck_a } else { &state.deck_b };
+                    let d = state.deck(deck);
                     let tempo_pct = (d.tempo - 1.0) * 100.0;
                     let sign = if tempo_pct >= 0.0 { "+" } else { "" };
                     ui.label(
@@ -101,6 +94,45 @@ impl DeckPanel {
                             .color(theme::TEXT)
                             .monospace(),
                     );
+                    if d.key_lock {
+                        ui.label(
+                            egui::RichText::new("KEY LOCK")
+                                .color(theme::ACCENT_CYAN)
+                                .monospace(),
+                        );
+                    }
+                    if let Some(lp) = d.loop_info.filter(|l| l.active) {
+                        let len = match lp.beats {
+                            Some(b) if b >= 1.0 => format!(" {}", b),
+                            Some(b) => format!(" 1/{}", (1.0 / b).round()),
+                            None => String::new(),
+                        };
+                        let label = if lp.rolling { "ROLL" } else { "LOOP" };
+                        ui.label(
+                            egui::RichText::new(format!("{}{}", label, len))
+                                .color(theme::WARNING)
+                                .monospace(),
+                        );
+                    }
+                    if d.slip || d.reverse {
+                        let label = match (d.slip, d.reverse) {
+                            (true, true) => "SLIP REV",
+                            (true, false) => "SLIP",
+                            _ => "REV",
+                        };
+                        ui.label(
+                            egui::RichText::new(label)
+                                .color(theme::WARNING)
+                                .monospace(),
+                        );
+                    }
+                    if d.pitch != 0.0 {
+                        ui.label(
+                            egui::RichText::new(format!("Key {:+.2}", d.pitch))
+                                .color(theme::ACCENT_CYAN)
+                                .monospace(),
+                        );
+                    }
                 });
             });
 
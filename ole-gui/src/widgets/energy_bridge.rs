@@ -1,4 +1,5 @@
 use egui::{Color32, Ui, Vec2};
+use ole_audio::DeckId;
 
 use crate::state::GuiState;
 use crate::theme;
@@ -15,8 +16,8 @@ impl EnergyBridge {
 
         let center_y = rect.center().y;
         let crossfader_norm = (state.crossfader + 1.0) / 2.0; // -1..1 → 0..1
-        let energy_a = state.deck_a.peak_level.min(1.0);
-        let energy_b = state.deck_b.peak_level.min(1.0);
+        let energy_a = state.deck(DeckId::A).peak_level.min(1.0);
+        let energy_b = state.deck(DeckId::B).peak_level.min(1.0);
         let total_energy = (energy_a + energy_b) * 0.5;
         let sync = state.sync_quality;
         let frame = state.frame_count;
@@ -87,7 +88,7 @@ impl EnergyBridge {
         // Crossfader position indicator: a bright point on the bridge
         let cf_x = bridge_left + crossfader_norm * bridge_width;
         let cf_y = center_y + (crossfader_norm * 6.0 + time).sin() * wave_amp;
-        let cf_pulse = 2.0 + (state.beat_pulse_a.max(state.beat_pulse_b)) * 4.0;
+        let cf_pulse = 2.0 + (state.view(DeckId::A).beat_pulse.max(state.view(DeckId::B).beat_pulse)) * 4.0;
 
         // Glow around crossfader point
         let glow_color = Color32::from_rgba_unmultiplied(0xff, 0xff, 0xff, 30);
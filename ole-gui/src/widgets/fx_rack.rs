@@ -1,16 +1,17 @@
 use crossbeam_channel::Sender;
 use egui::Ui;
 
-use ole_audio::AudioCommand;
+use ole_audio::{AudioCommand, DeckId, EffectSlotState};
 use crate::state::GuiState;
 use crate::theme;
 
 pub struct FxRack;
 
 impl FxRack {
-    pub fn show(ui: &mut Ui, state: &mut GuiState, _cmd_tx: &Sender<AudioCommand>, is_deck_a: bool) {
-        let label = if is_deck_a { "FX DECK A" } else { "FX DECK B" };
-        let deck_color = theme::CyberTheme::deck_color(is_deck_a);
+    pub fn show(ui: &mut Ui, state: &mut GuiState, _cmd_tx: &Sender<AudioCommand>, deck: DeckId) {
+        let label = format!("FX DECK {}", deck.name());
+        let deck_color = theme::CyberTheme::deck_color(deck);
+        let channel = state.channel(deck);
 
         ui.vertical(|ui| {
             ui.label(
@@ -20,39 +21,20 @@ impl FxRack {
                     .monospace(),
             );
 
-            // Filter
-            let (filter_en, filter_lvl) = if is_deck_a {
-                (state.filter_a_enabled, state.filter_a_level)
-            } else {
-                (state.filter_b_enabled, state.filter_b_level)
-            };
-            Self::fx_toggle(ui, "FILT", filter_en, filter_lvl, deck_color);
-
-            // Delay
-            let (delay_en, delay_lvl) = if is_deck_a {
-                (state.delay_a_enabled, state.delay_a_level)
-            } else {
-                (state.delay_b_enabled, state.delay_b_level)
-            };
-            Self::fx_toggle(ui, "DLY", delay_en, delay_lvl, deck_color);
-
-            // Reverb
-            let (reverb_en, reverb_lvl) = if is_deck_a {
-                (state.reverb_a_enabled, state.reverb_a_level)
-            } else {
-                (state.reverb_b_enabled, state.reverb_b_level)
-            };
-            Self::fx_toggle(ui, "VERB", reverb_en, reverb_lvl, deck_color);
+            // Effect chain, in processing order
+            for (i, slot) in channel.effects.iter().enumerate() {
+                Self::fx_slot(ui, i + 1, slot, deck_color);
+            }
         });
     }
 
-    fn fx_toggle(ui: &mut Ui, name: &str, enabled: bool, level: u8, color: egui::Color32) {
+    fn fx_slot(ui: &mut Ui, number: usize, slot: &EffectSlotState, color: egui::Color32) {
         ui.horizontal(|ui| {
-            let text_color = if enabled { color } else { theme::TEXT_DIM };
-            let status = if enabled {
-                format!("[{}] {}", name, level)
-            } else {
-                format!("[{}] OFF", name)
+            let text_color = if slot.enabled { color } else { theme::TEXT_DIM };
+            let status = match (slot.enabled, slot.mix < 1.0) {
+                (true, true) => format!("{} [{}] {:.0}%", number, slot.name, slot.mix * 100.0),
+                (true, false) => format!("{} [{}] ON", number, slot.name),
+                (false, _) => format!("{} [{}] OFF", number, slot.name),
             };
             ui.label(egui::RichText::new(status).color(text_color).monospace());
         });
@@ -1,10 +1,10 @@
 use crossbeam_channel::Sender;
-use egui::Ui;
+use egui::{Color32, Ui};
 
-use ole_audio::AudioCommand;
+use ole_audio::{AudioCommand, ChannelEq, DeckId, EqBand, Mixer};
 use crate::state::GuiState;
 use crate::theme;
-use crate::widgets::crossfader::draw_crossfader;
+use crate::widgets::crossfader::{draw_crossfader, draw_upfader};
 use crate::widgets::knob::knob;
 
 pub struct MixerPanel;
@@ -19,48 +19,196 @@ impl MixerPanel {
                     .monospace(),
             );
 
-            // Master volume knob
-            let mut vol = state.master_volume;
-            if knob(ui, "master_vol", &mut vol, 0.0..=2.0, "MASTER", theme::PRIMARY) {
-                let _ = cmd_tx.send(AudioCommand::SetMasterVolume(vol));
-                state.master_volume = vol;
-            }
+            // Master volume and headphone knobs
+            ui.horizontal(|ui| {
+                let mut vol = state.master_volume;
+                if knob(ui, "master_vol", &mut vol, 0.0..=2.0, "MASTER", theme::PRIMARY) {
+                    let _ = cmd_tx.send(AudioCommand::SetMasterVolume(vol));
+                    state.master_volume = vol;
+                }
 
-            ui.add_space(4.0);
+                let mut mix = state.cue_mix;
+                if knob(ui, "cue_mix", &mut mix, 0.0..=1.0, "HP MIX", theme::WARNING) {
+                    let _ = cmd_tx.send(AudioCommand::SetCueMix(mix));
+                    state.cue_mix = mix;
+                }
 
-            // EQ knobs (visual only for now)
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("HI").color(theme::TEXT_DIM).monospace());
-                ui.label(egui::RichText::new("MID").color(theme::TEXT_DIM).monospace());
-                ui.label(egui::RichText::new("LO").color(theme::TEXT_DIM).monospace());
+                let mut vol = state.headphone_volume;
+                if knob(ui, "phones_vol", &mut vol, 0.0..=2.0, "PHONES", theme::WARNING) {
+                    let _ = cmd_tx.send(AudioCommand::SetHeadphoneVolume(vol));
+                    state.headphone_volume = vol;
+                }
             });
 
             ui.add_space(4.0);
 
+            // Channel strips: trim and EQ knobs, kills below, upfader beside.
+            // Two strips per row: A B, then C D when four decks are shown
+            let decks: Vec<DeckId> = state.visible_deck_ids().collect();
+            for pair in decks.chunks(2) {
+                ui.horizontal(|ui| {
+                    for (i, &deck) in pair.iter().enumerate() {
+                        if i > 0 {
+                            ui.add_space(8.0);
+                        }
+                        Self::channel_strip(ui, state, cmd_tx, deck);
+                    }
+                });
+            }
+            let mode = state.eq_mode;
+            let mode_text = egui::RichText::new(mode.display_name())
+                .color(theme::TEXT_DIM)
+                .monospace();
+            if ui
+                .button(mode_text)
+                .on_hover_text("EQ mode: classic shelving or isolator")
+                .clicked()
+            {
+                let _ = cmd_tx.send(AudioCommand::SetEqMode(mode.toggle()));
+                state.eq_mode = mode.toggle();
+            }
+
+            ui.add_space(4.0);
+
             // Crossfader
             let mut xf = state.crossfader;
-            if draw_crossfader(ui, &mut xf) {
+            if draw_crossfader(ui, &mut xf, state.hamster) {
                 let _ = cmd_tx.send(AudioCommand::SetCrossfader(xf));
                 state.crossfader = xf;
             }
 
-            ui.add_space(4.0);
-
-            // Sync buttons
+            // Crossfader and upfader curves
             ui.horizontal(|ui| {
+                let curve = state.crossfader_curve;
+                let text = format!("XF {}", curve.display_name());
+                if ui
+                    .button(egui::RichText::new(text).color(theme::TEXT_DIM).monospace())
+                    .on_hover_text("Crossfader curve")
+                    .clicked()
+                {
+                    let _ = cmd_tx.send(AudioCommand::SetCrossfaderCurve(curve.next()));
+                    state.crossfader_curve = curve.next();
+                }
+
+                let mut sharpness = state.crossfader_sharpness;
+                if knob(ui, "xf_sharp", &mut sharpness, 0.0..=1.0, "SHARP", theme::TEXT_DIM) {
+                    let _ = cmd_tx.send(AudioCommand::SetCrossfaderSharpness(sharpness));
+                    state.crossfader_sharpness = sharpness;
+                }
+
+                let hamster = state.hamster;
+                let color = if hamster { theme::WARNING } else { theme::TEXT_DIM };
                 if ui
-                    .button(egui::RichText::new("SYNC A>B").color(theme::DECK_A).monospace())
+                    .button(egui::RichText::new("HAM").color(color).monospace())
+                    .on_hover_text("Hamster: reverse the crossfader")
                     .clicked()
                 {
-                    let _ = cmd_tx.send(AudioCommand::SyncAToB);
+                    let _ = cmd_tx.send(AudioCommand::SetHamster(!hamster));
+                    state.hamster = !hamster;
                 }
+
+                let curve = state.upfader_curve;
+                let text = format!("CH {}", curve.display_name());
                 if ui
-                    .button(egui::RichText::new("SYNC B>A").color(theme::DECK_B).monospace())
+                    .button(egui::RichText::new(text).color(theme::TEXT_DIM).monospace())
+                    .on_hover_text("Upfader curve")
                     .clicked()
                 {
-                    let _ = cmd_tx.send(AudioCommand::SyncBToA);
+                    let _ = cmd_tx.send(AudioCommand::SetUpfaderCurve(curve.next()));
+                    state.upfader_curve = curve.next();
+                }
+            });
+
+            ui.add_space(4.0);
+
+            // Sync buttons: each deck follows the leader (or the loudest other deck)
+            ui.horizontal(|ui| {
+                for deck in decks {
+                    let color = theme::CyberTheme::deck_color(deck);
+                    if ui
+                        .button(egui::RichText::new(format!("SYNC {}", deck.name())).color(color).monospace())
+                        .clicked()
+                    {
+                        let _ = cmd_tx.send(AudioCommand::Sync(deck));
+                    }
                 }
             });
         });
     }
+
+    /// Trim and HI/MID/LO knobs, cue, kill and crossfader assign buttons and upfader for one deck
+    fn channel_strip(
+        ui: &mut Ui,
+        state: &mut GuiState,
+        cmd_tx: &Sender<AudioCommand>,
+        deck: DeckId,
+    ) {
+        let color = theme::CyberTheme::deck_color(deck);
+        let channel = &mut state.channels[deck.index()];
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                let mut db = channel.trim;
+                let range = Mixer::TRIM_MIN_DB..=Mixer::TRIM_MAX_DB;
+                if knob(ui, (deck, "trim"), &mut db, range, "TRIM", theme::TEXT_DIM) {
+                    let _ = cmd_tx.send(AudioCommand::SetTrim(deck, db));
+                    channel.trim = db;
+                }
+                for band in EqBand::ALL.into_iter().rev() {
+                    let i = band as usize;
+                    let mut db = channel.eq_gains[i];
+                    let id = (deck, band.display_name());
+                    let range = ChannelEq::MIN_DB..=ChannelEq::MAX_DB;
+                    if knob(ui, id, &mut db, range, band.display_name(), color) {
+                        let _ = cmd_tx.send(AudioCommand::SetEq(deck, band, db));
+                        channel.eq_gains[i] = db;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                // Cue under the trim knob, kills under the EQ knobs
+                let cued = channel.cue;
+                let text_color = if cued { Color32::BLACK } else { theme::TEXT_DIM };
+                let button = egui::Button::new(
+                    egui::RichText::new("CUE").color(text_color).monospace().size(9.0),
+                )
+                .fill(if cued { theme::WARNING } else { theme::DIM })
+                .min_size(egui::Vec2::new(32.0, 14.0));
+                if ui.add(button).on_hover_text("Headphone cue (PFL)").clicked() {
+                    let _ = cmd_tx.send(AudioCommand::ToggleCue(deck));
+                    channel.cue = !cued;
+                }
+                for band in EqBand::ALL.into_iter().rev() {
+                    let i = band as usize;
+                    let killed = channel.eq_kills[i];
+                    let text_color = if killed { Color32::BLACK } else { theme::TEXT_DIM };
+                    let button = egui::Button::new(
+                        egui::RichText::new("KILL").color(text_color).monospace().size(9.0),
+                    )
+                    .fill(if killed { theme::DANGER } else { theme::DIM })
+                    .min_size(egui::Vec2::new(32.0, 14.0));
+                    if ui.add(button).clicked() {
+                        let _ = cmd_tx.send(AudioCommand::ToggleEqKill(deck, band));
+                        channel.eq_kills[i] = !killed;
+                    }
+                }
+            });
+            // Crossfader side: A, B or THRU (bypasses the crossfader)
+            let assign = channel.assign;
+            let text = format!("XF {}", assign.display_name());
+            if ui
+                .button(egui::RichText::new(text).color(theme::TEXT_DIM).monospace().size(9.0))
+                .on_hover_text("Crossfader assign: side A, side B or thru")
+                .clicked()
+            {
+                let _ = cmd_tx.send(AudioCommand::SetCrossfaderAssign(deck, assign.next()));
+                channel.assign = assign.next();
+            }
+        });
+
+        let mut position = channel.upfader;
+        if draw_upfader(ui, &mut position, color) {
+            let _ = cmd_tx.send(AudioCommand::SetUpfader(deck, position));
+            channel.upfader = position;
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use egui::Ui;
 
+use ole_audio::{DeckId, DeckState, SyncLeader, SyncRole};
+
 use crate::state::GuiState;
 use crate::theme;
 
@@ -9,47 +11,82 @@ impl PhaseWidget {
     pub fn show(ui: &mut Ui, state: &GuiState) {
         ui.horizontal(|ui| {
             // Deck A phase
-            Self::draw_phase_dots(ui, state.deck_a.beat_phase, theme::DECK_A, "A");
+            Self::draw_phase_dots(ui, state.deck(DeckId::A).beat_phase, theme::DECK_A, "A");
+            Self::draw_sync_role(ui, state.deck(DeckId::A), theme::DECK_A);
 
             ui.separator();
 
-            // Sync quality
-            let quality_text = if state.sync_quality > 0.95 {
-                "LOCKED"
-            } else if state.sync_quality > 0.5 {
-                "SYNC"
-            } else {
-                "---"
-            };
-            let quality_color = if state.sync_quality > 0.95 {
-                theme::PRIMARY
-            } else if state.sync_quality > 0.5 {
-                theme::WARNING
+            // Sync lock status, from the follower's phase error
+            let follower = state
+                .channels
+                .iter()
+                .map(|channel| &channel.deck)
+                .find(|d| d.sync_role == SyncRole::Follower);
+            if let Some(follower) = follower {
+                let error = follower.phase_error.unwrap_or(0.0);
+                let (text, color) = if error.abs() < 0.02 {
+                    ("LOCKED".to_string(), theme::PRIMARY)
+                } else {
+                    (format!("LOCK {:+.2}", error), theme::WARNING)
+                };
+                ui.label(egui::RichText::new(text).color(color).monospace());
+                if state.sync_leader == SyncLeader::Clock {
+                    ui.label(
+                        egui::RichText::new(format!("CLOCK {:.1}", state.master_bpm))
+                            .color(theme::TEXT_DIM)
+                            .monospace(),
+                    );
+                }
             } else {
-                theme::TEXT_DIM
-            };
-            ui.label(egui::RichText::new(quality_text).color(quality_color).monospace());
+                // Sync quality
+                let quality_text = if state.sync_quality > 0.95 {
+                    "LOCKED"
+                } else if state.sync_quality > 0.5 {
+                    "SYNC"
+                } else {
+                    "---"
+                };
+                let quality_color = if state.sync_quality > 0.95 {
+                    theme::PRIMARY
+                } else if state.sync_quality > 0.5 {
+                    theme::WARNING
+                } else {
+                    theme::TEXT_DIM
+                };
+                ui.label(egui::RichText::new(quality_text).color(quality_color).monospace());
 
-            // BPM display
-            let bpm_a = state.deck_a.bpm.unwrap_or(0.0) * state.deck_a.tempo;
-            let bpm_b = state.deck_b.bpm.unwrap_or(0.0) * state.deck_b.tempo;
-            if bpm_a > 0.0 && bpm_b > 0.0 {
-                let diff = bpm_a - bpm_b;
-                let sign = if diff >= 0.0 { "+" } else { "" };
-                ui.label(
-                    egui::RichText::new(format!("{}{:.1}", sign, diff))
-                        .color(theme::TEXT_DIM)
-                        .monospace(),
-                );
+                // BPM display
+                let bpm_a = state.deck(DeckId::A).bpm.unwrap_or(0.0) * state.deck(DeckId::A).tempo;
+                let bpm_b = state.deck(DeckId::B).bpm.unwrap_or(0.0) * state.deck(DeckId::B).tempo;
+                if bpm_a > 0.0 && bpm_b > 0.0 {
+                    let diff = bpm_a - bpm_b;
+                    let sign = if diff >= 0.0 { "+" } else { "" };
+                    ui.label(
+                        egui::RichText::new(format!("{}{:.1}", sign, diff))
+                            .color(theme::TEXT_DIM)
+                            .monospace(),
+                    );
+                }
             }
 
             ui.separator();
 
             // Deck B phase
-            Self::draw_phase_dots(ui, state.deck_b.beat_phase, theme::DECK_B, "B");
+            Self::draw_phase_dots(ui, state.deck(DeckId::B).beat_phase, theme::DECK_B, "B");
+            Self::draw_sync_role(ui, state.deck(DeckId::B), theme::DECK_B);
         });
     }
 
+    fn draw_sync_role(ui: &mut Ui, deck: &DeckState, color: egui::Color32) {
+        if deck.sync_role != SyncRole::Off {
+            ui.label(
+                egui::RichText::new(deck.sync_role.display_name())
+                    .color(color)
+                    .monospace(),
+            );
+        }
+    }
+
     fn draw_phase_dots(ui: &mut Ui, phase: f32, color: egui::Color32, label: &str) {
         ui.label(egui::RichText::new(label).color(color).monospace());
 
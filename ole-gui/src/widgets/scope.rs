@@ -1,4 +1,5 @@
 use egui::{Color32, Rect, Ui, Vec2};
+use ole_audio::DeckId;
 
 use crate::state::{GuiState, ScopeMode, SPECTRUM_BANDS, WATERFALL_DEPTH};
 use crate::theme;
@@ -29,7 +30,7 @@ impl ScopeWidget {
         Self::draw_scope_line(
             painter,
             rect,
-            state.deck_a.scope_samples.as_slice(),
+            state.deck(DeckId::A).scope_samples.as_slice(),
             center_y,
             half_height,
             theme::DECK_A,
@@ -38,7 +39,7 @@ impl ScopeWidget {
         Self::draw_scope_line(
             painter,
             rect,
-            state.deck_b.scope_samples.as_slice(),
+            state.deck(DeckId::B).scope_samples.as_slice(),
             center_y,
             half_height,
             theme::DECK_B,
@@ -100,11 +101,11 @@ impl ScopeWidget {
 
         let decks: &[(&[f32], Color32)] = &[
             (
-                state.deck_a.scope_samples.as_slice(),
+                state.deck(DeckId::A).scope_samples.as_slice(),
                 Color32::from_rgba_unmultiplied(0x00, 0xff, 0x41, 0x60),
             ),
             (
-                state.deck_b.scope_samples.as_slice(),
+                state.deck(DeckId::B).scope_samples.as_slice(),
                 Color32::from_rgba_unmultiplied(0x00, 0xff, 0xcc, 0x40),
             ),
         ];
@@ -149,12 +150,12 @@ impl ScopeWidget {
         // Draw both decks as butterfly (L up, R down)
         let decks: &[(&[f32], Color32, Color32)] = &[
             (
-                state.deck_a.scope_samples.as_slice(),
+                state.deck(DeckId::A).scope_samples.as_slice(),
                 Color32::from_rgba_unmultiplied(0x00, 0xff, 0x41, 0xC0), // L green
                 Color32::from_rgba_unmultiplied(0xff, 0x00, 0x66, 0xA0), // R pink
             ),
             (
-                state.deck_b.scope_samples.as_slice(),
+                state.deck(DeckId::B).scope_samples.as_slice(),
                 Color32::from_rgba_unmultiplied(0x00, 0xff, 0xcc, 0x80), // L cyan
                 Color32::from_rgba_unmultiplied(0x00, 0x66, 0xff, 0x60), // R blue
             ),
@@ -1,4 +1,5 @@
 use egui::{Rect, Ui, Vec2};
+use ole_audio::DeckId;
 
 use crate::state::{GuiState, AFTERGLOW_HISTORY, SPECTRUM_BANDS};
 use crate::theme;
@@ -14,7 +15,7 @@ impl SpectrumWidget {
         // Background
         painter.rect_filled(rect, 0.0, theme::BG);
 
-        let bands = &state.deck_a.spectrum.bands;
+        let bands = &state.deck(DeckId::A).spectrum.bands;
         let band_count = bands.len().min(SPECTRUM_BANDS);
         if band_count == 0 {
             return;
@@ -1,4 +1,5 @@
 use egui::Ui;
+use ole_audio::{DeckId, RecordingStatus};
 
 use crate::state::GuiState;
 use crate::theme;
@@ -16,7 +17,7 @@ impl StatusBar {
             );
 
             // BPM display
-            let bpm_a = state.deck_a.bpm.unwrap_or(0.0) * state.deck_a.tempo;
+            let bpm_a = state.deck(DeckId::A).bpm.unwrap_or(0.0) * state.deck(DeckId::A).tempo;
             if bpm_a > 0.0 {
                 ui.label(
                     egui::RichText::new(format!("BPM:{:.1}", bpm_a))
@@ -46,6 +47,27 @@ impl StatusBar {
                 );
             }
 
+            // Quantize indicator
+            if state.quantize {
+                ui.label(
+                    egui::RichText::new(format!("[Q {}]", state.quantize_resolution.display_name()))
+                        .color(theme::WARNING)
+                        .monospace(),
+                );
+            }
+
+            // Recording indicator (warns once samples were dropped)
+            if let RecordingStatus::Recording { secs, dropped, .. } = state.recording {
+                let secs = secs as u64;
+                let color = if dropped > 0 { theme::WARNING } else { theme::DANGER };
+                ui.label(
+                    egui::RichText::new(format!("[REC {:02}:{:02}]", secs / 60, secs % 60))
+                        .color(color)
+                        .strong()
+                        .monospace(),
+                );
+            }
+
             // LUFS
             if state.mastering_lufs.momentary > -60.0 {
                 ui.label(
@@ -1,13 +1,14 @@
 use egui::{Color32, Rect, Sense, Ui, Vec2};
 
 use ole_analysis::FrequencyBand;
+use ole_audio::DeckId;
 use crate::state::GuiState;
 use crate::theme;
 
-pub fn draw_waveform(ui: &mut Ui, state: &GuiState, is_deck_a: bool) -> Option<f64> {
-    let deck = if is_deck_a { &state.deck_a } else { &state.deck_b };
-    let zoom = if is_deck_a { state.zoom_a } else { state.zoom_b };
-    let deck_color = theme::CyberTheme::deck_color(is_deck_a);
+pub fn draw_waveform(ui: &mut Ui, state: &GuiState, deck_id: DeckId) -> Option<f64> {
+    let deck = state.deck(deck_id);
+    let zoom = state.view(deck_id).zoom;
+    let deck_color = theme::CyberTheme::deck_color(deck_id);
 
     let desired_size = Vec2::new(ui.available_width(), 60.0);
     let (response, painter) = ui.allocate_painter(desired_size, Sense::click());
@@ -90,6 +91,21 @@ pub fn draw_waveform(ui: &mut Ui, state: &GuiState, is_deck_a: bool) -> Option<f
         i += step.max(0.001);
     }
 
+    // Draw ghost playhead: where the track would be without the slip action
+    if let Some(slip_pos) = deck.slip_position {
+        if deck.duration > 0.0 {
+            let frac = slip_pos / deck.duration;
+            if frac >= view_start && frac <= view_end {
+                let gx = rect.left()
+                    + ((frac - view_start) / viewport * rect.width() as f64) as f32;
+                painter.line_segment(
+                    [egui::pos2(gx, rect.top()), egui::pos2(gx, rect.bottom())],
+                    egui::Stroke::new(1.0, deck_color.gamma_multiply(0.4)),
+                );
+            }
+        }
+    }
+
     // Draw playhead
     let playhead_x = rect.left()
         + ((position_frac - view_start) / viewport * rect.width() as f64) as f32;
@@ -103,22 +119,86 @@ pub fn draw_waveform(ui: &mut Ui, state: &GuiState, is_deck_a: bool) -> Option<f
         );
     }
 
-    // Draw beat markers
+    // Draw beat markers, segment by segment through the tempo map (taller on downbeats)
     if let Some(ref grid) = deck.beat_grid_info {
-        if grid.has_grid && grid.bpm > 0.0 && deck.duration > 0.0 {
-            let beat_dur = 60.0 / grid.bpm as f64;
-            let mut beat_time = grid.first_beat_offset_secs;
-            while beat_time < deck.duration {
-                let frac = beat_time / deck.duration;
+        if grid.has_grid && deck.duration > 0.0 {
+            for (i, &(start, start_beat, bpm)) in grid.tempo_map.iter().enumerate() {
+                if bpm <= 0.0 {
+                    continue;
+                }
+                let end = grid
+                    .tempo_map
+                    .get(i + 1)
+                    .map_or(deck.duration, |&(next, _, _)| next.min(deck.duration));
+                let beat_dur = 60.0 / bpm as f64;
+                let mut beat = start_beat.ceil();
+                let mut beat_time = start + (beat - start_beat) * beat_dur;
+                while beat_time < end - 1e-6 {
+                    let frac = beat_time / deck.duration;
+                    if frac >= view_start && frac <= view_end {
+                        let bx = rect.left()
+                            + ((frac - view_start) / viewport * rect.width() as f64) as f32;
+                        let downbeat = (beat as i64 - grid.downbeat as i64).rem_euclid(4) == 0;
+                        let (len, color) = if downbeat {
+                            (8.0, theme::TEXT_DIM)
+                        } else {
+                            (4.0, theme::DIM)
+                        };
+                        painter.line_segment(
+                            [egui::pos2(bx, rect.top()), egui::pos2(bx, rect.top() + len)],
+                            egui::Stroke::new(1.0, color),
+                        );
+                    }
+                    beat += 1.0;
+                    beat_time += beat_dur;
+                }
+            }
+        }
+
+        // Phrase markers
+        for &phrase in &grid.phrases_secs {
+            if deck.duration > 0.0 {
+                let frac = phrase / deck.duration;
                 if frac >= view_start && frac <= view_end {
-                    let bx = rect.left()
+                    let px = rect.left()
                         + ((frac - view_start) / viewport * rect.width() as f64) as f32;
                     painter.line_segment(
-                        [egui::pos2(bx, rect.top()), egui::pos2(bx, rect.top() + 4.0)],
-                        egui::Stroke::new(1.0, theme::DIM),
+                        [egui::pos2(px, rect.top()), egui::pos2(px, rect.bottom())],
+                        egui::Stroke::new(1.0, theme::ACCENT_PINK.gamma_multiply(0.5)),
                     );
                 }
-                beat_time += beat_dur;
+            }
+        }
+    }
+
+    // Draw loop region (brighter while looping)
+    if let Some(ref lp) = deck.loop_info {
+        if deck.duration > 0.0 {
+            let to_x = |secs: f64| {
+                let frac = (secs / deck.duration).clamp(view_start, view_end);
+                rect.left() + ((frac - view_start) / viewport * rect.width() as f64) as f32
+            };
+            let (x0, x1) = (to_x(lp.start), to_x(lp.end));
+            if x1 > x0 {
+                let alpha = if lp.active { 48 } else { 16 };
+                painter.rect_filled(
+                    Rect::from_min_max(egui::pos2(x0, rect.top()), egui::pos2(x1, rect.bottom())),
+                    0.0,
+                    Color32::from_rgba_unmultiplied(0xff, 0xff, 0x00, alpha),
+                );
+            }
+        }
+    }
+    if let Some(loop_in) = deck.loop_in {
+        if deck.duration > 0.0 {
+            let frac = loop_in / deck.duration;
+            if frac >= view_start && frac <= view_end {
+                let lx = rect.left()
+                    + ((frac - view_start) / viewport * rect.width() as f64) as f32;
+                painter.line_segment(
+                    [egui::pos2(lx, rect.top()), egui::pos2(lx, rect.bottom())],
+                    egui::Stroke::new(1.0, theme::WARNING),
+                );
             }
         }
     }
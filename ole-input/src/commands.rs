@@ -3,7 +3,10 @@
 use std::path::PathBuf;
 
 // Re-export types for use in commands
-pub use ole_audio::{DelayModulation, FilterMode, FilterType, MasteringPreset};
+pub use ole_audio::{
+    CrossfaderAssign, CrossfaderCurve, DeckId, DelayModulation, EffectKind, EqBand, EqMode,
+    FilterMode, FilterType, Interpolation, MasteringPreset, QuantizeResolution, UpfaderCurve,
+};
 
 /// Input modes (vim-style)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -28,13 +31,6 @@ impl Mode {
     }
 }
 
-/// Deck identifier
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DeckId {
-    A,
-    B,
-}
-
 /// Navigation direction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
@@ -89,6 +85,34 @@ pub enum Command {
     SetCue(DeckId, u8),  // Set cue point 1-4
     JumpCue(DeckId, u8), // Jump to cue point 1-4
 
+    // Loops
+    LoopIn(DeckId),
+    LoopOut(DeckId),
+    AutoLoop(DeckId, f32), // Loop N beats (1/32 - 32), same length again exits
+    LoopHalve(DeckId),
+    LoopDouble(DeckId),
+    LoopMove(DeckId, f32), // Move loop by N beats
+    Reloop(DeckId),        // Exit loop or re-enter the last one
+    LoopRoll(DeckId, f32), // Loop N beats while the track runs underneath
+    LoopRollRelease(DeckId),
+
+    // Slip mode: loops, cue jumps, reverse, beatjumps and tape stops
+    // return to where the track would have been
+    ToggleSlip(DeckId),
+    SlipRelease(DeckId), // End the slip action (key released)
+    SetReverse(DeckId, bool),
+
+    // Quantize: snap cue/loop points, hold jumps and play for the next grid line
+    ToggleQuantize,
+    SetQuantizeResolution(QuantizeResolution),
+    CycleQuantizeResolution,
+
+    // Beat grid corrections (saved to the library cache)
+    ShiftGrid(DeckId, f64),      // Move the grid by N seconds
+    SetDownbeat(DeckId),         // Playback position becomes a downbeat
+    ScaleGridTempo(DeckId, f64), // 2.0 = double BPM, 0.5 = halve
+    SetGridBpm(DeckId, f32),     // Typed or tapped BPM
+
     // Tempo
     SetTempo(DeckId, f32),
     AdjustTempo(DeckId, f32),
@@ -97,13 +121,51 @@ pub enum Command {
     SetGain(DeckId, f32),
     AdjustGain(DeckId, f32),
 
+    // Channel EQ
+    SetEqGain(DeckId, EqBand, f32),    // dB
+    AdjustEqGain(DeckId, EqBand, f32), // dB
+    ToggleEqKill(DeckId, EqBand),
+    SetEqMode(EqMode),
+
+    // Channel strips
+    SetUpfader(DeckId, f32),    // 0.0 - 1.0
+    AdjustUpfader(DeckId, f32),
+    SetTrim(DeckId, f32),       // dB
+    AdjustTrim(DeckId, f32),
+    SetUpfaderCurve(UpfaderCurve),
+
     // Sync
     Sync(DeckId),
+    ToggleSyncLock(DeckId),      // Keep tempo and phase locked to the sync leader
+    SetMasterClock(Option<f32>), // Some(bpm) = master clock leads, None = automatic
 
     // Crossfader
     SetCrossfader(f32),
     MoveCrossfader(Direction),
     CenterCrossfader,
+    SetCrossfaderCurve(CrossfaderCurve),
+    SetCrossfaderSharpness(f32), // 0.0 - 1.0
+    ToggleHamster,               // Reverse the crossfader
+    SetCrossfaderAssign(DeckId, CrossfaderAssign),
+    CycleCrossfaderAssign(DeckId),
+
+    // Headphone cue (PFL)
+    ToggleCue(DeckId),
+    SetCueMix(f32),          // 0.0 = cue only, 1.0 = master only
+    SetHeadphoneVolume(f32), // 0.0 - 2.0
+
+    // Recording (WAV or FLAC by extension)
+    StartRecording(PathBuf),
+    StopRecording,
+
+    // Audio output (None = system default)
+    ShowAudioOutput,
+    ListAudioOutputs,
+    SetAudioHost(Option<String>),
+    SetAudioDevice(Option<String>),
+    SetSampleRate(Option<u32>),
+    SetBufferSize(Option<u32>),
+    SetInterpolation(Interpolation), // Playback quality, all decks
 
     // Effects (toggle/adjust)
     ToggleEffect(DeckId, EffectType),
@@ -126,9 +188,12 @@ pub enum Command {
     SetVinylNoise(DeckId, f32),  // 0.0-1.0
     SetVinylWarmth(DeckId, f32), // 0.0-1.0
 
-    // Time stretching (pitch-independent tempo)
-    ToggleTimeStretch(DeckId),
-    SetTimeStretchRatio(DeckId, f32), // 0.25-4.0
+    // Key lock (pitch-independent tempo)
+    ToggleKeyLock(DeckId),
+
+    // Key shift in semitones, without changing tempo
+    SetPitch(DeckId, f32),
+    AdjustPitch(DeckId, f32),
 
     // Delay modulation (tape character)
     SetDelayModulation(DeckId, DelayModulation),
@@ -146,6 +211,7 @@ pub enum Command {
     SetTheme(String),
     CycleFocus,
     Focus(DeckId),
+    SetVisibleDecks(usize), // Deck layout: 2 or 4 decks on screen
 
     // Mode changes
     EnterCommandMode,
@@ -193,12 +259,29 @@ pub enum Command {
     TriggerTapeStop(DeckId),  // Start the stop effect
     TriggerTapeStart(DeckId), // Spin back up
 
+    // Transition effects
+    TriggerEchoOut(DeckId),    // Cut the input and stop the deck, echoes ring out
+    TriggerNoiseSweep(DeckId), // Noise riser over the sweep's bars
+
+    // Convolution reverb
+    LoadImpulse(DeckId, PathBuf), // Impulse response file (WAV, FLAC, ...)
+    SetImpulseWet(DeckId, f32),   // 0.0-1.0
+
     // Flanger effect
     ToggleFlanger(DeckId),
 
     // Bitcrusher effect
     ToggleBitcrusher(DeckId),
 
+    // Effect chain (slots count from 0)
+    AddEffect(DeckId, usize, EffectKind),
+    RemoveEffect(DeckId, usize),
+    MoveEffect(DeckId, usize, usize), // from, to
+    ToggleEffectSlot(DeckId, usize),
+    ToggleEffectKind(DeckId, EffectKind),      // first slot of that kind
+    SetEffectMix(DeckId, usize, f32),          // 0.0 = dry, 1.0 = wet
+    SetEffectParam(DeckId, usize, usize, f32), // slot, parameter index, value
+
     // Help navigation
     HelpScrollUp,
     HelpScrollDown,
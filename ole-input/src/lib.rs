@@ -3,6 +3,7 @@
 mod commands;
 
 pub use commands::{
-    Command, DeckId, DelayModulation, Direction, EffectType, FilterMode, FilterType,
-    MasteringPreset, Mode, VinylPresetId,
+    Command, CrossfaderAssign, CrossfaderCurve, DeckId, DelayModulation, Direction, EffectKind,
+    EffectType, EqBand, EqMode, FilterMode, FilterType, Interpolation, MasteringPreset, Mode,
+    QuantizeResolution, UpfaderCurve, VinylPresetId,
 };
@@ -1,7 +1,8 @@
 //! SQLite cache for track analysis results
 //!
-//! Stores BPM, key, and metadata analysis to avoid re-analyzing unchanged files.
+//! Stores BPM, bars, key, and metadata analysis to avoid re-analyzing unchanged files.
 
+use ole_analysis::BeatGrid;
 use rusqlite::{params, Connection};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -30,6 +31,10 @@ pub struct CachedAnalysis {
     pub bpm: Option<f32>,
     /// BPM detection confidence (0.0-1.0)
     pub bpm_confidence: Option<f32>,
+    /// Beat number of the first downbeat (0-3)
+    pub downbeat: Option<u32>,
+    /// Bars where phrases start
+    pub phrases: Vec<u32>,
     /// Detected key in Camelot notation (e.g., "8A", "12B")
     pub key: Option<String>,
     /// Key detection confidence (0.0-1.0)
@@ -40,6 +45,59 @@ pub struct CachedAnalysis {
     pub artist: String,
 }
 
+impl CachedAnalysis {
+    /// Take BPM, downbeat and phrases from a hand-corrected beat grid
+    pub fn apply_grid_edit(&mut self, edit: &GridEdit) {
+        self.bpm = Some(edit.bpm);
+        self.downbeat = Some(edit.downbeat);
+        self.phrases = edit.phrases.clone();
+    }
+}
+
+/// A beat grid corrected by hand
+///
+/// Kept apart from the analysis results, so rescans don't overwrite it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridEdit {
+    /// BPM of the track (the tempo covering the most beats)
+    pub bpm: f32,
+    /// Position of the first beat in seconds
+    pub first_beat_secs: f64,
+    /// Tempo segments as (start beat, BPM), the first at beat 0
+    pub tempo_map: Vec<(f64, f32)>,
+    /// Beat number of the first downbeat (0-3)
+    pub downbeat: u32,
+    /// Bars where phrases start
+    pub phrases: Vec<u32>,
+}
+
+impl GridEdit {
+    /// Capture a beat grid
+    pub fn from_grid(grid: &BeatGrid) -> Self {
+        Self {
+            bpm: grid.bpm,
+            first_beat_secs: grid.first_beat_offset as f64 / (grid.sample_rate as f64 * 2.0),
+            tempo_map: grid.tempo_map(),
+            downbeat: grid.downbeat,
+            phrases: grid.phrases().to_vec(),
+        }
+    }
+
+    /// Rebuild the beat grid for audio at `sample_rate`
+    pub fn to_grid(&self, sample_rate: u32) -> BeatGrid {
+        let first_beat_offset = (self.first_beat_secs * sample_rate as f64 * 2.0).round() as u64;
+        let mut grid = BeatGrid::with_tempo_map(
+            self.bpm,
+            first_beat_offset,
+            sample_rate,
+            1.0,
+            &self.tempo_map,
+        );
+        grid.set_bars(self.downbeat, self.phrases.clone());
+        grid
+    }
+}
+
 /// Analysis cache backed by SQLite
 pub struct AnalysisCache {
     conn: Connection,
@@ -60,7 +118,18 @@ impl AnalysisCache {
             key_confidence REAL,
             title TEXT NOT NULL,
             artist TEXT NOT NULL,
-            analyzed_at INTEGER NOT NULL
+            analyzed_at INTEGER NOT NULL,
+            downbeat INTEGER,
+            phrases TEXT
+        );
+        CREATE TABLE IF NOT EXISTS grid_edits (
+            path TEXT PRIMARY KEY,
+            bpm REAL NOT NULL,
+            first_beat_secs REAL NOT NULL,
+            tempo_map TEXT NOT NULL,
+            downbeat INTEGER NOT NULL,
+            phrases TEXT,
+            edited_at INTEGER NOT NULL
         );
         CREATE INDEX IF NOT EXISTS idx_path ON tracks(path);
         CREATE INDEX IF NOT EXISTS idx_key ON tracks(key);
@@ -76,9 +145,29 @@ impl AnalysisCache {
 
         let conn = Connection::open(db_path)?;
         conn.execute_batch(Self::SCHEMA)?;
+        Self::migrate(&conn)?;
         Ok(Self { conn })
     }
 
+    /// Columns added since the first schema, with their types
+    const ADDED_COLUMNS: &'static [(&'static str, &'static str)] =
+        &[("downbeat", "INTEGER"), ("phrases", "TEXT")];
+
+    /// Add columns missing from databases created with an older schema
+    fn migrate(conn: &Connection) -> Result<(), CacheError> {
+        let mut stmt = conn.prepare("PRAGMA table_info(tracks)")?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (name, kind) in Self::ADDED_COLUMNS {
+            if !columns.iter().any(|c| c == name) {
+                conn.execute(&format!("ALTER TABLE tracks ADD COLUMN {name} {kind}"), [])?;
+            }
+        }
+        Ok(())
+    }
+
     /// Open an in-memory database (for testing)
     #[cfg(test)]
     pub fn in_memory() -> Result<Self, CacheError> {
@@ -97,7 +186,7 @@ impl AnalysisCache {
         self.conn
             .query_row(
                 "SELECT path, file_size, modified_time, duration_secs, bpm, bpm_confidence,
-                        key, key_confidence, title, artist
+                        key, key_confidence, title, artist, downbeat, phrases
                  FROM tracks
                  WHERE path = ?1 AND file_size = ?2 AND modified_time = ?3",
                 params![path.to_string_lossy().to_string(), file_size, modified_time],
@@ -113,6 +202,8 @@ impl AnalysisCache {
                         key_confidence: row.get(7)?,
                         title: row.get(8)?,
                         artist: row.get(9)?,
+                        downbeat: row.get(10)?,
+                        phrases: decode_phrases(row.get(11)?),
                     })
                 },
             )
@@ -132,8 +223,8 @@ impl AnalysisCache {
             r#"INSERT OR REPLACE INTO tracks
                (path, file_size, modified_time, duration_secs,
                 bpm, bpm_confidence, key, key_confidence,
-                title, artist, analyzed_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+                title, artist, analyzed_at, downbeat, phrases)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"#,
             params![
                 analysis.path.to_string_lossy().to_string(),
                 analysis.file_size,
@@ -146,16 +237,71 @@ impl AnalysisCache {
                 analysis.title,
                 analysis.artist,
                 now,
+                analysis.downbeat,
+                encode_phrases(&analysis.phrases),
             ],
         )?;
         Ok(())
     }
 
+    /// Get the hand-corrected beat grid of a track, if any
+    pub fn get_grid_edit(&self, path: &Path) -> Option<GridEdit> {
+        self.conn
+            .query_row(
+                "SELECT bpm, first_beat_secs, tempo_map, downbeat, phrases
+                 FROM grid_edits
+                 WHERE path = ?1",
+                [path.to_string_lossy().to_string()],
+                |row| {
+                    Ok(GridEdit {
+                        bpm: row.get(0)?,
+                        first_beat_secs: row.get(1)?,
+                        tempo_map: decode_tempo_map(&row.get::<_, String>(2)?),
+                        downbeat: row.get(3)?,
+                        phrases: decode_phrases(row.get(4)?),
+                    })
+                },
+            )
+            .ok()
+    }
+
+    /// Store a hand-corrected beat grid
+    ///
+    /// The track's cached BPM, downbeat and phrases are updated to match.
+    pub fn store_grid_edit(&self, path: &Path, edit: &GridEdit) -> Result<(), CacheError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = path.to_string_lossy().to_string();
+        let phrases = encode_phrases(&edit.phrases);
+
+        self.conn.execute(
+            r#"INSERT OR REPLACE INTO grid_edits
+               (path, bpm, first_beat_secs, tempo_map, downbeat, phrases, edited_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![
+                path,
+                edit.bpm,
+                edit.first_beat_secs,
+                encode_tempo_map(&edit.tempo_map),
+                edit.downbeat,
+                phrases,
+                now,
+            ],
+        )?;
+        self.conn.execute(
+            "UPDATE tracks SET bpm = ?1, downbeat = ?2, phrases = ?3 WHERE path = ?4",
+            params![edit.bpm, edit.downbeat, phrases, path],
+        )?;
+        Ok(())
+    }
+
     /// Get all cached tracks, sorted by key then BPM
     pub fn get_all_sorted(&self) -> Result<Vec<CachedAnalysis>, CacheError> {
         let mut stmt = self.conn.prepare(
             "SELECT path, file_size, modified_time, duration_secs, bpm, bpm_confidence,
-                    key, key_confidence, title, artist
+                    key, key_confidence, title, artist, downbeat, phrases
              FROM tracks
              ORDER BY
                  CASE WHEN key IS NULL THEN 1 ELSE 0 END,  -- NULLs last
@@ -177,6 +323,8 @@ impl AnalysisCache {
                     key_confidence: row.get(7)?,
                     title: row.get(8)?,
                     artist: row.get(9)?,
+                    downbeat: row.get(10)?,
+                    phrases: decode_phrases(row.get(11)?),
                 })
             })?
             .filter_map(|r| r.ok())
@@ -189,7 +337,7 @@ impl AnalysisCache {
     pub fn get_by_key(&self, key: &str) -> Result<Vec<CachedAnalysis>, CacheError> {
         let mut stmt = self.conn.prepare(
             "SELECT path, file_size, modified_time, duration_secs, bpm, bpm_confidence,
-                    key, key_confidence, title, artist
+                    key, key_confidence, title, artist, downbeat, phrases
              FROM tracks
              WHERE key = ?1
              ORDER BY bpm ASC",
@@ -208,6 +356,8 @@ impl AnalysisCache {
                     key_confidence: row.get(7)?,
                     title: row.get(8)?,
                     artist: row.get(9)?,
+                    downbeat: row.get(10)?,
+                    phrases: decode_phrases(row.get(11)?),
                 })
             })?
             .filter_map(|r| r.ok())
@@ -240,6 +390,44 @@ impl AnalysisCache {
     }
 }
 
+/// Phrase start bars as stored in the database ("0,16,32")
+fn encode_phrases(phrases: &[u32]) -> Option<String> {
+    if phrases.is_empty() {
+        return None;
+    }
+    let bars: Vec<String> = phrases.iter().map(|bar| bar.to_string()).collect();
+    Some(bars.join(","))
+}
+
+fn decode_phrases(phrases: Option<String>) -> Vec<u32> {
+    phrases
+        .map(|s| {
+            s.split(',')
+                .filter_map(|bar| bar.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Tempo map as stored in the database ("0:174,64:175.5")
+fn encode_tempo_map(tempo_map: &[(f64, f32)]) -> String {
+    let segments: Vec<String> = tempo_map
+        .iter()
+        .map(|(beat, bpm)| format!("{beat}:{bpm}"))
+        .collect();
+    segments.join(",")
+}
+
+fn decode_tempo_map(tempo_map: &str) -> Vec<(f64, f32)> {
+    tempo_map
+        .split(',')
+        .filter_map(|segment| {
+            let (beat, bpm) = segment.split_once(':')?;
+            Some((beat.trim().parse().ok()?, bpm.trim().parse().ok()?))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +440,8 @@ mod tests {
             duration_secs: 180.5,
             bpm: Some(128.0),
             bpm_confidence: Some(0.95),
+            downbeat: Some(1),
+            phrases: vec![0, 16, 32],
             key: Some("8A".to_string()),
             key_confidence: Some(0.87),
             title: "Test Track".to_string(),
@@ -272,9 +462,73 @@ mod tests {
         let retrieved = retrieved.unwrap();
         assert_eq!(retrieved.title, "Test Track");
         assert_eq!(retrieved.bpm, Some(128.0));
+        assert_eq!(retrieved.downbeat, Some(1));
+        assert_eq!(retrieved.phrases, vec![0, 16, 32]);
         assert_eq!(retrieved.key, Some("8A".to_string()));
     }
 
+    #[test]
+    fn test_migrates_old_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tracks (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                file_size INTEGER NOT NULL,
+                modified_time INTEGER NOT NULL,
+                duration_secs REAL NOT NULL,
+                bpm REAL,
+                bpm_confidence REAL,
+                key TEXT,
+                key_confidence REAL,
+                title TEXT NOT NULL,
+                artist TEXT NOT NULL,
+                analyzed_at INTEGER NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute_batch(AnalysisCache::SCHEMA).unwrap();
+        AnalysisCache::migrate(&conn).unwrap();
+        let cache = AnalysisCache { conn };
+
+        let analysis = test_analysis();
+        cache.store(&analysis).unwrap();
+        let retrieved = cache
+            .get(&analysis.path, analysis.file_size, analysis.modified_time)
+            .unwrap();
+        assert_eq!(retrieved.phrases, vec![0, 16, 32]);
+    }
+
+    #[test]
+    fn test_grid_edit_round_trip() {
+        let cache = AnalysisCache::in_memory().unwrap();
+        let analysis = test_analysis();
+        cache.store(&analysis).unwrap();
+        assert!(cache.get_grid_edit(&analysis.path).is_none());
+
+        let mut grid =
+            BeatGrid::with_tempo_map(87.0, 44100, 44100, 0.6, &[(0.0, 87.0), (64.0, 87.5)]);
+        grid.set_bars(1, vec![0, 16]);
+        grid.scale_tempo(2.0);
+        let edit = GridEdit::from_grid(&grid);
+        cache.store_grid_edit(&analysis.path, &edit).unwrap();
+
+        let stored = cache.get_grid_edit(&analysis.path).unwrap();
+        assert_eq!(stored, edit);
+        let restored = stored.to_grid(48000);
+        assert_eq!(restored.tempo_map(), vec![(0.0, 174.0), (128.0, 175.0)]);
+        assert!((restored.first_beat_offset as f64 - 0.5 * 96000.0).abs() < 1.0);
+        assert_eq!(restored.downbeat, 2);
+
+        // The library shows the corrected BPM, and a rescan keeps the edit
+        let retrieved = cache
+            .get(&analysis.path, analysis.file_size, analysis.modified_time)
+            .unwrap();
+        assert_eq!(retrieved.bpm, Some(174.0));
+        cache.store(&analysis).unwrap();
+        assert_eq!(cache.get_grid_edit(&analysis.path), Some(edit));
+    }
+
     #[test]
     fn test_cache_invalidation_file_size() {
         let cache = AnalysisCache::in_memory().unwrap();
@@ -1,6 +1,6 @@
 //! Simple configuration persistence for OLE
 //!
-//! Stores user preferences like last scanned folder.
+//! Stores user preferences like last scanned folder, mixer and audio setup.
 
 use std::fs;
 use std::io;
@@ -11,6 +11,59 @@ use std::path::{Path, PathBuf};
 pub struct Config {
     /// Last folder that was scanned for tracks
     pub last_scan_folder: Option<PathBuf>,
+    /// Mixer setup
+    pub mixer: MixerConfig,
+    /// Output device setup
+    pub audio: AudioConfig,
+    /// Output device for the headphone cue, when the main device has
+    /// fewer than four channels
+    pub cue_device: Option<String>,
+}
+
+/// Mixer setup kept between sessions
+///
+/// Curves are stored by name so the config stays independent of the audio
+/// engine; unknown or empty names fall back to the engine's defaults.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MixerConfig {
+    /// Crossfader curve name ("linear", "power", "cut")
+    pub crossfader_curve: String,
+    /// Crossfader curve sharpness (0.0 - 1.0)
+    pub crossfader_sharpness: f32,
+    /// Reversed crossfader
+    pub hamster: bool,
+    /// Upfader curve name ("linear", "slow", "fast")
+    pub upfader_curve: String,
+    /// Trim in dB for decks A to D
+    pub trims: [f32; 4],
+    /// Crossfader side for decks A to D ("a", "b", "thru")
+    pub assigns: [String; 4],
+}
+
+/// Config key suffixes for decks A to D
+const DECK_KEYS: [&str; 4] = ["a", "b", "c", "d"];
+
+/// Deck index of a per-deck key such as "trim_c"
+fn deck_key(key: &str, prefix: &str) -> Option<usize> {
+    let deck = key.strip_prefix(prefix)?;
+    DECK_KEYS.iter().position(|&k| k == deck)
+}
+
+/// Output device setup kept between sessions
+///
+/// `None` means the system default for each setting.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AudioConfig {
+    /// Audio host name (e.g. "ALSA", "JACK")
+    pub host: Option<String>,
+    /// Output device name
+    pub device: Option<String>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Buffer size in frames
+    pub buffer_size: Option<u32>,
+    /// Deck interpolation name ("linear", "hermite", "sinc")
+    pub interpolation: Option<String>,
 }
 
 impl Config {
@@ -73,7 +126,34 @@ impl Config {
                             config.last_scan_folder = Some(PathBuf::from(value));
                         }
                     }
-                    _ => {} // Ignore unknown keys
+                    "cue_device" if !value.is_empty() => {
+                        config.cue_device = Some(value.to_string())
+                    }
+                    "audio_host" if !value.is_empty() => {
+                        config.audio.host = Some(value.to_string())
+                    }
+                    "audio_device" if !value.is_empty() => {
+                        config.audio.device = Some(value.to_string())
+                    }
+                    "sample_rate" => config.audio.sample_rate = value.parse().ok(),
+                    "buffer_size" => config.audio.buffer_size = value.parse().ok(),
+                    "interpolation" if !value.is_empty() => {
+                        config.audio.interpolation = Some(value.to_string())
+                    }
+                    "crossfader_curve" => config.mixer.crossfader_curve = value.to_string(),
+                    "crossfader_sharpness" => {
+                        config.mixer.crossfader_sharpness = value.parse().unwrap_or_default()
+                    }
+                    "hamster" => config.mixer.hamster = value == "true",
+                    "upfader_curve" => config.mixer.upfader_curve = value.to_string(),
+                    _ => {
+                        if let Some(deck) = deck_key(key, "trim_") {
+                            config.mixer.trims[deck] = value.parse().unwrap_or_default();
+                        } else if let Some(deck) = deck_key(key, "crossfader_assign_") {
+                            config.mixer.assigns[deck] = value.to_string();
+                        }
+                        // Ignore unknown keys
+                    }
                 }
             }
         }
@@ -90,6 +170,48 @@ impl Config {
             lines.push(format!("last_scan_folder={}", folder.display()));
         }
 
+        if let Some(ref device) = self.cue_device {
+            lines.push(format!("cue_device={}", device));
+        }
+
+        let audio = &self.audio;
+        if let Some(ref host) = audio.host {
+            lines.push(format!("audio_host={}", host));
+        }
+        if let Some(ref device) = audio.device {
+            lines.push(format!("audio_device={}", device));
+        }
+        if let Some(rate) = audio.sample_rate {
+            lines.push(format!("sample_rate={}", rate));
+        }
+        if let Some(frames) = audio.buffer_size {
+            lines.push(format!("buffer_size={}", frames));
+        }
+        if let Some(ref interpolation) = audio.interpolation {
+            lines.push(format!("interpolation={}", interpolation));
+        }
+
+        let mixer = &self.mixer;
+        if !mixer.crossfader_curve.is_empty() {
+            lines.push(format!("crossfader_curve={}", mixer.crossfader_curve));
+        }
+        lines.push(format!(
+            "crossfader_sharpness={}",
+            mixer.crossfader_sharpness
+        ));
+        lines.push(format!("hamster={}", mixer.hamster));
+        if !mixer.upfader_curve.is_empty() {
+            lines.push(format!("upfader_curve={}", mixer.upfader_curve));
+        }
+        for (deck, trim) in DECK_KEYS.iter().zip(mixer.trims) {
+            lines.push(format!("trim_{}={}", deck, trim));
+        }
+        for (deck, assign) in DECK_KEYS.iter().zip(&mixer.assigns) {
+            if !assign.is_empty() {
+                lines.push(format!("crossfader_assign_{}={}", deck, assign));
+            }
+        }
+
         lines.join("\n")
     }
 }
@@ -130,4 +252,42 @@ mod tests {
 
         assert_eq!(parsed.last_scan_folder, config.last_scan_folder);
     }
+
+    #[test]
+    fn test_mixer_roundtrip() {
+        let config = Config {
+            mixer: MixerConfig {
+                crossfader_curve: "cut".to_string(),
+                crossfader_sharpness: 0.75,
+                hamster: true,
+                upfader_curve: "slow".to_string(),
+                trims: [-3.5, 2.0, 0.0, -1.5],
+                assigns: ["a", "b", "thru", "a"].map(String::from),
+            },
+            ..Default::default()
+        };
+
+        let parsed = Config::parse(&config.serialize());
+        assert_eq!(parsed.mixer, config.mixer);
+        assert!(parsed.last_scan_folder.is_none());
+        assert!(parsed.cue_device.is_none());
+        assert_eq!(parsed.audio, AudioConfig::default());
+    }
+
+    #[test]
+    fn test_audio_roundtrip() {
+        let config = Config {
+            audio: AudioConfig {
+                host: Some("JACK".to_string()),
+                device: Some("Scarlett 2i4 USB: Audio (hw:2,0)".to_string()),
+                sample_rate: Some(44100),
+                buffer_size: Some(256),
+                interpolation: Some("sinc".to_string()),
+            },
+            ..Default::default()
+        };
+
+        let parsed = Config::parse(&config.serialize());
+        assert_eq!(parsed.audio, config.audio);
+    }
 }
@@ -5,7 +5,7 @@ mod config;
 mod loader;
 mod scanner;
 
-pub use cache::{AnalysisCache, CacheError, CachedAnalysis};
-pub use config::Config;
+pub use cache::{AnalysisCache, CacheError, CachedAnalysis, GridEdit};
+pub use config::{AudioConfig, Config, MixerConfig};
 pub use loader::{LoadError, LoadedTrack, TrackLoader, TrackMetadata};
 pub use scanner::{LibraryScanner, ScanConfig, ScanError, ScanProgress, ScanResult};
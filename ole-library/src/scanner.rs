@@ -3,7 +3,7 @@
 //! Scans directories for audio files, analyzes BPM and key using multiple
 //! threads, and stores results in the cache.
 
-use crate::cache::{AnalysisCache, CacheError, CachedAnalysis};
+use crate::cache::{AnalysisCache, CacheError, CachedAnalysis, GridEdit};
 use crate::loader::{LoadError, TrackLoader};
 use crossbeam_channel::{self, Receiver, Sender};
 use ole_analysis::{BeatGridAnalyzer, CamelotKey, KeyAnalyzer};
@@ -453,9 +453,12 @@ impl LibraryScanner {
                     }
 
                     match analyze_track(&loader, &path) {
-                        Ok(analysis) => {
-                            // Store in cache
+                        Ok(mut analysis) => {
+                            // Store in cache, keeping any hand-corrected beat grid
                             if let Ok(cache) = cache.lock() {
+                                if let Some(edit) = cache.get_grid_edit(&path) {
+                                    analysis.apply_grid_edit(&edit);
+                                }
                                 let _ = cache.store(&analysis);
                             }
                             results.lock().unwrap().push(analysis);
@@ -496,6 +499,16 @@ impl LibraryScanner {
     pub fn get_all_tracks(&self) -> Result<Vec<CachedAnalysis>, CacheError> {
         self.cache.lock().unwrap().get_all_sorted()
     }
+
+    /// Get the hand-corrected beat grid of a track
+    pub fn get_grid_edit(&self, path: &Path) -> Option<GridEdit> {
+        self.cache.lock().unwrap().get_grid_edit(path)
+    }
+
+    /// Save a hand-corrected beat grid
+    pub fn store_grid_edit(&self, path: &Path, edit: &GridEdit) -> Result<(), CacheError> {
+        self.cache.lock().unwrap().store_grid_edit(path, edit)
+    }
 }
 
 /// Analyze a single track for BPM and key
@@ -516,14 +529,18 @@ fn analyze_track(loader: &TrackLoader, path: &Path) -> Result<CachedAnalysis, Sc
     let beat_analyzer = BeatGridAnalyzer::new(track.sample_rate);
     let mut key_analyzer = KeyAnalyzer::new(track.sample_rate);
 
-    // Analyze BPM (use first 30 seconds)
-    let analysis_samples = track.samples.len().min(track.sample_rate as usize * 60); // 30 sec stereo
-    let beat_grid = beat_analyzer.analyze(&track.samples[..analysis_samples]);
+    // Analyze BPM over the whole track (tempo map)
+    let beat_grid = beat_analyzer.analyze(&track.samples);
     let (bpm, bpm_confidence) = beat_grid
+        .as_ref()
         .map(|g| (Some(g.bpm), Some(g.confidence)))
         .unwrap_or((None, None));
+    let (downbeat, phrases) = beat_grid
+        .map(|g| (Some(g.downbeat), g.phrases().to_vec()))
+        .unwrap_or_default();
 
     // Analyze key (use first 30 seconds)
+    let analysis_samples = track.samples.len().min(track.sample_rate as usize * 60); // 30 sec stereo
     let detected_key = key_analyzer.analyze(&track.samples[..analysis_samples]);
     let (key_str, key_confidence) = detected_key
         .map(|k| {
@@ -539,6 +556,8 @@ fn analyze_track(loader: &TrackLoader, path: &Path) -> Result<CachedAnalysis, Sc
         duration_secs: track.metadata.duration_secs,
         bpm,
         bpm_confidence,
+        downbeat,
+        phrases,
         key: key_str,
         key_confidence,
         title: track.metadata.title,
@@ -0,0 +1,31 @@
+//! Headless renderer for OLE - scripted mixes without a sound card
+//!
+//! Drives the audio engine from a timeline script (timestamped commands)
+//! as fast as the machine allows and writes the result to WAV or FLAC:
+//! - Script: Timeline parsing
+//! - Render: Block-by-block rendering with sample-accurate events
+
+mod render;
+mod script;
+
+pub use render::{render_to_file, RenderOptions, RenderStats, Renderer};
+pub use script::{parse_time, Action, Event, RampTarget, Script, ScriptError};
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors that can occur while rendering
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Script error: {0}")]
+    Script(#[from] ScriptError),
+    #[error("Failed to load {path}: {source}")]
+    Load {
+        path: PathBuf,
+        source: ole_library::LoadError,
+    },
+    #[error("Unsupported output format (use .wav or .flac)")]
+    UnsupportedFormat,
+}
@@ -0,0 +1,37 @@
+//! ole-render - render a timeline script to WAV or FLAC
+//!
+//! Usage: ole-render <script> <output.wav|output.flac> [--rate <hz>]
+
+use ole_render::{render_to_file, RenderOptions, Script};
+use std::path::PathBuf;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut options = RenderOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rate" | "-r" => {
+                let rate = args.next().and_then(|r| r.parse().ok());
+                options.sample_rate = rate.ok_or_else(|| anyhow::anyhow!("--rate needs Hz"))?;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [script_path, output] = paths.as_slice() else {
+        anyhow::bail!("usage: ole-render <script> <output.wav|output.flac> [--rate <hz>]");
+    };
+
+    let script = Script::load(script_path)?;
+    let stats = render_to_file(script, output, &options)?;
+    let secs = stats.secs() as u64;
+    println!(
+        "Rendered {}:{:02} to {} in {:.1}s ({:.0}x real time)",
+        secs / 60,
+        secs % 60,
+        output.display(),
+        stats.elapsed.as_secs_f64(),
+        stats.speed()
+    );
+    Ok(())
+}
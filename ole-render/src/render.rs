@@ -0,0 +1,360 @@
+//! Renderer - runs the engine over a timeline, faster than real time
+
+use crate::script::{Action, Event, RampTarget, Script};
+use crate::RenderError;
+use ole_audio::{AudioCommand, Deck, EngineState, FlacWriter, RecordFormat, WavWriter};
+use ole_library::TrackLoader;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Render settings
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub sample_rate: u32,
+    /// Stop here even if decks still play (e.g. a loop left running)
+    pub max_secs: f64,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            max_secs: 3.0 * 3600.0,
+        }
+    }
+}
+
+/// How a render went
+#[derive(Debug, Clone, Copy)]
+pub struct RenderStats {
+    pub frames: u64,
+    pub sample_rate: u32,
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    /// Length of the render in seconds
+    pub fn secs(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+
+    /// How many times faster than real time the render ran
+    pub fn speed(&self) -> f64 {
+        self.secs() / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/// A ramp in progress
+#[derive(Debug, Clone, Copy)]
+struct ActiveRamp {
+    target: RampTarget,
+    from: f32,
+    to: f32,
+    start: u64,
+    frames: u64,
+}
+
+/// Drives `EngineState::process` from a script, block by block
+pub struct Renderer {
+    engine: EngineState,
+    loader: TrackLoader,
+    /// Events still to fire, in time order
+    events: VecDeque<Event>,
+    ramps: Vec<ActiveRamp>,
+    sample_rate: u32,
+    /// Frames rendered so far
+    frame: u64,
+    end_frame: Option<u64>,
+    max_frames: u64,
+    buffer: Vec<f32>,
+}
+
+impl Renderer {
+    /// Frames per block (also how often ramps step)
+    const BLOCK_FRAMES: usize = 512;
+
+    pub fn new(script: Script, options: &RenderOptions) -> Self {
+        let sample_rate = options.sample_rate;
+        let to_frames = |secs: f64| (secs * sample_rate as f64).round() as u64;
+        Self {
+            engine: EngineState::new(sample_rate),
+            loader: TrackLoader::with_sample_rate(sample_rate),
+            end_frame: script.end.map(to_frames),
+            events: script.events.into(),
+            ramps: Vec::new(),
+            sample_rate,
+            frame: 0,
+            max_frames: to_frames(options.max_secs),
+            buffer: vec![0.0; Self::BLOCK_FRAMES * 2],
+        }
+    }
+
+    /// The engine being rendered (for inspecting its state)
+    pub fn engine(&self) -> &EngineState {
+        &self.engine
+    }
+
+    /// Frames rendered so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Frame the next event fires on
+    fn next_event_frame(&self) -> Option<u64> {
+        let event = self.events.front()?;
+        Some((event.time * self.sample_rate as f64).round() as u64)
+    }
+
+    /// Render the next block of interleaved stereo samples, or `None` once
+    /// the script has ended
+    ///
+    /// Events fire on their exact frame: a block stops short of the next one.
+    pub fn next_block(&mut self) -> Result<Option<&[f32]>, RenderError> {
+        while self
+            .next_event_frame()
+            .is_some_and(|frame| frame <= self.frame)
+        {
+            if let Some(event) = self.events.pop_front() {
+                self.apply(event.action)?;
+            }
+        }
+        if self.is_finished() {
+            return Ok(None);
+        }
+
+        let mut frames = Self::BLOCK_FRAMES as u64;
+        for limit in [
+            self.next_event_frame(),
+            self.end_frame,
+            Some(self.max_frames),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            frames = frames.min(limit - self.frame);
+        }
+        self.step_ramps();
+
+        let output = &mut self.buffer[..frames as usize * 2];
+        output.fill(0.0);
+        self.engine.process(output, None);
+        self.frame += frames;
+        Ok(Some(output))
+    }
+
+    /// Past `end`, the length limit, or (without `end`) all events done
+    /// and no deck playing
+    fn is_finished(&self) -> bool {
+        if self.frame >= self.max_frames {
+            return true;
+        }
+        match self.end_frame {
+            Some(end) => self.frame >= end,
+            None => {
+                self.events.is_empty()
+                    && self.ramps.is_empty()
+                    && !self.engine.decks.iter().any(|deck| deck.is_playing())
+            }
+        }
+    }
+
+    fn apply(&mut self, action: Action) -> Result<(), RenderError> {
+        match action {
+            Action::Load { deck, path } => {
+                let track = self
+                    .loader
+                    .load(&path)
+                    .map_err(|source| RenderError::Load {
+                        path: path.clone(),
+                        source,
+                    })?;
+                let name = if track.metadata.title != "Unknown" {
+                    Some(track.metadata.title.clone())
+                } else {
+                    path.file_name().map(|s| s.to_string_lossy().to_string())
+                };
+                let samples = Arc::new(track.samples);
+                let waveform = Arc::new(track.waveform_overview);
+                let enhanced = Arc::new(track.enhanced_waveform);
+                let sr = track.sample_rate;
+                self.engine.handle_command(AudioCommand::LoadDeck(
+                    deck, samples, sr, name, waveform, enhanced, None,
+                ));
+            }
+            Action::Command(cmd) => self.engine.handle_command(cmd),
+            Action::ExitLoop(deck) => {
+                if is_looping(self.engine.deck(deck)) {
+                    self.engine.handle_command(AudioCommand::Reloop(deck));
+                }
+            }
+            Action::Ramp { target, to, secs } => {
+                let mixer = &self.engine.mixer;
+                let from = match target {
+                    RampTarget::Crossfader => mixer.crossfader(),
+                    RampTarget::Upfader(deck) => mixer.upfader(deck),
+                    RampTarget::MasterVolume => mixer.master_volume(),
+                };
+                // A new ramp on the same control replaces the old one
+                self.ramps.retain(|ramp| ramp.target != target);
+                self.ramps.push(ActiveRamp {
+                    target,
+                    from,
+                    to,
+                    start: self.frame,
+                    frames: (secs * self.sample_rate as f64).round() as u64,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Move ramping controls to where they should be at this frame
+    fn step_ramps(&mut self) {
+        let frame = self.frame;
+        for ramp in &self.ramps {
+            let t = if ramp.frames == 0 {
+                1.0
+            } else {
+                ((frame - ramp.start) as f64 / ramp.frames as f64).min(1.0) as f32
+            };
+            let value = ramp.from + (ramp.to - ramp.from) * t;
+            self.engine.handle_command(ramp.target.command(value));
+        }
+        self.ramps.retain(|ramp| frame < ramp.start + ramp.frames);
+    }
+}
+
+fn is_looping(deck: &Deck) -> bool {
+    deck.state().loop_info.is_some_and(|l| l.active)
+}
+
+/// Render a script to a WAV or FLAC file (by extension)
+pub fn render_to_file(
+    script: Script,
+    output: &Path,
+    options: &RenderOptions,
+) -> Result<RenderStats, RenderError> {
+    let format = RecordFormat::from_path(output).ok_or(RenderError::UnsupportedFormat)?;
+    let file = BufWriter::new(File::create(output)?);
+    let started = Instant::now();
+    let mut renderer = Renderer::new(script, options);
+    match format {
+        RecordFormat::Wav => {
+            let mut writer = WavWriter::new(file, options.sample_rate)?;
+            while let Some(block) = renderer.next_block()? {
+                writer.write(block)?;
+            }
+            writer.finish()?;
+        }
+        RecordFormat::Flac => {
+            let mut writer = FlacWriter::new(file, options.sample_rate)?;
+            while let Some(block) = renderer.next_block()? {
+                writer.write(block)?;
+            }
+            writer.finish()?;
+        }
+    }
+    Ok(RenderStats {
+        frames: renderer.frame(),
+        sample_rate: options.sample_rate,
+        elapsed: started.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Write a 4s stereo tone to a temporary WAV file
+    fn tone_file(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ole-render-{}-{}.wav", std::process::id(), name));
+        let samples: Vec<f32> = (0..SAMPLE_RATE * 4)
+            .flat_map(|i| {
+                let s = (i as f32 * 0.05).sin() * 0.5;
+                [s, s]
+            })
+            .collect();
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), SAMPLE_RATE).unwrap();
+        writer.write(&samples).unwrap();
+        std::fs::write(&path, writer.finish().unwrap().into_inner()).unwrap();
+        path
+    }
+
+    fn render_all(script: &str) -> Vec<f32> {
+        let script = Script::parse(script, &std::env::temp_dir()).unwrap();
+        let options = RenderOptions {
+            sample_rate: SAMPLE_RATE,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::new(script, &options);
+        let mut output = Vec::new();
+        while let Some(block) = renderer.next_block().unwrap() {
+            output.extend_from_slice(block);
+        }
+        output
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0f32, |p, s| p.max(s.abs()))
+    }
+
+    #[test]
+    fn test_events_fire_on_their_frame() {
+        let track = tone_file("timing");
+        let output = render_all(&format!(
+            "0 load a {}\n0.5 play a\n1.5 end\n",
+            track.display()
+        ));
+        let _ = std::fs::remove_file(&track);
+
+        assert_eq!(output.len(), (SAMPLE_RATE as f64 * 1.5) as usize * 2);
+        let start = SAMPLE_RATE as usize / 2 * 2;
+        assert_eq!(peak(&output[..start]), 0.0);
+        assert!(peak(&output[start..]) > 0.1);
+    }
+
+    #[test]
+    fn test_render_is_reproducible() {
+        let track = tone_file("repro");
+        let script = format!(
+            "0 load a {0}\n0 load b {0}\n0 xf -1\n0 play a\n0.5 play b\n0.5 xf 1 over 1\n\
+             1 eq a low kill\n1.2 filter b hp 5\n2 end\n",
+            track.display()
+        );
+        let first = render_all(&script);
+        let second = render_all(&script);
+        let _ = std::fs::remove_file(&track);
+
+        assert_eq!(first.len(), SAMPLE_RATE as usize * 2 * 2);
+        assert!(peak(&first) > 0.1);
+        assert!(first == second, "renders differ");
+    }
+
+    #[test]
+    fn test_render_without_end_stops_with_the_decks() {
+        // The 4s track plays out, then the render stops
+        let track = tone_file("playout");
+        let output = render_all(&format!("0 load a {}\n1 play a\n", track.display()));
+        let _ = std::fs::remove_file(&track);
+
+        let secs = output.len() as f64 / 2.0 / SAMPLE_RATE as f64;
+        assert!((secs - 5.0).abs() < 0.05, "rendered {}s", secs);
+    }
+
+    #[test]
+    fn test_ramp_reaches_its_target() {
+        let script = Script::parse("0 xf -1\n0 xf 1 over 0.5\n1 end\n", Path::new(".")).unwrap();
+        let mut renderer = Renderer::new(script, &RenderOptions::default());
+        while renderer.next_block().unwrap().is_some() {}
+        assert_eq!(renderer.engine().mixer.crossfader(), 1.0);
+    }
+}
@@ -0,0 +1,389 @@
+//! Timeline scripts - timestamped engine commands
+//!
+//! One event per line, `<time> <command> [args]`, with `#` comments:
+//!
+//! ```text
+//! 0:00    load a intro.flac
+//! 0:00    play a
+//! 1:30    load b next.mp3
+//! 1:45    sync b
+//! 1:45    play b
+//! 1:46    xf 1 over 16
+//! 2:10    eq a low kill
+//! 2:30    assign c thru
+//! 3:00    end
+//! ```
+//!
+//! Decks are `a` to `d`. Times are seconds (`90.5`) or `m:ss` / `h:mm:ss`.
+//! Paths are relative to the script's directory. Without `end` the render runs until no deck
+//! plays after the last event.
+
+use ole_audio::{AudioCommand, CrossfaderAssign, DeckId, EqBand, FilterType};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors in a timeline script
+#[derive(Error, Debug, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Mixer control that can be moved gradually (`over <secs>`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RampTarget {
+    Crossfader,
+    Upfader(DeckId),
+    MasterVolume,
+}
+
+impl RampTarget {
+    /// Command setting the control to `value`
+    pub fn command(&self, value: f32) -> AudioCommand {
+        match self {
+            RampTarget::Crossfader => AudioCommand::SetCrossfader(value),
+            RampTarget::Upfader(deck) => AudioCommand::SetUpfader(*deck, value),
+            RampTarget::MasterVolume => AudioCommand::SetMasterVolume(value),
+        }
+    }
+}
+
+/// What happens at an event
+#[derive(Debug)]
+pub enum Action {
+    /// Decode a track and load it to a deck
+    Load { deck: DeckId, path: PathBuf },
+    /// Send a command to the engine
+    Command(AudioCommand),
+    /// Leave the deck's loop if it is in one
+    ExitLoop(DeckId),
+    /// Move a control to `to` over `secs`
+    Ramp {
+        target: RampTarget,
+        to: f32,
+        secs: f64,
+    },
+}
+
+/// A timestamped action
+#[derive(Debug)]
+pub struct Event {
+    /// Seconds from the start of the render
+    pub time: f64,
+    pub action: Action,
+}
+
+/// A parsed timeline
+#[derive(Debug, Default)]
+pub struct Script {
+    /// Events in time order (same-time events keep their script order)
+    pub events: Vec<Event>,
+    /// Where the render stops, if the script says
+    pub end: Option<f64>,
+}
+
+impl Script {
+    /// Read and parse a script file
+    pub fn load(path: &Path) -> Result<Self, crate::RenderError> {
+        let text = std::fs::read_to_string(path)?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        Ok(Self::parse(&text, base_dir)?)
+    }
+
+    /// Parse a script; relative track paths are resolved against `base_dir`
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Self, ScriptError> {
+        let mut script = Script::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |message: String| ScriptError {
+                line: line_no,
+                message,
+            };
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let time =
+                parse_time(parts[0]).ok_or_else(|| err(format!("bad time '{}'", parts[0])))?;
+            let Some(&command) = parts.get(1) else {
+                return Err(err("missing command".into()));
+            };
+            if command == "end" {
+                script.end = Some(time);
+                continue;
+            }
+            let action = parse_action(command, &parts[2..], base_dir).map_err(err)?;
+            script.events.push(Event { time, action });
+        }
+        script.events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(script)
+    }
+}
+
+/// Parse `90.5`, `1:30` or `1:01:30.5` to seconds
+pub fn parse_time(s: &str) -> Option<f64> {
+    let mut secs = 0.0;
+    for (i, part) in s.split(':').enumerate() {
+        if i > 2 {
+            return None;
+        }
+        let value: f64 = part.parse().ok()?;
+        if value < 0.0 {
+            return None;
+        }
+        secs = secs * 60.0 + value;
+    }
+    Some(secs)
+}
+
+/// Parse beats as `4` or `1/8`
+fn parse_beats(s: &str) -> Option<f32> {
+    match s.split_once('/') {
+        Some((num, den)) => {
+            let den: f32 = den.parse().ok()?;
+            (den != 0.0).then_some(num.parse::<f32>().ok()? / den)
+        }
+        None => s.parse().ok(),
+    }
+}
+
+fn parse_action(command: &str, args: &[&str], base_dir: &Path) -> Result<Action, String> {
+    let usage = |form: &str| format!("usage: {} {}", command, form);
+    let deck = |i: usize| {
+        args.get(i)
+            .and_then(|d| DeckId::parse(d))
+            .ok_or_else(|| format!("{}: expected deck a-d", command))
+    };
+    let number = |i: usize| -> Result<f32, String> {
+        args.get(i)
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| format!("{}: expected a number", command))
+    };
+    // Trailing "over <secs>" turns a fader move into a ramp
+    let ramp = |target: RampTarget, value_at: usize| -> Result<Action, String> {
+        let to = number(value_at)?;
+        match args.get(value_at + 1..) {
+            Some(["over", secs]) => {
+                let secs = parse_time(secs).ok_or_else(|| usage("<value> over <secs>"))?;
+                Ok(Action::Ramp { target, to, secs })
+            }
+            Some([]) | None => Ok(Action::Command(target.command(to))),
+            _ => Err(usage("<value> [over <secs>]")),
+        }
+    };
+    let send = |cmd: AudioCommand| Ok(Action::Command(cmd));
+
+    match command {
+        "load" => {
+            let deck = deck(0)?;
+            if args.len() < 2 {
+                return Err(usage("<a-d> <path>"));
+            }
+            let path = args[1..].join(" ");
+            let path = Path::new(path.trim_matches(['\'', '"']));
+            Ok(Action::Load {
+                deck,
+                path: base_dir.join(path),
+            })
+        }
+        "play" => send(AudioCommand::Play(deck(0)?)),
+        "pause" => send(AudioCommand::Pause(deck(0)?)),
+        "stop" => send(AudioCommand::Stop(deck(0)?)),
+        "seek" => {
+            let deck = deck(0)?;
+            let secs = args
+                .get(1)
+                .and_then(|t| parse_time(t))
+                .ok_or_else(|| usage("<a-d> <time>"))?;
+            send(AudioCommand::Seek(deck, secs))
+        }
+        "tempo" => {
+            let (deck, tempo) = (deck(0)?, number(1)?);
+            send(AudioCommand::SetTempo(deck, tempo))
+        }
+        "pitch" => {
+            let (deck, st) = (deck(0)?, number(1)?);
+            send(AudioCommand::SetPitch(deck, st))
+        }
+        "keylock" => {
+            let deck = deck(0)?;
+            let on = match args.get(1).copied() {
+                Some("on") => true,
+                Some("off") => false,
+                _ => return Err(usage("<a-d> <on|off>")),
+            };
+            send(AudioCommand::SetKeyLock(deck, on))
+        }
+        // "sync b" makes deck B follow the sync leader (or the loudest other deck)
+        "sync" => send(AudioCommand::Sync(deck(0)?)),
+        "synclock" => send(AudioCommand::ToggleSyncLock(deck(0)?)),
+        "cue" => {
+            let deck = deck(0)?;
+            let num = number(1)? as u8;
+            if !(1..=4).contains(&num) {
+                return Err(usage("<a-d> <1-4>"));
+            }
+            send(AudioCommand::JumpCue(deck, num))
+        }
+        "setcue" => {
+            let deck = deck(0)?;
+            let num = number(1)? as u8;
+            if !(1..=4).contains(&num) {
+                return Err(usage("<a-d> <1-4>"));
+            }
+            send(AudioCommand::SetCue(deck, num))
+        }
+        "beatjump" => {
+            let (deck, beats) = (deck(0)?, number(1)? as i32);
+            send(AudioCommand::Beatjump(deck, beats))
+        }
+        "loop" => {
+            let deck = deck(0)?;
+            match args.get(1).copied() {
+                Some("off") => Ok(Action::ExitLoop(deck)),
+                Some(beats) => match parse_beats(beats) {
+                    Some(beats) if beats > 0.0 => send(AudioCommand::AutoLoop(deck, beats)),
+                    _ => Err(usage("<a-d> <beats|off>")),
+                },
+                None => Err(usage("<a-d> <beats|off>")),
+            }
+        }
+        "xf" | "crossfader" => ramp(RampTarget::Crossfader, 0),
+        "assign" => {
+            let deck = deck(0)?;
+            let assign = args
+                .get(1)
+                .and_then(|a| CrossfaderAssign::parse(a))
+                .ok_or_else(|| usage("<a-d> <a|b|thru>"))?;
+            send(AudioCommand::SetCrossfaderAssign(deck, assign))
+        }
+        "fader" => ramp(RampTarget::Upfader(deck(0)?), 1),
+        "volume" => ramp(RampTarget::MasterVolume, 0),
+        "trim" => {
+            let (deck, db) = (deck(0)?, number(1)?);
+            send(AudioCommand::SetTrim(deck, db))
+        }
+        "eq" => {
+            let deck = deck(0)?;
+            let band = args
+                .get(1)
+                .and_then(|b| EqBand::parse(b))
+                .ok_or_else(|| usage("<a-d> <low|mid|high> <db|kill>"))?;
+            if args.get(2) == Some(&"kill") {
+                return send(AudioCommand::ToggleEqKill(deck, band));
+            }
+            let db = number(2)?;
+            send(AudioCommand::SetEq(deck, band, db))
+        }
+        "filter" => {
+            let deck = deck(0)?;
+            let (filter_type, level) = match (args.get(1).copied(), args.get(2)) {
+                (Some("off"), _) => (FilterType::LowPass, 0),
+                (Some(kind), Some(level)) => {
+                    let filter_type = match kind {
+                        "lp" => FilterType::LowPass,
+                        "hp" => FilterType::HighPass,
+                        "bp" => FilterType::BandPass,
+                        _ => return Err(usage("<a-d> <lp|hp|bp> <1-10> or off")),
+                    };
+                    let level = level
+                        .parse::<u8>()
+                        .map_err(|_| usage("<a-d> <lp|hp|bp> <1-10> or off"))?;
+                    (filter_type, level.min(10))
+                }
+                _ => return Err(usage("<a-d> <lp|hp|bp> <1-10> or off")),
+            };
+            send(AudioCommand::SetFilterPreset(deck, filter_type, level))
+        }
+        "delay" | "reverb" => {
+            let deck = deck(0)?;
+            let level = number(1)?.clamp(0.0, 5.0) as u8;
+            if command == "delay" {
+                send(AudioCommand::SetDelayLevel(deck, level))
+            } else {
+                send(AudioCommand::SetReverbLevel(deck, level))
+            }
+        }
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("90.5"), Some(90.5));
+        assert_eq!(parse_time("1:30"), Some(90.0));
+        assert_eq!(parse_time("1:01:30.5"), Some(3690.5));
+        assert_eq!(parse_time("1:2:3:4"), None);
+        assert_eq!(parse_time("-1"), None);
+        assert_eq!(parse_time("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let text = "\
+# a short mix
+0:00 load a 'intro.flac'
+0:00 play a
+1:00 xf 1 over 8   # into deck B
+0:30 fader b 0.5
+0:45 loop b off
+1:30 assign d thru
+2:00 end
+";
+        let script = Script::parse(text, Path::new("/music")).unwrap();
+        assert_eq!(script.end, Some(120.0));
+        let times: Vec<f64> = script.events.iter().map(|e| e.time).collect();
+        assert_eq!(times, [0.0, 0.0, 30.0, 45.0, 60.0, 90.0]);
+
+        match &script.events[0].action {
+            Action::Load { deck, path } => {
+                assert_eq!(*deck, DeckId::A);
+                assert_eq!(path, Path::new("/music/intro.flac"));
+            }
+            other => panic!("expected a load, got {:?}", other),
+        }
+        assert!(matches!(
+            script.events[1].action,
+            Action::Command(AudioCommand::Play(DeckId::A))
+        ));
+        assert!(matches!(
+            script.events[2].action,
+            Action::Command(AudioCommand::SetUpfader(DeckId::B, v)) if v == 0.5
+        ));
+        assert!(matches!(
+            script.events[3].action,
+            Action::ExitLoop(DeckId::B)
+        ));
+        assert!(matches!(
+            script.events[4].action,
+            Action::Ramp {
+                target: RampTarget::Crossfader,
+                to,
+                secs,
+            } if to == 1.0 && secs == 8.0
+        ));
+        assert!(matches!(
+            script.events[5].action,
+            Action::Command(AudioCommand::SetCrossfaderAssign(
+                DeckId::D,
+                CrossfaderAssign::Thru
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_script_errors_name_the_line() {
+        let err = Script::parse("0 play a\n\n1 play e\n", Path::new(".")).unwrap_err();
+        assert_eq!(err.line, 3);
+        let err = Script::parse("soon play a\n", Path::new(".")).unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = Script::parse("0 scratch a\n", Path::new(".")).unwrap_err();
+        assert_eq!(err.message, "unknown command 'scratch'");
+    }
+}